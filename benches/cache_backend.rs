@@ -0,0 +1,61 @@
+//! Compares the backends of the cached entries on a synthetic workload of
+//! 100k modules, which are resolved from all the available threads.
+//!
+//! `cargo bench --bench cache_backend --features concurrent-alt`
+
+mod common;
+
+use common::{fixture, write};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nodejs_resolver::{Cache, CacheBackend, Options, Resolver};
+use std::{path::PathBuf, sync::Arc};
+
+const PACKAGES: usize = 100;
+const MODULES: usize = 1000;
+
+fn cache_backend(c: &mut Criterion) {
+    let root = fixture("cache-backend", |root| {
+        for package in 0..PACKAGES {
+            let dir = root.join(format!("node_modules/pkg-{package}"));
+            write(
+                &dir.join("package.json"),
+                &format!(r#"{{ "name": "pkg-{package}", "main": "lib/m-0.js" }}"#),
+            );
+            for module in 0..MODULES {
+                write(&dir.join(format!("lib/m-{module}.js")), "");
+            }
+        }
+    });
+    let jobs: Vec<(PathBuf, String)> = (0..MODULES)
+        .flat_map(|module| {
+            let root = &root;
+            (0..PACKAGES)
+                .map(move |package| (root.clone(), format!("pkg-{package}/lib/m-{module}")))
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("cache_backend");
+    // each iteration resolves the whole workload.
+    group.sample_size(10);
+    for (name, backend) in [
+        ("dash_map", CacheBackend::DashMap),
+        ("sharded_rw_lock", CacheBackend::ShardedRwLock),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    Resolver::new(Options {
+                        external_cache: Some(Arc::new(Cache::default().with_backend(backend))),
+                        ..Default::default()
+                    })
+                },
+                |resolver| resolver.prefetch(&jobs),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, cache_backend);
+criterion_main!(benches);
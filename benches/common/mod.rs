@@ -0,0 +1,23 @@
+//! The fixtures shared by the benchmarks.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+pub fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// Returns the directory of the generated tree `name`, it is generated by
+/// `gen` only once, the tree of the previous run is reused.
+pub fn fixture(name: &str, gen: impl FnOnce(&Path)) -> PathBuf {
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let done = root.join(".done");
+    if !done.exists() {
+        let _ = std::fs::remove_dir_all(&root);
+        gen(&root);
+        write(&done, "");
+    }
+    root
+}
@@ -0,0 +1,150 @@
+//! The resolution on the generated `node_modules` trees of the common
+//! layouts. Each tree is resolved cold, by a new cache, and warm, by a new
+//! resolver sharing the cache of the previous runs, so the files are read
+//! once but the results are not memoized.
+//!
+//! `cargo bench --bench resolve [-- filter]`
+
+mod common;
+
+use common::write;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use nodejs_resolver::{Cache, Options, Resolver};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const PACKAGES: usize = 500;
+
+fn package_json(name: &str, extra: &str) -> String {
+    format!(r#"{{ "name": "{name}", "version": "1.0.0", "main": "lib/index.js"{extra} }}"#)
+}
+
+fn gen_package(dir: &Path, name: &str) {
+    write(&dir.join("package.json"), &package_json(name, ""));
+    write(&dir.join("lib/index.js"), "");
+    write(&dir.join("lib/util.js"), "");
+}
+
+/// `node_modules/p-{i}`, such as the layout of npm.
+fn gen_flat(root: &Path) -> Vec<(PathBuf, String)> {
+    let mut jobs = vec![];
+    for i in 0..PACKAGES {
+        gen_package(&root.join(format!("node_modules/p-{i}")), &format!("p-{i}"));
+        jobs.push((root.join("src"), format!("p-{i}")));
+        jobs.push((root.join("src"), format!("p-{i}/lib/util")));
+    }
+    write(&root.join("src/index.js"), "");
+    jobs
+}
+
+/// `node_modules/p-{i}/node_modules/q-{i}`, the dependencies are nested
+/// and the shared one is hoisted, such as the layout of npm@2.
+fn gen_nested(root: &Path) -> Vec<(PathBuf, String)> {
+    let mut jobs = vec![];
+    gen_package(&root.join("node_modules/shared"), "shared");
+    for i in 0..PACKAGES {
+        let dir = root.join(format!("node_modules/p-{i}"));
+        gen_package(&dir, &format!("p-{i}"));
+        let nested = dir.join(format!("node_modules/q-{i}"));
+        gen_package(&nested, &format!("q-{i}"));
+        jobs.push((dir.join("lib"), format!("q-{i}")));
+        jobs.push((nested.join("lib"), String::from("shared")));
+    }
+    jobs
+}
+
+/// `node_modules/.pnpm/p-{i}@1.0.0/node_modules/p-{i}` linked from
+/// `node_modules/p-{i}`, and the dependency of each package is linked to
+/// its sibling in the store, such as the layout of pnpm.
+#[cfg(unix)]
+fn gen_pnpm(root: &Path) -> Vec<(PathBuf, String)> {
+    use std::os::unix::fs::symlink;
+    let mut jobs = vec![];
+    let store = root.join("node_modules/.pnpm");
+    for i in 0..PACKAGES {
+        let name = format!("p-{i}");
+        let modules = store.join(format!("{name}@1.0.0/node_modules"));
+        gen_package(&modules.join(&name), &name);
+        symlink(
+            format!(".pnpm/{name}@1.0.0/node_modules/{name}"),
+            root.join(format!("node_modules/{name}")),
+        )
+        .unwrap();
+        let dependency = format!("p-{}", (i + 1) % PACKAGES);
+        symlink(
+            format!("../../{dependency}@1.0.0/node_modules/{dependency}"),
+            modules.join(&dependency),
+        )
+        .unwrap();
+        jobs.push((root.to_path_buf(), name.clone()));
+        jobs.push((modules.join(&name).join("lib"), dependency));
+    }
+    jobs
+}
+
+/// `node_modules/@s-{k}/p-{i}` with the `"exports"` of conditions and
+/// patterns, such as the packages of a monorepo.
+fn gen_scoped(root: &Path) -> Vec<(PathBuf, String)> {
+    let exports = r#", "exports": {
+        ".": { "import": "./lib/index.mjs", "default": "./lib/index.js" },
+        "./feature/*": { "types": "./types/*.d.ts", "default": "./lib/feature/*.js" }
+    }"#;
+    let mut jobs = vec![];
+    for i in 0..PACKAGES {
+        let name = format!("@s-{}/p-{i}", i % 20);
+        let dir = root.join("node_modules").join(&name);
+        write(&dir.join("package.json"), &package_json(&name, exports));
+        write(&dir.join("lib/index.js"), "");
+        write(&dir.join("lib/feature/a.js"), "");
+        jobs.push((root.to_path_buf(), name.clone()));
+        jobs.push((root.to_path_buf(), format!("{name}/feature/a")));
+    }
+    jobs
+}
+
+fn bench_tree(c: &mut Criterion, name: &str, gen: fn(&Path) -> Vec<(PathBuf, String)>) {
+    // the tree is small, it is generated by each run to list the jobs.
+    let root = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("resolve-{name}"));
+    let _ = std::fs::remove_dir_all(&root);
+    let jobs = gen(&root);
+
+    let resolve_all = |resolver: Resolver| {
+        for (path, request) in &jobs {
+            resolver.resolve(path, request).unwrap();
+        }
+    };
+    let mut group = c.benchmark_group(format!("resolve::{name}"));
+    group.sample_size(20);
+    group.bench_function("cold", |b| {
+        b.iter_batched(
+            || Resolver::new(Options::default()),
+            resolve_all,
+            BatchSize::PerIteration,
+        )
+    });
+    let cache = Arc::new(Cache::default());
+    let resolver = || {
+        Resolver::new(Options {
+            external_cache: Some(cache.clone()),
+            ..Default::default()
+        })
+    };
+    resolve_all(resolver());
+    group.bench_function("warm", |b| {
+        b.iter_batched(resolver, resolve_all, BatchSize::PerIteration)
+    });
+    group.finish();
+}
+
+fn resolve(c: &mut Criterion) {
+    bench_tree(c, "flat", gen_flat);
+    bench_tree(c, "nested", gen_nested);
+    #[cfg(unix)]
+    bench_tree(c, "pnpm", gen_pnpm);
+    bench_tree(c, "scoped", gen_scoped);
+}
+
+criterion_group!(benches, resolve);
+criterion_main!(benches);
@@ -4,15 +4,26 @@ use nodejs_resolver::{ResolveResult, Resolver};
 
 // cargo run --example simple -- `pwd`/tests/fixtures/simple .
 // cargo watch -x 'run --example simple -- `pwd`/tests/fixtures/simple .'
+// Prints the trace as JSON, see `nodejs_resolver::Trace` for the schema:
+// cargo run --example simple -- `pwd`/tests/fixtures/simple . --json
 
 fn main() {
-    let path = env::args().nth(1).expect("path");
-    let request = env::args().nth(2).expect("request");
+    let json = env::args().any(|arg| arg == "--json");
+    let mut args = env::args().skip(1).filter(|arg| arg != "--json");
+    let path = args.next().expect("path");
+    let request = args.next().expect("request");
     let resolver = Resolver::new(Default::default());
     let path_to_resolve = PathBuf::from(&path);
+    if json {
+        let trace = resolver.trace(&path_to_resolve, &request);
+        println!("{}", serde_json::to_string_pretty(&trace).unwrap());
+        return;
+    }
     match resolver.resolve(&path_to_resolve, &request) {
-        Ok(ResolveResult::Resource(resource)) => println!("{:?}", resource.join()),
-        Ok(ResolveResult::Ignored) => println!("Ignored"),
+        Ok(ResolveResult::File(resource)) => println!("{:?}", resource.join()),
+        Ok(ResolveResult::Directory(resource)) => println!("{:?} (directory)", resource.join()),
+        Ok(ResolveResult::Builtin(name)) => println!("{name} (builtin)"),
+        Ok(ResolveResult::Ignored { reason, .. }) => println!("Ignored by {reason:?}"),
         Err(err) => println!("{err:?}"),
     }
 }
@@ -0,0 +1,24 @@
+//! `Request::parse` never panics, and the query and fragment it splits
+//! are in their forms.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nodejs_resolver::{
+    kind::{classify, PathKind},
+    Request,
+};
+
+fuzz_target!(|request: &str| {
+    let parsed = Request::parse(request);
+    assert!(parsed.query().is_empty() || parsed.query().starts_with('?'));
+    assert!(parsed.fragment().is_empty() || parsed.fragment().starts_with('#'));
+    let parts = parsed.target().len() + parsed.query().len() + parsed.fragment().len();
+    assert!(parts <= request.len());
+    if !matches!(classify(request), PathKind::BuiltIn | PathKind::Url) {
+        assert_eq!(classify(request), parsed.kind());
+    }
+    if let Ok(parsed) = Request::try_parse(request) {
+        assert!(!parsed.target().is_empty() || parsed.is_directory());
+    }
+});
@@ -0,0 +1,58 @@
+//! `Resolver::resolve` never panics on the generated specifiers from the
+//! fixtures, and the resolved file exists. The specifier is the fuzzed
+//! string after one of the prefixes, so the relative, absolute, internal
+//! and bare requests are all reached.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use nodejs_resolver::{Options, ResolveResult, Resolver};
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+const PREFIXES: [&str; 10] = [
+    "", "./", "../", "/", "#", "@", "node:", "file://", "./node_modules/", "lib/",
+];
+
+const ISSUERS: [&str; 6] = [
+    "simple",
+    "exports-field",
+    "imports-field",
+    "alias",
+    "browser-module",
+    "tsconfig-paths",
+];
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("tests")
+        .join("fixtures")
+}
+
+fn resolver() -> &'static Resolver {
+    static RESOLVER: OnceLock<Resolver> = OnceLock::new();
+    RESOLVER.get_or_init(|| {
+        Resolver::new(Options {
+            browser_field: true,
+            condition_names: vec![String::from("node"), String::from("import")],
+            extensions: vec![String::from(".js"), String::from(".json"), String::from(".ts")],
+            ..Default::default()
+        })
+    })
+}
+
+fuzz_target!(|input: (u8, u8, &str)| {
+    let (prefix, issuer, rest) = input;
+    let request = format!("{}{rest}", PREFIXES[usize::from(prefix) % PREFIXES.len()]);
+    let issuer = fixtures().join(ISSUERS[usize::from(issuer) % ISSUERS.len()]);
+    if let Ok(ResolveResult::File(resource)) = resolver().resolve(&issuer, &request) {
+        assert!(
+            resource.path.is_file(),
+            "'{request}' is resolved to '{}' which is not a file",
+            resource.path.display()
+        );
+    }
+});
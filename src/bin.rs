@@ -0,0 +1,39 @@
+use crate::{info::NormalizedPath, RResult, Resolver};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+impl Resolver {
+    /// Returns the script of `bin_name` in the `"bin"` field of the package
+    /// `package_name`, which is found by walking the `modules` directories
+    /// from `from_dir`. If the `"bin"` field is a string, its name is the
+    /// package name without the scope.
+    /// Returns `None` if the package, the name or the script is missing.
+    pub fn resolve_bin(
+        &self,
+        package_name: &str,
+        bin_name: &str,
+        from_dir: &Path,
+    ) -> RResult<Option<PathBuf>> {
+        let mut found = None;
+        self.walk_packages(from_dir, package_name, |_, pkg_info| {
+            found = Some(pkg_info.clone());
+            false
+        })?;
+        let Some(pkg_info) = found else {
+            return Ok(None);
+        };
+        let script = match pkg_info.data().raw().get("bin") {
+            Some(Value::String(script)) => {
+                let name = package_name.rsplit('/').next().unwrap_or(package_name);
+                (name == bin_name).then_some(script.as_str())
+            }
+            Some(Value::Object(map)) => map.get(bin_name).and_then(Value::as_str),
+            _ => None,
+        };
+        let Some(script) = script else {
+            return Ok(None);
+        };
+        let path = NormalizedPath::new(pkg_info.dir().join(script));
+        Ok(self.load_entry(&path).is_file().then(|| path.to_path_buf()))
+    }
+}
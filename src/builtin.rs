@@ -0,0 +1,90 @@
+use crate::{Error, Info, ResolveResult, Resolver, State};
+
+/// The builtin modules of Node.js, same as `require("module").builtinModules`.
+const BUILTIN_MODULES: [&str; 54] = [
+    "assert",
+    "assert/strict",
+    "async_hooks",
+    "buffer",
+    "child_process",
+    "cluster",
+    "console",
+    "constants",
+    "crypto",
+    "dgram",
+    "diagnostics_channel",
+    "dns",
+    "dns/promises",
+    "domain",
+    "events",
+    "fs",
+    "fs/promises",
+    "http",
+    "http2",
+    "https",
+    "inspector",
+    "inspector/promises",
+    "module",
+    "net",
+    "os",
+    "path",
+    "path/posix",
+    "path/win32",
+    "perf_hooks",
+    "process",
+    "punycode",
+    "querystring",
+    "readline",
+    "readline/promises",
+    "repl",
+    "stream",
+    "stream/consumers",
+    "stream/promises",
+    "stream/web",
+    "string_decoder",
+    "sys",
+    "timers",
+    "timers/promises",
+    "tls",
+    "trace_events",
+    "tty",
+    "url",
+    "util",
+    "util/types",
+    "v8",
+    "vm",
+    "wasi",
+    "worker_threads",
+    "zlib",
+];
+
+/// The builtin modules which can only be imported with the `node:` scheme.
+const SCHEME_ONLY_MODULES: [&str; 4] = ["sea", "sqlite", "test", "test/reporters"];
+
+pub(crate) const NODE_SCHEME: &str = "node:";
+
+impl Resolver {
+    /// Resolves the builtin module to its name with `node:` scheme, such as
+    /// `node:fs` for `fs`, if `builtin_modules` is enabled.
+    pub(crate) fn resolve_as_builtin(&self, info: Info) -> State {
+        if !self.options.builtin_modules {
+            return State::Resolving(info);
+        }
+        let target = info.request().target();
+        match target.strip_prefix(NODE_SCHEME) {
+            Some(name)
+                if BUILTIN_MODULES.contains(&name) || SCHEME_ONLY_MODULES.contains(&name) =>
+            {
+                State::Success(ResolveResult::Builtin(target.to_string()))
+            }
+            // same as `ERR_UNKNOWN_BUILTIN_MODULE` of Node.js.
+            Some(_) => State::Error(Error::UnexpectedValue(format!(
+                "No such built-in module: {target}"
+            ))),
+            None if BUILTIN_MODULES.contains(&target) => {
+                State::Success(ResolveResult::Builtin(format!("{NODE_SCHEME}{target}")))
+            }
+            None => State::Resolving(info),
+        }
+    }
+}
@@ -1,11 +1,194 @@
 use crate::entry::Entry;
-use crate::fs::CachedFS;
+use crate::fs::{CacheValidation, CachedFS, FileSystemCaseSensitivity, RetryPolicy};
+use crate::normalize::UnicodeNormalization;
+use crate::{ManifestErrorPolicy, Options};
 use rustc_hash::FxHasher;
 use std::{hash::BuildHasherDefault, path::Path, sync::Arc};
 
+/// The concurrent map of the cached entries, see `Cache::with_backend`.
+#[cfg(feature = "concurrent-alt")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CacheBackend {
+    #[default]
+    DashMap,
+    /// The shards of `FxHashMap` guarded by `RwLock`, which could have
+    /// less contention than `DashMap` at high parallelism.
+    ShardedRwLock,
+}
+
+/// File entries keyed by normalized paths, the key shares the path
+/// with the entry.
+#[derive(Debug)]
+pub(crate) enum EntryMap {
+    DashMap(dashmap::DashMap<Arc<Path>, Arc<Entry>, BuildHasherDefault<FxHasher>>),
+    #[cfg(feature = "concurrent-alt")]
+    ShardedRwLock(crate::sharded::ShardedMap<Arc<Path>, Arc<Entry>>),
+}
+
+impl EntryMap {
+    pub(crate) fn get(&self, path: &Path) -> Option<Arc<Entry>> {
+        match self {
+            Self::DashMap(map) => map.get(path).map(|entry| entry.clone()),
+            #[cfg(feature = "concurrent-alt")]
+            Self::ShardedRwLock(map) => map.get(path),
+        }
+    }
+
+    /// Returns the cached entry of `path`, `entry` is inserted if it is
+    /// absent.
+    pub(crate) fn get_or_insert(&self, path: Arc<Path>, entry: Arc<Entry>) -> Arc<Entry> {
+        match self {
+            Self::DashMap(map) => map.entry(path).or_insert(entry).clone(),
+            #[cfg(feature = "concurrent-alt")]
+            Self::ShardedRwLock(map) => map.get_or_insert(path, entry),
+        }
+    }
+
+    pub(crate) fn retain<F: FnMut(&Arc<Path>) -> bool>(&self, mut f: F) {
+        match self {
+            Self::DashMap(map) => map.retain(|path, _| f(path)),
+            #[cfg(feature = "concurrent-alt")]
+            Self::ShardedRwLock(map) => map.retain(|path, _| f(path)),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::DashMap(map) => map.len(),
+            #[cfg(feature = "concurrent-alt")]
+            Self::ShardedRwLock(map) => map.len(),
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        match self {
+            Self::DashMap(map) => map.clear(),
+            #[cfg(feature = "concurrent-alt")]
+            Self::ShardedRwLock(map) => map.clear(),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct Cache {
     pub fs: CachedFS,
-    /// File entries keyed by normalized paths
-    pub entries: dashmap::DashMap<Box<Path>, Arc<Entry>, BuildHasherDefault<FxHasher>>,
+    /// Entries are partitioned by the options which affect the content of
+    /// entry, such as `description_file`, `modules` and `boundaries`, to
+    /// prevent the pollution between the resolvers which shared this cache
+    /// with different options.
+    partitions: dashmap::DashMap<Box<str>, Arc<EntryMap>, BuildHasherDefault<FxHasher>>,
+    #[cfg(feature = "concurrent-alt")]
+    backend: CacheBackend,
+}
+
+impl Cache {
+    /// Creates a cache which validates the cached files by `validation`,
+    /// `Cache::default()` uses `CacheValidation::Mtime`.
+    #[must_use]
+    pub fn with_validation(validation: CacheValidation) -> Self {
+        Self {
+            fs: CachedFS::with_validation(validation),
+            ..Default::default()
+        }
+    }
+
+    /// Matches the names in the paths by `case_sensitivity` rather than
+    /// the underlying file system, such as emulating macOS on Linux in
+    /// tests.
+    #[must_use]
+    pub fn with_case_sensitivity(mut self, case_sensitivity: FileSystemCaseSensitivity) -> Self {
+        self.fs.set_case_sensitivity(case_sensitivity);
+        self
+    }
+
+    /// Normalizes the paths and the requests to `normalization`, so the
+    /// names written in NFC and NFD share the entries and the results.
+    /// Default is `None` on all platforms, the resolved paths are in the
+    /// form if it is set, such as NFC rather than the NFD names on macOS.
+    #[must_use]
+    pub fn with_unicode_normalization(mut self, normalization: UnicodeNormalization) -> Self {
+        self.fs.set_unicode_normalization(normalization);
+        self
+    }
+
+    /// Retries the file system operations failed by the transient errors
+    /// by `retry`, such as the network file systems in CI. The transient
+    /// error left after the retries fails the resolution, rather than the
+    /// file is reported as missing.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.fs.set_retry(retry);
+        self
+    }
+
+    /// Stores the entries in `backend` rather than `DashMap`.
+    #[cfg(feature = "concurrent-alt")]
+    #[must_use]
+    pub fn with_backend(mut self, backend: CacheBackend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub(crate) fn entries(&self, options: &Options) -> Arc<EntryMap> {
+        // the description files are cached in the entries, and the ones
+        // which can't be parsed are replaced by the policy.
+        let json = if options.lenient_json {
+            "lenient"
+        } else {
+            "strict"
+        };
+        let policy = match &options.on_manifest_error {
+            ManifestErrorPolicy::Error => String::from("error"),
+            ManifestErrorPolicy::Ignore => String::from("ignore"),
+            // each warning is reported by its own entries.
+            ManifestErrorPolicy::WarnAndIgnore(warning) => {
+                format!("warn:{:p}", Arc::as_ptr(warning).cast::<()>())
+            }
+        };
+        let key = [options.description_file.as_str(), json, &policy]
+            .into_iter()
+            .chain(options.modules.iter().map(String::as_str))
+            .chain(std::iter::once("\u{1}"))
+            .chain(options.boundaries.iter().filter_map(|dir| dir.to_str()))
+            .collect::<Vec<_>>()
+            .join("\0");
+        if let Some(entries) = self.partitions.get(key.as_str()) {
+            return entries.clone();
+        }
+        self.partitions
+            .entry(key.into())
+            .or_insert_with(|| Arc::new(self.new_entry_map()))
+            .clone()
+    }
+
+    fn new_entry_map(&self) -> EntryMap {
+        #[cfg(feature = "concurrent-alt")]
+        if self.backend == CacheBackend::ShardedRwLock {
+            return EntryMap::ShardedRwLock(Default::default());
+        }
+        EntryMap::DashMap(Default::default())
+    }
+
+    /// The count of the entries in all partitions.
+    pub fn len(&self) -> usize {
+        self.partitions.iter().map(|entries| entries.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes the entries of `dir` and its descendants in all partitions,
+    /// the descendants are removed because they refer to the parent.
+    pub(crate) fn remove_entries(&self, dir: &Path) {
+        self.partitions
+            .iter()
+            .for_each(|entries| entries.retain(|path| !path.starts_with(dir)));
+    }
+
+    /// Clear the entries and the cached files.
+    pub fn clear(&self) {
+        self.partitions.iter().for_each(|entries| entries.clear());
+        self.fs.clear();
+    }
 }
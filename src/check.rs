@@ -0,0 +1,183 @@
+use crate::{
+    info::NormalizedPath,
+    map::{escape_json_pointer, validate_exports_field},
+    Error, ExportsFieldMisuse, ModuleType, RResult, ResolveResult, Resolver,
+};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+const IMPORT_CONDITION: &str = "import";
+const MODULE_CONDITION: &str = "module";
+const REQUIRE_CONDITION: &str = "require";
+
+/// A resolution hazard in the description file, see
+/// [`Resolver::check_package`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// JSON pointer to the offending value, such as
+    /// `/exports/.~1feature/import`.
+    pub pointer: String,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The `"exports"` field is malformed, same as the error reported by
+    /// `strict_exports`.
+    InvalidExports(ExportsFieldMisuse),
+    /// The target of `"exports"` doesn't exist, or the pattern matches
+    /// no file.
+    MissingTarget(String),
+    /// The main field can't be resolved to a file.
+    MissingMain(String),
+    /// The file is matched by `"import"` or `"module"` but it is
+    /// CommonJS, or by `"require"` but it is an ES module.
+    FormatMismatch {
+        condition: String,
+        path: PathBuf,
+        module_type: ModuleType,
+    },
+}
+
+impl Resolver {
+    /// Checks the description file in `pkg_dir` for the hazards of the
+    /// resolution, such as a publint for the resolver: the malformed
+    /// `"exports"`, the missing targets of `"exports"` and the main
+    /// fields, and the format of the files mismatched with the
+    /// conditions. The format of a file is decided by its extension and
+    /// the `"type"` field. Returns an error if there is no description
+    /// file in `pkg_dir`.
+    pub fn check_package(&self, pkg_dir: &Path) -> RResult<Vec<Diagnostic>> {
+        let pkg_dir = NormalizedPath::new(pkg_dir);
+        let pkg_info = self
+            .load_description_file(pkg_dir.as_ref())?
+            .filter(|pkg_info| pkg_info.dir() == &pkg_dir)
+            .ok_or_else(|| {
+                Error::UnexpectedValue(format!(
+                    "No {} in {}",
+                    self.options.description_file,
+                    pkg_dir.as_ref().display()
+                ))
+            })?;
+        let pkg_dir = pkg_info.dir().as_ref();
+        let mut diagnostics = vec![];
+
+        for field in &self.options.main_fields {
+            let Some(main) = pkg_info.data().raw().get(field).and_then(Value::as_str) else {
+                continue;
+            };
+            let request = match main.starts_with("./") {
+                true => main.to_string(),
+                false => format!("./{main}"),
+            };
+            if !matches!(self.resolve(pkg_dir, &request), Ok(ResolveResult::File(_))) {
+                diagnostics.push(Diagnostic {
+                    pointer: format!("/{}", escape_json_pointer(field)),
+                    kind: DiagnosticKind::MissingMain(main.to_string()),
+                });
+            }
+        }
+
+        for field in &self.options.exports_field {
+            let Some(root) = pkg_info.data().get_filed(field).filter(|root| !root.is_null())
+            else {
+                continue;
+            };
+            let pointer = field.iter().fold(String::new(), |pointer, key| {
+                format!("{pointer}/{}", escape_json_pointer(key))
+            });
+            if let Some((pointer, misuse)) = validate_exports_field(root, &pointer) {
+                diagnostics.push(Diagnostic {
+                    pointer,
+                    kind: DiagnosticKind::InvalidExports(misuse),
+                });
+            }
+            self.check_targets(pkg_dir, root, pointer, None, &mut diagnostics);
+            break;
+        }
+        Ok(diagnostics)
+    }
+
+    /// Checks each target in `value`, `condition` is the nearest one of
+    /// `"import"`, `"module"` and `"require"` matching the target.
+    fn check_targets(
+        &self,
+        pkg_dir: &Path,
+        value: &Value,
+        pointer: String,
+        condition: Option<&str>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        match value {
+            Value::String(target) => {
+                if let Some(kind) = self.check_target(pkg_dir, target, condition) {
+                    diagnostics.push(Diagnostic { pointer, kind });
+                }
+            }
+            Value::Array(targets) => {
+                for (index, target) in targets.iter().enumerate() {
+                    let pointer = format!("{pointer}/{index}");
+                    self.check_targets(pkg_dir, target, pointer, condition, diagnostics);
+                }
+            }
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let pointer = format!("{pointer}/{}", escape_json_pointer(key));
+                    let condition = match key.as_str() {
+                        IMPORT_CONDITION | MODULE_CONDITION | REQUIRE_CONDITION => {
+                            Some(key.as_str())
+                        }
+                        // the subpaths.
+                        _ if key.starts_with('.') => None,
+                        _ => condition,
+                    };
+                    self.check_targets(pkg_dir, value, pointer, condition, diagnostics);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_target(
+        &self,
+        pkg_dir: &Path,
+        target: &str,
+        condition: Option<&str>,
+    ) -> Option<DiagnosticKind> {
+        // it is reported as the malformed `"exports"`.
+        let relative = target.strip_prefix("./")?;
+        if let Some((prefix, suffix)) = target.split_once('*') {
+            let matched = self.list_files(pkg_dir, prefix).iter().any(|file| {
+                file.strip_prefix(prefix)
+                    .and_then(|file| file.strip_suffix(suffix))
+                    .map_or(false, |star| !star.is_empty())
+            });
+            return (!matched).then(|| DiagnosticKind::MissingTarget(target.to_string()));
+        }
+        let path = pkg_dir.join(relative);
+        let entry = self.load_entry(&path);
+        // the folder mapping.
+        if target.ends_with('/') {
+            return (!entry.is_dir()).then(|| DiagnosticKind::MissingTarget(target.to_string()));
+        }
+        if !entry.is_file() {
+            return Some(DiagnosticKind::MissingTarget(target.to_string()));
+        }
+        let condition = condition?;
+        let module_type = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("mjs") => ModuleType::Module,
+            Some("cjs") => ModuleType::CommonJs,
+            Some("js") => self.module_type_of(&path).ok()??,
+            _ => return None,
+        };
+        let expected = match condition {
+            REQUIRE_CONDITION => ModuleType::CommonJs,
+            _ => ModuleType::Module,
+        };
+        (module_type != expected).then(|| DiagnosticKind::FormatMismatch {
+            condition: condition.to_string(),
+            path,
+            module_type,
+        })
+    }
+}
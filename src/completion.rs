@@ -0,0 +1,236 @@
+use crate::{
+    description::ExportsForm, info::NormalizedPath, kind::PathKind,
+    resolve::get_module_name_from_request, EnforceExtension, Resolver,
+};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompletionKind {
+    /// A file, its extension is stripped if it is one of `extensions`.
+    File,
+    Directory,
+    /// A package in the `modules` directories, or a scope of packages.
+    Package,
+    /// A subpath in the `"exports"` field of the package, the subpath
+    /// pattern is completed until the `*`.
+    Subpath,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    /// The whole specifier to replace the partial one, such as
+    /// `./components` or `lodash/fp`.
+    pub specifier: String,
+    pub kind: CompletionKind,
+}
+
+/// The rank of a candidate, the lower is the better.
+type Rank = (bool, usize, usize);
+
+impl Resolver {
+    /// Returns the candidates completing the partial `specifier` imported
+    /// by `issuer`, such as `"./comp"` or `"lod"`. The names starting with
+    /// the partial name in the same case are ranked first, then the nearer
+    /// packages and the shorter specifiers. The directories are listed by
+    /// the cache of the resolver.
+    pub fn completion(&self, issuer: &Path, specifier: &str) -> Vec<Completion> {
+        let Some(dir) = issuer.parent() else {
+            return vec![];
+        };
+        let mut candidates = vec![];
+        match Self::parse(specifier).kind() {
+            PathKind::Relative | PathKind::AbsolutePosix | PathKind::AbsoluteWin => {
+                let (prefix, partial) = split_last(specifier);
+                let prefix_dir = if prefix.is_empty() { "." } else { prefix };
+                self.complete_in_dir(&dir.join(prefix_dir), prefix, partial, 0, &mut candidates);
+            }
+            PathKind::Normal => {
+                let name = get_module_name_from_request(specifier);
+                if name == specifier {
+                    self.complete_packages(dir, specifier, &mut candidates);
+                } else {
+                    self.complete_subpaths(
+                        dir,
+                        name,
+                        &specifier[name.len() + 1..],
+                        &mut candidates,
+                    );
+                }
+            }
+            PathKind::Internal | PathKind::BuiltIn | PathKind::Url => {}
+        }
+        candidates.sort_by(|(a, a_rank), (b, b_rank)| {
+            (a_rank, &a.specifier).cmp(&(b_rank, &b.specifier))
+        });
+        let mut seen = std::collections::HashSet::new();
+        candidates
+            .into_iter()
+            .map(|(completion, _)| completion)
+            .filter(|completion| seen.insert(completion.specifier.clone()))
+            .collect()
+    }
+
+    /// Lists the names of `dir`, `None` if it is not a directory.
+    pub(crate) fn list_dir(&self, dir: &Path) -> Option<std::sync::Arc<Vec<String>>> {
+        let dir = NormalizedPath::new(dir);
+        let entry = self.load_entry(dir.as_ref());
+        if !entry.is_dir() {
+            return None;
+        }
+        self.cache
+            .fs
+            .read_dir(dir.as_ref(), entry.cached_stat())
+            .ok()
+    }
+
+    fn complete_in_dir(
+        &self,
+        dir: &Path,
+        prefix: &str,
+        partial: &str,
+        order: usize,
+        candidates: &mut Vec<(Completion, Rank)>,
+    ) {
+        let Some(names) = self.list_dir(dir) else {
+            return;
+        };
+        let strip = !matches!(self.options.enforce_extension, EnforceExtension::Enabled);
+        for name in names.iter() {
+            let Some(mismatch) = starts_with(name, partial) else {
+                continue;
+            };
+            let (name, kind) = if self.load_entry(&dir.join(name)).is_dir() {
+                (name.as_str(), CompletionKind::Directory)
+            } else {
+                let stem = self
+                    .options
+                    .extensions
+                    .iter()
+                    .filter(|ext| strip && !ext.is_empty())
+                    .find_map(|ext| name.strip_suffix(ext.as_str()))
+                    .filter(|stem| !stem.is_empty());
+                (stem.unwrap_or(name), CompletionKind::File)
+            };
+            let specifier = format!("{prefix}{name}");
+            let rank = (mismatch, order, specifier.len());
+            candidates.push((Completion { specifier, kind }, rank));
+        }
+    }
+
+    /// The packages in the `modules` directories walked from `dir`, the
+    /// nearer ones are ranked first.
+    fn complete_packages(
+        &self,
+        dir: &Path,
+        partial: &str,
+        candidates: &mut Vec<(Completion, Rank)>,
+    ) {
+        let (scope, partial) = match partial.split_once('/') {
+            Some((scope, partial)) => (Some(scope), partial),
+            None => (None, partial),
+        };
+        let modules_dirs = dir
+            .ancestors()
+            .flat_map(|dir| {
+                self.options
+                    .modules
+                    .iter()
+                    .map(move |module| dir.join(module))
+            })
+            .chain(self.options.global_paths.iter().cloned());
+        for (order, modules_dir) in modules_dirs.enumerate() {
+            let (dir, prefix) = match scope {
+                Some(scope) => (modules_dir.join(scope), format!("{scope}/")),
+                None => (modules_dir, String::new()),
+            };
+            let Some(names) = self.list_dir(&dir) else {
+                continue;
+            };
+            for name in names.iter() {
+                // such as `.bin` and `.pnpm`.
+                if name.starts_with('.') {
+                    continue;
+                }
+                let Some(mismatch) = starts_with(name, partial) else {
+                    continue;
+                };
+                if !self.load_entry(&dir.join(name)).is_dir() {
+                    continue;
+                }
+                let specifier = format!("{prefix}{name}");
+                let rank = (mismatch, order, specifier.len());
+                let kind = CompletionKind::Package;
+                candidates.push((Completion { specifier, kind }, rank));
+            }
+        }
+    }
+
+    /// The subpaths of the package `name`, they are the keys of the
+    /// `"exports"` field if it exists, otherwise the files in the package.
+    fn complete_subpaths(
+        &self,
+        dir: &Path,
+        name: &str,
+        partial: &str,
+        candidates: &mut Vec<(Completion, Rank)>,
+    ) {
+        let mut found = None;
+        let _ = self.walk_packages(dir, name, |entry, pkg_info| {
+            found = Some((entry.path().to_path_buf(), pkg_info.clone()));
+            false
+        });
+        let Some((pkg_dir, pkg_info)) = found else {
+            return;
+        };
+        let exports = pkg_info.data().exports();
+        match exports.map(ExportsForm::of) {
+            Some(ExportsForm::Subpaths) => {
+                let keys = exports.and_then(|exports| exports.as_object());
+                for key in keys.into_iter().flat_map(|keys| keys.keys()) {
+                    let Some(subpath) = key.strip_prefix("./") else {
+                        continue;
+                    };
+                    let subpath = subpath
+                        .split_once('*')
+                        .map_or(subpath, |(prefix, _)| prefix);
+                    let Some(mismatch) = starts_with(subpath, partial) else {
+                        continue;
+                    };
+                    let specifier = format!("{name}/{subpath}");
+                    let rank = (mismatch, 0, specifier.len());
+                    let kind = CompletionKind::Subpath;
+                    candidates.push((Completion { specifier, kind }, rank));
+                }
+            }
+            Some(ExportsForm::Null) | None => {
+                let (prefix, partial) = split_last(partial);
+                let prefix = format!("{name}/{prefix}");
+                let dir = pkg_dir.join(&prefix[name.len() + 1..]);
+                self.complete_in_dir(&dir, &prefix, partial, 0, candidates);
+            }
+            // the subpaths are not exported.
+            Some(_) => {}
+        }
+    }
+}
+
+/// Splits `specifier` after the last separator.
+fn split_last(specifier: &str) -> (&str, &str) {
+    match specifier.rfind(['/', '\\']) {
+        Some(index) => (&specifier[..=index], &specifier[index + 1..]),
+        None => ("", specifier),
+    }
+}
+
+/// Returns whether the case is mismatched if `name` starts with `partial`
+/// ignoring the case, the hidden names are only matched by a leading `.`.
+fn starts_with(name: &str, partial: &str) -> Option<bool> {
+    if name.starts_with('.') && !partial.starts_with('.') {
+        return None;
+    }
+    if name.starts_with(partial) {
+        return Some(false);
+    }
+    let lower = name.to_lowercase();
+    lower.starts_with(&partial.to_lowercase()).then_some(true)
+}
@@ -1,8 +1,35 @@
+use crate::{info::NormalizedPath, parse::Request, Attempt, AttemptPhase, ModuleType};
+
 #[derive(Debug)]
 pub struct Context {
     pub depth: Depth,
     pub fully_specified: Bool,
     pub resolve_to_context: Bool,
+    /// The alias keys which are being resolved, used to detect the cycle.
+    pub alias_chain: Vec<String>,
+    /// The module type of the issuer, only set when `module_type_defaults`
+    /// is enabled.
+    pub module_type: Option<ModuleType>,
+    /// The locations which had been attempted, only recorded when it is
+    /// `Some`, used to explain the failed resolution.
+    pub attempts: Option<Vec<Attempt>>,
+    /// The depth of `_resolve` which is called by the walk of ancestors
+    /// in `resolve_as_modules`, it should not walk again.
+    pub modules_walk_depth: Option<u16>,
+    /// The `(dir, request, fully_specified)` which are being resolved by
+    /// `_resolve`, used to detect the cycle across alias, exports and
+    /// main fields.
+    pub resolving: Vec<(NormalizedPath, Request, bool)>,
+    /// The times of the request being redirected, used to check the
+    /// `max_redirects`.
+    pub redirects: u16,
+    /// The extensions passed to `Resolver::resolve_with_extensions`, which
+    /// replace `extensions` of the options.
+    pub extensions: Option<Vec<String>>,
+    /// The chain of the symlinks followed by the last `SymlinkPlugin`,
+    /// from the original path to the real path, empty if it is not a
+    /// symlink.
+    pub symlinks: Vec<std::path::PathBuf>,
 }
 
 impl Context {
@@ -11,6 +38,26 @@ impl Context {
             depth: Depth::new(),
             fully_specified: Bool(fully_specified),
             resolve_to_context: Bool(resolve_to_context),
+            alias_chain: vec![],
+            module_type: None,
+            attempts: None,
+            modules_walk_depth: None,
+            resolving: vec![],
+            redirects: 0,
+            extensions: None,
+            symlinks: vec![],
+        }
+    }
+
+    pub fn record<F: FnOnce() -> String>(&mut self, phase: AttemptPhase, detail: F) {
+        if let Some(attempts) = self.attempts.as_mut() {
+            let detail = detail();
+            if !attempts
+                .iter()
+                .any(|attempt| attempt.phase == phase && attempt.detail == detail)
+            {
+                attempts.push(Attempt { phase, detail });
+            }
         }
     }
 }
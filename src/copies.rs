@@ -0,0 +1,87 @@
+use crate::{
+    description::DescriptionData, entry::Entry, info::NormalizedPath, kind::PathKind,
+    resolve::get_module_name_from_request, RResult, Resolver,
+};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// A physical copy of package found in the modules directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageCopy {
+    /// The directory of package, it is the real path if it is a symlink.
+    pub dir: PathBuf,
+    /// The `version` in the description file.
+    pub version: Option<String>,
+}
+
+impl Resolver {
+    /// Returns every distinct copy of the package of `request` which is
+    /// reachable from `path`, rather than the first one which `resolve`
+    /// uses. They are in the order of the walk of `modules` directories,
+    /// then `global_paths`. It is useful to detect the duplicated packages.
+    pub fn find_package_copies(&self, path: &Path, request: &str) -> RResult<Vec<PackageCopy>> {
+        let request = Self::parse(request);
+        if request.kind() != PathKind::Normal {
+            return Ok(vec![]);
+        }
+        let name = get_module_name_from_request(request.target());
+        let mut seen = HashSet::new();
+        let mut copies = vec![];
+        self.walk_packages(path, name, |entry, pkg_info| {
+            let dir = entry
+                .symlink()
+                .as_deref()
+                .unwrap_or(entry.path())
+                .to_path_buf();
+            if seen.insert(dir.clone()) {
+                copies.push(PackageCopy {
+                    dir,
+                    version: pkg_info.data().version().map(String::from),
+                });
+            }
+            true
+        })?;
+        Ok(copies)
+    }
+
+    /// Calls `f` with the packages named `name` in the order of the walk
+    /// of `modules` directories from `path`, which is same as the one of
+    /// `resolve`, then `global_paths`, until `f` returns `false`.
+    pub(crate) fn walk_packages<F: FnMut(&Entry, &Arc<DescriptionData>) -> bool>(
+        &self,
+        path: &Path,
+        name: &str,
+        mut f: F,
+    ) -> RResult<()> {
+        let path = NormalizedPath::new(path);
+        let modules_dirs = self
+            .modules_walk(path.as_ref())
+            .flat_map(|dir| {
+                self.options
+                    .modules
+                    .iter()
+                    .map(move |module| dir.join(module))
+            })
+            .chain(self.options.global_paths.iter().cloned());
+        for modules_dir in modules_dirs {
+            let entry = self.load_entry(&modules_dir.join(name));
+            if !entry.is_dir() {
+                continue;
+            }
+            let Some(pkg_info) = entry.pkg_info(self)? else {
+                continue;
+            };
+            // the description file belongs to an ancestor.
+            if pkg_info.dir().as_ref() != entry.path() {
+                continue;
+            }
+            if !f(&entry, pkg_info) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::Resolver;
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+thread_local! {
+    /// The paths of the entries loaded by the resolution in progress on
+    /// this thread, `None` if it is not recorded.
+    static RECORDING: RefCell<Option<Vec<Arc<Path>>>> = RefCell::new(None);
+}
+
+/// Records `path` if a resolution on this thread is being recorded.
+pub(crate) fn record(path: &Arc<Path>) {
+    RECORDING.with(|recording| {
+        if let Some(paths) = recording.borrow_mut().as_mut() {
+            paths.push(path.clone());
+        }
+    });
+}
+
+/// Calls `f` and returns the paths of the entries loaded by it, the
+/// outer recording also receives them.
+pub(crate) fn recording<T>(f: impl FnOnce() -> T) -> (T, Vec<Arc<Path>>) {
+    let outer = RECORDING.with(|recording| recording.borrow_mut().replace(vec![]));
+    let result = f();
+    let mut paths = RECORDING.with(|recording| {
+        let mut recording = recording.borrow_mut();
+        let paths = recording.take().unwrap_or_default();
+        if let Some(mut outer) = outer {
+            outer.extend(paths.iter().cloned());
+            *recording = Some(outer);
+        }
+        paths
+    });
+    paths.sort_unstable();
+    paths.dedup();
+    (result, paths)
+}
+
+impl Resolver {
+    /// Returns the `(path, request)` passed to `resolve` whose memoized
+    /// results may be changed by `changed_paths`, which are reported by a
+    /// file watcher, so only these edges need to be resolved again. It
+    /// only works with `track_dependencies`, and the dependencies are kept
+    /// after the events until they are resolved again.
+    ///
+    /// A result depends on the paths looked up by it, the description
+    /// files of their ancestors, and the other JSON files such as
+    /// tsconfig, which affect every result.
+    #[must_use]
+    pub fn affected_resolutions(&self, changed_paths: &[PathBuf]) -> Vec<(PathBuf, String)> {
+        let is_config = |path: &PathBuf| {
+            !path.ends_with(&self.options.description_file)
+                && path.extension().map_or(false, |ext| ext == "json")
+        };
+        let changed_config = changed_paths.iter().any(is_config);
+        let is_affected = |dependencies: &[Arc<Path>]| {
+            changed_config
+                || changed_paths.iter().any(|changed| {
+                    let scope = changed
+                        .ends_with(&self.options.description_file)
+                        .then(|| changed.parent())
+                        .flatten();
+                    dependencies.iter().any(|dependency| {
+                        dependency.starts_with(changed)
+                            || scope.map_or(false, |scope| dependency.starts_with(scope))
+                    })
+                })
+        };
+        let mut affected: Vec<(PathBuf, String)> = self
+            .dependencies
+            .iter()
+            .filter(|item| is_affected(item.value()))
+            .map(|item| (item.key().0.clone(), item.key().1.clone()))
+            .collect();
+        for (_, resolver) in &self.roots {
+            affected.extend(resolver.affected_resolutions(changed_paths));
+        }
+        affected.sort();
+        affected.dedup();
+        affected
+    }
+}
@@ -1,9 +1,56 @@
 use crate::info::NormalizedPath;
-use crate::{AliasMap, Error, RResult};
+use crate::log::enter_span;
+use crate::{AliasMap, Error, ModuleType, RResult};
 use once_cell::sync::OnceCell;
 use std::path::Path;
 use std::sync::Arc;
 
+/// The form of the `"exports"` field, the sugar forms are the shorthand of
+/// the main subpath `"."`.
+/// reference: https://nodejs.org/api/packages.html#exports-sugar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportsForm {
+    /// `null`, which is the same as the absent field, the main fields
+    /// are used.
+    Null,
+    /// A target or an array of the fallbacks, such as `"./index.js"`,
+    /// which is the sugar of `{ ".": "./index.js" }`.
+    Target,
+    /// The conditions without subpaths, such as
+    /// `{ "import": "./index.mjs" }`, which is the sugar of
+    /// `{ ".": { "import": "./index.mjs" } }`.
+    Conditions,
+    /// The subpaths starting with `.`, such as
+    /// `{ ".": "./index.js", "./a": "./a.js" }`, the empty object exports
+    /// nothing.
+    Subpaths,
+    /// The subpaths mixed with the conditions, which is invalid.
+    Mixed,
+    /// The other values, such as `false`, which export nothing.
+    Invalid,
+}
+
+impl ExportsForm {
+    #[must_use]
+    pub fn of(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Self::Null,
+            serde_json::Value::String(_) | serde_json::Value::Array(_) => Self::Target,
+            serde_json::Value::Object(map) => {
+                let subpaths = map.keys().filter(|key| key.starts_with('.')).count();
+                if subpaths == map.len() {
+                    Self::Subpaths
+                } else if subpaths == 0 {
+                    Self::Conditions
+                } else {
+                    Self::Mixed
+                }
+            }
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => Self::Invalid,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct PkgJSON {
     name: Option<Box<str>>,
@@ -12,12 +59,23 @@ pub struct PkgJSON {
 }
 
 impl PkgJSON {
-    pub(crate) fn parse(content: &str, file_path: &Path) -> RResult<Self> {
-        let json: serde_json::Value =
-            tracing::debug_span!("serde_json_from_str").in_scope(|| {
-                serde_json::from_str(content)
-                    .map_err(|error| Error::UnexpectedJson((file_path.into(), error)))
-            })?;
+    /// Parse the content of description file, the comments and trailing
+    /// commas are tolerated if `lenient` is true.
+    pub(crate) fn parse(content: &str, file_path: &Path, lenient: bool) -> RResult<Self> {
+        let json: serde_json::Value = {
+            enter_span!("serde_json_from_str", path = %file_path.display());
+            serde_json::from_str(content).or_else(|error| {
+                let lenient_json = if lenient {
+                    jsonc_parser::parse_to_serde_value(content, &Default::default())
+                        .ok()
+                        .flatten()
+                } else {
+                    None
+                };
+                // report the error of strict parsing, which has the precise position.
+                lenient_json.ok_or_else(|| Error::UnexpectedJson((file_path.into(), error)))
+            })?
+        };
 
         let name = json.get("name").and_then(|v| v.as_str()).map(|s| s.into());
 
@@ -70,6 +128,35 @@ impl PkgJSON {
         self.name.as_deref()
     }
 
+    pub fn version(&self) -> Option<&str> {
+        self.raw.get("version").and_then(|value| value.as_str())
+    }
+
+    pub fn exports(&self) -> Option<&serde_json::Value> {
+        self.raw.get("exports")
+    }
+
+    /// The form of the `"exports"` field, `None` if it is absent.
+    pub fn exports_form(&self) -> Option<ExportsForm> {
+        self.exports().map(ExportsForm::of)
+    }
+
+    pub fn imports(&self) -> Option<&serde_json::Value> {
+        self.raw.get("imports")
+    }
+
+    /// The `"sideEffects"` field, which could be a boolean or a list of globs.
+    pub fn side_effects(&self) -> Option<&serde_json::Value> {
+        self.raw.get("sideEffects")
+    }
+
+    pub fn module_type(&self) -> ModuleType {
+        match self.raw.get("type").and_then(|value| value.as_str()) {
+            Some("module") => ModuleType::Module,
+            _ => ModuleType::CommonJs,
+        }
+    }
+
     pub fn raw(&self) -> &Arc<serde_json::Value> {
         &self.raw
     }
@@ -99,3 +186,43 @@ impl DescriptionData {
         &self.json
     }
 }
+
+#[test]
+fn exports_form_test() {
+    use serde_json::json;
+    let form = |value: serde_json::Value| ExportsForm::of(&value);
+    assert_eq!(form(json!(null)), ExportsForm::Null);
+    assert_eq!(form(json!("./index.js")), ExportsForm::Target);
+    assert_eq!(form(json!(["./a.js", "./b.js"])), ExportsForm::Target);
+    assert_eq!(
+        form(json!({ "import": "./index.mjs", "default": "./index.js" })),
+        ExportsForm::Conditions
+    );
+    assert_eq!(
+        form(json!({ "node": { "require": "./index.js" } })),
+        ExportsForm::Conditions
+    );
+    assert_eq!(form(json!({ ".": "./index.js" })), ExportsForm::Subpaths);
+    assert_eq!(
+        form(json!({ ".": { "import": "./index.mjs" }, "./a": "./a.js" })),
+        ExportsForm::Subpaths
+    );
+    assert_eq!(form(json!({})), ExportsForm::Subpaths);
+    assert_eq!(
+        form(json!({ "./a": "./a.js", "import": "./index.mjs" })),
+        ExportsForm::Mixed
+    );
+    assert_eq!(form(json!(false)), ExportsForm::Invalid);
+    assert_eq!(form(json!(1)), ExportsForm::Invalid);
+
+    let pkg = |content: &str| PkgJSON::parse(content, Path::new("package.json"), false).unwrap();
+    assert_eq!(pkg(r#"{ "main": "./index.js" }"#).exports_form(), None);
+    assert_eq!(
+        pkg(r#"{ "exports": null }"#).exports_form(),
+        Some(ExportsForm::Null)
+    );
+    assert_eq!(
+        pkg(r#"{ "exports": { "require": "./index.js" } }"#).exports_form(),
+        Some(ExportsForm::Conditions)
+    );
+}
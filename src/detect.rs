@@ -0,0 +1,149 @@
+//! The detection of the layout of `node_modules` by the files of the
+//! package managers in the project root, see `Options::detect_layout`.
+
+use crate::{Error, Options, RResult};
+use std::path::{Path, PathBuf};
+
+/// The lockfiles of the package managers which hoist the packages.
+const HOISTED_LOCKFILES: [&str; 5] = [
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "bun.lockb",
+    "bun.lock",
+];
+
+/// The package managers in `"packageManager"` and `"engines"`.
+const PACKAGE_MANAGERS: [&str; 4] = ["pnpm", "yarn", "npm", "bun"];
+
+/// How the packages are installed by the package manager.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum InstallLayout {
+    /// Plug'n'Play of yarn, the packages are not in `node_modules`, which
+    /// is not supported by the resolver.
+    Pnp,
+    /// The symlinks to the virtual store `node_modules/.pnpm` of pnpm.
+    PnpmStore,
+    /// The packages hoisted to `node_modules` by npm, yarn classic, bun or
+    /// pnpm with `node-linker=hoisted`.
+    Hoisted,
+}
+
+/// The project detected by [`ProjectLayout::detect`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectLayout {
+    /// The directory of the lockfile, or the description file with the
+    /// hint of the package manager.
+    pub root: PathBuf,
+    pub layout: InstallLayout,
+    /// The package manager declared by `"packageManager"` or `"engines"`
+    /// in the description file of `root`, such as `"pnpm"`.
+    pub package_manager: Option<String>,
+    /// Whether `root` is the root of the workspaces, declared by
+    /// `"workspaces"` or `pnpm-workspace.yaml`.
+    pub workspaces: bool,
+}
+
+impl ProjectLayout {
+    /// Detects the project from `dir` and its ancestors, the nearest
+    /// directory with `.pnp.cjs` or a lockfile is the root, otherwise the
+    /// nearest one with the hint of the package manager in package.json,
+    /// so the hint of a nested package doesn't hide the lockfile of the
+    /// project. The layout is decided in the order of Plug'n'Play, the
+    /// lockfile, then the hint, and pnpm is trusted only if its virtual
+    /// store exists. Returns `None` if there is no such directory.
+    #[must_use]
+    pub fn detect(dir: &Path) -> Option<Self> {
+        dir.ancestors()
+            .find_map(|dir| Self::detect_in(dir, false))
+            .or_else(|| dir.ancestors().find_map(|dir| Self::detect_in(dir, true)))
+    }
+
+    /// Detects `dir` as the root, by the hint only if `hinted`.
+    fn detect_in(dir: &Path, hinted: bool) -> Option<Self> {
+        let exists = |name: &str| dir.join(name).is_file();
+        let manifest = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok());
+        let package_manager = manifest.as_ref().and_then(package_manager_of);
+        let pnpm_store = || dir.join("node_modules").join(".pnpm").is_dir();
+        let layout = if exists(".pnp.cjs") || exists(".pnp.js") {
+            InstallLayout::Pnp
+        } else if exists("pnpm-lock.yaml") {
+            match pnpm_store() {
+                true => InstallLayout::PnpmStore,
+                false => InstallLayout::Hoisted,
+            }
+        } else if HOISTED_LOCKFILES.into_iter().any(exists) {
+            InstallLayout::Hoisted
+        } else if hinted {
+            match package_manager.as_deref()? {
+                "pnpm" if pnpm_store() => InstallLayout::PnpmStore,
+                _ => InstallLayout::Hoisted,
+            }
+        } else {
+            return None;
+        };
+        let workspaces = exists("pnpm-workspace.yaml")
+            || manifest
+                .as_ref()
+                .map_or(false, |manifest| manifest.get("workspaces").is_some());
+        Some(Self {
+            root: dir.to_path_buf(),
+            layout,
+            package_manager,
+            workspaces,
+        })
+    }
+
+    /// Configures `options` for the layout, the virtual store of pnpm is
+    /// read directly by `pnpm_store`. Returns an error for Plug'n'Play.
+    pub fn apply(&self, options: Options) -> RResult<Options> {
+        match self.layout {
+            InstallLayout::Pnp => Err(Error::UnexpectedValue(format!(
+                "Plug'n'Play is not supported, found in '{}'",
+                self.root.display()
+            ))),
+            InstallLayout::PnpmStore => Ok(Options {
+                symlinks: true,
+                pnpm_store: true,
+                ..options
+            }),
+            InstallLayout::Hoisted => Ok(Options {
+                pnpm_store: false,
+                ..options
+            }),
+        }
+    }
+}
+
+/// The name of the package manager in `"packageManager"`, such as
+/// `"pnpm@8.6.0"`, or the one in `"engines"`.
+fn package_manager_of(manifest: &serde_json::Value) -> Option<String> {
+    let declared = manifest
+        .get("packageManager")
+        .and_then(|value| value.as_str())
+        .and_then(|value| value.split('@').next());
+    let engines = manifest.get("engines").and_then(|value| value.as_object());
+    let hinted = || {
+        PACKAGE_MANAGERS
+            .into_iter()
+            .find(|name| engines.map_or(false, |engines| engines.contains_key(*name)))
+    };
+    declared
+        .filter(|name| PACKAGE_MANAGERS.contains(name))
+        .or_else(hinted)
+        .map(String::from)
+}
+
+impl Options {
+    /// Configures the options by the layout of the project detected from
+    /// `dir`, see [`ProjectLayout::detect`]. The options are unchanged if
+    /// nothing is detected. Returns an error for Plug'n'Play.
+    pub fn detect_layout(self, dir: &Path) -> RResult<Self> {
+        match ProjectLayout::detect(dir) {
+            Some(project) => project.apply(self),
+            None => Ok(self),
+        }
+    }
+}
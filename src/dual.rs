@@ -0,0 +1,75 @@
+use crate::{
+    module_type::insert_condition, Cache, Options, RResult, ResolveResult, Resolver, Resource,
+};
+use std::{path::Path, sync::Arc};
+
+const BROWSER_CONDITION: &str = "browser";
+
+/// The results of resolving one request for both node and browser target.
+#[derive(Debug)]
+pub struct ResolvedPair {
+    pub node: RResult<ResolveResult<Resource>>,
+    pub browser: RResult<ResolveResult<Resource>>,
+}
+
+/// A pair of resolvers which are derived from the same options and
+/// shared the same cache, the only difference is whether `"browser"`
+/// filed and condition are enabled.
+///
+/// It is not a single pass, `resolve` runs two full resolutions, one for
+/// each target, so it costs about twice as much as `Resolver::resolve`.
+/// Only the work of the file system is shared by the cache, the stats and
+/// the parsed description files are loaded once, but the walk of the
+/// directories and the fields are done by each resolution.
+#[derive(Debug)]
+pub struct DualResolver {
+    node: Resolver,
+    browser: Resolver,
+}
+
+impl DualResolver {
+    #[must_use]
+    pub fn new(options: Options) -> Self {
+        let cache = options
+            .external_cache
+            .clone()
+            .unwrap_or_else(|| Arc::new(Cache::default()));
+
+        let mut node_condition_names = options.condition_names.clone();
+        node_condition_names.retain(|name| name != BROWSER_CONDITION);
+        let node = Resolver::new(Options {
+            browser_field: false,
+            condition_names: node_condition_names,
+            external_cache: Some(cache.clone()),
+            ..options.clone()
+        });
+
+        let mut browser_condition_names = options.condition_names.clone();
+        insert_condition(&mut browser_condition_names, BROWSER_CONDITION);
+        let browser = Resolver::new(Options {
+            browser_field: true,
+            condition_names: browser_condition_names,
+            external_cache: Some(cache),
+            ..options
+        });
+
+        Self { node, browser }
+    }
+
+    pub fn node(&self) -> &Resolver {
+        &self.node
+    }
+
+    pub fn browser(&self) -> &Resolver {
+        &self.browser
+    }
+
+    /// Resolves `request` by the node resolver, then by the browser one,
+    /// see the cost in [`DualResolver`].
+    pub fn resolve(&self, path: &Path, request: &str) -> ResolvedPair {
+        ResolvedPair {
+            node: self.node.resolve(path, request),
+            browser: self.browser.resolve(path, request),
+        }
+    }
+}
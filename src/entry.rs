@@ -7,7 +7,13 @@ use std::{
     time::SystemTime,
 };
 
-use crate::{description::DescriptionData, Error, RResult, Resolver};
+use crate::{
+    dependencies,
+    description::DescriptionData,
+    fs::{is_transient, long_path, FileSystemCaseSensitivity, RetryPolicy},
+    normalize::UnicodeNormalization,
+    Error, ManifestErrorPolicy, RResult, Resolver,
+};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct EntryStat {
@@ -36,14 +42,32 @@ impl EntryStat {
         self.modified
     }
 
-    fn stat(path: &Path) -> Self {
-        if let Ok(meta) = path.metadata() {
-            // This field might not be available on all platforms,
-            // and will return an Err on platforms where it is not available.
-            let modified = meta.modified().ok();
-            Self::new(Some(meta.file_type()), modified)
-        } else {
-            Self::new(None, None)
+    pub fn is_file(&self) -> bool {
+        self.file_type.map_or(false, |ft| ft.is_file())
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.file_type.map_or(false, |ft| ft.is_dir())
+    }
+
+    /// Returns the error only if the file is unknown rather than missing,
+    /// such as the transient error left after the retries or
+    /// `Error::PathTooLong`, the other errors mean the file doesn't exist.
+    fn stat(
+        path: &Path,
+        case_sensitivity: FileSystemCaseSensitivity,
+        retry: RetryPolicy,
+    ) -> RResult<Self> {
+        let path = long_path(path)?;
+        match retry.run(|| case_sensitivity.metadata(&path)) {
+            Ok(meta) => {
+                // This field might not be available on all platforms,
+                // and will return an Err on platforms where it is not available.
+                let modified = meta.modified().ok();
+                Ok(Self::new(Some(meta.file_type()), modified))
+            }
+            Err(error) if is_transient(&error) => Err(Error::Io(error)),
+            Err(_) => Ok(Self::new(None, None)),
         }
     }
 }
@@ -51,15 +75,26 @@ impl EntryStat {
 #[derive(Debug)]
 pub struct Entry {
     parent: Option<Arc<Entry>>,
-    path: Box<Path>,
+    path: Arc<Path>,
     // None: package.json does not exist
     pkg_info: OnceCell<Option<Arc<DescriptionData>>>,
     stat: OnceCell<EntryStat>,
+    /// Whether the relative `modules` directories exist under `self.path`,
+    /// in the order of `options.modules`.
+    modules: OnceCell<Box<[bool]>>,
     /// None represent the `self.path` is not a symlink
     symlink: OnceCell<Option<Box<Path>>>,
     /// If `self.path` is a symlink, then return canonicalized path,
     /// else return `self.path`
     real: OnceCell<Box<Path>>,
+    /// The `case_sensitivity` of the file system where the entry is stat.
+    case_sensitivity: FileSystemCaseSensitivity,
+
+    /// The `retry` of the file system operations on the entry.
+    retry: RetryPolicy,
+
+    /// The form of the canonicalized path of the symlink.
+    unicode_normalization: UnicodeNormalization,
 }
 
 impl Entry {
@@ -82,49 +117,99 @@ impl Entry {
                 } else {
                     Cow::Owned(path.join(pkg_name))
                 };
-                match resolver
-                    .cache
-                    .fs
-                    .read_description_file(&pkg_path, EntryStat::default())
-                {
+                match resolver.cache.fs.read_description_file(
+                    &pkg_path,
+                    EntryStat::default(),
+                    resolver.options.lenient_json,
+                ) {
                     Ok(info) => {
                         return Ok(Some(info));
                     }
                     Err(error @ (Error::UnexpectedJson(_) | Error::UnexpectedValue(_))) => {
-                        // Return bad json
-                        return Err(error);
+                        // bad json
+                        match &resolver.options.on_manifest_error {
+                            ManifestErrorPolicy::Error => return Err(error),
+                            ManifestErrorPolicy::Ignore => {}
+                            ManifestErrorPolicy::WarnAndIgnore(warning) => {
+                                warning.warn(&pkg_path, &error);
+                            }
+                        }
+                    }
+                    Err(Error::Io(error)) if is_transient(&error) => {
+                        return Err(Error::Io(error));
                     }
                     Err(Error::Io(_)) => {
                         // package.json not found
                     }
-                    _ => unreachable!(),
+                    // such as `Error::PathTooLong` on Windows.
+                    Err(error) => return Err(error),
                 };
             }
-            if let Some(parent) = &self.parent() {
-                return parent.pkg_info(resolver).cloned();
+            match &self.parent() {
+                Some(parent) if !resolver.is_boundary(path) => {
+                    return parent.pkg_info(resolver).cloned();
+                }
+                _ => {}
             }
             Ok(None)
         })
     }
 
     pub fn is_file(&self) -> bool {
-        self.cached_stat()
-            .file_type()
-            .map_or(false, |ft| ft.is_file())
+        self.cached_stat().is_file()
     }
 
     pub fn is_dir(&self) -> bool {
-        self.cached_stat()
-            .file_type()
-            .map_or(false, |ft| ft.is_dir())
+        self.cached_stat().is_dir()
     }
 
     pub fn exists(&self) -> bool {
         self.cached_stat().file_type().is_some()
     }
 
+    /// Returns the stat, the failure of an unknown file is returned to
+    /// fail the resolution rather than cached, see `EntryStat::stat`.
+    pub fn try_stat(&self) -> RResult<EntryStat> {
+        self.stat
+            .get_or_try_init(|| EntryStat::stat(&self.path, self.case_sensitivity, self.retry))
+            .copied()
+    }
+
+    /// Same as `try_stat`, but the unknown file is reported as missing.
     pub fn cached_stat(&self) -> EntryStat {
-        *self.stat.get_or_init(|| EntryStat::stat(&self.path))
+        self.try_stat().unwrap_or_default()
+    }
+
+    /// Whether `self.path` has the `modules[index]` directory, it is
+    /// computed once for all the relative `modules`, so the walk of
+    /// ancestors from the same subtree does not stat them again.
+    pub fn has_modules_dir(&self, resolver: &Resolver, index: usize) -> bool {
+        let modules = self.modules.get_or_init(|| {
+            resolver
+                .options
+                .modules
+                .iter()
+                .map(|module| {
+                    !Path::new(module).is_absolute()
+                        && EntryStat::stat(
+                            &self.path.join(module),
+                            self.case_sensitivity,
+                            self.retry,
+                        )
+                        // look it up by the later stat if it is unknown.
+                        .map_or(true, |stat| stat.is_dir())
+                })
+                .collect()
+        });
+        let exists = modules.get(index).copied().unwrap_or_default();
+        if !exists {
+            // the missing directory is a dependency of the failed lookups,
+            // the flag itself is cached without loading its entry.
+            if let Some(module) = resolver.options.modules.get(index) {
+                dependencies::record(&Arc::from(self.path.join(module)));
+            }
+        }
+        exists
     }
 
     pub fn real(&self) -> Option<&Path> {
@@ -135,16 +220,23 @@ impl Entry {
         self.real.get_or_init(|| path);
     }
 
+    pub fn init_pkg_info(&self, pkg_info: Arc<DescriptionData>) {
+        self.pkg_info.get_or_init(|| Some(pkg_info));
+    }
+
     /// Returns the canonicalized path of `self.path` if it is a symlink.
     /// Returns None if `self.path` is not a symlink.
     pub fn symlink(&self) -> &Option<Box<Path>> {
         self.symlink.get_or_init(|| {
             debug_assert!(self.path.is_absolute());
-            if self.path.read_link().is_err() {
+            let path = long_path(&self.path).ok()?;
+            if path.read_link().is_err() {
                 return None;
             }
-            match dunce::canonicalize(&self.path) {
-                Ok(symlink_path) => Some(Box::from(symlink_path)),
+            match dunce::canonicalize(&path) {
+                Ok(symlink_path) => Some(Box::from(
+                    self.unicode_normalization.normalize_path(&symlink_path),
+                )),
                 Err(_) => None,
             }
         })
@@ -153,21 +245,26 @@ impl Entry {
 
 impl Resolver {
     pub(super) fn load_entry(&self, path: &Path) -> Arc<Entry> {
-        if let Some(cached) = self.cache.entries.get(path) {
-            cached.clone()
+        let entry = self.load_entry_unrecorded(path);
+        dependencies::record(&entry.path);
+        entry
+    }
+
+    /// Same as `load_entry`, but it is not recorded as a dependency of the
+    /// resolution, such as the ancestors of an entry.
+    fn load_entry_unrecorded(&self, path: &Path) -> Arc<Entry> {
+        let path = self.cache.fs.unicode_normalization().normalize_path(path);
+        if let Some(cached) = self.entries.get(&path) {
+            cached
         } else {
-            let entry = Arc::new(self.load_entry_uncached(path));
-            self.cache
-                .entries
-                .entry(path.into())
-                .or_insert(entry.clone());
-            entry
+            let entry = Arc::new(self.load_entry_uncached(&path));
+            self.entries.get_or_insert(entry.path.clone(), entry)
         }
     }
 
     fn load_entry_uncached(&self, path: &Path) -> Entry {
         let parent = if let Some(parent) = path.parent() {
-            let entry = self.load_entry(parent);
+            let entry = self.load_entry_unrecorded(parent);
             Some(entry)
         } else {
             None
@@ -177,14 +274,61 @@ impl Resolver {
             path: path.into(),
             pkg_info: OnceCell::default(),
             stat: OnceCell::default(),
+            modules: OnceCell::default(),
             symlink: OnceCell::default(),
             real: OnceCell::default(),
+            case_sensitivity: self.cache.fs.case_sensitivity(),
+            retry: self.cache.fs.retry(),
+            unicode_normalization: self.cache.fs.unicode_normalization(),
         }
     }
 
+    /// Whether `dir` is one of `boundaries`, the walk of ancestors stops
+    /// at it.
+    pub(crate) fn is_boundary(&self, dir: &Path) -> bool {
+        self.options
+            .boundaries
+            .iter()
+            .any(|boundary| boundary == dir)
+    }
+
+    /// Returns the nearest description file from `dir`, it is parsed once
+    /// and shared with the resolution.
+    pub fn load_description_file(&self, dir: &Path) -> RResult<Option<Arc<DescriptionData>>> {
+        let entry = self.load_entry(dir);
+        entry.pkg_info(self).cloned()
+    }
+
+    /// Whether `path` is a file. It shares the stat cache with the
+    /// resolution, so the result is kept until the entry is invalidated.
+    #[must_use]
+    pub fn is_file(&self, path: &Path) -> bool {
+        self.load_entry(path).is_file()
+    }
+
+    /// Whether `path` is a directory, same cache as [`Resolver::is_file`].
+    #[must_use]
+    pub fn is_dir(&self, path: &Path) -> bool {
+        self.load_entry(path).is_dir()
+    }
+
+    /// Returns the cached stat of `path`, same cache as [`Resolver::is_file`].
+    #[must_use]
+    pub fn read_meta(&self, path: &Path) -> EntryStat {
+        self.load_entry(path).cached_stat()
+    }
+
     // TODO: should put entries as a parament.
     pub fn clear_entries(&self) {
-        self.cache.entries.clear();
+        self.entries.clear();
+        self.tsconfigs.clear();
+        self.import_maps.clear();
+        self.results.clear();
+        self.scoped_results.clear();
+        self.dependencies.clear();
+        for (_, resolver) in &self.roots {
+            resolver.clear_entries();
+        }
     }
 
     #[must_use]
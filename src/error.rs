@@ -1,13 +1,223 @@
-use std::{io, path::Path};
+use crate::InvalidRequest;
+use serde::Serialize;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
     UnexpectedJson((Box<Path>, serde_json::Error)),
     UnexpectedValue(String),
-    ResolveFailedTag,
-    Overflow,
+    ResolveFailed(Box<ResolveFailed>),
+    /// The resolution exceeded the budget of `Options`.
+    Overflow(Budget),
+    /// The alias keys which are applied recursively and formed a cycle,
+    /// such as `["a", "b", "a"]`.
+    AliasCycle(Vec<String>),
+    /// The `(dir, request)` pairs which are re-resolved recursively and
+    /// formed a cycle, the first pair is the same as the last one.
+    ResolveCycle(Vec<(PathBuf, String)>),
     CantFindTsConfig(Box<Path>),
+    InvalidExportsField(Box<InvalidExportsField>),
+    VersionMismatch(Box<VersionMismatch>),
+    UnsupportedDirImport(Box<UnsupportedDirImport>),
+    PackagePathNotExported(Box<PackagePathNotExported>),
+    InvalidRequest(InvalidRequest),
+    InvalidSpecifier(Box<InvalidSpecifier>),
+    /// The path exceeds `MAX_PATH` on Windows and it can't be prefixed by
+    /// `\\?\`, such as a relative path.
+    PathTooLong(PathBuf),
+    /// The resolved file is rejected by `Options::before_resolved`.
+    Rejected(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    /// Exceeded `max_depth`, which is carried.
+    MaxDepth(u16),
+    /// Exceeded `max_redirects`, which is carried.
+    MaxRedirects(u16),
+}
+
+impl std::fmt::Display for Budget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Budget::MaxDepth(max) => write!(f, "exceeded max_depth({max})"),
+            Budget::MaxRedirects(max) => write!(f, "exceeded max_redirects({max})"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResolveFailed {
+    /// The request which can't be resolved.
+    pub request: String,
+    /// The path where the request was resolved from.
+    pub path: PathBuf,
+    /// Similar requests which could be resolved, such as the same file
+    /// with another extension or casing. Empty if nothing found.
+    pub suggestions: Vec<String>,
+    /// The locations which had been attempted, in order.
+    pub attempts: Vec<Attempt>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttemptPhase {
+    /// Applied `alias`, `prefix` or `fallback`.
+    Alias,
+    /// Looked up `exports` or `imports` field.
+    Exports,
+    /// Tried the path as a file.
+    File,
+    /// Tried the path with `extensions`.
+    Extensions,
+    /// Looked up the `modules` directory, such as `node_modules`.
+    Modules,
+}
+
+impl AttemptPhase {
+    const ALL: [Self; 5] = [
+        Self::Alias,
+        Self::Exports,
+        Self::File,
+        Self::Extensions,
+        Self::Modules,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Alias => "aliases",
+            Self::Exports => "exports",
+            Self::File => "files",
+            Self::Extensions => "extensions tried",
+            Self::Modules => "modules directories walked",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Attempt {
+    pub phase: AttemptPhase,
+    pub detail: String,
+}
+
+impl std::fmt::Display for ResolveFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Can't resolve '{}' in '{}'",
+            self.request,
+            self.path.display()
+        )?;
+        for phase in AttemptPhase::ALL {
+            let mut attempts = self
+                .attempts
+                .iter()
+                .filter(|attempt| attempt.phase == phase)
+                .peekable();
+            if attempts.peek().is_none() {
+                continue;
+            }
+            write!(f, "\n  {}:", phase.title())?;
+            for attempt in attempts {
+                write!(f, "\n    {}", attempt.detail)?;
+            }
+        }
+        if !self.suggestions.is_empty() {
+            write!(f, "\n  Did you mean {}?", self.suggestions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Misuses of `exports` field, only reported when `strict_exports` is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportsFieldMisuse {
+    /// The target does not start with `"./"`.
+    TargetNotRelative,
+    /// The target escapes the package by `".."`.
+    TargetOutOfPackage,
+    /// A condition is placed after `"default"` condition.
+    ConditionAfterDefault,
+    /// The keys mixed subpath keys and condition keys.
+    MixedKeys,
+}
+
+#[derive(Debug)]
+pub struct InvalidExportsField {
+    /// The description file which contains the `exports` field.
+    pub path: Box<Path>,
+    /// JSON pointer to the offending value, such as `/exports/./import`.
+    pub pointer: String,
+    pub misuse: ExportsFieldMisuse,
+}
+
+/// The version of the resolved package doesn't satisfy the range.
+#[derive(Debug)]
+pub struct VersionMismatch {
+    pub request: String,
+    pub range: String,
+    /// `None` if the description file or its `version` is missing.
+    pub version: Option<String>,
+    /// The directory where the description file located.
+    pub path: Option<PathBuf>,
+}
+
+/// A directory is imported in ESM mode, same as `ERR_UNSUPPORTED_DIR_IMPORT`
+/// of Node.js.
+#[derive(Debug)]
+pub struct UnsupportedDirImport {
+    pub request: String,
+    /// The imported directory.
+    pub path: PathBuf,
+    /// The request of the main file in the directory if it exists, such
+    /// as `./dir/index.js`.
+    pub suggestion: Option<String>,
+}
+
+/// The subpath is not exported by the `exports` field of the package, same
+/// as `ERR_PACKAGE_PATH_NOT_EXPORTED` of Node.js.
+#[derive(Debug)]
+pub struct PackagePathNotExported {
+    /// The request, such as `pkg/lib/utils`.
+    pub request: String,
+    /// The requested subpath, such as `./lib/utils`.
+    pub subpath: String,
+    /// The description file which contains the field.
+    pub path: Box<Path>,
+    /// The nearest subpath exported by the package, such as `./utils`.
+    pub suggestion: Option<String>,
+}
+
+impl std::fmt::Display for PackagePathNotExported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Package path {} is not exported in {}",
+            self.request,
+            self.path.display()
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean '{suggestion}'?")?;
+        }
+        Ok(())
+    }
+}
+
+/// The target of `exports` or `imports` field contains an invalid segment,
+/// same as `ERR_INVALID_MODULE_SPECIFIER` of Node.js.
+#[derive(Debug)]
+pub struct InvalidSpecifier {
+    pub request: String,
+    /// The mapped target, such as `./dist/../secret.js`.
+    pub target: String,
+    /// The offending segment, such as `..`, `node_modules` or `a%2Fb`.
+    pub segment: String,
+    /// The description file which contains the field.
+    pub path: Box<Path>,
 }
 
 impl From<std::io::Error> for Error {
@@ -0,0 +1,136 @@
+//! The expansion of `${NAME}` and the leading `~` in the options loaded
+//! from a config shared across machines, see `Options::expand_env`.
+
+use crate::{
+    options::{Alias, RootProfile},
+    AliasMap, Error, Options, RResult,
+};
+use std::{borrow::Cow, path::PathBuf};
+
+fn home_dir() -> RResult<String> {
+    let name = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var(name).map_err(|_| {
+        Error::UnexpectedValue(format!(
+            "Can't expand '~', environment variable '{name}' is not defined"
+        ))
+    })
+}
+
+/// Replaces `${NAME}` by the environment variable and the leading `~`
+/// followed by a separator by the home directory.
+pub(crate) fn expand(value: &str) -> RResult<Cow<'_, str>> {
+    let home = value
+        .strip_prefix('~')
+        .filter(|rest| rest.is_empty() || rest.starts_with(['/', '\\']));
+    if home.is_none() && !value.contains("${") {
+        return Ok(Cow::Borrowed(value));
+    }
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+    if let Some(after) = home {
+        expanded.push_str(&home_dir()?);
+        rest = after;
+    }
+    while let Some(start) = rest.find("${") {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            return Err(Error::UnexpectedValue(format!(
+                "Unclosed '${{' in '{value}'"
+            )));
+        };
+        let name = &after[..end];
+        let var = std::env::var(name).map_err(|_| {
+            Error::UnexpectedValue(format!(
+                "Can't expand '{value}', environment variable '{name}' is not defined"
+            ))
+        })?;
+        expanded.push_str(&var);
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(Cow::Owned(expanded))
+}
+
+fn expand_path(path: PathBuf) -> RResult<PathBuf> {
+    match path.to_str().map(expand).transpose()? {
+        Some(Cow::Owned(expanded)) => Ok(PathBuf::from(expanded)),
+        _ => Ok(path),
+    }
+}
+
+fn expand_alias(alias: Alias) -> RResult<Alias> {
+    alias
+        .into_iter()
+        .map(|(key, targets)| {
+            let targets = targets
+                .into_iter()
+                .map(|target| match target {
+                    AliasMap::Target(target) => Ok(AliasMap::Target(expand(&target)?.into_owned())),
+                    AliasMap::Ignored => Ok(AliasMap::Ignored),
+                })
+                .collect::<RResult<_>>()?;
+            Ok((key, targets))
+        })
+        .collect()
+}
+
+impl Options {
+    /// Expands `${NAME}` by the environment variable and the leading `~`
+    /// by the home directory in the targets of `alias`, `fallback` and
+    /// `scoped_alias`, the directories of `scoped_alias`, `roots` and
+    /// `tsconfig`. It should be called once when the options are built
+    /// from a config, so the expanded values participate in
+    /// `Options::fingerprint`. Returns an error if a variable is not
+    /// defined.
+    pub fn expand_env(self) -> RResult<Self> {
+        let scoped_alias = self
+            .scoped_alias
+            .into_iter()
+            .map(|(dir, alias)| Ok((expand_path(dir)?, expand_alias(alias)?)))
+            .collect::<RResult<_>>()?;
+        let roots = self
+            .roots
+            .into_iter()
+            .map(|profile| {
+                Ok(RootProfile {
+                    root: expand_path(profile.root)?,
+                    alias: profile.alias.map(expand_alias).transpose()?,
+                    tsconfig: profile.tsconfig.map(expand_path).transpose()?,
+                    extensions: profile.extensions,
+                })
+            })
+            .collect::<RResult<_>>()?;
+        Ok(Self {
+            alias: expand_alias(self.alias)?,
+            fallback: expand_alias(self.fallback)?,
+            scoped_alias,
+            roots,
+            tsconfig: self.tsconfig.map(expand_path).transpose()?,
+            ..self
+        })
+    }
+}
+
+#[test]
+fn expand_test() {
+    std::env::set_var("NODEJS_RESOLVER_EXPAND", "/opt/app");
+    assert!(matches!(expand("./a/b"), Ok(Cow::Borrowed("./a/b"))));
+    assert!(matches!(expand("~lodash"), Ok(Cow::Borrowed("~lodash"))));
+    assert_eq!(
+        expand("${NODEJS_RESOLVER_EXPAND}/src/${NODEJS_RESOLVER_EXPAND}").unwrap(),
+        "/opt/app/src//opt/app"
+    );
+    if let Ok(home) = home_dir() {
+        assert_eq!(expand("~").unwrap(), home);
+        assert_eq!(expand("~/.config").unwrap(), format!("{home}/.config"));
+    }
+    assert!(matches!(
+        expand("${NODEJS_RESOLVER_UNDEFINED}/src"),
+        Err(Error::UnexpectedValue(_))
+    ));
+    assert!(matches!(
+        expand("${NODEJS_RESOLVER_EXPAND"),
+        Err(Error::UnexpectedValue(_))
+    ));
+}
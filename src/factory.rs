@@ -0,0 +1,66 @@
+use crate::{Cache, Options, Resolver};
+use dashmap::DashMap;
+use rustc_hash::FxHasher;
+use std::{hash::BuildHasherDefault, sync::Arc};
+
+/// The fingerprint of the options and the addresses of the hooks.
+type FactoryKey = (u64, [Option<usize>; 3]);
+
+/// Hands out the resolvers sharing one `Cache`, the resolvers of the same
+/// options are reused, such as the resolvers requested for each dependency
+/// type by the `ResolverFactory` of webpack and rspack.
+#[derive(Debug, Default)]
+pub struct ResolverFactory {
+    cache: Arc<Cache>,
+    resolvers: DashMap<FactoryKey, Arc<Resolver>, BuildHasherDefault<FxHasher>>,
+}
+
+impl ResolverFactory {
+    #[must_use]
+    pub fn new(cache: Arc<Cache>) -> Self {
+        Self {
+            cache,
+            resolvers: Default::default(),
+        }
+    }
+
+    /// Returns the cache shared by the resolvers.
+    #[must_use]
+    pub fn cache(&self) -> Arc<Cache> {
+        self.cache.clone()
+    }
+
+    /// Returns the resolver of `options`, it is created if no resolver of
+    /// the same options has been handed out. `external_cache` of `options`
+    /// is replaced by the cache of the factory.
+    pub fn get(&self, options: Options) -> Arc<Resolver> {
+        let key = (options.fingerprint(), options.hook_addresses());
+        if let Some(resolver) = self.resolvers.get(&key) {
+            return resolver.clone();
+        }
+        self.resolvers
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Resolver::new(Options {
+                    external_cache: Some(self.cache.clone()),
+                    ..options
+                }))
+            })
+            .clone()
+    }
+
+    /// The count of the resolvers handed out.
+    pub fn len(&self) -> usize {
+        self.resolvers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+
+    /// Drops the resolvers and their memoized results, the cache is kept
+    /// for the resolvers handed out later.
+    pub fn clear(&self) {
+        self.resolvers.clear();
+    }
+}
@@ -1,33 +1,268 @@
 use crate::{
     description::{DescriptionData, PkgJSON},
     entry::EntryStat,
+    normalize::UnicodeNormalization,
     tsconfig::TsConfig,
     RResult,
 };
 use rustc_hash::FxHasher;
 use std::{
+    borrow::Cow,
     fmt::Debug,
     fs,
     hash::BuildHasherDefault,
+    io,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::SystemTime,
 };
 
 use dashmap::DashMap;
+use xxhash_rust::xxh3::xxh3_64;
 
 use std::time::Duration;
 
+/// How the cached files are validated before being reused.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CacheValidation {
+    /// The cached files are always reused, it assumes that the files
+    /// are never changed.
+    Never,
+    /// The cached files are reused if the modified time is not changed.
+    #[default]
+    Mtime,
+    /// The cached files are reused if the hash of the content is not
+    /// changed, the file is read again but not parsed. It is useful when
+    /// the modified time is unreliable, such as the files copied by the
+    /// layers of Docker. The names in directory are always read again.
+    ContentHash,
+}
+
+/// How the names in the paths are matched. The underlying file system is
+/// emulated by the other ones, so the casing bugs of macOS and Windows
+/// could be caught on Linux, and vice versa.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FileSystemCaseSensitivity {
+    /// Same as the underlying file system.
+    #[default]
+    Native,
+    /// `A.js` is not `a.js`, such as Linux.
+    Sensitive,
+    /// `A.js` is `a.js`, such as the defaults of macOS and Windows.
+    Insensitive,
+}
+
+impl FileSystemCaseSensitivity {
+    /// Returns the path on the underlying file system which `path` refers
+    /// to, which differs in casing only if `path` doesn't exist and it is
+    /// `Insensitive`.
+    pub(crate) fn native_path(self, path: &Path) -> Cow<Path> {
+        if self != Self::Insensitive || path.symlink_metadata().is_ok() {
+            return Cow::Borrowed(path);
+        }
+        let mut native = PathBuf::new();
+        for component in path.components() {
+            let candidate = native.join(component);
+            if candidate.symlink_metadata().is_ok() {
+                native = candidate;
+                continue;
+            }
+            let name = component.as_os_str().to_string_lossy().to_lowercase();
+            let found = fs::read_dir(&native).ok().and_then(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name())
+                    .find(|found| found.to_string_lossy().to_lowercase() == name)
+            });
+            match found {
+                Some(found) => native.push(found),
+                None => return Cow::Borrowed(path),
+            }
+        }
+        Cow::Owned(native)
+    }
+
+    /// The `path` should be prefixed by `long_path`.
+    pub(crate) fn metadata(self, path: &Path) -> io::Result<fs::Metadata> {
+        #[cfg(test)]
+        if let Some(kind) = injected_error(path) {
+            return Err(kind.into());
+        }
+        match self {
+            Self::Native => path.metadata(),
+            Self::Insensitive => self.native_path(path).metadata(),
+            Self::Sensitive => {
+                let meta = path.metadata()?;
+                // every name should be listed by its parent as is.
+                for ancestor in path.ancestors() {
+                    let (Some(parent), Some(name)) = (ancestor.parent(), ancestor.file_name())
+                    else {
+                        continue;
+                    };
+                    let listed = fs::read_dir(parent)?
+                        .filter_map(|entry| entry.ok())
+                        .any(|entry| entry.file_name() == name);
+                    if !listed {
+                        return Err(io::ErrorKind::NotFound.into());
+                    }
+                }
+                Ok(meta)
+            }
+        }
+    }
+}
+
+/// The `errno` of the stale file handle of NFS.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const ESTALE: Option<i32> = Some(116);
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+const ESTALE: Option<i32> = Some(70);
+#[cfg(not(unix))]
+const ESTALE: Option<i32> = None;
+
+/// Whether `error` is transient, such as `EINTR` or `ESTALE` of the
+/// network file systems, so the operation could succeed if it is retried.
+/// It is not the same as `NotFound`, the file could exist.
+#[must_use]
+pub fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    ) || (ESTALE.is_some() && error.raw_os_error() == ESTALE)
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The error of the stat injected by the tests, such as the transient
+    /// ones which could not be made on the real file system.
+    pub(crate) static INJECTED_ERROR: std::cell::RefCell<Option<(PathBuf, io::ErrorKind)>> =
+        Default::default();
+}
+
+#[cfg(test)]
+fn injected_error(path: &Path) -> Option<io::ErrorKind> {
+    INJECTED_ERROR.with(|injected| {
+        let injected = injected.borrow();
+        let (injected, kind) = injected.as_ref()?;
+        (injected == path).then_some(*kind)
+    })
+}
+
+/// The longest path which the Windows APIs accept without the `\\?\`
+/// prefix, including the terminating null.
+pub const MAX_PATH: usize = 260;
+
+/// Returns `path` with the `\\?\` prefix if it exceeds `MAX_PATH` on
+/// Windows, the deep `node_modules` could be. `Error::PathTooLong` is
+/// returned if the path can't be prefixed, such as a relative path.
+#[cfg(windows)]
+pub(crate) fn long_path(path: &Path) -> RResult<Cow<'_, Path>> {
+    use std::{
+        ffi::OsString,
+        path::{Component, Prefix},
+    };
+    if path.as_os_str().len() < MAX_PATH {
+        return Ok(Cow::Borrowed(path));
+    }
+    let mut components = path.components();
+    let mut long = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Verbatim(_) | Prefix::VerbatimDisk(_) | Prefix::VerbatimUNC(..) => {
+                return Ok(Cow::Borrowed(path));
+            }
+            Prefix::Disk(disk) => Some(OsString::from(format!(r"\\?\{}:", disk as char))),
+            Prefix::UNC(server, share) => {
+                let mut long = OsString::from(r"\\?\UNC\");
+                long.push(server);
+                long.push(r"\");
+                long.push(share);
+                Some(long)
+            }
+            Prefix::DeviceNS(_) => None,
+        },
+        _ => None,
+    };
+    // the verbatim path is not normalized, so `.` and `..` are not allowed.
+    for component in components {
+        match (&mut long, component) {
+            (Some(_), Component::RootDir) => {}
+            (Some(long), Component::Normal(name)) => {
+                long.push(r"\");
+                long.push(name);
+            }
+            _ => long = None,
+        }
+    }
+    match long {
+        Some(long) => Ok(Cow::Owned(PathBuf::from(long))),
+        None => Err(crate::Error::PathTooLong(path.to_path_buf())),
+    }
+}
+
+#[cfg(not(windows))]
+pub(crate) fn long_path(path: &Path) -> RResult<Cow<'_, Path>> {
+    Ok(Cow::Borrowed(path))
+}
+
+/// Retries the file system operations which failed by the transient
+/// errors, see `Cache::with_retry`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The times to retry after the first failure.
+    pub retries: u32,
+    /// The delay before the first retry, it is doubled for each next one.
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub(crate) fn run<T, F: FnMut() -> io::Result<T>>(self, mut op: F) -> io::Result<T> {
+        let mut backoff = self.backoff;
+        for _ in 0..self.retries {
+            match op() {
+                Err(error) if is_transient(&error) => {
+                    std::thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                result => return result,
+            }
+        }
+        op()
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct CachedFS {
+    validation: CacheValidation,
+
+    case_sensitivity: FileSystemCaseSensitivity,
+
+    retry: RetryPolicy,
+
+    unicode_normalization: UnicodeNormalization,
+
     /// Caches raw files
     entries: CachedMap<String>,
 
     /// Caches parsed package.json
     descriptions: CachedMap<DescriptionData>,
 
+    /// Caches package.json parsed by `Options::lenient_json`, which is
+    /// apart from the strict one.
+    lenient_descriptions: CachedMap<DescriptionData>,
+
     /// Caches tsconfig.json
     tsconfigs: CachedMap<serde_json::Value>,
+
+    /// Caches the names of the children in directory
+    dirs: CachedMap<Vec<String>>,
+
+    /// The number of the active sessions of the resolvers sharing this
+    /// cache, the cached files are reused without validation while it is
+    /// not zero.
+    sessions: AtomicUsize,
 }
 
 pub type CachedMap<T> = DashMap<PathBuf, CachedEntry<T>, BuildHasherDefault<FxHasher>>;
@@ -36,6 +271,8 @@ pub type CachedMap<T> = DashMap<PathBuf, CachedEntry<T>, BuildHasherDefault<FxHa
 pub struct CachedEntry<T: Sized> {
     content: Arc<T>,
     stat: EntryStat,
+    /// The hash of the file content, only for `CacheValidation::ContentHash`.
+    hash: Option<u64>,
 }
 
 impl<T> Clone for CachedEntry<T> {
@@ -43,15 +280,17 @@ impl<T> Clone for CachedEntry<T> {
         Self {
             content: Arc::clone(&self.content),
             stat: self.stat,
+            hash: self.hash,
         }
     }
 }
 
 impl<T: Sized> CachedEntry<T> {
-    fn new(content: T, stat: EntryStat) -> Self {
+    fn new(content: T, stat: EntryStat, hash: Option<u64>) -> Self {
         Self {
             content: content.into(),
             stat,
+            hash,
         }
     }
 
@@ -63,35 +302,75 @@ impl<T: Sized> CachedEntry<T> {
 const DEBOUNCE_INTERVAL: Duration = Duration::from_millis(300);
 
 impl CachedFS {
-    pub fn read_file(&self, path: &Path, file_stat: EntryStat) -> RResult<Arc<String>> {
-        if let Some(cached) = self.entries.get(path) {
-            if self.is_modified(file_stat.modified(), cached.stat.modified()) {
-                return Ok(cached.value().content());
-            }
+    #[must_use]
+    pub fn with_validation(validation: CacheValidation) -> Self {
+        Self {
+            validation,
+            ..Default::default()
         }
-        let string = fs::read_to_string(path)?;
-        let entry = CachedEntry::new(string, file_stat);
-        self.entries.insert(path.to_path_buf(), entry.clone());
-        Ok(entry.content())
+    }
+
+    pub fn validation(&self) -> CacheValidation {
+        self.validation
+    }
+
+    pub fn case_sensitivity(&self) -> FileSystemCaseSensitivity {
+        self.case_sensitivity
+    }
+
+    pub fn retry(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    pub fn unicode_normalization(&self) -> UnicodeNormalization {
+        self.unicode_normalization
+    }
+
+    pub(crate) fn set_unicode_normalization(&mut self, normalization: UnicodeNormalization) {
+        self.unicode_normalization = normalization;
+    }
+
+    pub(crate) fn set_retry(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
+
+    /// Pins the cached files for a session, see [`crate::Session`].
+    pub(crate) fn pin(&self) {
+        self.sessions.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Unpins the cached files, and returns whether it is the last session.
+    pub(crate) fn unpin(&self) -> bool {
+        self.sessions.fetch_sub(1, Ordering::AcqRel) == 1
+    }
+
+    pub(crate) fn is_pinned(&self) -> bool {
+        self.sessions.load(Ordering::Acquire) > 0
+    }
+
+    pub(crate) fn set_case_sensitivity(&mut self, case_sensitivity: FileSystemCaseSensitivity) {
+        self.case_sensitivity = case_sensitivity;
+    }
+
+    pub fn read_file(&self, path: &Path, file_stat: EntryStat) -> RResult<Arc<String>> {
+        self.read_with(&self.entries, path, file_stat, Ok)
     }
 
     pub fn read_description_file(
         &self,
         path: &Path,
         file_stat: EntryStat,
+        lenient: bool,
     ) -> RResult<Arc<DescriptionData>> {
-        if let Some(cached) = self.descriptions.get(path) {
-            if self.is_modified(file_stat.modified(), cached.stat.modified()) {
-                return Ok(cached.value().content());
-            }
-        }
-        let string = fs::read_to_string(path)?;
-        let json = PkgJSON::parse(&string, path)?;
-        let dir = path.parent().unwrap().to_path_buf();
-        let info = DescriptionData::new(json, dir);
-        let entry = CachedEntry::new(info, file_stat);
-        self.descriptions.insert(path.to_path_buf(), entry.clone());
-        Ok(entry.content())
+        let descriptions = match lenient {
+            true => &self.lenient_descriptions,
+            false => &self.descriptions,
+        };
+        self.read_with(descriptions, path, file_stat, |string| {
+            let json = PkgJSON::parse(&string, path, lenient)?;
+            let dir = path.parent().unwrap().to_path_buf();
+            Ok(DescriptionData::new(json, dir))
+        })
     }
 
     pub fn read_tsconfig(
@@ -99,18 +378,104 @@ impl CachedFS {
         path: &Path,
         file_stat: EntryStat,
     ) -> RResult<Arc<serde_json::Value>> {
-        if let Some(cached) = self.tsconfigs.get(path) {
-            if self.is_modified(file_stat.modified(), cached.stat.modified()) {
+        self.read_with(&self.tsconfigs, path, file_stat, |string| {
+            TsConfig::parse(&string, path)
+        })
+    }
+
+    pub fn read_dir(&self, path: &Path, file_stat: EntryStat) -> RResult<Arc<Vec<String>>> {
+        if let Some(cached) = self.dirs.get(path) {
+            if self.is_valid(&cached, file_stat) {
                 return Ok(cached.value().content());
             }
         }
-        let string = fs::read_to_string(path)?;
-        let serde_json = TsConfig::parse(&string, path)?;
-        let entry = CachedEntry::new(serde_json, file_stat);
-        self.tsconfigs.insert(path.to_path_buf(), entry.clone());
+        let native = self.case_sensitivity.native_path(path);
+        let native = long_path(&native)?;
+        let mut names = self
+            .retry
+            .run(|| fs::read_dir(&native))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        let entry = CachedEntry::new(names, file_stat, None);
+        self.dirs.insert(path.to_path_buf(), entry.clone());
         Ok(entry.content())
     }
 
+    /// Returns the cached content of the file if it is valid, otherwise
+    /// reads and parses the file, then caches it.
+    fn read_with<T, F: FnOnce(String) -> RResult<T>>(
+        &self,
+        map: &CachedMap<T>,
+        path: &Path,
+        file_stat: EntryStat,
+        parse: F,
+    ) -> RResult<Arc<T>> {
+        let (string, hash) = if self.validation == CacheValidation::ContentHash && !self.is_pinned()
+        {
+            let string = self.read_to_string(path)?;
+            let hash = xxh3_64(string.as_bytes());
+            if let Some(cached) = map.get(path) {
+                if cached.hash == Some(hash) {
+                    return Ok(cached.value().content());
+                }
+            }
+            (string, Some(hash))
+        } else {
+            if let Some(cached) = map.get(path) {
+                if self.is_valid(&cached, file_stat) {
+                    return Ok(cached.value().content());
+                }
+            }
+            (self.read_to_string(path)?, None)
+        };
+        let entry = CachedEntry::new(parse(string)?, file_stat, hash);
+        map.insert(path.to_path_buf(), entry.clone());
+        Ok(entry.content())
+    }
+
+    fn read_to_string(&self, path: &Path) -> RResult<String> {
+        let native = self.case_sensitivity.native_path(path);
+        let native = long_path(&native)?;
+        Ok(self.retry.run(|| fs::read_to_string(&native))?)
+    }
+
+    fn is_valid<T>(&self, cached: &CachedEntry<T>, file_stat: EntryStat) -> bool {
+        if self.is_pinned() {
+            return true;
+        }
+        match self.validation {
+            CacheValidation::Never => true,
+            CacheValidation::Mtime => {
+                self.is_modified(file_stat.modified(), cached.stat.modified())
+            }
+            CacheValidation::ContentHash => false,
+        }
+    }
+
+    /// Removes the cached contents of `path` and its descendants, and the
+    /// names in its parent directory.
+    pub fn invalidate(&self, path: &Path) {
+        self.entries.retain(|key, _| !key.starts_with(path));
+        self.descriptions.retain(|key, _| !key.starts_with(path));
+        self.lenient_descriptions
+            .retain(|key, _| !key.starts_with(path));
+        self.tsconfigs.retain(|key, _| !key.starts_with(path));
+        self.dirs.retain(|key, _| !key.starts_with(path));
+        if let Some(parent) = path.parent() {
+            self.dirs.remove(parent);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.entries.clear();
+        self.descriptions.clear();
+        self.lenient_descriptions.clear();
+        self.tsconfigs.clear();
+        self.dirs.clear();
+    }
+
     fn is_modified(&self, before: Option<SystemTime>, after: Option<SystemTime>) -> bool {
         if let (Some(before), Some(after)) = (before, after) {
             if before.duration_since(after).expect("after > before") < DEBOUNCE_INTERVAL {
@@ -120,3 +485,64 @@ impl CachedFS {
         false
     }
 }
+
+#[test]
+fn retry_policy_test() {
+    if let Some(estale) = ESTALE {
+        assert!(is_transient(&io::Error::from_raw_os_error(estale)));
+    }
+    assert!(is_transient(&io::Error::from(io::ErrorKind::Interrupted)));
+    assert!(!is_transient(&io::Error::from(io::ErrorKind::NotFound)));
+
+    let retry = RetryPolicy {
+        retries: 2,
+        backoff: Duration::from_millis(1),
+    };
+    let mut calls = 0;
+    let result = retry.run(|| {
+        calls += 1;
+        if calls < 3 {
+            Err(io::Error::from(io::ErrorKind::Interrupted))
+        } else {
+            Ok(calls)
+        }
+    });
+    assert_eq!(result.unwrap(), 3);
+
+    let mut calls = 0;
+    let result: io::Result<()> = retry.run(|| {
+        calls += 1;
+        Err(io::Error::from(io::ErrorKind::NotFound))
+    });
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    assert_eq!(calls, 1);
+
+    let result: io::Result<()> = retry.run(|| Err(io::Error::from(io::ErrorKind::Interrupted)));
+    assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Interrupted);
+}
+
+#[cfg(windows)]
+#[test]
+fn long_path_test() {
+    let name = "a".repeat(100);
+    let short = PathBuf::from(format!(r"C:\{name}"));
+    assert_eq!(long_path(&short).unwrap(), short);
+
+    let long = PathBuf::from(format!(r"C:\{name}/{name}\{name}"));
+    assert_eq!(
+        long_path(&long).unwrap(),
+        PathBuf::from(format!(r"\\?\C:\{name}\{name}\{name}"))
+    );
+    let unc = PathBuf::from(format!(r"\\server\share\{name}\{name}\{name}"));
+    assert_eq!(
+        long_path(&unc).unwrap(),
+        PathBuf::from(format!(r"\\?\UNC\server\share\{name}\{name}\{name}"))
+    );
+    let verbatim = PathBuf::from(format!(r"\\?\C:\{name}\{name}\{name}"));
+    assert_eq!(long_path(&verbatim).unwrap(), verbatim);
+
+    let relative = PathBuf::from(format!(r"{name}\{name}\{name}"));
+    assert!(
+        matches!(long_path(&relative), Err(crate::Error::PathTooLong(path)) if path == relative)
+    );
+}
@@ -0,0 +1,104 @@
+use crate::{RResult, ResolveResult, Resolver, Resource};
+use indexmap::IndexSet;
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+/// A resolved dependency, `issuer` imports `resolved` by `request`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Edge {
+    pub issuer: PathBuf,
+    pub request: String,
+    pub resolved: PathBuf,
+}
+
+/// Wraps a resolver and records every successful resolution as an edge.
+#[derive(Debug)]
+pub struct ResolutionGraph {
+    resolver: Resolver,
+    /// Keep the order of insertion and ignore the duplicated edges.
+    edges: RwLock<IndexSet<Edge>>,
+}
+
+impl ResolutionGraph {
+    #[must_use]
+    pub fn new(resolver: Resolver) -> Self {
+        Self {
+            resolver,
+            edges: Default::default(),
+        }
+    }
+
+    pub fn resolver(&self) -> &Resolver {
+        &self.resolver
+    }
+
+    /// Resolve `request` imported by the file `issuer`, and record the edge
+    /// if it had been resolved to a resource.
+    pub fn resolve(&self, issuer: &Path, request: &str) -> RResult<ResolveResult<Resource>> {
+        let dir = issuer.parent().unwrap_or(issuer);
+        let result = self.resolver.resolve(dir, request)?;
+        if let ResolveResult::File(resource) = &result {
+            self.edges.write().unwrap().insert(Edge {
+                issuer: issuer.to_path_buf(),
+                request: request.to_string(),
+                resolved: resource.path.clone(),
+            });
+        }
+        Ok(result)
+    }
+
+    #[must_use]
+    pub fn edges(&self) -> Vec<Edge> {
+        self.edges.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Returns the edges which are imported by `issuer`.
+    #[must_use]
+    pub fn dependencies(&self, issuer: &Path) -> Vec<Edge> {
+        self.filter(|edge| edge.issuer == issuer)
+    }
+
+    /// Returns the edges which are resolved to `file`.
+    #[must_use]
+    pub fn dependents(&self, file: &Path) -> Vec<Edge> {
+        self.filter(|edge| edge.resolved == file)
+    }
+
+    pub fn clear(&self) {
+        self.edges.write().unwrap().clear();
+    }
+
+    /// Export the graph in the format of graphviz.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        fn quote<T: std::fmt::Display>(s: &T) -> String {
+            format!(
+                "\"{}\"",
+                s.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        }
+        let mut dot = String::from("digraph {\n");
+        for edge in self.edges.read().unwrap().iter() {
+            dot.push_str(&format!(
+                "    {} -> {} [label={}];\n",
+                quote(&edge.issuer.display()),
+                quote(&edge.resolved.display()),
+                quote(&edge.request)
+            ));
+        }
+        dot.push('}');
+        dot
+    }
+
+    fn filter<F: Fn(&Edge) -> bool>(&self, f: F) -> Vec<Edge> {
+        self.edges
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|edge| f(edge))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,259 @@
+//! reference: https://html.spec.whatwg.org/multipage/webappapis.html#import-maps
+
+use crate::{
+    context::Context, info::NormalizedPath, log::debug, log::depth, AttemptPhase, Error, Info,
+    RResult, Resolver,
+};
+use std::{path::Path, sync::Arc};
+
+/// The keys and the addresses, which are sorted by the descending order of
+/// keys so that the longest prefix is matched first. The address is `None`
+/// if it is `null` or invalid, which blocks the specifier.
+type SpecifierMap = Vec<(String, Option<String>)>;
+
+#[derive(Debug, Default)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    /// The scope prefixes and their maps, sorted by the descending order of
+    /// prefixes so that the more specific scope is tried first.
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+/// Whether the specifier is a URL or a path rather than a bare specifier.
+fn is_url_like(specifier: &str) -> bool {
+    specifier.starts_with('/')
+        || specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || specifier.starts_with("file:")
+}
+
+/// Resolves the URL-like `specifier` against `base` to an absolute path,
+/// the trailing slash is kept. Returns `None` for the other URLs, such as
+/// `https:`, which can't be found on the file system.
+fn resolve_url_like(specifier: &str, base: &Path) -> Option<String> {
+    let path = specifier.strip_prefix("file://").unwrap_or(specifier);
+    if path.contains(':') {
+        return None;
+    }
+    Some(
+        NormalizedPath::new(base.join(path))
+            .as_ref()
+            .display()
+            .to_string(),
+    )
+}
+
+fn parse_specifier_map(value: &serde_json::Value, base: &Path) -> SpecifierMap {
+    let Some(map) = value.as_object() else {
+        return vec![];
+    };
+    let mut list: SpecifierMap = map
+        .iter()
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, address)| {
+            let key = if is_url_like(key) {
+                resolve_url_like(key, base).unwrap_or_else(|| key.to_string())
+            } else {
+                key.to_string()
+            };
+            let address = address
+                .as_str()
+                .filter(|address| is_url_like(address))
+                .and_then(|address| resolve_url_like(address, base))
+                // the address of a prefix must be a prefix too.
+                .filter(|address| !key.ends_with('/') || address.ends_with('/'));
+            (key, address)
+        })
+        .collect();
+    list.sort_by(|a, b| b.0.cmp(&a.0));
+    list
+}
+
+impl ImportMap {
+    /// Parses the import map, the keys and addresses are resolved against
+    /// `base`, the directory of the import map.
+    pub fn parse(json: &serde_json::Value, base: &Path) -> RResult<Self> {
+        let Some(json) = json.as_object() else {
+            return Err(Error::UnexpectedValue(String::from(
+                "Import map should be an object",
+            )));
+        };
+        let imports = json
+            .get("imports")
+            .map(|imports| parse_specifier_map(imports, base))
+            .unwrap_or_default();
+        let mut scopes: Vec<(String, SpecifierMap)> = json
+            .get("scopes")
+            .and_then(|scopes| scopes.as_object())
+            .map(|scopes| {
+                scopes
+                    .iter()
+                    .filter_map(|(prefix, map)| {
+                        let prefix = resolve_url_like(prefix, base)?;
+                        Some((prefix, parse_specifier_map(map, base)))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        scopes.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(Self { imports, scopes })
+    }
+
+    /// Returns the mapped path of `specifier` imported from `referrer`, or
+    /// `None` if it is not mapped. The URL-like specifier is resolved
+    /// against `referrer` before matching.
+    pub fn resolve(&self, specifier: &str, referrer: &Path) -> Result<Option<String>, String> {
+        let normalized = if is_url_like(specifier) {
+            match resolve_url_like(specifier, referrer) {
+                Some(normalized) => normalized,
+                None => return Ok(None),
+            }
+        } else {
+            specifier.to_string()
+        };
+        let referrer = format!("{}/", referrer.display());
+        let scopes = self.scopes.iter().filter(|(prefix, _)| {
+            prefix == &referrer || (prefix.ends_with('/') && referrer.starts_with(prefix.as_str()))
+        });
+        for (_, map) in scopes {
+            if let Some(address) = Self::resolve_in(map, &normalized)? {
+                return Ok(Some(address));
+            }
+        }
+        Self::resolve_in(&self.imports, &normalized)
+    }
+
+    fn resolve_in(map: &SpecifierMap, specifier: &str) -> Result<Option<String>, String> {
+        for (key, address) in map {
+            if key == specifier {
+                return match address {
+                    Some(address) => Ok(Some(address.to_string())),
+                    None => Err(key.to_string()),
+                };
+            }
+            let Some(after_prefix) = key
+                .ends_with('/')
+                .then(|| specifier.strip_prefix(key.as_str()))
+                .flatten() else {
+                continue;
+            };
+            let Some(address) = address else {
+                return Err(key.to_string());
+            };
+            let url = NormalizedPath::new(format!("{address}{after_prefix}"))
+                .as_ref()
+                .display()
+                .to_string();
+            // backtracking above the address is not allowed.
+            if !url.starts_with(address.as_str()) {
+                return Err(key.to_string());
+            }
+            return Ok(Some(url));
+        }
+        Ok(None)
+    }
+}
+
+impl Resolver {
+    fn load_import_map(&self, location: &Path) -> RResult<Arc<ImportMap>> {
+        if let Some(cached) = self.import_maps.get(location) {
+            return Ok(cached.clone());
+        }
+        let entry = self.load_entry(location);
+        let content = self.cache.fs.read_file(location, entry.cached_stat())?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|error| Error::UnexpectedJson((location.into(), error)))?;
+        let import_map = Arc::new(ImportMap::parse(&json, location.parent().unwrap())?);
+        self.import_maps.insert(location.into(), import_map.clone());
+        Ok(import_map)
+    }
+
+    /// Maps the request of `info` by the import map at `location`.
+    pub(crate) fn apply_import_map(
+        &self,
+        location: &Path,
+        info: Info,
+        context: &mut Context,
+    ) -> RResult<Info> {
+        let import_map = self.load_import_map(location)?;
+        let target = info.request().target();
+        let mapped = import_map
+            .resolve(target, info.normalized_path().as_ref())
+            .map_err(|key| {
+                Error::UnexpectedValue(format!(
+                    "'{target}' is blocked by '{key}' in the import map {}",
+                    location.display()
+                ))
+            })?;
+        let Some(mapped) = mapped else {
+            return Ok(info);
+        };
+        debug!(
+            "ImportMap maps '{target}' to '{mapped}'({})",
+            depth(&context.depth)
+        );
+        context.record(AttemptPhase::Alias, || format!("'{target}' -> '{mapped}'"));
+        Ok(info.with_path(mapped).with_target(""))
+    }
+}
+
+#[test]
+fn import_map_resolve_test() {
+    let base = Path::new("/app");
+    let json = serde_json::json!({
+        "imports": {
+            "a": "./a.js",
+            "a/": "./lib/a/",
+            "a/b/": "./lib/b/",
+            "blocked": null,
+            "remote": "https://example.com/remote.js",
+            "bare": "bare-target",
+            "invalid/": "./lib/invalid.js",
+            "./src/old.js": "./src/new.js",
+            "/app/vendor/": "./vendor-v2/",
+        },
+        "scopes": {
+            "/app/legacy/": { "a": "./legacy/a.js" },
+            "/app/legacy/deep/": { "a/": "./legacy/deep/a/" },
+            "/app/other": { "a": "./other.js" },
+        },
+    });
+    let map = ImportMap::parse(&json, base).unwrap();
+    let resolve = |specifier: &str, referrer: &str| map.resolve(specifier, Path::new(referrer));
+    let some = |path: &str| Ok(Some(String::from(path)));
+
+    assert_eq!(resolve("a", "/app/src"), some("/app/a.js"));
+    assert_eq!(resolve("a/x.js", "/app/src"), some("/app/lib/a/x.js"));
+    assert_eq!(resolve("a/b/x.js", "/app/src"), some("/app/lib/b/x.js"));
+    assert_eq!(resolve("a/../../x.js", "/app/src"), Err(String::from("a/")));
+    assert_eq!(resolve("c", "/app/src"), Ok(None));
+    assert_eq!(resolve("blocked", "/app/src"), Err(String::from("blocked")));
+    assert_eq!(resolve("remote", "/app/src"), Err(String::from("remote")));
+    assert_eq!(resolve("bare", "/app/src"), Err(String::from("bare")));
+    assert_eq!(
+        resolve("invalid/x.js", "/app/src"),
+        Err(String::from("invalid/"))
+    );
+    assert_eq!(resolve("./old.js", "/app/src"), some("/app/src/new.js"));
+    assert_eq!(
+        resolve("../src/old.js", "/app/src"),
+        some("/app/src/new.js")
+    );
+    assert_eq!(resolve("./other.js", "/app/src"), Ok(None));
+    assert_eq!(
+        resolve("/app/vendor/x.js", "/app"),
+        some("/app/vendor-v2/x.js")
+    );
+
+    // scopes.
+    assert_eq!(resolve("a", "/app/legacy"), some("/app/legacy/a.js"));
+    // cascading to the less specific scope and then the top-level imports.
+    assert_eq!(resolve("a", "/app/legacy/deep"), some("/app/legacy/a.js"));
+    assert_eq!(
+        resolve("a/x.js", "/app/legacy/deep"),
+        some("/app/legacy/deep/a/x.js")
+    );
+    assert_eq!(resolve("a/b/x.js", "/app/legacy"), some("/app/lib/b/x.js"));
+    // the scope without trailing slash only matches the exact referrer.
+    assert_eq!(resolve("a", "/app/other/x"), some("/app/a.js"));
+}
@@ -4,7 +4,7 @@ use path_absolutize::Absolutize;
 use std::os::unix::ffi::OsStrExt;
 #[cfg(windows)]
 use std::os::windows::ffi::OsStrExt;
-use std::{borrow::Cow, path::Path, sync::Arc};
+use std::{path::Path, sync::Arc};
 
 #[cfg(windows)]
 fn has_trailing_slash(p: &Path) -> bool {
@@ -38,6 +38,14 @@ impl AsRef<Path> for NormalizedPath {
     }
 }
 
+impl std::ops::Deref for NormalizedPath {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Info {
     path: NormalizedPath,
@@ -70,6 +78,12 @@ impl Info {
         }
     }
 
+    /// Same as `with_path`, but shares the normalized path without copying.
+    #[must_use]
+    pub fn with_normalized_path(self, path: NormalizedPath) -> Self {
+        Self { path, ..self }
+    }
+
     #[must_use]
     pub fn with_request(self, request: Request) -> Self {
         Self { request, ..self }
@@ -91,13 +105,25 @@ impl Info {
         &self.request
     }
 
+    /// Returns the path joined with the target, the path is shared
+    /// rather than copied if the target is empty.
     #[must_use]
-    pub fn to_resolved_path(&self) -> Cow<'_, Path> {
+    pub fn to_resolved_path(&self) -> NormalizedPath {
         if self.request.target().is_empty() || self.request.target() == "." {
-            Cow::Borrowed(&self.path.0)
+            self.path.clone()
         } else {
-            let p = NormalizedPath::new(self.path.as_ref().join(self.request.target()));
-            Cow::Owned(p.0.to_path_buf())
+            NormalizedPath::new(self.path.as_ref().join(self.request.target()))
         }
     }
 }
+
+#[test]
+fn test_to_resolved_path() {
+    let info = Info::new("/a/b", Default::default());
+    assert!(Arc::ptr_eq(&info.to_resolved_path().0, &info.path.0));
+    let info = info.with_target("./c/../d");
+    assert_eq!(info.to_resolved_path().as_ref(), Path::new("/a/b/d"));
+    let path = info.to_resolved_path();
+    let info = info.with_normalized_path(path.clone());
+    assert!(Arc::ptr_eq(&info.normalized_path().0, &path.0));
+}
@@ -1,14 +1,70 @@
-use crate::Resolver;
+//! The classification of requests, which is used by the resolver to
+//! decide how to resolve a request.
+
+use crate::{builtin::NODE_SCHEME, parse::Request, Resolver};
 use daachorse::{CharwiseDoubleArrayAhoCorasick, CharwiseDoubleArrayAhoCorasickBuilder, MatchKind};
 use once_cell::sync::Lazy;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// The kind of request, it decides how the request is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum PathKind {
+    /// Resolved from the directory of issuer, such as `./a`, `../a`, `.`
+    /// and the empty request.
     Relative,
+    /// Absolute path in Windows, such as `C:\a` and `C:/a`.
     AbsoluteWin,
+    /// Absolute path in POSIX, such as `/a`.
     AbsolutePosix,
+    /// Resolved by the `imports` field of package.json, such as `#a`.
     Internal,
+    /// Resolved from the modules directories, such as `a`, `@scope/a`
+    /// and `a/b`.
     Normal,
+    /// The builtin module with `node:` scheme, such as `node:fs`. The bare
+    /// names, such as `fs`, are `Normal`, they are resolved as the builtin
+    /// modules only if `builtin_modules` is enabled.
+    BuiltIn,
+    /// The URL with the other scheme, such as `https://a.com/a.js` and
+    /// `data:text/javascript,`, which is not found on the file system.
+    /// `file:` URLs are `Url` too, though they are resolved as the paths
+    /// in the ESM mode.
+    Url,
+}
+
+/// Returns the kind of `request` as the resolver does, the query and
+/// fragment of `request` are ignored.
+///
+/// ```
+/// use nodejs_resolver::kind::{classify, PathKind};
+///
+/// assert_eq!(classify("./a.svg?inline#icon"), PathKind::Relative);
+/// assert_eq!(classify("#internal"), PathKind::Internal);
+/// assert_eq!(classify("react"), PathKind::Normal);
+/// assert_eq!(classify("node:fs"), PathKind::BuiltIn);
+/// assert_eq!(classify("https://a.com/a.js"), PathKind::Url);
+/// ```
+#[must_use]
+pub fn classify(request: &str) -> PathKind {
+    if request.starts_with(NODE_SCHEME) {
+        PathKind::BuiltIn
+    } else if has_url_scheme(request) {
+        PathKind::Url
+    } else {
+        Request::from_request(request).kind()
+    }
+}
+
+/// Whether `request` starts with the scheme of a URL, the single letter
+/// one is the drive of Windows, such as `C:`.
+fn has_url_scheme(request: &str) -> bool {
+    request.split_once(':').map_or(false, |(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
 }
 
 static ABSOLUTE_WIN_PATTERN_LENGTH_TWO: [&str; 52] = [
@@ -108,3 +164,19 @@ fn test_resolver() {
     ));
     assert!(matches!(Resolver::get_target_kind("fs"), PathKind::Normal));
 }
+
+#[test]
+fn test_classify() {
+    assert_eq!(classify(""), PathKind::Relative);
+    assert_eq!(classify("./a?b#c"), PathKind::Relative);
+    assert_eq!(classify("/a#b"), PathKind::AbsolutePosix);
+    assert_eq!(classify("C:\\a"), PathKind::AbsoluteWin);
+    assert_eq!(classify("#a"), PathKind::Internal);
+    assert_eq!(classify("@scope/a"), PathKind::Normal);
+    assert_eq!(classify("node:fs"), PathKind::BuiltIn);
+    assert_eq!(classify("fs"), PathKind::Normal);
+    assert_eq!(classify("file:///a.js"), PathKind::Url);
+    assert_eq!(classify("data:text/javascript,"), PathKind::Url);
+    assert_eq!(classify("C:path"), PathKind::Normal);
+    assert_eq!(classify("./a:b"), PathKind::Relative);
+}
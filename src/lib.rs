@@ -17,88 +17,255 @@
 //! let resolver = Resolver::new(Default::default());
 //!
 //! resolver.resolve(&cwd.join("./src"), "foo");
-//! // -> ResolveResult::Info(ResolveInfo {
-//! //    path: PathBuf::from("<cwd>/node_modules/foo/index.js")
-//! //    request: Request {
-//! //       target: "",
-//! //       fragment: "",
-//! //       query: ""
-//! //    }
+//! // -> ResolveResult::File(Resource {
+//! //    path: PathBuf::from("<cwd>/node_modules/foo/index.js"),
+//! //    query: None,
+//! //    fragment: None,
+//! //    ..
 //! //  })
 //! //
 //!
 //! resolver.resolve(&cwd.join("./src"), "./foo");
-//! // -> ResolveResult::Info(ResolveInfo {
-//! //    path: PathBuf::from("<cwd>/src/foo.js")
-//! //    request: Request {
-//! //       target: "",
-//! //       fragment: "",
-//! //       query: ""
-//! //    }
+//! // -> ResolveResult::File(Resource {
+//! //    path: PathBuf::from("<cwd>/src/foo.js"),
+//! //    query: None,
+//! //    fragment: None,
+//! //    ..
 //! //  })
 //! //
 //! ```
 //!
+//! ## Ordering
+//!
+//! The result only depends on the options and the files, it is same across
+//! runs and threads. When several candidates are available:
+//!
+//! - `extensions`, `main_fields`, `main_files`, `modules` and the targets
+//!   of an alias are tried in order, the first resolved one wins.
+//! - The keys of `alias`, `fallback`, `prefix`, `scoped_alias`,
+//!   `extension_alias` and the rules of `rewrites` are matched in order,
+//!   the former declared one wins.
+//! - The keys of `"exports"`, `"imports"` and `"browser"` in package.json
+//!   and the conditions in them are matched in the declared order, unless
+//!   `prefer_condition_order` is enabled, then the conditions are matched
+//!   in the order of `condition_names`.
+//! - The `paths` in tsconfig prefer the exact key, then the longest prefix
+//!   before `*`, then the former declared one.
+//!
 
+mod bin;
+mod builtin;
 mod cache;
+mod check;
+mod completion;
 mod context;
+mod copies;
+mod dependencies;
 mod description;
+mod detect;
+mod dual;
 mod entry;
 mod error;
+mod expand;
+mod factory;
 mod fs;
+mod graph;
+mod import_map;
 mod info;
-mod kind;
+pub mod kind;
+mod loader;
+mod lockfile;
 mod log;
 mod map;
+mod mode;
+mod module_type;
+mod normalize;
 mod options;
 mod parse;
 mod plugin;
+mod pnpm;
 mod resolve;
 mod resource;
+mod roots;
+mod session;
+#[cfg(feature = "concurrent-alt")]
+mod sharded;
+mod source;
+mod specifier;
 mod state;
+mod suggest;
+mod targets;
+mod trace;
 mod tsconfig;
 mod tsconfig_path;
+mod version;
+mod warm;
+mod watch;
 
 pub use cache::Cache;
+#[cfg(feature = "concurrent-alt")]
+pub use cache::CacheBackend;
+pub use check::{Diagnostic, DiagnosticKind};
+pub use completion::{Completion, CompletionKind};
 use context::Context;
-pub use description::DescriptionData;
-pub use error::Error;
-use info::Info;
+pub use copies::PackageCopy;
+pub use description::{DescriptionData, ExportsForm, PkgJSON};
+pub use detect::{InstallLayout, ProjectLayout};
+pub use dual::{DualResolver, ResolvedPair};
+pub use entry::EntryStat;
+pub use error::{
+    Attempt, AttemptPhase, Budget, Error, ExportsFieldMisuse, InvalidExportsField,
+    InvalidSpecifier, PackagePathNotExported, ResolveFailed, UnsupportedDirImport, VersionMismatch,
+};
+pub use factory::ResolverFactory;
+pub use fs::{is_transient, CacheValidation, FileSystemCaseSensitivity, RetryPolicy, MAX_PATH};
+pub use graph::{Edge, ResolutionGraph};
+use info::{Info, NormalizedPath};
 use kind::PathKind;
-use log::{color, depth};
+pub use lockfile::{DependencyGraph, LockedVersions, LockfileMismatch};
+use log::{color, debug, depth, enter_span};
+pub use map::MappedTarget;
+use module_type::{insert_condition, switch_condition};
+pub use normalize::UnicodeNormalization;
 use options::EnforceExtension::{Auto, Disabled, Enabled};
-pub use options::{AliasMap, EnforceExtension, Options};
+pub use options::{
+    AliasMap, BeforeResolved, BuildEnv, CompatMode, EnforceExtension, ExportsPriority,
+    LockfileCheck, LockfileWarning, ManifestErrorPolicy, ManifestWarning, ModuleType, Options,
+    PathPreference, PipelineStage, PrefixMap, ResolutionMode, RootProfile, Target, Verdict,
+};
+pub use parse::{InvalidRequest, Request};
 use plugin::{
     AliasPlugin, BrowserFieldPlugin, ImportsFieldPlugin, ParsePlugin, Plugin, PreferRelativePlugin,
-    SymlinkPlugin,
+    PrefixPlugin, RewritePlugin, ScopedAliasPlugin, SymlinkPlugin,
 };
+pub use regex::Regex;
 pub use resource::Resource;
+pub use session::Session;
 use state::State;
+pub use targets::ExportedSubpath;
+pub use trace::{Trace, TraceOutcome};
+pub use tsconfig_path::TsConfigInfo;
+pub use warm::WarmEntries;
+pub use watch::FsEventKind;
 
 #[derive(Debug)]
 pub struct Resolver {
-    pub options: Options,
+    pub(crate) options: Options,
+    /// The fingerprint of the options passed to `Resolver::new`.
+    pub(crate) fingerprint: u64,
     pub(crate) cache: std::sync::Arc<Cache>,
+    /// The partition of `cache.entries` for `options`.
+    pub(crate) entries: std::sync::Arc<cache::EntryMap>,
+    /// The parsed `tsconfig.json` keyed by its location.
+    pub(crate) tsconfigs: dashmap::DashMap<
+        Box<std::path::Path>,
+        std::sync::Arc<TsConfigInfo>,
+        std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    >,
+    /// The parsed import maps keyed by its location.
+    pub(crate) import_maps: dashmap::DashMap<
+        Box<std::path::Path>,
+        std::sync::Arc<import_map::ImportMap>,
+        std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    >,
+    /// The successful results keyed by the path, the request and the
+    /// extensions of the request, the options are fixed for a resolver so
+    /// they are not part of the key.
+    /// It is cleared with entries by [`Resolver::clear_entries`].
+    pub(crate) results: Results,
+    /// The results memoized in the sessions of build scopes, which are
+    /// dropped by [`Resolver::drop_build_scope`] while the entries are kept.
+    pub(crate) scoped_results: dashmap::DashMap<
+        Box<str>,
+        std::sync::Arc<Results>,
+        std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    >,
+    /// The paths looked up by the memoized results, only recorded if
+    /// `track_dependencies` is enabled. They are kept after the results are
+    /// cleared by the events, see [`Resolver::affected_resolutions`].
+    pub(crate) dependencies: dashmap::DashMap<
+        ResultKey,
+        Vec<std::sync::Arc<std::path::Path>>,
+        std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+    >,
+    /// The events deferred by the sessions on the cache.
+    pub(crate) sessions: session::Sessions,
+    /// The resolvers of `roots` in the options, the inner roots first.
+    pub(crate) roots: Vec<(Box<std::path::Path>, Resolver)>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ResolveResult<T: Clone> {
-    Resource(T),
-    Ignored,
+    /// Resolved to a file.
+    File(T),
+    /// Resolved to a directory, only if `resolve_to_context` is enabled.
+    Directory(T),
+    /// The builtin module of Node.js with `node:` scheme, such as `node:fs`,
+    /// only if `builtin_modules` is enabled.
+    Builtin(String),
+    Ignored {
+        reason: IgnoredReason,
+        /// The original request passed to `resolve`.
+        request: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IgnoredReason {
+    /// Mapped to `false` by the key in `alias` or `fallback`.
+    Alias(String),
+    /// Mapped to `false` by the key in the `"browser"` field,
+    /// `dir` is the directory where the description file located.
+    BrowserField {
+        dir: std::path::PathBuf,
+        key: String,
+    },
+    /// Can't be resolved but matches the prefix in `optional_prefixes`.
+    Optional(String),
 }
 
 pub type RResult<T> = Result<T, Error>;
 
+/// Whether `request` is `prefix` or in it, such as `fsevents/lib` in
+/// `fsevents`, but not `fsevents-x`. The prefix ends with `/` matches all
+/// the requests starting with it.
+fn is_optional(prefix: &str, request: &str) -> bool {
+    request.strip_prefix(prefix).map_or(false, |rest| {
+        rest.is_empty() || prefix.ends_with('/') || rest.starts_with('/')
+    })
+}
+
+/// The path, the request and the extensions of the request.
+type ResultKey = (std::path::PathBuf, String, Option<Vec<String>>);
+
+type Results = dashmap::DashMap<
+    ResultKey,
+    ResolveResult<Resource>,
+    std::hash::BuildHasherDefault<rustc_hash::FxHasher>,
+>;
+
 impl Resolver {
+    /// # Panics
+    ///
+    /// Panics if `empty_module` of the options is not a file.
     #[must_use]
     pub fn new(options: Options) -> Self {
         log::enable_by_env();
+        let fingerprint = options.fingerprint();
 
         let cache = if let Some(external_cache) = options.external_cache.as_ref() {
             external_cache.clone()
         } else {
             std::sync::Arc::new(Cache::default())
         };
+        let roots = Self::root_resolvers(&options, &cache);
+
+        let options = Self::with_mode_options(options);
+        let options = match options.target {
+            Some(target) => Self::with_target_options(options, target),
+            None => options,
+        };
 
         let enforce_extension = match options.enforce_extension {
             Auto => {
@@ -125,12 +292,110 @@ impl Resolver {
             None => None,
         };
 
+        let empty_module = options.empty_module.map(|path| {
+            let path = std::env::current_dir().map_or_else(|_| path.clone(), |cwd| cwd.join(&path));
+            let path = NormalizedPath::new(path).to_path_buf();
+            assert!(
+                path.is_file(),
+                "The empty_module '{}' is not a file",
+                path.display()
+            );
+            path
+        });
+
+        // the boundaries are compared with the normalized paths of the walk.
+        let normalization = cache.fs.unicode_normalization();
+        let boundaries = options
+            .boundaries
+            .iter()
+            .map(|boundary| {
+                let boundary = std::env::current_dir()
+                    .map_or_else(|_| boundary.clone(), |cwd| cwd.join(boundary));
+                let boundary = NormalizedPath::new(boundary);
+                normalization.normalize_path(&boundary).into_owned()
+            })
+            .collect();
+
         let options = Options {
             enforce_extension,
             tsconfig,
+            boundaries,
+            empty_module,
             ..options
         };
-        Self { options, cache }
+        let options = if options.resolve_types {
+            Self::with_types_options(options)
+        } else {
+            options
+        };
+        let options = match options.env {
+            Some(env) => Self::with_env_options(options, env),
+            None => options,
+        };
+        let entries = cache.entries(&options);
+        Self {
+            options,
+            fingerprint,
+            cache,
+            entries,
+            tsconfigs: Default::default(),
+            import_maps: Default::default(),
+            results: Default::default(),
+            scoped_results: Default::default(),
+            dependencies: Default::default(),
+            sessions: Default::default(),
+            roots,
+        }
+    }
+
+    /// Returns the options used by the resolver, which are normalized by
+    /// `mode`, `resolve_types` and so on.
+    #[must_use]
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// Returns the fingerprint of the options passed to `Resolver::new`,
+    /// see `Options::fingerprint`.
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Returns `empty_module` of the options, which the requests mapped to
+    /// `false` are resolved to.
+    #[must_use]
+    pub fn empty_module_path(&self) -> Option<&std::path::Path> {
+        self.options.empty_module.as_deref()
+    }
+
+    /// Applies `before_resolved` of the options to the resolved file.
+    fn check_resolved(&self, path: &std::path::Path, request: &str, info: Info) -> RResult<Info> {
+        let Some(hook) = &self.options.before_resolved else {
+            return Ok(info);
+        };
+        let resolved = info.normalized_path();
+        match hook.before_resolved(path, request, resolved.as_ref()) {
+            Verdict::Accept => Ok(info),
+            Verdict::Reject => Err(Error::Rejected(resolved.as_ref().to_path_buf())),
+            Verdict::Replace(replaced) => {
+                if !replaced.is_absolute() || !self.load_entry(&replaced).try_stat()?.is_file() {
+                    return Err(Error::UnexpectedValue(format!(
+                        "'{request}' resolved to '{}' is replaced by '{}' in before_resolved, which is not an absolute path to a file",
+                        resolved.as_ref().display(),
+                        replaced.display()
+                    )));
+                }
+                Ok(info.with_path(replaced))
+            }
+        }
+    }
+
+    /// Returns the cache of the resolver, pass it to `external_cache` to
+    /// share the cache with another resolver.
+    #[must_use]
+    pub fn cache(&self) -> std::sync::Arc<Cache> {
+        self.cache.clone()
     }
 
     pub fn resolve(
@@ -138,31 +403,103 @@ impl Resolver {
         path: &std::path::Path,
         request: &str,
     ) -> RResult<ResolveResult<Resource>> {
-        tracing::debug!(
+        self.resolve_memoized(path, request, None, None)
+    }
+
+    /// Same as `resolve`, but only `extensions` are tried for the request
+    /// and the requests redirected from it, such as `[".css", ".scss"]`
+    /// for the import of CSS. `binary_extensions` are still tried at last.
+    pub fn resolve_with_extensions(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        extensions: &[String],
+    ) -> RResult<ResolveResult<Resource>> {
+        self.resolve_memoized(path, request, Some(extensions), None)
+    }
+
+    /// Resolves with the results memoized in `build_scope`, or the ones of
+    /// the resolver if it is `None`.
+    fn resolve_memoized(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        extensions: Option<&[String]>,
+        build_scope: Option<&str>,
+    ) -> RResult<ResolveResult<Resource>> {
+        self.apply_deferred_fs_events();
+        if let Some(resolver) = self.root_resolver(path) {
+            return resolver.resolve_memoized(path, request, extensions, build_scope);
+        }
+        // the shard is not locked during the resolution.
+        let scoped_results = build_scope.map(|build_scope| {
+            self.scoped_results
+                .entry(build_scope.into())
+                .or_default()
+                .clone()
+        });
+        let results = scoped_results.as_deref().unwrap_or(&self.results);
+        let normalization = self.cache.fs.unicode_normalization();
+        let path = &*normalization.normalize_path(path);
+        let request = &*normalization.normalize_str(request);
+        let key = (
+            path.to_path_buf(),
+            request.to_string(),
+            extensions.map(<[String]>::to_vec),
+        );
+        if let Some(cached) = results.get(&key) {
+            return Ok(cached.clone());
+        }
+        let resolve = || {
+            #[cfg(feature = "tracing")]
+            if self.options.debug {
+                return tracing::subscriber::with_default(log::debug_subscriber(), || {
+                    self.resolve_uncached(path, request, extensions)
+                });
+            }
+            self.resolve_uncached(path, request, extensions)
+        };
+        let result = if self.options.track_dependencies {
+            let (result, dependencies) = dependencies::recording(resolve);
+            // the failed ones are recorded too, so they are resolved again
+            // after the missing paths are added.
+            self.dependencies.insert(key.clone(), dependencies);
+            result?
+        } else {
+            resolve()?
+        };
+        if let (Some(check), ResolveResult::File(resource)) =
+            (&self.options.lockfile_check, &result)
+        {
+            if let Some(mismatch) = self.verify_locked_version(resource) {
+                check.warning.warn(&mismatch);
+            }
+        }
+        results.insert(key, result.clone());
+        Ok(result)
+    }
+
+    fn resolve_uncached(
+        &self,
+        path: &std::path::Path,
+        request: &str,
+        extensions: Option<&[String]>,
+    ) -> RResult<ResolveResult<Resource>> {
+        enter_span!("resolve", request, path = %path.display());
+        debug!(
             "{:-^30}\nTry to resolve '{}' in '{}'",
             color::green(&"[RESOLVER]"),
             color::cyan(&request),
             color::cyan(&path.display().to_string())
         );
         // let start = std::time::Instant::now();
-        let parsed = Self::parse(request);
+        if let Some(reason) = Request::invalid_reason(request) {
+            return Err(Error::InvalidRequest(reason));
+        }
+        let parsed = Self::parse(&self.to_mode_request(request)?);
         let info = Info::new(path, parsed);
-        let mut context = Context::new(
-            self.options.fully_specified,
-            self.options.resolve_to_context,
-        );
-        let result = if let Some(tsconfig_location) = self.options.tsconfig.as_ref() {
-            self._resolve_with_tsconfig(info, tsconfig_location, &mut context)
-        } else {
-            self._resolve(info, &mut context)
-        };
-
-        let result = result.map_failed(|info| {
-            type FallbackPlugin<'a> = AliasPlugin<'a>;
-            FallbackPlugin::new(&self.options.fallback).apply(self, info, &mut context)
-        });
-        let result =
-            result.map_success(|info| SymlinkPlugin::default().apply(self, info, &mut context));
+        let mut context = self.new_context(path, extensions)?;
+        let result = self.resolve_with_context(info.clone(), &mut context);
 
         // let duration = start.elapsed().as_millis();
         // println!("time cost: {:?} us", duration); // us
@@ -176,48 +513,281 @@ impl Resolver {
         // }
 
         match result {
-            State::Success(ResolveResult::Ignored) => Ok(ResolveResult::Ignored),
-            State::Success(ResolveResult::Resource(info)) => {
+            State::Success(ResolveResult::Ignored { reason, .. }) => {
+                if let Some(path) = self.empty_module_path() {
+                    let info = Info::new(path, Default::default());
+                    return Ok(ResolveResult::File(Resource::new(info, self)));
+                }
+                Ok(ResolveResult::Ignored {
+                    reason,
+                    request: request.to_string(),
+                })
+            }
+            State::Success(ResolveResult::File(info)) => {
+                if let Some(source) = self.redirect_to_source(request, &info)? {
+                    return Ok(source);
+                }
+                let info = self.check_resolved(path, request, info)?;
+                let mut resource = Resource::new(info, self);
+                if context.symlinks.last() == Some(&resource.path) {
+                    resource.symlinks = context.symlinks;
+                }
+                Ok(ResolveResult::File(resource))
+            }
+            State::Success(ResolveResult::Directory(info)) => {
                 let resource = Resource::new(info, self);
-                Ok(ResolveResult::Resource(resource))
+                Ok(ResolveResult::Directory(resource))
             }
+            State::Success(ResolveResult::Builtin(name)) => Ok(ResolveResult::Builtin(name)),
             State::Error(err) => Err(err),
-            State::Resolving(_) | State::Failed(_) => Err(Error::ResolveFailedTag),
+            State::Resolving(_) | State::Failed(_) => {
+                if let Some(prefix) = self
+                    .options
+                    .optional_prefixes
+                    .iter()
+                    .find(|prefix| is_optional(prefix, request))
+                {
+                    return Ok(ResolveResult::Ignored {
+                        reason: IgnoredReason::Optional(prefix.to_string()),
+                        request: request.to_string(),
+                    });
+                }
+                // resolve again to record the attempts, so that the
+                // successful resolution does not pay for it.
+                Err(Error::ResolveFailed(Box::new(ResolveFailed {
+                    request: request.to_string(),
+                    path: path.to_path_buf(),
+                    suggestions: self.suggest_for_failed(path, request),
+                    attempts: self.record_attempts(path, info, extensions)?,
+                })))
+            }
         }
     }
 
-    fn _resolve(&self, info: Info, context: &mut Context) -> State {
-        tracing::debug!(
-            "Resolving '{request}' in '{path}'",
-            request = color::cyan(&info.request().target()),
-            path = color::cyan(&info.normalized_path().as_ref().display())
+    /// Resolves `info` again with the attempts recorded.
+    fn record_attempts(
+        &self,
+        path: &std::path::Path,
+        info: Info,
+        extensions: Option<&[String]>,
+    ) -> RResult<Vec<Attempt>> {
+        let mut context = self.new_context(path, extensions)?;
+        context.attempts = Some(vec![]);
+        self.resolve_with_context(info, &mut context);
+        Ok(context.attempts.unwrap_or_default())
+    }
+
+    fn new_context(
+        &self,
+        path: &std::path::Path,
+        extensions: Option<&[String]>,
+    ) -> RResult<Context> {
+        let mut context = Context::new(
+            self.options.fully_specified,
+            self.options.resolve_to_context,
         );
+        context.extensions = extensions.map(<[String]>::to_vec);
+        if self.options.module_type_defaults {
+            context.module_type = Some(self.module_type_of(path)?.unwrap_or(ModuleType::CommonJs));
+            if context.module_type == Some(ModuleType::Module) {
+                context.fully_specified.set(true);
+            }
+        }
+        Ok(context)
+    }
 
-        context.depth.increase();
-        if context.depth.cmp(127).is_ge() {
-            return State::Error(Error::Overflow);
+    fn resolve_with_context(&self, info: Info, context: &mut Context) -> State {
+        let info = match self.options.import_map.as_ref() {
+            Some(location) => match self.apply_import_map(location, info, context) {
+                Ok(info) => info,
+                Err(error) => return State::Error(error),
+            },
+            None => info,
+        };
+        let result = if let Some(tsconfig_location) = self.options.tsconfig.as_ref() {
+            self._resolve_with_tsconfig(info, tsconfig_location, context)
+        } else {
+            self._resolve(info, context)
+        };
+
+        let result = result.map_failed(|info| self.resolve_as_global_paths(info, context));
+        let result = result.map_failed(|info| {
+            type FallbackPlugin<'a> = AliasPlugin<'a>;
+            FallbackPlugin::new(&self.options.fallback).apply(self, info, context)
+        });
+        result.map_success(|info| SymlinkPlugin::default().apply(self, info, context))
+    }
+
+    fn with_types_options(options: Options) -> Options {
+        fn prepend(list: &[&str], rest: Vec<String>) -> Vec<String> {
+            list.iter()
+                .map(|item| item.to_string())
+                .chain(
+                    rest.into_iter()
+                        .filter(|item| !list.contains(&item.as_str())),
+                )
+                .collect()
+        }
+        let mut condition_names = options.condition_names;
+        insert_condition(&mut condition_names, "types");
+        Options {
+            main_fields: prepend(&["types", "typings"], options.main_fields),
+            extensions: prepend(&[".d.ts"], options.extensions),
+            condition_names,
+            ..options
         }
+    }
 
-        let state = ParsePlugin::default()
-            .apply(self, info, context)
-            .then(|info| AliasPlugin::new(&self.options.alias).apply(self, info, context))
-            .then(|info| PreferRelativePlugin::default().apply(self, info, context))
-            .then(|info| {
+    fn with_env_options(options: Options, env: BuildEnv) -> Options {
+        let other = match env {
+            BuildEnv::Development => BuildEnv::Production,
+            BuildEnv::Production => BuildEnv::Development,
+        };
+        let mut condition_names = options.condition_names;
+        switch_condition(&mut condition_names, env.condition(), other.condition());
+        Options {
+            condition_names,
+            ..options
+        }
+    }
+
+    fn with_target_options(options: Options, target: Target) -> Options {
+        let mut extensions = match options
+            .target_extensions
+            .iter()
+            .find(|(item, _)| *item == target)
+        {
+            Some((_, extensions)) => extensions.clone(),
+            None => options.extensions,
+        };
+        let mut binary_extensions = options.binary_extensions;
+        if !target.has_native_addons() {
+            extensions.retain(|ext| ext != ".node");
+            binary_extensions.retain(|ext| ext != ".node");
+        }
+        Options {
+            extensions,
+            binary_extensions,
+            ..options
+        }
+    }
+
+    fn apply_stage(&self, stage: PipelineStage, info: Info, context: &mut Context) -> State {
+        match stage {
+            // the `#` specifiers are owned by `"imports"` in Node.js.
+            PipelineStage::ScopedAlias | PipelineStage::Alias
+                if self.options.compat == CompatMode::Node
+                    && info.request().kind() == PathKind::Internal =>
+            {
+                State::Resolving(info)
+            }
+            PipelineStage::Rewrite => {
+                RewritePlugin::new(&self.options.rewrites).apply(self, info, context)
+            }
+            PipelineStage::Prefix => {
+                PrefixPlugin::new(&self.options.prefix).apply(self, info, context)
+            }
+            PipelineStage::ScopedAlias => {
+                ScopedAliasPlugin::new(&self.options.scoped_alias).apply(self, info, context)
+            }
+            PipelineStage::Alias => {
+                AliasPlugin::new(&self.options.alias).apply(self, info, context)
+            }
+            PipelineStage::PreferRelative => {
+                PreferRelativePlugin::default().apply(self, info, context)
+            }
+            PipelineStage::ImportsField | PipelineStage::BrowserField => {
                 let request = info.to_resolved_path();
                 let entry = self.load_entry(&request);
                 let pkg_info = match entry.pkg_info(self) {
-                    Ok(pkg_info) => pkg_info,
+                    Ok(Some(pkg_info)) => pkg_info,
+                    Ok(None) => return State::Resolving(info),
                     Err(error) => return State::Error(error),
                 };
-                if let Some(pkg_info) = pkg_info {
-                    ImportsFieldPlugin::new(pkg_info)
-                        .apply(self, info, context)
-                        .then(|info| {
-                            BrowserFieldPlugin::new(pkg_info, false).apply(self, info, context)
-                        })
+                if stage == PipelineStage::ImportsField {
+                    ImportsFieldPlugin::new(pkg_info).apply(self, info, context)
                 } else {
-                    State::Resolving(info)
+                    BrowserFieldPlugin::new(pkg_info, false).apply(self, info, context)
                 }
+            }
+        }
+    }
+
+    fn _resolve(&self, info: Info, context: &mut Context) -> State {
+        enter_span!(
+            "redirect",
+            request = %info.request(),
+            path = %info.normalized_path().as_ref().display(),
+        );
+        debug!(
+            "Resolving '{request}' in '{path}'",
+            request = color::cyan(&info.request().target()),
+            path = color::cyan(&info.normalized_path().as_ref().display())
+        );
+
+        // the redirects are resolved recursively, so the depth also bounds
+        // the stack.
+        context.depth.increase();
+        if context.depth.cmp(self.options.max_depth).is_ge() {
+            return State::Error(Error::Overflow(Budget::MaxDepth(self.options.max_depth)));
+        }
+        // the first one and the walk of ancestors in `resolve_as_modules`
+        // are not redirected.
+        let depth = context.depth.value();
+        if depth > 1 && context.modules_walk_depth != Some(depth) {
+            context.redirects += 1;
+            if context.redirects > self.options.max_redirects {
+                return State::Error(Error::Overflow(Budget::MaxRedirects(
+                    self.options.max_redirects,
+                )));
+            }
+        }
+
+        let key = (
+            info.normalized_path().clone(),
+            info.request().clone(),
+            context.fully_specified.get(),
+        );
+        let cycle = context
+            .resolving
+            .iter()
+            .position(|item| item == &key)
+            .map(|index| {
+                context.resolving[index..]
+                    .iter()
+                    .chain(std::iter::once(&key))
+                    .map(|(path, request, _)| (path.to_path_buf(), request.to_string()))
+                    .collect::<Vec<_>>()
+            });
+        context.resolving.push(key);
+
+        let mut cycle = cycle;
+        let state = self
+            .options
+            .pipeline
+            .iter()
+            .fold(
+                ParsePlugin::default().apply(self, info, context),
+                |state, stage| {
+                    state.then(|info| {
+                        // check the cycle after alias so that `AliasCycle` is preferred.
+                        if matches!(
+                            stage,
+                            PipelineStage::PreferRelative
+                                | PipelineStage::ImportsField
+                                | PipelineStage::BrowserField
+                        ) {
+                            if let Some(cycle) = cycle.take() {
+                                return State::Error(Error::ResolveCycle(cycle));
+                            }
+                        }
+                        self.apply_stage(*stage, info, context)
+                    })
+                },
+            )
+            .then(|info| match cycle {
+                Some(cycle) => State::Error(Error::ResolveCycle(cycle)),
+                None => State::Resolving(info),
             })
             .then(|info| {
                 if matches!(
@@ -226,18 +796,39 @@ impl Resolver {
                 ) {
                     self.resolve_as_context(info, context)
                         .then(|info| self.resolve_as_fully_specified(info, context))
-                        .then(|info| self.resolve_as_file(info, context))
-                        .then(|info| self.resolve_as_dir(info, context))
+                        .then(|info| self.resolve_as_file_or_dir(info, context))
                 } else {
                     self.resolve_as_modules(info, context)
                 }
             });
 
+        context.resolving.pop();
         context.depth.decrease();
         state
     }
 }
 
+#[test]
+fn memo_test() {
+    let case_path = test_helper::p(vec!["simple"]);
+    let resolver = Resolver::new(Default::default());
+    let resolved = resolver.resolve(&case_path, "./lib/index").unwrap();
+    assert_eq!(resolver.results.len(), 1);
+    let memoized = resolver.resolve(&case_path, "./lib/index").unwrap();
+    match (resolved, memoized) {
+        (ResolveResult::File(resolved), ResolveResult::File(memoized)) => {
+            assert_eq!(resolved.path, memoized.path)
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(resolver.results.len(), 1);
+    // failed results are not memoized.
+    assert!(resolver.resolve(&case_path, "./not-exist").is_err());
+    assert_eq!(resolver.results.len(), 1);
+    resolver.clear_entries();
+    assert!(resolver.results.is_empty());
+}
+
 #[cfg(debug_assertions)]
 pub mod test_helper {
     #[must_use]
@@ -252,7 +843,64 @@ pub mod test_helper {
     }
 
     #[must_use]
-    pub fn vec_to_set(vec: Vec<&str>) -> std::collections::HashSet<String> {
-        std::collections::HashSet::from_iter(vec.into_iter().map(|s| s.to_string()))
+    pub fn vec_to_list(vec: Vec<&str>) -> Vec<String> {
+        vec.into_iter().map(|s| s.to_string()).collect()
+    }
+}
+
+#[test]
+fn build_scope_test() {
+    let case_path = test_helper::p(vec!["simple"]);
+    let resolver = Resolver::new(Default::default());
+    let session = resolver.begin_scoped_session("build-1");
+    assert_eq!(session.build_scope(), Some("build-1"));
+    assert!(session.resolve(&case_path, "./lib/index").is_ok());
+    assert!(resolver.results.is_empty());
+    assert_eq!(resolver.scoped_results.get("build-1").unwrap().len(), 1);
+    drop(session);
+    let entries = resolver.entries.len();
+    assert!(resolver.drop_build_scope("build-1"));
+    assert!(!resolver.drop_build_scope("build-1"));
+    assert!(resolver.scoped_results.is_empty());
+    // the entries are kept for the next build.
+    assert_eq!(resolver.entries.len(), entries);
+}
+
+#[test]
+fn transient_stat_error_test() {
+    use std::io;
+    let case_path = test_helper::p(vec!["simple"]);
+    let file = case_path.join("lib").join("index.js");
+    fs::INJECTED_ERROR.with(|injected| {
+        *injected.borrow_mut() = Some((file.clone(), io::ErrorKind::Interrupted));
+    });
+    let resolver = Resolver::new(Default::default());
+    let result = resolver.resolve(&case_path, "./lib/index");
+    // the unknown file is not cached, it is resolved after the recovery.
+    fs::INJECTED_ERROR.with(|injected| injected.borrow_mut().take());
+    match result {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::Interrupted),
+        result => panic!("{result:?}"),
+    }
+    match resolver.resolve(&case_path, "./lib/index") {
+        Ok(ResolveResult::File(resource)) => assert_eq!(resource.path, file),
+        result => panic!("{result:?}"),
+    }
+
+    // the error of the nested resolution, which redirects to the source.
+    let case_path = test_helper::p(vec!["source-root", "app"]);
+    let source = test_helper::p(vec!["source-root", "packages", "a", "src", "index.ts"]);
+    fs::INJECTED_ERROR.with(|injected| {
+        *injected.borrow_mut() = Some((source, io::ErrorKind::TimedOut));
+    });
+    let resolver = Resolver::new(Options {
+        source_field: Some(String::from("source")),
+        ..Default::default()
+    });
+    let result = resolver.resolve(&case_path, "@ws/a");
+    fs::INJECTED_ERROR.with(|injected| injected.borrow_mut().take());
+    match result {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::TimedOut),
+        result => panic!("{result:?}"),
     }
 }
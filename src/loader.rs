@@ -0,0 +1,32 @@
+use crate::{module_type::insert_condition, EnforceExtension, Options, ResolutionMode, Resolver};
+
+impl Resolver {
+    /// Creates a resolver for loaders and plugins of webpack or rspack,
+    /// which is the same as `resolveLoader` of webpack:
+    ///
+    /// - `main_fields` is `["loader", "main"]`.
+    /// - `extensions` is `[".js"]` and `main_files` is `["index"]`.
+    /// - `modules` is `["node_modules"]`.
+    /// - `"loader"`, `"require"` and `"node"` are added to `condition_names`.
+    ///
+    /// The other options are kept, pass the same `external_cache` of the
+    /// normal resolver to share the cache with it.
+    #[must_use]
+    pub fn new_loader_resolver(options: Options) -> Self {
+        let mut condition_names = options.condition_names;
+        for name in ["loader", "require", "node"] {
+            insert_condition(&mut condition_names, name);
+        }
+        Resolver::new(Options {
+            mode: ResolutionMode::Default,
+            main_fields: vec![String::from("loader"), String::from("main")],
+            extensions: vec![String::from(".js")],
+            enforce_extension: EnforceExtension::Disabled,
+            main_files: vec![String::from("index")],
+            modules: vec![String::from("node_modules")],
+            browser_field: false,
+            condition_names,
+            ..options
+        })
+    }
+}
@@ -0,0 +1,504 @@
+//! The lockfiles of the project: the logical dependency graph, which
+//! resolves the bare requests by the dependencies declared for each
+//! package rather than the directories installed in `node_modules`, see
+//! `Options::dependency_graph`, and the versions pinned for the packages,
+//! which verify the installed ones, see `Options::lockfile_check`. The
+//! parsers of each lockfile are behind the feature of the same name.
+
+#[cfg(any(feature = "package-lock", feature = "pnpm-lock", feature = "yarn-lock"))]
+use crate::{Error, RResult};
+use crate::{Resolver, Resource};
+#[cfg(feature = "package-lock")]
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// The fields of the dependencies in the entries of package-lock.json.
+#[cfg(feature = "package-lock")]
+const DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "optionalDependencies",
+    "peerDependencies",
+    "devDependencies",
+];
+
+/// The packages and the directories of their dependencies, the packages
+/// are keyed by their directories, including the project and the
+/// workspaces.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct DependencyGraph {
+    packages: BTreeMap<PathBuf, BTreeMap<String, PathBuf>>,
+}
+
+impl DependencyGraph {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the package in `dir` with the directories of its dependencies
+    /// keyed by the names, the name is the alias if the dependency is
+    /// installed by an alias.
+    pub fn add_package(&mut self, dir: PathBuf, dependencies: Vec<(String, PathBuf)>) {
+        self.packages.entry(dir).or_default().extend(dependencies);
+    }
+
+    /// Builds the graph from `content` of package-lock.json in `root`,
+    /// only the `"packages"` of lockfile version 2 and 3 is supported. The
+    /// location of each dependency is looked up by the keys in the
+    /// lockfile, same as the hoisting of npm, and the linked workspaces
+    /// are mapped to their directories.
+    #[cfg(feature = "package-lock")]
+    pub fn from_package_lock(root: &Path, content: &str) -> RResult<Self> {
+        let path = root.join("package-lock.json");
+        let lockfile: Value = serde_json::from_str(content)
+            .map_err(|error| Error::UnexpectedJson((path.clone().into(), error)))?;
+        let Some(packages) = lockfile.get("packages").and_then(Value::as_object) else {
+            return Err(Error::UnexpectedValue(format!(
+                "No \"packages\" in '{}', the lockfile version 1 is not supported",
+                path.display()
+            )));
+        };
+        let location = |key: &str| match packages.get(key) {
+            Some(entry) if entry.get("link").and_then(Value::as_bool) == Some(true) => entry
+                .get("resolved")
+                .and_then(Value::as_str)
+                .map(|resolved| join(root, resolved)),
+            Some(_) => Some(join(root, key)),
+            None => None,
+        };
+        let mut graph = Self::new();
+        for (key, entry) in packages {
+            if entry.get("link").and_then(Value::as_bool) == Some(true) {
+                continue;
+            }
+            let names = DEPENDENCY_FIELDS
+                .iter()
+                .filter_map(|field| entry.get(field).and_then(Value::as_object))
+                .flat_map(|dependencies| dependencies.keys());
+            let mut dependencies = vec![];
+            for name in names {
+                // from the nested `node_modules` to the root.
+                let mut base = key.as_str();
+                let dir = loop {
+                    let key = match base.is_empty() {
+                        true => format!("node_modules/{name}"),
+                        false => format!("{base}/node_modules/{name}"),
+                    };
+                    if let Some(dir) = location(&key) {
+                        break Some(dir);
+                    }
+                    if base.is_empty() {
+                        break None;
+                    }
+                    base = base
+                        .rfind("/node_modules/")
+                        .map_or("", |index| &base[..index]);
+                };
+                if let Some(dir) = dir {
+                    dependencies.push((name.clone(), dir));
+                }
+            }
+            graph.add_package(join(root, key), dependencies);
+        }
+        Ok(graph)
+    }
+
+    /// Returns the dependencies of the innermost package containing
+    /// `path`, `None` if `path` is not in any package of the graph.
+    pub(crate) fn dependencies_of(&self, path: &Path) -> Option<&BTreeMap<String, PathBuf>> {
+        path.ancestors().find_map(|dir| self.packages.get(dir))
+    }
+}
+
+/// The versions of the packages pinned by a lockfile, they are keyed by
+/// the locations in package-lock.json, or by the names in the lockfiles
+/// which don't record the locations.
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+pub struct LockedVersions {
+    root: PathBuf,
+    locations: BTreeMap<PathBuf, String>,
+    names: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// A package whose version is not pinned by the lockfile, see
+/// [`crate::Resolver::verify_locked_version`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockfileMismatch {
+    /// The directory of the package.
+    pub path: PathBuf,
+    pub name: String,
+    /// `None` if the description file has no `version`.
+    pub version: Option<String>,
+    /// The versions pinned for the package, it is empty if the package is
+    /// absent in the lockfile.
+    pub pinned: Vec<String>,
+}
+
+impl LockedVersions {
+    /// Creates the empty versions of the project in `root`, the locations
+    /// are relative to it.
+    #[must_use]
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            ..Default::default()
+        }
+    }
+
+    /// Pins `version` for the package `name`, and for the package in `dir`
+    /// if it is some.
+    pub fn insert(&mut self, name: &str, version: &str, dir: Option<PathBuf>) {
+        if let Some(dir) = dir {
+            self.locations.insert(dir, version.to_string());
+        }
+        self.names
+            .entry(name.to_string())
+            .or_default()
+            .insert(version.to_string());
+    }
+
+    /// Returns the versions pinned for the package `name` in `dir`, the
+    /// version of the location is preferred. Returns `None` if the package
+    /// is absent in the lockfile.
+    #[must_use]
+    pub fn pinned(&self, dir: &Path, name: &str) -> Option<Vec<&str>> {
+        if let Some(version) = self.locations.get(dir) {
+            return Some(vec![version]);
+        }
+        self.names
+            .get(name)
+            .map(|versions| versions.iter().map(String::as_str).collect())
+    }
+
+    /// Parses package-lock.json or npm-shrinkwrap.json in `root`, the
+    /// locations are read from `"packages"` of the lockfile version 2 and
+    /// 3, or the nested `"dependencies"` of the version 1.
+    #[cfg(feature = "package-lock")]
+    pub fn from_package_lock(root: &Path, content: &str) -> RResult<Self> {
+        fn walk(versions: &mut LockedVersions, dir: &Path, dependencies: &Value) {
+            for (name, entry) in dependencies.as_object().into_iter().flatten() {
+                let dir = join(dir, &format!("node_modules/{name}"));
+                if let Some(version) = entry.get("version").and_then(Value::as_str) {
+                    versions.insert(name, version, Some(dir.clone()));
+                }
+                if let Some(dependencies) = entry.get("dependencies") {
+                    walk(versions, &dir, dependencies);
+                }
+            }
+        }
+        let lockfile: Value = serde_json::from_str(content).map_err(|error| {
+            Error::UnexpectedJson((root.join("package-lock.json").into(), error))
+        })?;
+        let mut versions = Self::new(root.to_path_buf());
+        match lockfile.get("packages").and_then(Value::as_object) {
+            Some(packages) => {
+                for (key, entry) in packages {
+                    let Some((_, name)) = key.rsplit_once("node_modules/") else {
+                        // the project and the workspaces.
+                        continue;
+                    };
+                    if let Some(version) = entry.get("version").and_then(Value::as_str) {
+                        versions.insert(name, version, Some(join(root, key)));
+                    }
+                }
+            }
+            None => {
+                if let Some(dependencies) = lockfile.get("dependencies") {
+                    walk(&mut versions, root, dependencies);
+                }
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Parses pnpm-lock.yaml in `root`, the keys of `packages` are read
+    /// line by line, such as `/a@1.0.0(b@2.0.0):` of version 6, `a@1.0.0:`
+    /// of version 9 and `/a/1.0.0_b@2.0.0:` of version 5.
+    #[cfg(feature = "pnpm-lock")]
+    pub fn from_pnpm_lock(root: &Path, content: &str) -> RResult<Self> {
+        let mut versions = Self::new(root.to_path_buf());
+        let mut in_packages = false;
+        let mut v5 = false;
+        for line in content.lines() {
+            if let Some(version) = line.strip_prefix("lockfileVersion:") {
+                v5 = version
+                    .trim()
+                    .trim_matches(|c| c == '\'' || c == '"')
+                    .starts_with('5');
+            }
+            if !line.starts_with(' ') && !line.trim().is_empty() {
+                in_packages = line.trim_end() == "packages:";
+                continue;
+            }
+            let Some(key) = line
+                .strip_prefix("  ")
+                .filter(|key| in_packages && !key.starts_with(' '))
+                .and_then(|key| key.trim_end().strip_suffix(':'))
+            else {
+                continue;
+            };
+            let key = key
+                .trim_matches(|c| c == '\'' || c == '"')
+                .trim_start_matches('/');
+            // the peer dependencies are suffixed to the version by `_` in
+            // version 5, and by `(...)` in the later versions.
+            let pair = match v5 {
+                true => key.rsplit_once('/').map(|(name, version)| {
+                    let version = version
+                        .split_once('_')
+                        .map_or(version, |(version, _)| version);
+                    (name, version)
+                }),
+                false => {
+                    let key = key.split_once('(').map_or(key, |(key, _)| key);
+                    key.get(1..)
+                        .and_then(|rest| rest.find('@'))
+                        .map(|index| (&key[..=index], &key[index + 2..]))
+                }
+            };
+            let Some((name, version)) = pair else {
+                return Err(Error::UnexpectedValue(format!(
+                    "Invalid package '{key}' in '{}'",
+                    root.join("pnpm-lock.yaml").display()
+                )));
+            };
+            versions.insert(name, version, None);
+        }
+        Ok(versions)
+    }
+
+    /// Parses yarn.lock in `root`, both the format of yarn classic and the
+    /// YAML of yarn berry are read line by line, the name is taken from
+    /// the first descriptor of each entry.
+    #[cfg(feature = "yarn-lock")]
+    pub fn from_yarn_lock(root: &Path, content: &str) -> RResult<Self> {
+        let mut versions = Self::new(root.to_path_buf());
+        let mut name = None;
+        for line in content.lines() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            if !line.starts_with(' ') {
+                let descriptor = line
+                    .trim_end()
+                    .trim_end_matches(':')
+                    .split(", ")
+                    .next()
+                    .unwrap_or_default()
+                    .trim_matches('"');
+                name = descriptor
+                    .get(1..)
+                    .and_then(|rest| rest.find('@'))
+                    .map(|index| descriptor[..=index].to_string());
+                if name.is_none() && descriptor != "__metadata" {
+                    return Err(Error::UnexpectedValue(format!(
+                        "Invalid descriptor '{descriptor}' in '{}'",
+                        root.join("yarn.lock").display()
+                    )));
+                }
+                continue;
+            }
+            let version = line
+                .trim()
+                .strip_prefix("version")
+                .map(|version| version.trim_start_matches(':').trim().trim_matches('"'));
+            if let (Some(name), Some(version)) = (&name, version) {
+                versions.insert(name, version, None);
+            }
+        }
+        Ok(versions)
+    }
+
+    pub(crate) fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Resolver {
+    /// Verifies the package of `resource` against `Options::lockfile_check`,
+    /// returns the mismatch if its version is not pinned by the lockfile.
+    /// Only the packages in the `modules` directories of the project are
+    /// verified, the ones without `"name"` are skipped.
+    pub fn verify_locked_version(&self, resource: &Resource) -> Option<LockfileMismatch> {
+        let versions = &self.options.lockfile_check.as_ref()?.versions;
+        let package_root = resource.package_root.as_ref()?;
+        if !package_root.starts_with(versions.root()) {
+            return None;
+        }
+        let description = resource
+            .description
+            .as_ref()
+            .filter(|description| description.dir().as_ref() == package_root)?;
+        let name = description.data().name()?;
+        let version = description.data().version();
+        let pinned = versions.pinned(package_root, name).unwrap_or_default();
+        if version.map_or(false, |version| pinned.contains(&version)) {
+            return None;
+        }
+        Some(LockfileMismatch {
+            path: package_root.clone(),
+            name: name.to_string(),
+            version: version.map(String::from),
+            pinned: pinned.into_iter().map(String::from).collect(),
+        })
+    }
+}
+
+/// Joins the key of package-lock.json, which is separated by `/`.
+#[cfg(feature = "package-lock")]
+fn join(root: &Path, key: &str) -> PathBuf {
+    key.split('/')
+        .filter(|name| !name.is_empty())
+        .fold(root.to_path_buf(), |dir, name| dir.join(name))
+}
+
+#[test]
+#[cfg(feature = "package-lock")]
+fn from_package_lock_test() {
+    let root = Path::new("/app");
+    let content = r#"{
+        "lockfileVersion": 3,
+        "packages": {
+            "": { "dependencies": { "a": "^1.0.0", "w": "*" } },
+            "node_modules/a": { "dependencies": { "b": "^2.0.0", "c": "^1.0.0" } },
+            "node_modules/a/node_modules/b": {},
+            "node_modules/b": {},
+            "node_modules/c": {},
+            "node_modules/w": { "resolved": "packages/w", "link": true },
+            "packages/w": { "peerDependencies": { "b": "*", "missing": "*" } }
+        }
+    }"#;
+    let graph = DependencyGraph::from_package_lock(root, content).unwrap();
+    let dependencies = |path: &str| {
+        graph
+            .dependencies_of(&join(root, path))
+            .unwrap()
+            .iter()
+            .map(|(name, dir)| (name.as_str(), dir.clone()))
+            .collect::<Vec<_>>()
+    };
+    let dir = |key: &str| join(root, key);
+    assert_eq!(
+        dependencies("src"),
+        vec![("a", dir("node_modules/a")), ("w", dir("packages/w"))]
+    );
+    assert_eq!(
+        dependencies("node_modules/a/lib"),
+        vec![
+            ("b", dir("node_modules/a/node_modules/b")),
+            ("c", dir("node_modules/c"))
+        ]
+    );
+    assert_eq!(
+        dependencies("packages/w"),
+        vec![("b", dir("node_modules/b"))]
+    );
+    assert!(graph.dependencies_of(Path::new("/other")).is_none());
+    assert!(DependencyGraph::from_package_lock(root, r#"{ "lockfileVersion": 1 }"#).is_err());
+}
+
+#[test]
+#[cfg(feature = "package-lock")]
+fn locked_versions_from_package_lock_test() {
+    let root = Path::new("/app");
+    let content = r#"{
+        "lockfileVersion": 3,
+        "packages": {
+            "": { "version": "0.1.0" },
+            "node_modules/a": { "version": "1.0.0" },
+            "node_modules/a/node_modules/b": { "version": "1.0.0" },
+            "node_modules/b": { "version": "2.0.0" },
+            "node_modules/@s/c": { "version": "3.0.0" }
+        }
+    }"#;
+    let versions = LockedVersions::from_package_lock(root, content).unwrap();
+    let dir = |key: &str| join(root, key);
+    assert_eq!(
+        versions.pinned(&dir("node_modules/a"), "a"),
+        Some(vec!["1.0.0"])
+    );
+    assert_eq!(
+        versions.pinned(&dir("node_modules/a/node_modules/b"), "b"),
+        Some(vec!["1.0.0"])
+    );
+    assert_eq!(
+        versions.pinned(&dir("node_modules/b"), "b"),
+        Some(vec!["2.0.0"])
+    );
+    // the location out of the lockfile is verified by the name.
+    assert_eq!(
+        versions.pinned(&dir("other/node_modules/b"), "b"),
+        Some(vec!["1.0.0", "2.0.0"])
+    );
+    assert_eq!(
+        versions.pinned(&dir("node_modules/@s/c"), "@s/c"),
+        Some(vec!["3.0.0"])
+    );
+    assert_eq!(versions.pinned(&dir("node_modules/d"), "d"), None);
+
+    let content = r#"{
+        "lockfileVersion": 1,
+        "dependencies": {
+            "a": { "version": "1.0.0", "dependencies": { "b": { "version": "1.0.0" } } },
+            "b": { "version": "2.0.0" }
+        }
+    }"#;
+    let versions = LockedVersions::from_package_lock(root, content).unwrap();
+    assert_eq!(
+        versions.pinned(&dir("node_modules/a/node_modules/b"), "b"),
+        Some(vec!["1.0.0"])
+    );
+    assert_eq!(
+        versions.pinned(&dir("node_modules/b"), "b"),
+        Some(vec!["2.0.0"])
+    );
+}
+
+#[test]
+#[cfg(feature = "pnpm-lock")]
+fn locked_versions_from_pnpm_lock_test() {
+    let root = Path::new("/app");
+    let pinned = |content: &str, name: &str| {
+        LockedVersions::from_pnpm_lock(root, content)
+            .unwrap()
+            .pinned(Path::new("/app/node_modules/x"), name)
+            .map(|versions| versions.join(","))
+    };
+    let v5 = "lockfileVersion: 5.4\n\npackages:\n\n  /a/1.0.0:\n    resolution: {integrity: sha512-a}\n\n  /@s/b/2.0.0_a@1.0.0:\n    dev: false\n";
+    assert_eq!(pinned(v5, "a").as_deref(), Some("1.0.0"));
+    assert_eq!(pinned(v5, "@s/b").as_deref(), Some("2.0.0"));
+    let v5 = "lockfileVersion: 5.4\n\npackages:\n\n  /string_decoder/1.3.0:\n    dev: false\n\n  /@s/c_d/1.0.0_a@1.0.0:\n    dev: false\n";
+    assert_eq!(pinned(v5, "string_decoder").as_deref(), Some("1.3.0"));
+    assert_eq!(pinned(v5, "@s/c_d").as_deref(), Some("1.0.0"));
+    let v6 = "lockfileVersion: '6.0'\n\npackages:\n\n  /string_decoder@1.3.0:\n    dev: false\n";
+    assert_eq!(pinned(v6, "string_decoder").as_deref(), Some("1.3.0"));
+    let v6 = "lockfileVersion: '6.0'\n\ndependencies:\n  a:\n    specifier: ^1.0.0\n    version: 1.0.0\n\npackages:\n\n  /a@1.0.0:\n    dev: false\n\n  /@s/b@2.0.0(a@1.0.0):\n    dev: false\n";
+    assert_eq!(pinned(v6, "a").as_deref(), Some("1.0.0"));
+    assert_eq!(pinned(v6, "@s/b").as_deref(), Some("2.0.0"));
+    let v9 = "lockfileVersion: '9.0'\n\npackages:\n\n  a@1.0.0:\n    resolution: {integrity: sha512-a}\n\n  '@s/b@2.0.0':\n    resolution: {integrity: sha512-b}\n\nsnapshots:\n\n  a@1.1.0: {}\n";
+    assert_eq!(pinned(v9, "a").as_deref(), Some("1.0.0"));
+    assert_eq!(pinned(v9, "@s/b").as_deref(), Some("2.0.0"));
+}
+
+#[test]
+#[cfg(feature = "yarn-lock")]
+fn locked_versions_from_yarn_lock_test() {
+    let root = Path::new("/app");
+    let pinned = |content: &str, name: &str| {
+        LockedVersions::from_yarn_lock(root, content)
+            .unwrap()
+            .pinned(Path::new("/app/node_modules/x"), name)
+            .map(|versions| versions.join(","))
+    };
+    let classic = "# yarn lockfile v1\n\n\na@^1.0.0, a@^1.1.0:\n  version \"1.1.0\"\n  resolved \"https://registry.yarnpkg.com/a\"\n\n\"@s/b@^2.0.0\":\n  version \"2.0.0\"\n  dependencies:\n    a \"^1.0.0\"\n";
+    assert_eq!(pinned(classic, "a").as_deref(), Some("1.1.0"));
+    assert_eq!(pinned(classic, "@s/b").as_deref(), Some("2.0.0"));
+    let berry = "__metadata:\n  version: 6\n\n\"a@npm:^1.0.0\":\n  version: 1.0.0\n  resolution: \"a@npm:1.0.0\"\n\n\"@s/b@npm:^2.0.0, @s/b@npm:^2.1.0\":\n  version: 2.1.0\n";
+    assert_eq!(pinned(berry, "a").as_deref(), Some("1.0.0"));
+    assert_eq!(pinned(berry, "@s/b").as_deref(), Some("2.1.0"));
+    assert_eq!(pinned(berry, "__metadata"), None);
+    assert!(LockedVersions::from_yarn_lock(root, "invalid:\n  version: 1\n").is_err());
+}
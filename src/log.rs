@@ -1,23 +1,67 @@
 use crate::context::Depth;
+#[cfg(feature = "tracing")]
 use tracing_subscriber::prelude::*;
 
+/// Same as `tracing::debug!`. Without the `tracing` feature, it is compiled
+/// to nothing and the arguments are only type checked.
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        #[cfg(feature = "tracing")]
+        tracing::debug!($($arg)+);
+        #[cfg(not(feature = "tracing"))]
+        if false {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+/// Enters a debug span with the fields until the end of the current
+/// scope, the events in it are grouped under the span.
+macro_rules! enter_span {
+    ($name:literal, $($fields:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!($name, $($fields)*).entered();
+    };
+}
+
+pub(crate) use {debug, enter_span};
+
+/// Print the trace to stderr by `RESOLVER_TRACE=<level>`, or by
+/// `RESOLVER_DEBUG=1` which is same as `RESOLVER_TRACE=DEBUG`.
+#[cfg(feature = "tracing")]
 pub fn enable_by_env() {
-    let is_enabled = std::env::var("RESOLVER_TRACE").map_or(false, |var| {
+    let filter = if std::env::var("RESOLVER_TRACE").map_or(false, |var| {
         matches!(var.as_str(), "TRACE" | "DEBUG" | "INFO" | "WARN" | "ERROR")
-    });
-    if !is_enabled {
+    }) {
+        tracing_subscriber::EnvFilter::from_env("RESOLVER_TRACE")
+    } else if std::env::var("RESOLVER_DEBUG").map_or(false, |var| var == "1") {
+        tracing_subscriber::EnvFilter::new("DEBUG")
+    } else {
         return;
-    }
-    let formatter = Formatter::default();
+    };
+    // the global subscriber may had been set by the previous resolver.
+    let _ = tracing_subscriber::Registry::default()
+        .with(Formatter::default())
+        .with(filter)
+        .try_init();
+}
+
+#[cfg(not(feature = "tracing"))]
+pub fn enable_by_env() {}
+
+/// The subscriber used by the resolver whose `debug` option is enabled.
+#[cfg(feature = "tracing")]
+pub fn debug_subscriber() -> impl tracing::Subscriber {
     tracing_subscriber::Registry::default()
-        .with(formatter)
-        .with(tracing_subscriber::EnvFilter::from_env("RESOLVER_TRACE"))
-        .init();
+        .with(Formatter::default())
+        .with(tracing_subscriber::EnvFilter::new("DEBUG"))
 }
 
 #[derive(Default)]
+#[cfg(feature = "tracing")]
 struct Formatter {}
 
+#[cfg(feature = "tracing")]
 impl<S> tracing_subscriber::Layer<S> for Formatter
 where
     S: tracing::Subscriber + std::fmt::Debug,
@@ -27,8 +71,10 @@ where
     }
 }
 
+#[cfg(feature = "tracing")]
 struct Data;
 
+#[cfg(feature = "tracing")]
 impl tracing::field::Visit for Data {
     fn record_debug(&mut self, _field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
         eprintln!("{value:?}");
@@ -1,6 +1,7 @@
 /// port from https://github.com/webpack/enhanced-resolve/blob/main/lib/util/entrypoints.js
-use crate::{Error, RResult};
-use std::collections::HashSet;
+use crate::{
+    description::ExportsForm, error::ExportsFieldMisuse, mode::percent_decode, Error, RResult,
+};
 
 type MappingValue = serde_json::Value;
 type ConditionalMapping = serde_json::Map<String, MappingValue>;
@@ -11,56 +12,20 @@ pub struct ExportsField;
 
 const DEFAULT_MARK: &str = "default";
 
-fn conditional_mapping<'a>(
-    map: &'a ConditionalMapping,
-    condition_names: &'a HashSet<String>,
-) -> RResult<Option<&'a serde_json::Value>> {
-    let mut lookup: Vec<(&ConditionalMapping, Vec<String>, usize)> =
-        vec![(map, map.keys().map(String::from).collect(), 0)];
-    'outer: while !lookup.is_empty() {
-        let (mapping, conditions, j) = lookup.last().unwrap();
-        let len = conditions.len();
-        for (i, condition) in conditions.iter().enumerate().skip(*j) {
-            if condition == DEFAULT_MARK {
-                if i != len - 1 {
-                    return Err(Error::UnexpectedValue(
-                        "Default condition should be last one".to_string(),
-                    ));
-                } else if let Some(value) = mapping.get(DEFAULT_MARK) {
-                    match value {
-                        MappingValue::Object(inner) => {
-                            let len = lookup.len();
-                            lookup[len - 1].2 = i + 1;
-                            lookup.push((inner, inner.keys().map(String::from).collect(), 0));
-                            continue 'outer;
-                        }
-                        _ => return Ok(Some(value)),
-                    }
-                }
-            }
-
-            if condition_names.contains(condition) {
-                if let Some(value) = mapping.get(condition) {
-                    match value {
-                        MappingValue::Object(inner) => {
-                            let len = lookup.len();
-                            lookup[len - 1].2 = i + 1;
-                            lookup.push((inner, inner.keys().map(String::from).collect(), 0));
-                            continue 'outer;
-                        }
-                        _ => return Ok(Some(value)),
-                    }
-                }
-            }
-        }
-        lookup.pop();
-    }
+const TYPES_MARK: &str = "types";
 
-    Ok(None)
+/// A target mapped by the `"exports"` or `"imports"` field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MappedTarget {
+    /// The target with the pattern substituted, such as `"./lib/a.js"`.
+    pub target: String,
+    /// The conditions matched from the outermost object to the target,
+    /// such as `["node", "import"]`, including `"default"`.
+    pub conditions: Vec<String>,
 }
 
 /// TODO: should seal all functions except
-///  `build_field` and `field_process`.
+///  `build_field`, `field_process` and `field_targets`.
 pub trait Field {
     fn check_target(relative_path: &str) -> Result<(), String> {
         let relative_path_chars = relative_path.chars().collect::<Vec<char>>();
@@ -121,50 +86,139 @@ pub trait Field {
         is_pattern: bool,
         is_subpath_mapping: bool,
         mapping: &MappingValue,
-        condition_names: &HashSet<String>,
-    ) -> RResult<Vec<String>> {
+        condition_names: &[String],
+        prefer_order: bool,
+    ) -> RResult<Vec<MappedTarget>> {
+        Ok(Self::conditional_mapping(
+            remaining_request,
+            is_pattern,
+            is_subpath_mapping,
+            mapping,
+            condition_names,
+            prefer_order,
+        )?
+        .unwrap_or_default())
+    }
+
+    /// Returns `None` if no condition matched in `mapping`, which means
+    /// the sibling conditions of `mapping` should be tried.
+    fn conditional_mapping(
+        remaining_request: &str,
+        is_pattern: bool,
+        is_subpath_mapping: bool,
+        mapping: &MappingValue,
+        condition_names: &[String],
+        prefer_order: bool,
+    ) -> RResult<Option<Vec<MappedTarget>>> {
         Ok(match mapping {
-            MappingValue::String(target) => {
-                vec![Self::target_mapping(
+            MappingValue::String(target) => Some(vec![MappedTarget {
+                target: Self::target_mapping(
                     remaining_request,
                     is_pattern,
                     is_subpath_mapping,
                     target,
-                )?]
-            }
+                )?,
+                conditions: vec![],
+            }]),
+            MappingValue::Array(target) if target.is_empty() => Some(vec![]),
             MappingValue::Array(target) => target
                 .iter()
                 .filter_map(|item| {
-                    Self::mapping(
+                    Self::conditional_mapping(
                         remaining_request,
                         is_pattern,
                         is_subpath_mapping,
                         item,
                         condition_names,
+                        prefer_order,
                     )
                     .ok()
+                    .flatten()
                 })
-                .flatten()
-                .collect(),
-            MappingValue::Object(map) => match conditional_mapping(map, condition_names)? {
-                Some(mapping_value) => Self::mapping(
-                    remaining_request,
-                    is_pattern,
-                    is_subpath_mapping,
-                    mapping_value,
-                    condition_names,
-                )?,
-                None => vec![],
-            },
-            _ => vec![],
+                .reduce(|mut list, item| {
+                    list.extend(item);
+                    list
+                }),
+            MappingValue::Object(map) => {
+                let contains =
+                    |condition: &str| condition_names.iter().any(|name| name == condition);
+                // same as TypeScript, `"types"` is matched before the sibling
+                // conditions wherever it is declared.
+                let types = map
+                    .get_key_value(TYPES_MARK)
+                    .filter(|_| contains(TYPES_MARK));
+                let is_moved = |condition: &str| types.is_some() && condition == TYPES_MARK;
+                // by the priority of `condition_names`, `"default"` is the last.
+                let rest: Box<dyn Iterator<Item = (&String, &MappingValue)>> = if prefer_order {
+                    Box::new(
+                        condition_names
+                            .iter()
+                            .filter(|name| !is_moved(name) && *name != DEFAULT_MARK)
+                            .filter_map(|name| map.get_key_value(name.as_str()))
+                            .chain(map.get_key_value(DEFAULT_MARK)),
+                    )
+                } else {
+                    Box::new(map.iter().filter(|(condition, _)| !is_moved(condition)))
+                };
+                for (condition, value) in types.into_iter().chain(rest) {
+                    let is_default = condition == DEFAULT_MARK;
+                    if is_default
+                        && map
+                            .keys()
+                            .skip_while(|key| *key != DEFAULT_MARK)
+                            .skip(1)
+                            .any(|key| !is_moved(key))
+                    {
+                        return Err(Error::UnexpectedValue(
+                            "Default condition should be last one".to_string(),
+                        ));
+                    }
+                    if !is_default && !contains(condition) {
+                        continue;
+                    }
+                    let mapped = Self::conditional_mapping(
+                        remaining_request,
+                        is_pattern,
+                        is_subpath_mapping,
+                        value,
+                        condition_names,
+                        prefer_order,
+                    )?;
+                    if let Some(mut mapped) = mapped {
+                        for item in &mut mapped {
+                            item.conditions.insert(0, condition.to_string());
+                        }
+                        return Ok(Some(mapped));
+                    }
+                }
+                None
+            }
+            _ => Some(vec![]),
         })
     }
 
     fn field_process<'a>(
         root: &'a serde_json::Value,
         target: &'a str,
-        condition_names: &'a HashSet<String>,
+        condition_names: &'a [String],
+        prefer_order: bool,
     ) -> RResult<Vec<String>> {
+        Ok(
+            Self::field_targets(root, target, condition_names, prefer_order)?
+                .into_iter()
+                .map(|item| item.target)
+                .collect(),
+        )
+    }
+
+    /// Same as `field_process`, but the matched conditions of each target
+    /// are kept.
+    fn field_targets(
+        root: &serde_json::Value,
+        target: &str,
+        condition_names: &[String],
+        prefer_order: bool,
+    ) -> RResult<Vec<MappedTarget>> {
         let request = Self::assert_request(target)?;
         let Some((mapping, remaining_request, is_subpath_mapping, is_pattern)) = Self::find_match(root, &request)? else {
             return Ok(vec![])
@@ -175,6 +229,7 @@ pub trait Field {
             is_subpath_mapping,
             mapping,
             condition_names,
+            prefer_order,
         )
     }
 }
@@ -222,49 +277,39 @@ impl Field for ExportsField {
         json_value: &'a serde_json::Value,
         request: &'a str,
     ) -> RResult<Option<(&'a MappingValue, &'a str, bool, bool)>> {
-        match json_value {
-            serde_json::Value::Object(map) => {
-                for (i, key) in map.keys().enumerate() {
-                    if !key.starts_with('.') {
-                        if i == 0 {
-                            for key in map.keys() {
-                                if key.starts_with('.') || key.starts_with('/') {
-                                    return Err(Error::UnexpectedValue(format!(
-                                        "Export field key should be relative path and start with \"./\", but got {key}"
-                                    )));
-                                }
-                            }
-                            // {"." => Object};
-                            if request != "." {
-                                return Ok(None);
-                            } else {
-                                return Ok(Some((json_value, ".", false, false)));
-                            }
-                        } else {
-                            return Err(Error::UnexpectedValue(format!(
-                                "Export field key should be relative path and start with \".\", but got {key}"
-                            )));
-                        }
-                    } else if key.len() == 1 {
-                        // key == "."
-                        continue;
-                    } else if key.as_bytes().get(1) != Some(&b'/') {
-                        return Err(Error::UnexpectedValue(format!(
-                            "Export field key should be relative path and start with \"./\", but got {key}"
-                        )));
-                    }
+        let key_error = |key: &str, prefix: &str| {
+            Err(Error::UnexpectedValue(format!(
+                "Export field key should be relative path and start with \"{prefix}\", but got {key}"
+            )))
+        };
+        let form = ExportsForm::of(json_value);
+        let map = match (form, json_value) {
+            (ExportsForm::Target | ExportsForm::Conditions, _) => {
+                let mut conditions = json_value
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|map| map.keys());
+                if let Some(key) = conditions.find(|key| key.starts_with('/')) {
+                    return key_error(key, "./");
                 }
-                return Ok(find_normalized_match_in_object(map, request));
+                // {"." => Target} or {"." => Object};
+                return Ok((request == ".").then_some((json_value, ".", false, false)));
             }
-            serde_json::Value::Array(_) | serde_json::Value::String(_) => {
-                if request != "." {
-                    Ok(None)
-                } else {
-                    Ok(Some((json_value, ".", false, false)))
-                }
+            (ExportsForm::Subpaths | ExportsForm::Mixed, serde_json::Value::Object(map)) => map,
+            _ => return Ok(None),
+        };
+        if form == ExportsForm::Mixed && !map.keys().next().unwrap().starts_with('.') {
+            let key = map.keys().find(|key| key.starts_with('.')).unwrap();
+            return key_error(key, "./");
+        }
+        for key in map.keys() {
+            if !key.starts_with('.') {
+                return key_error(key, ".");
+            } else if key.len() > 1 && key.as_bytes().get(1) != Some(&b'/') {
+                return key_error(key, "./");
             }
-            _ => Ok(None),
         }
+        Ok(find_normalized_match_in_object(map, request))
     }
 }
 
@@ -336,6 +381,88 @@ impl Field for ImportsField {
     }
 }
 
+/// Returns the first segment of the mapped `target` which Node.js rejects
+/// as an invalid module specifier, that is `.`, `..` or `node_modules`
+/// even if they are percent-encoded, or the segment contains the encoded
+/// `/` or `\`. The leading `./` of the target is allowed.
+pub(crate) fn invalid_segment(target: &str) -> Option<&str> {
+    let target = target.strip_prefix("./").unwrap_or(target);
+    target
+        .split(['/', '\\'])
+        .find(|segment| match percent_decode(segment) {
+            Some(decoded) => matches!(
+                decoded.to_ascii_lowercase().as_str(),
+                "." | ".." | "node_modules"
+            ),
+            None => true,
+        })
+}
+
+pub(crate) fn escape_json_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Check the whole `exports` tree and return the JSON pointer of
+/// the first misuse, `pointer` is the location of `root`.
+pub(crate) fn validate_exports_field(
+    root: &MappingValue,
+    pointer: &str,
+) -> Option<(String, ExportsFieldMisuse)> {
+    if let MappingValue::Object(map) = root {
+        let is_subpath_map = map.keys().next().map_or(false, |key| key.starts_with('.'));
+        if is_subpath_map {
+            for (key, value) in map {
+                let pointer = format!("{pointer}/{}", escape_json_pointer(key));
+                if !key.starts_with('.') {
+                    return Some((pointer, ExportsFieldMisuse::MixedKeys));
+                }
+                if let Some(misuse) = validate_exports_target(value, pointer) {
+                    return Some(misuse);
+                }
+            }
+            return None;
+        }
+    }
+    validate_exports_target(root, pointer.to_string())
+}
+
+fn validate_exports_target(
+    value: &MappingValue,
+    pointer: String,
+) -> Option<(String, ExportsFieldMisuse)> {
+    match value {
+        MappingValue::String(target) => {
+            if !target.starts_with("./") {
+                Some((pointer, ExportsFieldMisuse::TargetNotRelative))
+            } else if ExportsField::check_target(target).is_err() {
+                Some((pointer, ExportsFieldMisuse::TargetOutOfPackage))
+            } else {
+                None
+            }
+        }
+        MappingValue::Array(targets) => targets.iter().enumerate().find_map(|(index, target)| {
+            validate_exports_target(target, format!("{pointer}/{index}"))
+        }),
+        MappingValue::Object(conditions) => {
+            let mut after_default = false;
+            for (key, value) in conditions {
+                let pointer = format!("{pointer}/{}", escape_json_pointer(key));
+                if key.starts_with('.') {
+                    return Some((pointer, ExportsFieldMisuse::MixedKeys));
+                } else if after_default {
+                    return Some((pointer, ExportsFieldMisuse::ConditionAfterDefault));
+                }
+                after_default = key == DEFAULT_MARK;
+                if let Some(misuse) = validate_exports_target(value, pointer) {
+                    return Some(misuse);
+                }
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
 fn get_next_list(path: &[char], target: char) -> Vec<Option<usize>> {
     // TODO: rewrite it use fp.
     let len = path.len();
@@ -434,7 +561,12 @@ mod exports_field_map_test {
         request: &str,
         condition_names: Vec<&str>,
     ) -> RResult<Vec<String>> {
-        ExportsField::field_process(&value, request, &test_helper::vec_to_set(condition_names))
+        ExportsField::field_process(
+            &value,
+            request,
+            &test_helper::vec_to_list(condition_names),
+            false,
+        )
     }
 
     fn should_equal(
@@ -1961,6 +2093,120 @@ mod exports_field_map_test {
         );
     }
 
+    #[test]
+    fn exports_field_nested_conditions_test() {
+        let value = || {
+            json!({
+                ".": {
+                    "browser": {
+                        "import": "./browser.mjs",
+                        "require": "./browser.cjs"
+                    },
+                    "import": {
+                        "node": "./node.mjs",
+                        "default": {
+                            "types": "./index.d.mts",
+                            "default": "./module.mjs"
+                        }
+                    },
+                    "module": [{ "worker": "./worker.js" }],
+                    "require": {
+                        "node": {
+                            "production": "./prod.cjs",
+                            "development": null
+                        }
+                    },
+                    "default": "./index.js"
+                }
+            })
+        };
+        should_equal(value(), ".", vec![], vec!["./index.js"]);
+        should_equal(value(), ".", vec!["browser"], vec!["./index.js"]);
+        should_equal(
+            value(),
+            ".",
+            vec!["browser", "import"],
+            vec!["./browser.mjs"],
+        );
+        should_equal(
+            value(),
+            ".",
+            vec!["browser", "require"],
+            vec!["./browser.cjs"],
+        );
+        should_equal(value(), ".", vec!["import"], vec!["./module.mjs"]);
+        should_equal(value(), ".", vec!["import", "node"], vec!["./node.mjs"]);
+        should_equal(value(), ".", vec!["import", "types"], vec!["./index.d.mts"]);
+        // no condition matched in the array, fallback to the next condition.
+        should_equal(value(), ".", vec!["module"], vec!["./index.js"]);
+        should_equal(value(), ".", vec!["module", "worker"], vec!["./worker.js"]);
+        should_equal(value(), ".", vec!["require"], vec!["./index.js"]);
+        should_equal(
+            value(),
+            ".",
+            vec!["require", "node", "production"],
+            vec!["./prod.cjs"],
+        );
+        // `null` means the condition is matched but excluded.
+        should_equal(value(), ".", vec!["require", "node", "development"], vec![]);
+    }
+
+    #[test]
+    fn exports_field_types_condition_test() {
+        // `"types"` is declared after the other conditions by mistake.
+        let value = || {
+            json!({
+                ".": {
+                    "import": "./index.mjs",
+                    "require": "./index.cjs",
+                    "types": "./index.d.ts"
+                },
+                "./nested": {
+                    "import": {
+                        "node": "./nested.mjs",
+                        "types": "./nested.d.mts"
+                    }
+                },
+                "./false": {
+                    "require": "./false.cjs",
+                    "types": null,
+                    "default": "./false.js"
+                },
+                "./default": {
+                    "import": "./default.mjs",
+                    "types": "./default.d.ts",
+                    "default": "./default.js"
+                }
+            })
+        };
+        should_equal(value(), ".", vec!["import"], vec!["./index.mjs"]);
+        should_equal(value(), ".", vec!["import", "types"], vec!["./index.d.ts"]);
+        should_equal(value(), ".", vec!["require", "types"], vec!["./index.d.ts"]);
+        should_equal(
+            value(),
+            "./nested",
+            vec!["import", "node"],
+            vec!["./nested.mjs"],
+        );
+        should_equal(
+            value(),
+            "./nested",
+            vec!["import", "node", "types"],
+            vec!["./nested.d.mts"],
+        );
+        should_equal(value(), "./nested", vec!["types"], vec![]);
+        // the members which are excluded for types are not fallen back.
+        should_equal(value(), "./false", vec!["require"], vec!["./false.cjs"]);
+        should_equal(value(), "./false", vec!["require", "types"], vec![]);
+        should_equal(value(), "./default", vec!["types"], vec!["./default.d.ts"]);
+        should_equal(
+            value(),
+            "./default",
+            vec!["import", "types"],
+            vec!["./default.d.ts"],
+        );
+    }
+
     #[test]
     fn exports_field_map_test_2() {
         // copy from node
@@ -2152,7 +2398,12 @@ fn imports_field_map_test() {
         request: &str,
         condition_names: Vec<&str>,
     ) -> RResult<Vec<String>> {
-        ImportsField::field_process(&value, request, &test_helper::vec_to_set(condition_names))
+        ImportsField::field_process(
+            &value,
+            request,
+            &test_helper::vec_to_list(condition_names),
+            false,
+        )
     }
 
     fn should_equal(
@@ -3061,6 +3312,24 @@ fn imports_field_map_test() {
     );
 }
 
+#[test]
+fn invalid_segment_test() {
+    assert_eq!(invalid_segment("./a/b.js"), None);
+    assert_eq!(invalid_segment("./a//b.js"), None);
+    assert_eq!(invalid_segment("./a/../b.js"), Some(".."));
+    assert_eq!(invalid_segment("../b.js"), Some(".."));
+    assert_eq!(invalid_segment("././a.js"), Some("."));
+    assert_eq!(invalid_segment("./%2e%2E/a.js"), Some("%2e%2E"));
+    assert_eq!(invalid_segment("./Node_Modules/a.js"), Some("Node_Modules"));
+    assert_eq!(
+        invalid_segment("./%6eode_modules/a.js"),
+        Some("%6eode_modules")
+    );
+    assert_eq!(invalid_segment("./a%2Fb.js"), Some("a%2Fb.js"));
+    assert_eq!(invalid_segment("./a\\..\\b.js"), Some(".."));
+    assert_eq!(invalid_segment("./.a/..b.js"), None);
+}
+
 #[test]
 fn check_target_test() {
     assert!(ExportsField::check_target("../a.js").is_err());
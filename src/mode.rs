@@ -0,0 +1,172 @@
+use crate::{
+    kind::PathKind,
+    module_type::{insert_condition, switch_condition},
+    CompatMode, Error, Options, RResult, ResolutionMode, Resolver,
+};
+use std::borrow::Cow;
+
+impl Resolver {
+    /// Overrides the options which conflict with the algorithm of `mode`.
+    pub(crate) fn with_mode_options(options: Options) -> Options {
+        match options.mode {
+            ResolutionMode::Default => options,
+            ResolutionMode::Esm | ResolutionMode::Cjs => {
+                let (insert, remove) = if options.mode == ResolutionMode::Esm {
+                    ("import", "require")
+                } else {
+                    ("require", "import")
+                };
+                let mut condition_names = options.condition_names;
+                switch_condition(&mut condition_names, insert, remove);
+                insert_condition(&mut condition_names, "module-sync");
+                insert_condition(&mut condition_names, "node");
+                Options {
+                    extensions: vec![
+                        String::from(".js"),
+                        String::from(".json"),
+                        String::from(".node"),
+                    ],
+                    enforce_extension: crate::EnforceExtension::Disabled,
+                    main_files: vec![String::from("index")],
+                    main_fields: vec![String::from("main")],
+                    browser_field: false,
+                    prefer_relative: false,
+                    fully_specified: options.mode == ResolutionMode::Esm,
+                    condition_names,
+                    ..options
+                }
+            }
+            ResolutionMode::Deno => Options {
+                extensions: vec![],
+                enforce_extension: crate::EnforceExtension::Disabled,
+                main_files: vec![],
+                main_fields: vec![],
+                browser_field: false,
+                prefer_relative: false,
+                fully_specified: true,
+                modules: vec![],
+                global_paths: vec![],
+                exports_field: vec![],
+                ..options
+            },
+        }
+    }
+
+    /// Converts the specifier to a request by URL semantics in ESM and Deno
+    /// mode, `file:` URLs are converted to paths and the relative or
+    /// absolute specifiers are percent-decoded. In `CompatMode::Node`, the
+    /// `?` and `#` in the package name, or in the whole request in CJS
+    /// mode, are escaped because they are not URL parts there.
+    pub(crate) fn to_mode_request<'a>(&self, request: &'a str) -> RResult<Cow<'a, str>> {
+        if self.options.compat == CompatMode::Node {
+            let end = match Self::get_target_kind(request) {
+                _ if self.options.mode == ResolutionMode::Cjs => request.len(),
+                PathKind::Internal => request.len(),
+                PathKind::Normal => package_name_len(request),
+                _ => 0,
+            };
+            if end > 0 {
+                return Ok(escape_query_and_fragment(request, end));
+            }
+        }
+        if !matches!(
+            self.options.mode,
+            ResolutionMode::Esm | ResolutionMode::Deno
+        ) {
+            return Ok(Cow::Borrowed(request));
+        }
+        let (path, rest) = match request.find(['?', '#']) {
+            Some(index) => request.split_at(index),
+            None => (request, ""),
+        };
+        let path = if let Some(url_path) = path.strip_prefix("file://") {
+            // `file:///C:/a.js` on windows.
+            match url_path.strip_prefix('/') {
+                Some(win) if win.as_bytes().get(1) == Some(&b':') => win,
+                _ => url_path,
+            }
+        } else if path.starts_with(['.', '/']) {
+            path
+        } else {
+            return Ok(Cow::Borrowed(request));
+        };
+        let decoded = percent_decode(path).ok_or_else(|| {
+            Error::UnexpectedValue(format!(
+                "Invalid module specifier {request}, must not include encoded '/' or '\\' characters"
+            ))
+        })?;
+        Ok(Cow::Owned(format!("{decoded}{rest}")))
+    }
+}
+
+/// Returns the length of the package name in a bare specifier, such as
+/// `@scope/name` in `@scope/name/sub`.
+fn package_name_len(request: &str) -> usize {
+    let skip = usize::from(request.starts_with('@'));
+    request
+        .match_indices('/')
+        .nth(skip)
+        .map_or(request.len(), |(index, _)| index)
+}
+
+/// Escapes the `?` and `#` in `request[..end]` after the first char by
+/// `\0`, so that they are parsed as a part of the target.
+fn escape_query_and_fragment(request: &str, end: usize) -> Cow<'_, str> {
+    let start = request.chars().next().map_or(0, char::len_utf8).min(end);
+    if !request[start..end].contains(['?', '#']) {
+        return Cow::Borrowed(request);
+    }
+    let escaped = request[start..end].replace('?', "\0?").replace('#', "\0#");
+    Cow::Owned(format!("{}{escaped}{}", &request[..start], &request[end..]))
+}
+
+/// Decode the `%XX` sequences, returns `None` if the encoded character is
+/// a path separator.
+pub(crate) fn percent_decode(path: &str) -> Option<String> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (byte, hex) {
+            (b'%', Some(b'/' | b'\\')) => return None,
+            (b'%', Some(value)) => {
+                decoded.push(value);
+                index += 3;
+            }
+            _ => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    Some(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+#[test]
+fn test_escape_query_and_fragment() {
+    assert_eq!(escape_query_and_fragment("a", 1), "a");
+    assert_eq!(escape_query_and_fragment("a?b#c", 5), "a\0?b\0#c");
+    assert_eq!(escape_query_and_fragment("#a#b", 4), "#a\0#b");
+    let request = "a?b/c#d";
+    let end = package_name_len(request);
+    assert_eq!(escape_query_and_fragment(request, end), "a\0?b/c#d");
+    assert_eq!(escape_query_and_fragment("ñ", 2), "ñ");
+    assert_eq!(escape_query_and_fragment("ñ?a", 4), "ñ\0?a");
+    assert_eq!(escape_query_and_fragment("", 0), "");
+    assert_eq!(package_name_len("@a/b/c"), 4);
+    assert_eq!(package_name_len("a"), 1);
+}
+
+#[test]
+fn test_percent_decode() {
+    assert_eq!(percent_decode("./a%20b.js").unwrap(), "./a b.js");
+    assert_eq!(percent_decode("./%E4%B8%AD.js").unwrap(), "./中.js");
+    assert_eq!(percent_decode("./100%.js").unwrap(), "./100%.js");
+    assert!(percent_decode("./a%2Fb.js").is_none());
+    assert!(percent_decode("./a%5cb.js").is_none());
+}
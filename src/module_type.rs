@@ -0,0 +1,77 @@
+use crate::{Context, ModuleType, RResult, Resolver};
+use std::{borrow::Cow, path::Path};
+
+const IMPORT_CONDITION: &str = "import";
+const REQUIRE_CONDITION: &str = "require";
+
+/// Appends `name` to `condition_names` if it is absent.
+pub(crate) fn insert_condition(condition_names: &mut Vec<String>, name: &str) {
+    if !condition_names.iter().any(|item| item == name) {
+        condition_names.push(String::from(name));
+    }
+}
+
+/// Replaces `remove` with `insert` in `condition_names` and keeps its
+/// priority, `insert` is appended if `remove` is absent.
+pub(crate) fn switch_condition(condition_names: &mut Vec<String>, insert: &str, remove: &str) {
+    match condition_names.iter().position(|item| item == remove) {
+        Some(index) if !condition_names.iter().any(|item| item == insert) => {
+            condition_names[index] = String::from(insert)
+        }
+        Some(index) => {
+            condition_names.remove(index);
+        }
+        None => insert_condition(condition_names, insert),
+    }
+}
+
+impl Resolver {
+    /// Returns the `"type"` of the nearest description file from `path`,
+    /// `None` if there is no description file.
+    pub(crate) fn module_type_of(&self, path: &Path) -> RResult<Option<ModuleType>> {
+        let entry = self.load_entry(path);
+        Ok(entry
+            .pkg_info(self)?
+            .as_ref()
+            .map(|pkg_info| pkg_info.data().module_type()))
+    }
+
+    /// The condition names used by `exports` and `imports` field,
+    /// `"import"` and `"require"` are switched by the module type.
+    pub(crate) fn condition_names(&self, context: &Context) -> Cow<[String]> {
+        let (insert, remove) = match context.module_type {
+            None => return Cow::Borrowed(&self.options.condition_names[..]),
+            Some(ModuleType::Module) => (IMPORT_CONDITION, REQUIRE_CONDITION),
+            Some(ModuleType::CommonJs) => (REQUIRE_CONDITION, IMPORT_CONDITION),
+        };
+        let mut condition_names = self.options.condition_names.clone();
+        switch_condition(&mut condition_names, insert, remove);
+        Cow::Owned(condition_names)
+    }
+
+    /// The extensions tried in order, `".mjs"` takes precedence over `".js"`
+    /// in the module package, and `binary_extensions` are tried at last.
+    /// The extensions of the request replace `extensions` of the options.
+    pub(crate) fn extensions(&self, context: &Context) -> Cow<[String]> {
+        let mut extensions = match &context.extensions {
+            Some(extensions) => Cow::Owned(extensions.clone()),
+            None => Cow::Borrowed(&self.options.extensions[..]),
+        };
+        if context.module_type == Some(ModuleType::Module) {
+            let position = |ext: &str| extensions.iter().position(|item| item == ext);
+            if let (Some(js), Some(mjs)) = (position(".js"), position(".mjs")) {
+                if mjs > js {
+                    let extensions = extensions.to_mut();
+                    let mjs = extensions.remove(mjs);
+                    extensions.insert(js, mjs);
+                }
+            }
+        }
+        for ext in &self.options.binary_extensions {
+            if !extensions.contains(ext) {
+                extensions.to_mut().push(ext.clone());
+            }
+        }
+        extensions
+    }
+}
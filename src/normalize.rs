@@ -0,0 +1,89 @@
+//! The Unicode normalization of the paths. A name could be written in
+//! the composed form (NFC), such as the specifiers in the source code,
+//! or in the decomposed form (NFD), such as the names stored by HFS+ on
+//! macOS, and the forms are the same file on such file systems.
+
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization as _};
+
+/// The form which the paths and the requests are normalized to, so the
+/// names written in different forms share the cached entries and the
+/// results.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum UnicodeNormalization {
+    /// The paths are kept as they are, the file systems of Linux and
+    /// Windows store the names as they are written. It is the default on
+    /// all platforms, so the resolved paths are the ones written.
+    None,
+    /// The composed form, it should be opted in on macOS, where the file
+    /// system is insensitive to the normalization. The resolved paths are
+    /// in NFC, which may differ from the names stored on the disk.
+    Nfc,
+    /// The decomposed form.
+    Nfd,
+}
+
+impl Default for UnicodeNormalization {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl UnicodeNormalization {
+    /// Returns `s` in the form, it is not allocated if `s` is already in it.
+    #[must_use]
+    pub fn normalize_str(self, s: &str) -> Cow<'_, str> {
+        if s.is_ascii() {
+            return Cow::Borrowed(s);
+        }
+        match self {
+            Self::None => Cow::Borrowed(s),
+            Self::Nfc if is_nfc(s) => Cow::Borrowed(s),
+            Self::Nfd if is_nfd(s) => Cow::Borrowed(s),
+            Self::Nfc => Cow::Owned(s.nfc().collect()),
+            Self::Nfd => Cow::Owned(s.nfd().collect()),
+        }
+    }
+
+    /// Same as `normalize_str`, the path which is not valid UTF-8 is kept.
+    #[must_use]
+    pub fn normalize_path(self, path: &Path) -> Cow<'_, Path> {
+        match path.to_str().map(|s| self.normalize_str(s)) {
+            Some(Cow::Owned(s)) => Cow::Owned(PathBuf::from(s)),
+            _ => Cow::Borrowed(path),
+        }
+    }
+}
+
+#[test]
+fn unicode_normalization_test() {
+    let nfc = "caf\u{e9}/\u{1e69}/\u{d55c}\u{ae00}";
+    let nfd = "cafe\u{301}/s\u{323}\u{307}/\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}";
+    assert_eq!(UnicodeNormalization::Nfd.normalize_str(nfc), nfd);
+    assert_eq!(UnicodeNormalization::Nfc.normalize_str(nfd), nfc);
+    // the marks are reordered by the class.
+    assert_eq!(
+        UnicodeNormalization::Nfc.normalize_str("s\u{307}\u{323}"),
+        "\u{1e69}"
+    );
+    // the composition exclusion is not composed.
+    assert_eq!(
+        UnicodeNormalization::Nfc.normalize_str("\u{2adc}"),
+        "\u{2add}\u{338}"
+    );
+    assert!(matches!(
+        UnicodeNormalization::Nfc.normalize_str(nfc),
+        Cow::Borrowed(_)
+    ));
+    assert!(matches!(
+        UnicodeNormalization::None.normalize_str(nfd),
+        Cow::Borrowed(_)
+    ));
+    assert_eq!(
+        UnicodeNormalization::Nfc.normalize_path(Path::new(nfd)),
+        Path::new(nfc)
+    );
+}
@@ -1,6 +1,12 @@
-use std::{collections::HashSet, path::PathBuf, sync::Arc};
+use regex::Regex;
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use xxhash_rust::xxh3::Xxh3;
 
-use crate::Cache;
+use crate::{Cache, DependencyGraph, Error, LockedVersions, LockfileMismatch, RResult};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum AliasMap {
@@ -15,24 +21,319 @@ pub enum EnforceExtension {
     Auto,
 }
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum PrefixMap {
+    /// Strip the prefix and resolve the rest as a module request,
+    /// such as `~lodash` to `lodash`.
+    Module,
+    /// Replace the prefix with a directory,
+    /// such as `@/utils` to `<dir>/utils`.
+    Dir(PathBuf),
+    /// Strip the prefix and the version range of the package, then resolve
+    /// the rest as a module request, such as `npm:preact@^10/hooks` to
+    /// `preact/hooks`.
+    Package,
+    /// Same as `Package`, but the scoped package is renamed as the npm
+    /// compatibility layer of JSR, such as `jsr:@std/path@^1/posix` to
+    /// `@jsr/std__path/posix`.
+    Jsr,
+}
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ResolutionMode {
+    /// The algorithm compatible with webpack, driven by the options.
+    Default,
+    /// Follow the ESM resolution algorithm of Node.js: no extension
+    /// guessing and index files for relative requests, `"import"`,
+    /// `"module-sync"` and `"node"` conditions and URL semantics for the
+    /// specifier.
+    Esm,
+    /// Follow `require` of Node.js, same as `Module._resolveFilename`:
+    /// `[".js", ".json", ".node"]` extensions, only `"main"` field, and
+    /// `"require"`, `"module-sync"` and `"node"` conditions as Node.js 22.
+    Cjs,
+    /// Follow the resolution of Deno for the projects without
+    /// `package.json`: only the relative and absolute specifiers, `file:`
+    /// URLs and the requests mapped by `import_map`, `alias` or `prefix`
+    /// are resolved. The extension must be explicit, and there is no
+    /// `modules` lookup, index files or fields of the description file.
+    Deno,
+}
+
+/// The behaviors where webpack deliberately deviates from Node.js.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum CompatMode {
+    /// Same as enhanced-resolve of webpack: `?` and `#` split the query
+    /// and fragment from every request, all targets of an array in
+    /// `exports` are tried until one of them is resolved, and `alias`
+    /// takes precedence over the `"imports"` field.
+    Webpack,
+    /// Same as Node.js: `?` and `#` are a part of the bare specifiers, and
+    /// of every request in `ResolutionMode::Cjs`, only the first valid
+    /// target of an array in `exports` is used, and the `#` specifiers are
+    /// only resolved by the `"imports"` field, `alias` and `scoped_alias`
+    /// are not applied to them.
+    Node,
+}
+
+/// The environment of the build, which selects the files shipped for
+/// development or production by the conditions of `exports`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum BuildEnv {
+    Development,
+    Production,
+}
+
+impl BuildEnv {
+    /// The condition name of the environment.
+    #[must_use]
+    pub fn condition(&self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Production => "production",
+        }
+    }
+}
+
+/// The environment which the bundle runs in, same as `target` of webpack.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Target {
+    Node,
+    Web,
+    WebWorker,
+    ElectronMain,
+}
+
+impl Target {
+    /// Whether the native addons, the `.node` files, could be loaded.
+    #[must_use]
+    pub fn has_native_addons(&self) -> bool {
+        matches!(self, Self::Node | Self::ElectronMain)
+    }
+}
+
+/// How `exports` works with the other fields of a package when it is present.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ExportsPriority {
+    /// Same as Node.js, `exports` fully encapsulates the package, the main
+    /// fields, main files and `"browser"` field are not used.
+    Encapsulate,
+    /// Try `exports` first, then fall back to the main fields and files
+    /// if the request is not exported or the target does not exist.
+    Fallback,
+    /// Ignore `exports`, same as webpack 4.
+    Ignore,
+}
+
+/// Whether a path such as `./foo` is resolved as the file `foo.js` or the
+/// directory `foo/` first when both exist.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PathPreference {
+    /// Same as Node.js and webpack, the file wins.
+    File,
+    /// The directory with the main fields or main files wins, and the file
+    /// is tried if the directory can't be resolved.
+    Directory,
+}
+
+/// A stage which redirects the request before it is resolved as a path or
+/// a module, see `Options::pipeline`. The `"exports"` field is applied in
+/// the resolution of the module rather than a stage.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PipelineStage {
+    /// `rewrites` of the options.
+    Rewrite,
+    /// `prefix` of the options.
+    Prefix,
+    /// `scoped_alias` of the options.
+    ScopedAlias,
+    /// `alias` of the options.
+    Alias,
+    /// `prefer_relative` of the options.
+    PreferRelative,
+    /// The `"imports"` field of the description file of the issuer.
+    ImportsField,
+    /// The `"browser"` field of the description file of the issuer, only
+    /// if `browser_field` is enabled.
+    BrowserField,
+}
+
+impl PipelineStage {
+    /// The built-in order of the stages.
+    pub const DEFAULT: [Self; 7] = [
+        Self::Rewrite,
+        Self::Prefix,
+        Self::ScopedAlias,
+        Self::Alias,
+        Self::PreferRelative,
+        Self::ImportsField,
+        Self::BrowserField,
+    ];
+}
+
+/// The verdict of `BeforeResolved` on a resolved file.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub enum Verdict {
+    /// Returns the file as is.
+    Accept,
+    /// Fails the resolution with `Error::Rejected`.
+    Reject,
+    /// Returns the file at the path instead, the query and fragment of the
+    /// request are kept. The path must be an absolute path to an existing
+    /// file, otherwise the resolution fails with `Error::UnexpectedValue`.
+    Replace(PathBuf),
+}
+
+/// The policy checked before a file is returned by `resolve`, see
+/// `Options::before_resolved`.
+pub trait BeforeResolved: std::fmt::Debug + Send + Sync {
+    /// Decides on `resolved`, the file which `request` from `path` is
+    /// resolved to.
+    fn before_resolved(&self, path: &Path, request: &str, resolved: &Path) -> Verdict;
+}
+
+/// What to do when a description file can't be parsed, see
+/// `Options::on_manifest_error`.
+#[derive(Debug, Clone)]
+pub enum ManifestErrorPolicy {
+    /// Fails the resolutions which read the description file.
+    Error,
+    /// The directory is treated as if it has no description file, so the
+    /// description file of its parent is used.
+    Ignore,
+    /// Same as `Ignore`, and the error is reported to the warning.
+    WarnAndIgnore(Arc<dyn ManifestWarning>),
+}
+
+/// The receiver of the description files ignored by
+/// `ManifestErrorPolicy::WarnAndIgnore`, such as the diagnostics of a
+/// bundler.
+pub trait ManifestWarning: std::fmt::Debug + Send + Sync {
+    /// Called once with the path of the description file and the error
+    /// until the entry is invalidated.
+    fn warn(&self, path: &Path, error: &Error);
+}
+
+/// The versions pinned by a lockfile to verify the resolved packages, see
+/// `Options::lockfile_check`.
+#[derive(Debug, Clone)]
+pub struct LockfileCheck {
+    pub versions: Arc<LockedVersions>,
+    pub warning: Arc<dyn LockfileWarning>,
+}
+
+/// The receiver of the packages mismatched with the lockfile, such as the
+/// diagnostics of a bundler.
+pub trait LockfileWarning: std::fmt::Debug + Send + Sync {
+    /// Called once for each resolution of a mismatched package until the
+    /// result is invalidated, the resolution itself still succeeds.
+    fn warn(&self, mismatch: &LockfileMismatch);
+}
+
+/// The `"type"` field in package.json.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum ModuleType {
+    CommonJs,
+    Module,
+}
+
 pub type Alias = Vec<(String, Vec<AliasMap>)>;
 
+/// The options overridden for the requests issued from a project root,
+/// see `Options::roots`. The fields which are `None` are inherited.
+#[derive(Debug, Clone, Default)]
+pub struct RootProfile {
+    /// The absolute path of the project root.
+    pub root: PathBuf,
+    pub alias: Option<Alias>,
+    pub tsconfig: Option<PathBuf>,
+    pub extensions: Option<Vec<String>>,
+}
+
+impl AliasMap {
+    /// Parse the alias in the format of webpack, the value of each key could
+    /// be a string, `false` or an array of them, such as
+    /// `{ "a": "./b", "c": ["./d", "./e"], "fs": false }`.
+    pub fn from_json(value: &serde_json::Value) -> RResult<Alias> {
+        fn parse(key: &str, value: &serde_json::Value) -> RResult<AliasMap> {
+            match value {
+                serde_json::Value::String(target) => Ok(AliasMap::Target(target.to_string())),
+                serde_json::Value::Bool(false) => Ok(AliasMap::Ignored),
+                _ => Err(Error::UnexpectedValue(format!(
+                    "Alias of {key} should be a string or false, but got {value}"
+                ))),
+            }
+        }
+        let Some(map) = value.as_object() else {
+            return Err(Error::UnexpectedValue(format!(
+                "Alias should be an object, but got {value}"
+            )));
+        };
+        map.iter()
+            .map(|(key, value)| {
+                let list = match value {
+                    serde_json::Value::Array(list) => list
+                        .iter()
+                        .map(|value| parse(key, value))
+                        .collect::<RResult<_>>()?,
+                    _ => vec![parse(key, value)?],
+                };
+                Ok((key.to_string(), list))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Options {
     /// Tried detect file with this extension.
     /// Default is `[".js", ".json", ".node"]`
     pub extensions: Vec<String>,
+    /// Extensions of native addons and other binary files, they are
+    /// tried after `extensions` and the resolved resource is marked as
+    /// [`crate::Resource::binary`], so its content should not be inspected.
+    /// Default is `[".node"]`.
+    pub binary_extensions: Vec<String>,
     /// Enforce that a extension from extensions must be used.
     /// Default is `Auto`.
     pub enforce_extension: EnforceExtension,
-    /// Maps key to value.
+    /// Maps key to value, the former declared key wins if several keys
+    /// match the request.
     /// Default is `vec![]`.
     /// The reason for using `Vec` instead `HashMap` to keep the order.
     pub alias: Alias,
+    /// Maps the prefix of request, it works before `alias`. The protocols
+    /// of Deno and Bun could be handled by
+    /// `("npm:", PrefixMap::Package)` and `("jsr:", PrefixMap::Jsr)`.
+    /// Default is `vec![]`.
+    pub prefix: Vec<(String, PrefixMap)>,
+    /// Rewrites the request matched by the regex with the template, such
+    /// as `^lodash/(.*)$` to `lodash-es/$1`, it works before `prefix`.
+    /// `$1` or `${name}` in the template is replaced by the capture group.
+    /// The former declared one wins, and the latter ones are tried if the
+    /// rewritten request can't be resolved.
+    /// Default is `vec![]`.
+    pub rewrites: Vec<(Regex, String)>,
+    /// Same as `alias`, but only applies when the request is resolved
+    /// from inside the directory, the matched scopes are tried in order
+    /// and before `alias`.
+    /// Default is `vec![]`.
+    pub scoped_alias: Vec<(PathBuf, Alias)>,
     /// Prefer to resolve request as relative request and
     /// fallback to resolving as modules.
     /// Default is `false`
     pub prefer_relative: bool,
+    /// The stages which redirect the request in order, a stage is disabled
+    /// if it is absent, such as removing `PipelineStage::BrowserField` to
+    /// skip the `"browser"` field of the issuer. The cycle of resolution is
+    /// checked before the first one of `PreferRelative`, `ImportsField`
+    /// and `BrowserField`, so that `Error::AliasCycle` is preferred.
+    /// The `"exports"` field is not a stage, it is applied when the package
+    /// is found in the modules directories, which is after all the stages,
+    /// so it can't run before `Alias`. It is disabled by
+    /// `ExportsPriority::Ignore` of `exports_priority`.
+    /// Default is `PipelineStage::DEFAULT`.
+    pub pipeline: Vec<PipelineStage>,
     /// Use of cache defined external, it designed to shared the info of `description_file`
     /// in different resolver.
     ///
@@ -45,38 +346,187 @@ pub struct Options {
     /// is a symlink.
     /// Default is `true`.
     pub symlinks: bool,
+    /// Whether to resolve the real path of the packages installed by pnpm
+    /// from the listing of its virtual store `node_modules/.pnpm`, rather
+    /// than walking the symlinks. The result is mapped to
+    /// `.pnpm/<name>@<version>/node_modules/<name>` directly, and the
+    /// symlink is only read if several versions of the package are in the
+    /// store. The layout is trusted, so the directories above the store
+    /// are not checked for symlinks. It takes effect only if `symlinks` is
+    /// enabled.
+    /// Default is `false`.
+    pub pnpm_store: bool,
+    /// The logical dependency graph of a lockfile, such as
+    /// `DependencyGraph::from_package_lock`. The bare request issued from
+    /// a package in the graph is resolved by the dependencies declared for
+    /// the package, rather than walking the `modules` directories, so the
+    /// hoisted packages which are not declared are not found. The requests
+    /// out of the packages in the graph are resolved as usual.
+    /// Default is `None`.
+    pub dependency_graph: Option<Arc<DependencyGraph>>,
+    /// Verifies the version of the package resolved from the `modules`
+    /// directories against the one pinned by the lockfile, such as
+    /// `LockedVersions::from_pnpm_lock`, the mismatch is reported to the
+    /// warning.
+    /// Default is `None`.
+    pub lockfile_check: Option<LockfileCheck>,
     /// A JSON file to describing this lib information.
     /// Default is `"package.json"`.
     pub description_file: String,
+    /// Print the trace of the resolution in this resolver to stderr,
+    /// same as `RESOLVER_DEBUG=1` but does not affect other resolvers.
+    /// It requires the `tracing` feature.
+    /// Default is `false`.
+    pub debug: bool,
+    /// Whether to tolerate comments and trailing commas in description
+    /// files, `tsconfig.json` is always parsed as JSONC.
+    /// Default is `false`.
+    pub lenient_json: bool,
+    /// The policy for the description files which can't be parsed, so a
+    /// corrupted one deep in `node_modules` doesn't fail the unrelated
+    /// resolutions.
+    /// Default is `ManifestErrorPolicy::Error`.
+    pub on_manifest_error: ManifestErrorPolicy,
     /// Resolve to a context instead of a file.
     /// Default is `false`
     pub resolve_to_context: bool,
-    /// Main file in this directory.
+    /// The file names tried in order when a directory is resolved, such as
+    /// `["index", "main"]`, the `extensions` are tried for each of them.
     /// Default is `["index"]`.
     pub main_files: Vec<String>,
+    /// Whether the file or the directory is tried first for the path.
+    /// Default is `PathPreference::File`.
+    pub path_preference: PathPreference,
     /// Main fields in Description.
     /// Default is `["main"]`.
     pub main_fields: Vec<String>,
+    /// The field in package.json of the source entry, such as `"source"`.
+    /// The bare request of a workspace package, which is not in `modules`
+    /// after the symlinks are resolved, is redirected to the source entry
+    /// rather than the built one, such as `src/index.ts` of `dist/index.js`.
+    /// Default is `None`.
+    pub source_field: Option<String>,
+    /// The source entries of the workspace packages keyed by the package
+    /// name, such as `("@ws/a", "./src/index.ts")`, they are relative to
+    /// the package and win over `source_field`.
+    /// Default is `[]`.
+    pub source_entries: Vec<(String, String)>,
     /// Whether read and parse `"browser"` filed
     /// in package.json.
     /// Default is `false`
     pub browser_field: bool,
-    /// Condition names for exports filed. Note that its type is a `HashSet`,
-    /// because the priority is related to the order in which the export field
-    /// fields are written.
+    /// The file which the requests mapped to `false` by `alias`, `fallback`
+    /// or the `"browser"` field are resolved to, such as a shim without
+    /// exports, for the bundlers which need a file rather than
+    /// `ResolveResult::Ignored`. The relative path is resolved from the
+    /// current directory, and `Resolver::new` panics if it is not a file.
+    /// Default is `None`.
+    pub empty_module: Option<PathBuf>,
+    /// The policy called with each resolved file, which could reject it
+    /// or replace it with another file, such as forbidding the `dist/` of
+    /// the workspace packages or redirecting it to `src/`. The verdict is
+    /// memoized with the result, and the directories, builtin modules
+    /// and the ignored requests are not checked.
+    /// Default is `None`.
+    pub before_resolved: Option<Arc<dyn BeforeResolved>>,
+    /// Condition names for exports filed. The priority is related to the
+    /// order in which the conditions are written in the field, unless
+    /// `prefer_condition_order` is enabled.
     /// Default is `[]`.
-    pub condition_names: HashSet<String>,
+    pub condition_names: Vec<String>,
+    /// Match the conditions of `"exports"` and `"imports"` field by the
+    /// order of `condition_names` rather than the declared order, such as
+    /// preferring `"module-sync"` or `"worker"` to the sibling conditions
+    /// declared before them. `"default"` is still the last one.
+    /// Default is `false`.
+    pub prefer_condition_order: bool,
     /// When this filed exists, it tries to read `baseURL`
     /// and `paths` in the corresponding tsconfig,
     /// and processes the mappings.
     /// Default is `None`.
     pub tsconfig: Option<PathBuf>,
+    /// The location of a Web import map, such as `importmap.json`. Its
+    /// `imports` and `scopes` are applied to the request before the other
+    /// options, the longest prefix wins and the scopes cascade from the
+    /// most specific one to the top-level `imports`. The keys and
+    /// addresses are resolved against the directory of the import map, and
+    /// the addresses which are not file paths, such as `https:` URLs, block
+    /// the request same as `null`.
+    /// Default is `None`.
+    pub import_map: Option<PathBuf>,
+    /// The project roots with their own `alias`, `tsconfig` and
+    /// `extensions`, a request is resolved with the profile of the
+    /// innermost root containing the path passed to `resolve`, and the
+    /// others use the options. They share the cache of the resolver.
+    /// Default is `[]`.
+    pub roots: Vec<RootProfile>,
+    /// The directories where the walk of ancestors stops, such as the
+    /// workspace root or the home directory. The description files and
+    /// `modules` directories above them are never looked up, which also
+    /// applies to the `extends` of tsconfig. The relative ones are resolved
+    /// from the current directory.
+    /// Default is `[]`.
+    pub boundaries: Vec<PathBuf>,
+    /// Record the paths looked up by each result, which are used by
+    /// `Resolver::affected_resolutions` to find the results changed by
+    /// the files in watch mode.
+    /// Default is `false`.
+    pub track_dependencies: bool,
     /// A list of directories to resolve modules from, can be absolute path or folder name.
     /// Default is `["node_modules"]`
     pub modules: Vec<String>,
+    /// Whether to resolve the builtin modules of Node.js, such as `fs` and
+    /// `node:fs`, to `ResolveResult::Builtin` rather than looking them up
+    /// in `modules`. It works after `alias`, so a builtin module could
+    /// still be replaced by an alias.
+    /// Default is `false`.
+    pub builtin_modules: bool,
+    /// The resolution algorithm, the options which conflict with the
+    /// algorithm are overridden.
+    /// Default is `ResolutionMode::Default`.
+    pub mode: ResolutionMode,
+    /// Adds the `"development"` or `"production"` condition into
+    /// `condition_names`, and removes the other one. The results are
+    /// memoized per resolver, so the resolvers with different `env` could
+    /// share `external_cache`.
+    /// Default is `None`.
+    pub env: Option<BuildEnv>,
+    /// Selects the extensions of the target in `target_extensions`, and
+    /// removes `.node` from `extensions` and `binary_extensions` for the
+    /// targets without the native addons, such as `Target::Web`.
+    /// Default is `None`.
+    pub target: Option<Target>,
+    /// The extensions for each target, which replace `extensions` when
+    /// `target` is the same, such as
+    /// `(Target::Web, vec![".web.js".into(), ".js".into()])`.
+    /// Default is `vec![]`.
+    pub target_extensions: Vec<(Target, Vec<String>)>,
+    /// Whether to follow webpack or Node.js where they are different.
+    /// Default is `CompatMode::Webpack`.
+    pub compat: CompatMode,
+    /// Whether to choose the defaults by the `"type"` field of the nearest
+    /// description file of the issuer. For `"module"`, the `"import"`
+    /// condition is used, `".mjs"` takes precedence over `".js"` and the
+    /// request must be fully specified. Otherwise, the `"require"`
+    /// condition is used.
+    /// Default is `false`.
+    pub module_type_defaults: bool,
+    /// Global module directories which are consulted after the ancestor
+    /// `modules` walk failed, same as the `NODE_PATH` in Node.js.
+    /// See [`Options::node_path`] to read them from the environment.
+    /// Default is `[]`.
+    pub global_paths: Vec<PathBuf>,
     /// Same as `alias`, but only used if default resolving fails.
     /// Default is `[]`.
     pub fallback: Alias,
+    /// The requests which are one of them or in one of them, such as
+    /// `fsevents` and `fsevents/lib` for `fsevents` but not `fsevents-x`,
+    /// are optional, such as the `optionalDependencies`. The prefix ends
+    /// with `/` matches all the requests starting with it. They are
+    /// resolved to `Ignored` rather than an error when they can't be
+    /// resolved.
+    /// Default is `[]`.
+    pub optional_prefixes: Vec<String>,
     /// Request passed to resolve is already fully specified and
     /// extensions or main files are not resolved for it.
     /// Default is `false`.
@@ -84,9 +534,44 @@ pub struct Options {
     /// A list of exports fields in descriptions files
     /// Default is `[["exports"]]`.
     pub exports_field: Vec<Vec<String>>,
+    /// Validate the whole exports field before using it, and report misuses
+    /// such as target not start with `"./"`, escaping the package by `".."`
+    /// and conditions after `"default"` as `Error::InvalidExportsField`.
+    /// Default is `false`.
+    pub strict_exports: bool,
+    /// Whether the main fields and files are still used for the package
+    /// with `exports`.
+    /// Default is `ExportsPriority::Encapsulate`.
+    pub exports_priority: ExportsPriority,
+    /// Resolve the declaration files for TypeScript instead of the scripts,
+    /// it puts `"types"` and `"typings"` before `main_fields`, `".d.ts"` before
+    /// `extensions` and adds `"types"` into `condition_names`, which is
+    /// matched before the sibling conditions in `exports` as TypeScript does.
+    /// Default is `false`.
+    pub resolve_types: bool,
+    /// The version of TypeScript used to select the `"typesVersions"` in
+    /// package.json, only works when `resolve_types` is enabled.
+    /// `None` represents the latest version.
+    /// Default is `None`.
+    pub typescript_version: Option<String>,
     /// A vector which maps extension to extension aliases.
     /// Default is `[]`.
     pub extension_alias: Vec<(String, Vec<String>)>,
+    /// The maximum depth of the nested resolution, such as the alias
+    /// which is resolved to an `exports` field which is resolved to
+    /// another alias. It returns `Error::Overflow` when it is reached.
+    /// The redirects are resolved recursively, so it also bounds the stack
+    /// used by a pathological alias chain, the walk of ancestors to find
+    /// modules is a loop and not counted. A large value needs a larger
+    /// stack of the resolving thread.
+    /// Default is `127`.
+    pub max_depth: u16,
+    /// The maximum times of the request being redirected to another one
+    /// by alias, `exports`, main fields and so on in a single resolution,
+    /// walking up the ancestors to find modules is not counted.
+    /// It returns `Error::Overflow` when exceeded.
+    /// Default is `1024`.
+    pub max_redirects: u16,
 }
 
 impl Default for Options {
@@ -97,41 +582,260 @@ impl Default for Options {
             String::from(".node"),
         ];
         let main_files = vec![String::from("index")];
+        let pipeline = PipelineStage::DEFAULT.to_vec();
+        let path_preference = PathPreference::File;
         let main_fields = vec![String::from("main")];
+        let source_field = None;
+        let source_entries = vec![];
         let description_file = String::from("package.json");
         let alias = vec![];
+        let prefix = vec![];
+        let rewrites = vec![];
+        let binary_extensions = vec![String::from(".node")];
+        let scoped_alias = vec![];
         let symlinks = true;
+        let pnpm_store = false;
+        let dependency_graph = None;
+        let lockfile_check = None;
         let browser_field = false;
-        let condition_names = HashSet::default();
+        let empty_module = None;
+        let condition_names = vec![];
+        let prefer_condition_order = false;
         let prefer_relative = false;
         let enforce_extension = EnforceExtension::Auto;
         let tsconfig = None;
+        let import_map = None;
+        let roots = vec![];
+        let boundaries = vec![];
+        let track_dependencies = false;
+        let before_resolved = None;
         let external_cache = None;
+        let debug = false;
+        let lenient_json = false;
+        let on_manifest_error = ManifestErrorPolicy::Error;
         let resolve_to_context = false;
         let modules = vec![String::from("node_modules")];
+        let builtin_modules = false;
+        let mode = ResolutionMode::Default;
+        let env = None;
+        let target = None;
+        let target_extensions = vec![];
+        let compat = CompatMode::Webpack;
+        let module_type_defaults = false;
+        let global_paths = vec![];
         let fallback = vec![];
+        let optional_prefixes = vec![];
         let fully_specified = false;
         let exports_field = vec![vec![String::from("exports")]];
+        let strict_exports = false;
+        let exports_priority = ExportsPriority::Encapsulate;
+        let resolve_types = false;
+        let typescript_version = None;
         let extension_alias = vec![];
+        let max_depth = 127;
+        let max_redirects = 1024;
         Self {
             fallback,
+            optional_prefixes,
             modules,
+            builtin_modules,
+            mode,
+            env,
+            target,
+            target_extensions,
+            compat,
+            module_type_defaults,
+            global_paths,
             extensions,
+            binary_extensions,
             enforce_extension,
             alias,
+            prefix,
+            rewrites,
+            scoped_alias,
             prefer_relative,
+            pipeline,
             external_cache,
             symlinks,
+            pnpm_store,
+            dependency_graph,
+            lockfile_check,
+            description_file,
+            debug,
+            lenient_json,
+            on_manifest_error,
+            resolve_to_context,
+            main_files,
+            path_preference,
+            main_fields,
+            source_field,
+            source_entries,
+            browser_field,
+            empty_module,
+            before_resolved,
+            condition_names,
+            prefer_condition_order,
+            tsconfig,
+            import_map,
+            roots,
+            boundaries,
+            track_dependencies,
+            fully_specified,
+            exports_field,
+            strict_exports,
+            exports_priority,
+            resolve_types,
+            typescript_version,
+            extension_alias,
+            max_depth,
+            max_redirects,
+        }
+    }
+}
+
+impl Options {
+    /// Read the directories listed in the `NODE_PATH` environment
+    /// variable, which could be used as `global_paths`.
+    #[must_use]
+    pub fn node_path() -> Vec<PathBuf> {
+        std::env::var_os("NODE_PATH")
+            .map(|paths| {
+                std::env::split_paths(&paths)
+                    .filter(|path| !path.as_os_str().is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    /// The hash of the fields which affect the resolutions, it is stable
+    /// across the runs on the same platform with the same version of the
+    /// crate, so it could validate a persistent cache, see
+    /// [`crate::WarmEntries::fingerprint`].
+    ///
+    /// All the fields participate but `external_cache` and `debug`. The
+    /// hooks, `before_resolved` and the warning of `on_manifest_error`,
+    /// participate by whether they are set rather than what they are, and
+    /// the rules of `rewrites` by the source of the regex.
+    #[must_use]
+    pub fn fingerprint(&self) -> u64 {
+        let Self {
+            extensions,
+            binary_extensions,
+            enforce_extension,
+            alias,
+            prefix,
+            rewrites,
+            scoped_alias,
+            prefer_relative,
+            pipeline,
+            external_cache: _,
+            symlinks,
+            pnpm_store,
+            dependency_graph,
+            lockfile_check,
             description_file,
+            debug: _,
+            lenient_json,
+            on_manifest_error,
             resolve_to_context,
             main_files,
+            path_preference,
             main_fields,
+            source_field,
+            source_entries,
             browser_field,
+            empty_module,
+            before_resolved,
             condition_names,
+            prefer_condition_order,
             tsconfig,
+            import_map,
+            roots,
+            boundaries,
+            track_dependencies,
+            modules,
+            builtin_modules,
+            mode,
+            env,
+            target,
+            target_extensions,
+            compat,
+            module_type_defaults,
+            global_paths,
+            fallback,
+            optional_prefixes,
             fully_specified,
             exports_field,
+            strict_exports,
+            exports_priority,
+            resolve_types,
+            typescript_version,
             extension_alias,
+            max_depth,
+            max_redirects,
+        } = self;
+        let mut hasher = Xxh3::new();
+        (extensions, binary_extensions, enforce_extension, alias).hash(&mut hasher);
+        for (regex, template) in rewrites {
+            (regex.as_str(), template).hash(&mut hasher);
+        }
+        (prefix, scoped_alias, prefer_relative, pipeline).hash(&mut hasher);
+        (symlinks, pnpm_store, dependency_graph).hash(&mut hasher);
+        lockfile_check
+            .as_ref()
+            .map(|check| &check.versions)
+            .hash(&mut hasher);
+        (description_file, lenient_json).hash(&mut hasher);
+        match on_manifest_error {
+            ManifestErrorPolicy::Error => 0.hash(&mut hasher),
+            ManifestErrorPolicy::Ignore => 1.hash(&mut hasher),
+            ManifestErrorPolicy::WarnAndIgnore(_) => 2.hash(&mut hasher),
+        }
+        (resolve_to_context, main_files, path_preference, main_fields).hash(&mut hasher);
+        (source_field, source_entries, browser_field, empty_module).hash(&mut hasher);
+        before_resolved.is_some().hash(&mut hasher);
+        (
+            condition_names,
+            prefer_condition_order,
+            tsconfig,
+            import_map,
+        )
+            .hash(&mut hasher);
+        for root in roots {
+            (&root.root, &root.alias, &root.tsconfig, &root.extensions).hash(&mut hasher);
+        }
+        (boundaries, track_dependencies, modules, builtin_modules).hash(&mut hasher);
+        (mode, env, target, target_extensions).hash(&mut hasher);
+        (compat, module_type_defaults, global_paths).hash(&mut hasher);
+        (fallback, optional_prefixes, fully_specified, exports_field).hash(&mut hasher);
+        (strict_exports, exports_priority, resolve_types).hash(&mut hasher);
+        (
+            typescript_version,
+            extension_alias,
+            max_depth,
+            max_redirects,
+        )
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The addresses of the hooks, they identify the hooks in the process
+    /// which are not covered by `fingerprint`.
+    pub(crate) fn hook_addresses(&self) -> [Option<usize>; 3] {
+        fn address<T: ?Sized>(hook: &Arc<T>) -> usize {
+            Arc::as_ptr(hook).cast::<()>() as usize
         }
+        let warning = match &self.on_manifest_error {
+            ManifestErrorPolicy::WarnAndIgnore(warning) => Some(address(warning)),
+            _ => None,
+        };
+        let lockfile_warning = self
+            .lockfile_check
+            .as_ref()
+            .map(|check| address(&check.warning));
+        [
+            self.before_resolved.as_ref().map(address),
+            warning,
+            lockfile_warning,
+        ]
     }
 }
@@ -1,7 +1,27 @@
 use crate::kind::PathKind;
 use crate::Resolver;
 
-#[derive(Clone, Debug)]
+/// The reason why a request is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidRequest {
+    /// The request is empty.
+    Empty,
+    /// The request only contains whitespaces.
+    Whitespace,
+    /// The request only contains the query or fragment, such as `?a`.
+    OnlyQueryOrFragment,
+    /// The request starts with `#!`, such as a shebang line.
+    Shebang,
+    /// The scoped package request without the name, such as `@scope` or
+    /// `@scope/`.
+    ScopeWithoutName,
+    /// The package request contains the encoded `/` or `\`, such as
+    /// `@scope%2Fname`, only in `ResolutionMode::Esm` and `Deno`.
+    EncodedSeparator,
+}
+
+/// The request which is split into the target, query and fragment.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Request {
     target: Box<str>,
     query: Option<Box<str>>,
@@ -24,11 +44,72 @@ impl Default for Request {
 
 impl std::fmt::Display for Request {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}{}", self.target(), self.query(), self.fragment())
+        let slash = if self.is_directory() { "/" } else { "" };
+        write!(
+            f,
+            "{}{slash}{}{}",
+            self.target(),
+            self.query(),
+            self.fragment()
+        )
     }
 }
 
 impl Request {
+    /// Parses the request as the resolver does:
+    ///
+    /// - The query starts with the first `?` and the fragment starts with
+    ///   the first `#` after the target, such as `./a.svg?inline#icon`.
+    /// - The leading `#` is a part of target, such as `#internal`.
+    /// - The `?` in the fragment is a part of fragment.
+    /// - `\0` escapes the following `#` or `?`, such as `./a\0#b.js`
+    ///   whose target is `./a#b.js`.
+    /// - The trailing `/` of target is removed and marked by
+    ///   [`Request::is_directory`].
+    ///
+    /// ```
+    /// use nodejs_resolver::Request;
+    ///
+    /// let request = Request::parse("./a.svg?inline#icon");
+    /// assert_eq!(request.target(), "./a.svg");
+    /// assert_eq!(request.query(), "?inline");
+    /// assert_eq!(request.fragment(), "#icon");
+    /// ```
+    #[must_use]
+    pub fn parse(request: &str) -> Self {
+        Self::from_request(request)
+    }
+
+    /// Same as [`Request::parse`], but rejects the request which does not
+    /// point to anything by itself. Note that the empty and query or
+    /// fragment only requests are accepted by `Resolver::resolve`, they
+    /// are resolved as the path itself.
+    pub fn try_parse(request: &str) -> Result<Self, InvalidRequest> {
+        if request.is_empty() {
+            return Err(InvalidRequest::Empty);
+        }
+        if let Some(reason) = Self::invalid_reason(request) {
+            return Err(reason);
+        }
+        let parsed = Self::parse(request);
+        if parsed.target().is_empty() && !parsed.is_directory() {
+            Err(InvalidRequest::OnlyQueryOrFragment)
+        } else {
+            Ok(parsed)
+        }
+    }
+
+    /// Returns the reason if the request is rejected by `Resolver::resolve`.
+    pub(crate) fn invalid_reason(request: &str) -> Option<InvalidRequest> {
+        if !request.is_empty() && request.trim().is_empty() {
+            Some(InvalidRequest::Whitespace)
+        } else if request.starts_with("#!") {
+            Some(InvalidRequest::Shebang)
+        } else {
+            None
+        }
+    }
+
     #[must_use]
     pub fn from_request(request: &str) -> Self {
         let (target, query, fragment) = Self::parse_identifier(request);
@@ -47,14 +128,17 @@ impl Request {
         }
     }
 
+    /// Returns the target without the trailing `/`.
     pub fn target(&self) -> &str {
         &self.target
     }
 
+    /// Returns the query starts with `?`, empty if there is no query.
     pub fn query(&self) -> &str {
         self.query.as_ref().map_or("", |query| query.as_ref())
     }
 
+    /// Returns the fragment starts with `#`, empty if there is no fragment.
     pub fn fragment(&self) -> &str {
         self.fragment
             .as_ref()
@@ -65,6 +149,7 @@ impl Request {
         self.kind
     }
 
+    /// Whether the target ends with `/`.
     pub fn is_directory(&self) -> bool {
         self.is_directory
     }
@@ -97,8 +182,15 @@ impl Request {
         let mut query: Option<usize> = None;
         let mut fragment: Option<usize> = None;
         let mut stats = ParseStats::Start;
-        for (index, c) in ident.as_bytes().iter().enumerate() {
+        let bytes = ident.as_bytes();
+        for (index, c) in bytes.iter().enumerate() {
+            let escaped = index > 0 && bytes[index - 1] == b'\0';
             match c {
+                b'#' | b'?' if escaped => {
+                    if let ParseStats::Start = stats {
+                        stats = ParseStats::Request;
+                    }
+                }
                 b'#' => match stats {
                     ParseStats::Request | ParseStats::Query => {
                         stats = ParseStats::Fragment;
@@ -125,17 +217,34 @@ impl Request {
         }
 
         match (query, fragment) {
-            (None, None) => (ident.into(), None, None),
-            (None, Some(j)) => (ident[0..j].into(), None, Some(ident[j..].into())),
-            (Some(i), None) => (ident[0..i].into(), Some(ident[i..].into()), None),
+            (None, None) => (Self::unescape(ident), None, None),
+            (None, Some(j)) => (
+                Self::unescape(&ident[0..j]),
+                None,
+                Some(Self::unescape(&ident[j..])),
+            ),
+            (Some(i), None) => (
+                Self::unescape(&ident[0..i]),
+                Some(Self::unescape(&ident[i..])),
+                None,
+            ),
             (Some(i), Some(j)) => (
-                ident[0..i].into(),
-                Some(ident[i..j].into()),
-                Some(ident[j..].into()),
+                Self::unescape(&ident[0..i]),
+                Some(Self::unescape(&ident[i..j])),
+                Some(Self::unescape(&ident[j..])),
             ),
         }
     }
 
+    /// Removes the `\0` which escapes `#` or `?`.
+    fn unescape(part: &str) -> Box<str> {
+        if part.contains('\0') {
+            part.replace("\0#", "#").replace("\0?", "?").into()
+        } else {
+            part.into()
+        }
+    }
+
     #[inline]
     fn is_target_directory(target: &str) -> bool {
         target.ends_with('/')
@@ -181,6 +290,13 @@ fn parse_identifier_test() {
     );
     should_parsed("#a?b#c?d", "#a", "?b", "#c?d");
 
+    // escaped
+    should_parsed("path/\0#a", "path/#a", "", "");
+    should_parsed("path/\0?a\0#b?c#d", "path/?a#b", "?c", "#d");
+    should_parsed("path/a?b\0#c#d", "path/a", "?b#c", "#d");
+    should_parsed("\0#a", "#a", "", "");
+    should_parsed("path/\0a", "path/\0a", "", "");
+
     // windows like
     should_parsed("path\\#", "path\\", "", "#");
     should_parsed("C:path\\as\\?", "C:path\\as\\", "?", "");
@@ -194,3 +310,47 @@ fn parse_identifier_test() {
         "#/not/a/hash?not-a-query",
     );
 }
+
+#[test]
+fn try_parse_test() {
+    assert_eq!(Request::try_parse(""), Err(InvalidRequest::Empty));
+    assert_eq!(Request::try_parse(" \t\n"), Err(InvalidRequest::Whitespace));
+    assert_eq!(
+        Request::try_parse("?a"),
+        Err(InvalidRequest::OnlyQueryOrFragment)
+    );
+    assert_eq!(
+        Request::try_parse("?a#b"),
+        Err(InvalidRequest::OnlyQueryOrFragment)
+    );
+    assert_eq!(
+        Request::try_parse("#!/usr/bin/env node"),
+        Err(InvalidRequest::Shebang)
+    );
+    assert_eq!(Request::try_parse("#a").unwrap().target(), "#a");
+    assert_eq!(Request::try_parse("/").unwrap().target(), "");
+    assert_eq!(Request::try_parse(" a").unwrap().target(), " a");
+}
+
+#[test]
+fn parse_fuzz_test() {
+    // all the requests consisted of these characters, up to the length.
+    const ALPHABET: [char; 9] = ['a', '.', '/', '\\', '#', '?', '\0', ' ', 'é'];
+    const MAX_LENGTH: u32 = 5;
+    for length in 0..=MAX_LENGTH {
+        for mut index in 0..ALPHABET.len().pow(length) {
+            let mut request = String::new();
+            for _ in 0..length {
+                request.push(ALPHABET[index % ALPHABET.len()]);
+                index /= ALPHABET.len();
+            }
+            let parsed = Request::parse(&request);
+            assert!(parsed.query().is_empty() || parsed.query().starts_with('?'));
+            assert!(parsed.fragment().is_empty() || parsed.fragment().starts_with('#'));
+            // the parts are joined to the request without escaping.
+            let unescaped = request.replace("\0#", "#").replace("\0?", "?");
+            assert_eq!(parsed.to_string(), unescaped);
+            let _ = Request::try_parse(&request);
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use super::Plugin;
-use crate::{log::depth, options::Alias, AliasMap, Context, Info, ResolveResult, Resolver, State};
+use crate::{
+    log::debug, log::depth, options::Alias, AliasMap, AttemptPhase, Context, Error, IgnoredReason,
+    Info, ResolveResult, Resolver, State,
+};
 
 pub struct AliasPlugin<'a>(&'a Alias);
 
@@ -35,7 +38,7 @@ impl<'a> Plugin for AliasPlugin<'a> {
                 (hit, from.as_str())
             };
             if hit {
-                tracing::debug!(
+                debug!(
                     "AliasPlugin works, triggered by '{from}'({})",
                     depth(&context.depth)
                 );
@@ -46,6 +49,11 @@ impl<'a> Plugin for AliasPlugin<'a> {
                                 // skip `target.starts_with(to)` to prevent infinite loop.
                                 continue;
                             }
+                            if context.alias_chain.iter().any(|applied| applied == from) {
+                                let mut chain = context.alias_chain.clone();
+                                chain.push(from.to_string());
+                                return State::Error(Error::AliasCycle(chain));
+                            }
                             let normalized_target = inner_target.replacen(key, to, 1);
                             let old_request = info.request();
                             let old_query = old_request.query();
@@ -60,12 +68,17 @@ impl<'a> Plugin for AliasPlugin<'a> {
                                     (false, true) => request.with_fragment(old_fragment),
                                     (false, false) => request,
                                 };
+                            context.record(AttemptPhase::Alias, || {
+                                format!("'{inner_target}' -> '{normalized_target}'")
+                            });
                             let alias_info = info.clone().with_request(request);
                             let fully_specified = context.fully_specified.get();
                             if fully_specified {
                                 context.fully_specified.set(false);
                             }
+                            context.alias_chain.push(from.to_string());
                             let state = resolver._resolve(alias_info, context);
+                            context.alias_chain.pop();
                             if fully_specified {
                                 context.fully_specified.set(true);
                             }
@@ -73,10 +86,15 @@ impl<'a> Plugin for AliasPlugin<'a> {
                                 return state;
                             }
                         }
-                        AliasMap::Ignored => return State::Success(ResolveResult::Ignored),
+                        AliasMap::Ignored => {
+                            return State::Success(ResolveResult::Ignored {
+                                reason: IgnoredReason::Alias(from.to_string()),
+                                request: inner_target.to_string(),
+                            })
+                        }
                     }
                 }
-                tracing::debug!("Leaving AliasPlugin({})", depth(&context.depth));
+                debug!("Leaving AliasPlugin({})", depth(&context.depth));
             }
         }
 
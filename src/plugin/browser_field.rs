@@ -1,6 +1,6 @@
 use crate::{
-    context::Context, description::DescriptionData, log::color, log::depth, AliasMap, Info,
-    PathKind, Plugin, ResolveResult, Resolver, State,
+    context::Context, description::DescriptionData, log::color, log::debug, log::depth, AliasMap,
+    IgnoredReason, Info, PathKind, Plugin, ResolveResult, Resolver, State,
 };
 use path_absolutize::Absolutize;
 use std::path::{Path, PathBuf};
@@ -61,13 +61,13 @@ impl<'a> Plugin for BrowserFieldPlugin<'a> {
                 false => Self::request_path_is_equal_alias_key_path(
                     &self.pkg_info.dir().as_ref().join(alias_key),
                     &info,
-                    &resolver.options.extensions,
+                    &resolver.extensions(context),
                 ),
             };
             if !should_deal_alias {
                 continue;
             }
-            tracing::debug!(
+            debug!(
                 "BrowserFiled in '{}' works, trigger by '{}'({})",
                 color::blue(&format!(
                     "{}/package.json",
@@ -100,9 +100,17 @@ impl<'a> Plugin for BrowserFieldPlugin<'a> {
                     if state.is_finished() {
                         return state;
                     }
-                    tracing::debug!("Leaving BrowserFiled({})", depth(&context.depth));
+                    debug!("Leaving BrowserFiled({})", depth(&context.depth));
+                }
+                AliasMap::Ignored => {
+                    return State::Success(ResolveResult::Ignored {
+                        reason: IgnoredReason::BrowserField {
+                            dir: self.pkg_info.dir().as_ref().to_path_buf(),
+                            key: alias_key.to_string(),
+                        },
+                        request: info.request().target().to_string(),
+                    })
                 }
-                AliasMap::Ignored => return State::Success(ResolveResult::Ignored),
             };
         }
         State::Resolving(info)
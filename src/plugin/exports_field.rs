@@ -1,13 +1,16 @@
 use crate::{
-    description::DescriptionData,
+    description::{DescriptionData, ExportsForm},
+    error::{InvalidExportsField, PackagePathNotExported},
     log::color,
+    log::debug,
     log::depth,
-    map::{ExportsField, Field},
+    map::{escape_json_pointer, validate_exports_field, ExportsField, Field},
     resolve::get_path_from_request,
-    Context, Error, Info, Resolver, State,
+    suggest::nearest_subpath,
+    AttemptPhase, CompatMode, Context, Error, Info, Resolver, State,
 };
 
-use super::Plugin;
+use super::{check_specifier, Plugin};
 
 pub struct ExportsFieldPlugin<'a> {
     pkg_info: &'a DescriptionData,
@@ -26,10 +29,33 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
 
         for field in &resolver.options.exports_field {
             let root = match self.pkg_info.data().get_filed(field) {
-                Some(exports_tree) => exports_tree,
-                None => continue,
+                // `null` is the same as the absent field.
+                Some(exports_tree) if ExportsForm::of(exports_tree) != ExportsForm::Null => {
+                    exports_tree
+                }
+                _ => continue,
             };
 
+            if resolver.options.strict_exports {
+                let pointer = field.iter().fold(String::new(), |pointer, key| {
+                    format!("{pointer}/{}", escape_json_pointer(key))
+                });
+                if let Some((pointer, misuse)) = validate_exports_field(root, &pointer) {
+                    let path = self
+                        .pkg_info
+                        .dir()
+                        .as_ref()
+                        .join(&resolver.options.description_file);
+                    return State::Error(Error::InvalidExportsField(Box::new(
+                        InvalidExportsField {
+                            path: path.into(),
+                            pointer,
+                            misuse,
+                        },
+                    )));
+                }
+            }
+
             if request.is_directory() {
                 return State::Error(Error::UnexpectedValue(format!(
                     "Resolving to directories is not possible with the exports field (request was {}/ in {})",
@@ -59,6 +85,7 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
                 }
             };
 
+            let subpath = normalized_target.clone();
             let remaining_target = if !query.is_empty() || !fragment.is_empty() {
                 let normalized_target = if normalized_target == "." {
                     String::from("./")
@@ -73,21 +100,39 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
             let list = match ExportsField::field_process(
                 root,
                 &remaining_target,
-                &resolver.options.condition_names,
+                &resolver.condition_names(context),
+                resolver.options.prefer_condition_order,
             ) {
                 Ok(list) => list,
                 Err(err) => return State::Error(err),
             };
+            context.record(AttemptPhase::Exports, || {
+                format!(
+                    "'{remaining_target}' in '{}' -> [{}]",
+                    self.pkg_info.dir().as_ref().display(),
+                    list.join(", ")
+                )
+            });
 
             if list.is_empty() {
-                return State::Error(Error::UnexpectedValue(format!(
-                    "Package path {target} is not exported in {}/package.json",
-                    self.pkg_info.dir().as_ref().display()
+                let path = self
+                    .pkg_info
+                    .dir()
+                    .as_ref()
+                    .join(&resolver.options.description_file);
+                let suggestion = nearest_subpath(root, &subpath);
+                return State::Error(Error::PackagePathNotExported(Box::new(
+                    PackagePathNotExported {
+                        request: target.to_string(),
+                        subpath,
+                        path: path.into(),
+                        suggestion,
+                    },
                 )));
             }
 
             for item in list {
-                tracing::debug!(
+                debug!(
                     "ExportsField in '{}' works, trigger by '{}', mapped to '{}'({})",
                     color::blue(&format!(
                         "{}/package.json",
@@ -105,14 +150,19 @@ impl<'a> Plugin for ExportsFieldPlugin<'a> {
                 }
                 let request = Resolver::parse(&item);
                 let info = Info::from(self.pkg_info.dir().clone()).with_request(request);
-                if let Err(msg) = ExportsField::check_target(info.request().target()) {
-                    let msg = format!("{msg} in {:?}/package.json", &self.pkg_info.dir());
-                    return State::Error(Error::UnexpectedValue(msg));
+                if let Err(error) =
+                    check_specifier(resolver, self.pkg_info, target, info.request().target())
+                {
+                    return State::Error(error);
                 }
                 let state = resolver._resolve(info, context);
                 if state.is_finished() {
                     return state;
                 }
+                // node does not fall back when the target does not exist.
+                if resolver.options.compat == CompatMode::Node {
+                    break;
+                }
             }
 
             return State::Failed(info);
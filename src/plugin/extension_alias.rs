@@ -32,8 +32,8 @@ impl<'a> Plugin for ExtensionAliasPlugin<'a> {
                     alias
                 );
                 let next = info.clone().with_target(target);
-                let path = next.to_resolved_path().to_path_buf();
-                let next = next.with_path(path).with_target("");
+                let path = next.to_resolved_path();
+                let next = next.with_normalized_path(path).with_target("");
                 let origin = context.fully_specified.get();
                 context.fully_specified.set(true);
                 let state = resolver._resolve(next, context);
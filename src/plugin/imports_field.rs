@@ -1,11 +1,12 @@
-use super::Plugin;
+use super::{check_specifier, Plugin};
 use crate::{
     context::Context,
     description::DescriptionData,
     log::color,
+    log::debug,
     log::depth,
     map::{Field, ImportsField},
-    Error, Info, PathKind, Resolver, State,
+    AttemptPhase, Error, Info, PathKind, Resolver, State,
 };
 
 pub struct ImportsFieldPlugin<'a> {
@@ -19,13 +20,12 @@ impl<'a> ImportsFieldPlugin<'a> {
 
     fn check_target(&self, resolver: &Resolver, info: Info) -> State {
         let path = info.to_resolved_path();
-        if resolver.load_entry(&path).is_file() {
-            if let Err(msg) = ImportsField::check_target(info.request().target()) {
-                let msg = format!("{msg} in {:?}/package.json", &self.pkg_info.dir().as_ref());
-                State::Error(Error::UnexpectedValue(msg))
-            } else {
-                State::Resolving(info)
-            }
+        let stat = match resolver.load_entry(&path).try_stat() {
+            Ok(stat) => stat,
+            Err(error) => return State::Error(error),
+        };
+        if stat.is_file() {
+            State::Resolving(info)
         } else {
             State::Error(Error::UnexpectedValue(format!(
                 "Package path {} can't imported in {:?}",
@@ -50,24 +50,39 @@ impl<'a> Plugin for ImportsFieldPlugin<'a> {
         let list = match ImportsField::field_process(
             root,
             info.request().target(),
-            &resolver.options.condition_names,
+            &resolver.condition_names(context),
+            resolver.options.prefer_condition_order,
         ) {
             Ok(list) => list,
             Err(err) => return State::Error(err),
         };
+        context.record(AttemptPhase::Exports, || {
+            format!(
+                "'{}' in '{}' -> [{}]",
+                info.request().target(),
+                self.pkg_info.dir().as_ref().display(),
+                list.join(", ")
+            )
+        });
 
         if let Some(item) = list.first() {
-            tracing::debug!(
+            debug!(
                 "ImportsField in '{}' works, trigger by '{}', mapped to '{}'({})",
                 color::blue(&format!("{:?}/package.json", self.pkg_info.dir().as_ref())),
                 color::blue(&info.request().target()),
                 color::blue(&item),
                 depth(&context.depth)
             );
+            let target = info.request().target();
             let request = Resolver::parse(item);
             let is_relative = !matches!(request.kind(), PathKind::Normal | PathKind::Internal);
             let info = Info::from(self.pkg_info.dir().clone()).with_request(request);
             if is_relative {
+                if let Err(error) =
+                    check_specifier(resolver, self.pkg_info, target, info.request().target())
+                {
+                    return State::Error(error);
+                }
                 self.check_target(resolver, info)
             } else {
                 let fully_specified = context.fully_specified.get();
@@ -1,5 +1,8 @@
 use super::Plugin;
-use crate::{description::DescriptionData, log::color, log::depth, Context, Info, Resolver, State};
+use crate::{
+    description::DescriptionData, log::color, log::debug, log::depth, Context, Info, Resolver,
+    State,
+};
 
 pub struct MainFieldPlugin<'a> {
     pkg_info: &'a DescriptionData,
@@ -14,10 +17,10 @@ impl<'a> MainFieldPlugin<'a> {
 impl<'a> Plugin for MainFieldPlugin<'a> {
     fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
         let resolved = info.to_resolved_path();
-        if !self.pkg_info.dir().as_ref().eq(&*resolved) {
+        if !self.pkg_info.dir().as_ref().eq(resolved.as_ref()) {
             return State::Resolving(info);
         }
-        let main_field_info = info.clone().with_path(resolved).with_target(".");
+        let main_field_info = info.clone().with_normalized_path(resolved).with_target(".");
 
         for user_main_field in &resolver.options.main_fields {
             if let Some(main_field) = self
@@ -31,7 +34,7 @@ impl<'a> Plugin for MainFieldPlugin<'a> {
                     // if it pointed to itself.
                     break;
                 }
-                tracing::debug!(
+                debug!(
                     "MainField in '{}' works, using {} field({})",
                     color::blue(&format!("{:?}/package.json", self.pkg_info.dir().as_ref())),
                     color::blue(user_main_field),
@@ -57,7 +60,7 @@ impl<'a> Plugin for MainFieldPlugin<'a> {
                 if state.is_finished() {
                     return state;
                 }
-                tracing::debug!("Leaving MainField({})", depth(&context.depth));
+                debug!("Leaving MainField({})", depth(&context.depth));
             }
         }
         State::Resolving(info)
@@ -7,9 +7,16 @@ mod main_field;
 mod main_file;
 mod parse;
 mod prefer_relative;
+mod prefix;
+mod rewrite;
+mod scoped_alias;
 mod symlink;
+mod types_versions;
 
-use crate::{context::Context, Info, Resolver, State};
+use crate::{
+    context::Context, description::DescriptionData, map::invalid_segment, Error, Info,
+    InvalidSpecifier, Resolver, State,
+};
 
 pub use alias::AliasPlugin;
 pub use browser_field::BrowserFieldPlugin;
@@ -20,8 +27,35 @@ pub use main_field::MainFieldPlugin;
 pub use main_file::MainFilePlugin;
 pub use parse::ParsePlugin;
 pub use prefer_relative::PreferRelativePlugin;
+pub use prefix::PrefixPlugin;
+pub use rewrite::RewritePlugin;
+pub use scoped_alias::ScopedAliasPlugin;
 pub use symlink::SymlinkPlugin;
+pub use types_versions::TypesVersionsPlugin;
 
 pub(crate) trait Plugin {
     fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State;
 }
+
+/// Returns `Error::InvalidSpecifier` if the `target` mapped from `request`
+/// by the `exports` or `imports` field of `pkg_info` has invalid segment.
+fn check_specifier(
+    resolver: &Resolver,
+    pkg_info: &DescriptionData,
+    request: &str,
+    target: &str,
+) -> Result<(), Error> {
+    match invalid_segment(target) {
+        Some(segment) => Err(Error::InvalidSpecifier(Box::new(InvalidSpecifier {
+            request: request.to_string(),
+            target: target.to_string(),
+            segment: segment.to_string(),
+            path: pkg_info
+                .dir()
+                .as_ref()
+                .join(&resolver.options.description_file)
+                .into(),
+        }))),
+        None => Ok(()),
+    }
+}
@@ -1,5 +1,5 @@
 use super::Plugin;
-use crate::{depth, Context, Info, Resolver, State};
+use crate::{depth, log::debug, Context, Info, Resolver, State};
 
 #[derive(Default)]
 pub struct ParsePlugin;
@@ -11,7 +11,7 @@ impl Plugin for ParsePlugin {
         let no_query = request.query().is_empty();
         let had_request = !info.request().target().is_empty();
         if no_query && had_hash && had_request {
-            tracing::debug!("ParsePlugin works({})", depth(&context.depth));
+            debug!("ParsePlugin works({})", depth(&context.depth));
             let target = format!(
                 "{}{}{}",
                 request.target(),
@@ -23,7 +23,7 @@ impl Plugin for ParsePlugin {
             if state.is_finished() {
                 return state;
             }
-            tracing::debug!("Leaving ParsePlugin({})", depth(&context.depth));
+            debug!("Leaving ParsePlugin({})", depth(&context.depth));
         }
         State::Resolving(info)
     }
@@ -1,5 +1,5 @@
 use super::Plugin;
-use crate::{kind::PathKind, log::depth, Context, Info, Resolver, State};
+use crate::{kind::PathKind, log::debug, log::depth, Context, Info, Resolver, State};
 
 #[derive(Default)]
 pub struct PreferRelativePlugin;
@@ -11,14 +11,14 @@ impl Plugin for PreferRelativePlugin {
         }
 
         if resolver.options.prefer_relative {
-            tracing::debug!("AliasPlugin works({})", depth(&context.depth));
+            debug!("AliasPlugin works({})", depth(&context.depth));
             let target = format!("./{}", info.request().target());
             let info = info.clone().with_target(&target);
             let stats = resolver._resolve(info, context);
             if stats.is_finished() {
                 return stats;
             }
-            tracing::debug!("Leaving AliasPlugin({})", depth(&context.depth));
+            debug!("Leaving AliasPlugin({})", depth(&context.depth));
         }
         State::Resolving(info)
     }
@@ -0,0 +1,92 @@
+use super::Plugin;
+use crate::{
+    log::debug, log::depth, options::PrefixMap, AttemptPhase, Context, Info, Resolver, State,
+};
+
+pub struct PrefixPlugin<'a>(&'a Vec<(String, PrefixMap)>);
+
+impl<'a> PrefixPlugin<'a> {
+    pub fn new(prefixes: &'a Vec<(String, PrefixMap)>) -> Self {
+        Self(prefixes)
+    }
+}
+
+impl<'a> Plugin for PrefixPlugin<'a> {
+    fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
+        let target = info.request().target();
+        for (prefix, to) in self.0 {
+            let Some(rest) = target.strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+            let prefix_info = match to {
+                PrefixMap::Module => {
+                    if rest.is_empty() {
+                        continue;
+                    }
+                    info.clone().with_target(rest)
+                }
+                PrefixMap::Dir(dir) => {
+                    let rest = rest.trim_start_matches('/');
+                    info.clone()
+                        .with_path(dir)
+                        .with_target(&format!("./{rest}"))
+                }
+                PrefixMap::Package => {
+                    let Some((name, subpath)) = split_version(rest) else {
+                        continue;
+                    };
+                    info.clone().with_target(&format!("{name}{subpath}"))
+                }
+                PrefixMap::Jsr => {
+                    let Some((name, subpath)) = split_version(rest) else {
+                        continue;
+                    };
+                    let Some((scope, name)) = name.strip_prefix('@').and_then(|name| name.split_once('/')) else {
+                        continue;
+                    };
+                    info.clone()
+                        .with_target(&format!("@jsr/{scope}__{name}{subpath}"))
+                }
+            };
+            context.record(AttemptPhase::Alias, || {
+                format!(
+                    "'{target}' -> '{}' in '{}'",
+                    prefix_info.request().target(),
+                    prefix_info.normalized_path().as_ref().display()
+                )
+            });
+            debug!(
+                "PrefixPlugin works, triggered by '{prefix}'({})",
+                depth(&context.depth)
+            );
+            let state = resolver._resolve(prefix_info, context);
+            if state.is_finished() {
+                return state;
+            }
+            debug!("Leaving PrefixPlugin({})", depth(&context.depth));
+        }
+        State::Resolving(info)
+    }
+}
+
+/// Splits `<name>[@<range>][/<subpath>]` into the name and the subpath
+/// with the leading `/`, the range is dropped.
+fn split_version(request: &str) -> Option<(&str, &str)> {
+    let scope_len = if request.starts_with('@') {
+        request.find('/')? + 1
+    } else {
+        0
+    };
+    let name_len = request[scope_len..]
+        .find(['@', '/'])
+        .map_or(request.len(), |index| scope_len + index);
+    if name_len == scope_len {
+        return None;
+    }
+    let (name, rest) = request.split_at(name_len);
+    let subpath = match rest.strip_prefix('@') {
+        Some(range) => range.find('/').map_or("", |index| &range[index..]),
+        None => rest,
+    };
+    Some((name, subpath))
+}
@@ -0,0 +1,42 @@
+use super::Plugin;
+use crate::{log::debug, log::depth, AttemptPhase, Context, Info, Resolver, State};
+use regex::Regex;
+
+pub struct RewritePlugin<'a>(&'a Vec<(Regex, String)>);
+
+impl<'a> RewritePlugin<'a> {
+    pub fn new(rewrites: &'a Vec<(Regex, String)>) -> Self {
+        Self(rewrites)
+    }
+}
+
+impl<'a> Plugin for RewritePlugin<'a> {
+    fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
+        let target = info.request().target();
+        for (regex, template) in self.0 {
+            let Some(captures) = regex.captures(target) else {
+                continue;
+            };
+            let mut rewritten = String::new();
+            captures.expand(template, &mut rewritten);
+            if rewritten == target {
+                // skip the rule which rewrites the request to itself to
+                // prevent infinite loop.
+                continue;
+            }
+            context.record(AttemptPhase::Alias, || {
+                format!("'{target}' -> '{rewritten}'")
+            });
+            debug!(
+                "RewritePlugin works, rewrote '{target}' to '{rewritten}'({})",
+                depth(&context.depth)
+            );
+            let state = resolver._resolve(info.clone().with_target(&rewritten), context);
+            if state.is_finished() {
+                return state;
+            }
+            debug!("Leaving RewritePlugin({})", depth(&context.depth));
+        }
+        State::Resolving(info)
+    }
+}
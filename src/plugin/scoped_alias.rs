@@ -0,0 +1,27 @@
+use super::{AliasPlugin, Plugin};
+use crate::{options::Alias, Context, Info, Resolver, State};
+use std::path::PathBuf;
+
+pub struct ScopedAliasPlugin<'a>(&'a Vec<(PathBuf, Alias)>);
+
+impl<'a> ScopedAliasPlugin<'a> {
+    pub fn new(scoped_alias: &'a Vec<(PathBuf, Alias)>) -> Self {
+        Self(scoped_alias)
+    }
+}
+
+impl<'a> Plugin for ScopedAliasPlugin<'a> {
+    fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
+        let mut info = info;
+        for (scope, alias) in self.0 {
+            if !info.normalized_path().as_ref().starts_with(scope) {
+                continue;
+            }
+            match AliasPlugin::new(alias).apply(resolver, info, context) {
+                State::Resolving(next) => info = next,
+                state => return state,
+            }
+        }
+        State::Resolving(info)
+    }
+}
@@ -1,5 +1,12 @@
 use super::Plugin;
-use crate::{log::depth, Context, Info, ResolveResult, Resolver, State};
+use crate::{
+    info::NormalizedPath, log::debug, log::depth, Context, Info, ResolveResult, Resolver, State,
+};
+use std::path::{Path, PathBuf};
+
+/// The limit of the symlinks followed by `symlink_chain`, same as the
+/// `MAXSYMLINKS` of Linux.
+const MAX_SYMLINKS: usize = 40;
 
 #[derive(Default)]
 pub struct SymlinkPlugin;
@@ -9,18 +16,29 @@ impl Plugin for SymlinkPlugin {
         debug_assert!(info.request().target().is_empty());
 
         if !resolver.options.symlinks {
-            return State::Success(ResolveResult::Resource(info));
+            return State::Success(ResolveResult::File(info));
         }
 
-        tracing::debug!("SymlinkPlugin works({})", depth(&context.depth));
+        debug!("SymlinkPlugin works({})", depth(&context.depth));
         let state = self.resolve_symlink(resolver, info, context);
-        tracing::debug!("Leaving SymlinkPlugin({})", depth(&context.depth));
+        debug!("Leaving SymlinkPlugin({})", depth(&context.depth));
         state
     }
 }
 
 impl SymlinkPlugin {
-    fn resolve_symlink(&self, resolver: &Resolver, info: Info, _context: &mut Context) -> State {
+    fn resolve_symlink(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
+        context.symlinks.clear();
+        if resolver.options.pnpm_store {
+            if let Some(path) = resolver.pnpm_real_path(info.normalized_path().as_ref()) {
+                let original = info.normalized_path().as_ref();
+                if original != path {
+                    context.symlinks = vec![original.to_path_buf(), path.clone()];
+                }
+                return State::Success(ResolveResult::File(info.with_path(path)));
+            }
+        }
+
         let head = resolver.load_entry(info.normalized_path().as_ref());
 
         let entry_path = head.path();
@@ -61,6 +79,9 @@ impl SymlinkPlugin {
                 path.push(c);
             }
             head.init_real(path.clone().into_boxed_path());
+            if entry_path != path {
+                context.symlinks = symlink_chain(entry_path, &path);
+            }
             info.with_path(path)
         } else {
             stack
@@ -69,6 +90,36 @@ impl SymlinkPlugin {
             info
         };
 
-        State::Success(ResolveResult::Resource(info))
+        State::Success(ResolveResult::File(info))
+    }
+}
+
+/// Returns the paths from `path` to `real` by following one symlink at a
+/// time, the first symlink in the components is followed first. `real`
+/// is always the last one, even if the links changed in the meantime.
+fn symlink_chain(path: &Path, real: &Path) -> Vec<PathBuf> {
+    let mut chain = vec![path.to_path_buf()];
+    let mut current = path.to_path_buf();
+    while current != real && chain.len() <= MAX_SYMLINKS {
+        let mut prefix = PathBuf::new();
+        let next = current
+            .components()
+            .enumerate()
+            .find_map(|(index, component)| {
+                prefix.push(component);
+                let link = prefix.read_link().ok()?;
+                let mut next = prefix.parent()?.join(link);
+                next.extend(current.components().skip(index + 1));
+                Some(NormalizedPath::new(next).as_ref().to_path_buf())
+            });
+        let Some(next) = next else {
+            break;
+        };
+        chain.push(next.clone());
+        current = next;
+    }
+    if current != real {
+        chain.push(real.to_path_buf());
     }
+    chain
 }
@@ -0,0 +1,129 @@
+use super::Plugin;
+use crate::{
+    description::DescriptionData,
+    log::color,
+    log::debug,
+    log::depth,
+    resolve::{get_module_name_from_request, get_path_from_request},
+    version::{parse_version, satisfies},
+    Context, Info, Resolver, State,
+};
+
+pub struct TypesVersionsPlugin<'a> {
+    pkg_info: &'a DescriptionData,
+}
+
+impl<'a> TypesVersionsPlugin<'a> {
+    pub fn new(pkg_info: &'a DescriptionData) -> Self {
+        Self { pkg_info }
+    }
+
+    /// Returns the path mappings under the first matched version selector.
+    fn select_paths<'b>(
+        types_versions: &'b serde_json::Map<String, serde_json::Value>,
+        version: Option<&str>,
+    ) -> Option<&'b serde_json::Map<String, serde_json::Value>> {
+        let version = version.map(parse_version);
+        types_versions
+            .iter()
+            .find(|(range, _)| satisfies(range, version))
+            .and_then(|(_, paths)| paths.as_object())
+    }
+
+    /// Returns the substitutions of the matched key, exact key is prior to
+    /// the pattern key, and the pattern with longer prefix wins.
+    fn find_substitutions(
+        paths: &serde_json::Map<String, serde_json::Value>,
+        subpath: &str,
+    ) -> Vec<String> {
+        let to_list = |value: &serde_json::Value, star_match: &str| -> Vec<String> {
+            value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.replacen('*', star_match, 1))
+                .collect()
+        };
+        if let Some(value) = paths.get(subpath) {
+            return to_list(value, "");
+        }
+        paths
+            .iter()
+            .filter_map(|(pattern, value)| {
+                let star_match = Resolver::match_star(pattern, subpath)?;
+                Some((pattern.find('*').unwrap_or_default(), star_match, value))
+            })
+            .max_by_key(|(prefix_len, ..)| *prefix_len)
+            .map(|(_, star_match, value)| to_list(value, star_match))
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> Plugin for TypesVersionsPlugin<'a> {
+    fn apply(&self, resolver: &Resolver, info: Info, context: &mut Context) -> State {
+        if !resolver.options.resolve_types {
+            return State::Resolving(info);
+        }
+        let Some(types_versions) = self
+            .pkg_info
+            .data()
+            .raw()
+            .get("typesVersions")
+            .and_then(|value| value.as_object()) else {
+            return State::Resolving(info);
+        };
+
+        let target = info.request().target();
+        let module_path = info
+            .normalized_path()
+            .as_ref()
+            .join(get_module_name_from_request(target));
+        if !self.pkg_info.dir().as_ref().eq(&module_path) {
+            return State::Resolving(info);
+        }
+        let Some(paths) = Self::select_paths(
+            types_versions,
+            resolver.options.typescript_version.as_deref(),
+        ) else {
+            return State::Resolving(info);
+        };
+
+        let subpath = match get_path_from_request(target) {
+            Some(subpath) => subpath.trim_start_matches('/').to_string(),
+            None => {
+                let raw = self.pkg_info.data().raw();
+                let types = raw
+                    .get("types")
+                    .or_else(|| raw.get("typings"))
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("index.d.ts");
+                types.trim_start_matches("./").to_string()
+            }
+        };
+
+        for substitution in Self::find_substitutions(paths, &subpath) {
+            debug!(
+                "TypesVersions in '{}' works, trigger by '{}', mapped to '{}'({})",
+                color::blue(&format!("{:?}/package.json", self.pkg_info.dir().as_ref())),
+                color::blue(&target),
+                color::blue(&substitution),
+                depth(&context.depth)
+            );
+            let target = format!("./{}", substitution.trim_start_matches("./"));
+            let info = Info::from(self.pkg_info.dir().clone()).with_target(&target);
+            let fully_specified = context.fully_specified.get();
+            if fully_specified {
+                context.fully_specified.set(false);
+            }
+            let state = resolver._resolve(info, context);
+            if fully_specified {
+                context.fully_specified.set(true);
+            }
+            if state.is_finished() {
+                return state;
+            }
+        }
+        State::Resolving(info)
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{info::NormalizedPath, Resolver};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+/// The directory of the virtual store of pnpm in `node_modules`.
+const VIRTUAL_STORE: &str = ".pnpm";
+
+/// The name of the package in the virtual store entry, such as `@a/b` of
+/// `@a+b@1.0.0_c@2.0.0`.
+fn package_name_of(id: &str) -> Option<String> {
+    let at = id.get(1..)?.find('@')? + 1;
+    Some(id[..at].replace('+', "/"))
+}
+
+/// The entries of the sorted `names` in the virtual store for `name`,
+/// there are several entries if the package is installed with different
+/// versions or peer dependencies.
+fn store_entries<'a>(names: &'a [String], name: &str) -> &'a [String] {
+    let prefix = format!("{}@", name.replace('/', "+"));
+    let start = names.partition_point(|id| id.as_str() < prefix.as_str());
+    let len = names[start..]
+        .iter()
+        .take_while(|id| id.starts_with(&prefix))
+        .count();
+    &names[start..start + len]
+}
+
+impl Resolver {
+    /// Returns the real path of `path` if it is in a package linked from
+    /// the virtual store of pnpm, that is
+    /// `.pnpm/<name>@<version>/node_modules/<name>`. The entry is looked up
+    /// from the listing of the store, and the symlink is only read if the
+    /// package is installed with several versions, so the chain of the
+    /// symlinks is not walked.
+    pub(crate) fn pnpm_real_path(&self, path: &Path) -> Option<PathBuf> {
+        let components: Vec<&OsStr> = path.iter().collect();
+        let index = components.iter().rposition(|c| *c == "node_modules")?;
+        let first = components.get(index + 1)?.to_str()?;
+        if first.starts_with('.') {
+            return None;
+        }
+        let len = if first.starts_with('@') { 2 } else { 1 };
+        let name = components
+            .get(index + 1..index + 1 + len)?
+            .iter()
+            .map(|c| c.to_str())
+            .collect::<Option<Vec<_>>>()?
+            .join("/");
+        let modules: PathBuf = components[..=index].iter().collect();
+
+        // `<store>/<id>/node_modules`, the dependencies of a package in the
+        // store are the siblings of it.
+        let dir = modules.parent()?;
+        let (store, linked_from_store) = match dir.parent() {
+            Some(store) if store.file_name() == Some(OsStr::new(VIRTUAL_STORE)) => {
+                if package_name_of(dir.file_name()?.to_str()?).as_ref() == Some(&name) {
+                    // it is the package itself rather than a link.
+                    return Some(path.to_path_buf());
+                }
+                (store.to_path_buf(), true)
+            }
+            _ => (modules.join(VIRTUAL_STORE), false),
+        };
+        let entry = self.load_entry(&store);
+        if !entry.is_dir() {
+            return None;
+        }
+        let names = self.cache.fs.read_dir(&store, entry.cached_stat()).ok()?;
+        let entries = store_entries(&names, &name);
+        let id = match entries {
+            // the packages in the store are always linked to the store, but
+            // the package of an importer could be linked to a workspace.
+            [id] if linked_from_store => id.as_str(),
+            [] => return None,
+            _ => {
+                let package = modules.join(&name);
+                let link = package.read_link().ok()?;
+                let target = NormalizedPath::new(package.parent()?.join(link));
+                let mut target = target.iter().rev().skip(len);
+                if target.next() != Some(OsStr::new("node_modules")) {
+                    return None;
+                }
+                let id = target.next()?.to_str()?;
+                if target.next() != Some(OsStr::new(VIRTUAL_STORE)) {
+                    return None;
+                }
+                entries.iter().find(|entry| *entry == id)?.as_str()
+            }
+        };
+
+        let mut real = store.join(id);
+        real.push("node_modules");
+        real.extend(&components[index + 1..]);
+        Some(real)
+    }
+}
+
+#[test]
+fn package_name_of_test() {
+    assert_eq!(package_name_of("a@1.0.0"), Some(String::from("a")));
+    assert_eq!(
+        package_name_of("@a+b@1.0.0_c@2.0.0"),
+        Some(String::from("@a/b"))
+    );
+    assert_eq!(package_name_of("a@1.0.0(b@2.0.0)"), Some(String::from("a")));
+    assert_eq!(package_name_of("node_modules"), None);
+}
+
+#[test]
+fn store_entries_test() {
+    let names = [
+        "@a+b@1.0.0",
+        "a-b@1.0.0",
+        "a@1.0.0",
+        "a@2.0.0_b@1.0.0",
+        "ab@1.0.0",
+    ]
+    .map(String::from);
+    assert_eq!(store_entries(&names, "a"), &names[2..4]);
+    assert_eq!(store_entries(&names, "@a/b"), &names[0..1]);
+    assert_eq!(store_entries(&names, "b"), &names[0..0]);
+}
+
+#[test]
+fn pnpm_real_path_test() {
+    use crate::test_helper::p;
+    let resolver = Resolver::new(Default::default());
+    let modules = p(vec!["pnpm-store", "node_modules"]);
+    let store = modules.join(VIRTUAL_STORE);
+    let a = store.join("a@1.0.0/node_modules/a");
+    assert_eq!(
+        resolver.pnpm_real_path(&modules.join("a/index.js")),
+        Some(a.join("index.js"))
+    );
+    assert_eq!(
+        resolver.pnpm_real_path(&modules.join("@s/c")),
+        Some(store.join("@s+c@1.0.0_a@1.0.0/node_modules/@s/c"))
+    );
+    assert_eq!(
+        resolver.pnpm_real_path(&a.join("index.js")),
+        Some(a.join("index.js"))
+    );
+    assert_eq!(
+        resolver.pnpm_real_path(&store.join("a@1.0.0/node_modules/b/index.js")),
+        Some(store.join("b@2.0.0/node_modules/b/index.js"))
+    );
+    assert_eq!(
+        resolver.pnpm_real_path(&store.join("@s+c@1.0.0_a@1.0.0/node_modules/a")),
+        Some(a)
+    );
+    // linked to the workspace.
+    assert_eq!(resolver.pnpm_real_path(&modules.join("w/index.js")), None);
+    assert_eq!(resolver.pnpm_real_path(&store), None);
+}
@@ -3,35 +3,47 @@ use crate::{
     info::NormalizedPath,
     kind::PathKind,
     log::color,
+    log::debug,
     plugin::{
         BrowserFieldPlugin, ExportsFieldPlugin, ExtensionAliasPlugin, ImportsFieldPlugin,
-        MainFieldPlugin, MainFilePlugin, Plugin,
+        MainFieldPlugin, MainFilePlugin, Plugin, TypesVersionsPlugin,
     },
-    Context, EnforceExtension, Info, ResolveResult, Resolver, State,
+    AttemptPhase, Context, EnforceExtension, Error, ExportsPriority, Info, InvalidRequest,
+    PathPreference, ResolutionMode, ResolveResult, Resolver, State, UnsupportedDirImport,
 };
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     path::{Path, PathBuf},
 };
 
 impl Resolver {
-    fn resolve_file_with_ext(&self, mut path: PathBuf, info: Info) -> State {
+    fn resolve_file_with_ext(&self, mut path: PathBuf, info: Info, context: &mut Context) -> State {
+        let extensions = self.extensions(context);
+        context.record(AttemptPhase::Extensions, || {
+            format!("'{}[{}]'", path.display(), extensions.join("|"))
+        });
         let v = unsafe { &mut *(&mut path as *mut PathBuf as *mut Vec<u8>) };
-        for ext in &self.options.extensions {
+        for ext in extensions.iter() {
             v.extend_from_slice(ext.as_bytes());
-            if self.load_entry(path.as_ref()).is_file() {
-                return State::Success(ResolveResult::Resource(
-                    info.with_path(path).with_target(""),
-                ));
+            match self.load_entry(path.as_ref()).try_stat() {
+                Ok(stat) if stat.is_file() => {
+                    return State::Success(ResolveResult::File(
+                        info.with_path(path).with_target(""),
+                    ));
+                }
+                Ok(_) => {}
+                // the file might exist, it is not a missing module.
+                Err(error) => return State::Error(error),
             }
             unsafe {
                 v.set_len(v.len() - ext.len());
             }
         }
-        tracing::debug!(
+        debug!(
             "'{}[{}]' is not a file",
             color::red(&path.display()),
-            color::red(&self.options.extensions.join("|"))
+            color::red(&extensions.join("|"))
         );
         State::Resolving(info)
     }
@@ -41,14 +53,17 @@ impl Resolver {
             return State::Resolving(info);
         }
         let path = info.to_resolved_path();
-        tracing::debug!(
+        debug!(
             "Attempting to load '{}' as a context",
             color::blue(&path.display())
         );
-        if self.load_entry(&path).is_dir() {
-            State::Success(ResolveResult::Resource(Info::new(path, Default::default())))
-        } else {
-            State::Failed(info)
+        match self.load_entry(&path).try_stat() {
+            Ok(stat) if stat.is_dir() => State::Success(ResolveResult::Directory(Info::new(
+                path,
+                Default::default(),
+            ))),
+            Ok(_) => State::Failed(info),
+            Err(error) => State::Error(error),
         }
     }
 
@@ -60,20 +75,45 @@ impl Resolver {
         let path = info.to_resolved_path();
         let request = info.request();
         let target = request.target();
-        if self.load_entry(&path).is_file() {
-            let path = path.to_path_buf();
-            State::Success(ResolveResult::Resource(
-                info.with_path(path).with_target(""),
-            ))
-        } else if matches!(
+        let stat = match self.load_entry(&path).try_stat() {
+            Ok(stat) => stat,
+            Err(error) => return State::Error(error),
+        };
+        let is_path = matches!(
             request.kind(),
             PathKind::AbsolutePosix | PathKind::AbsoluteWin | PathKind::Relative
-        ) || split_slash_from_request(target).is_some()
+        ) || split_slash_from_request(target).is_some();
+        if stat.is_file() {
+            State::Success(ResolveResult::File(
+                info.with_normalized_path(path).with_target(""),
+            ))
+        } else if is_path
+            && stat.is_dir()
+            && matches!(
+                self.options.mode,
+                ResolutionMode::Esm | ResolutionMode::Deno
+            )
         {
+            // same as `ERR_UNSUPPORTED_DIR_IMPORT` of Node.js.
+            let suggestion = self.options.main_files.iter().find_map(|main_file| {
+                self.extensions(context).iter().find_map(|ext| {
+                    let file = format!("{main_file}{ext}");
+                    self.load_entry(&path.join(&file))
+                        .is_file()
+                        .then(|| format!("{}/{file}", target.trim_end_matches('/')))
+                })
+            });
+            State::Error(Error::UnsupportedDirImport(Box::new(
+                UnsupportedDirImport {
+                    request: request.to_string(),
+                    path: path.to_path_buf(),
+                    suggestion,
+                },
+            )))
+        } else if is_path {
             State::Failed(info)
         } else {
-            let dir = path.to_path_buf();
-            let info = info.with_path(dir).with_target(".");
+            let info = info.with_normalized_path(path).with_target(".");
             context.fully_specified.set(false);
             let state = self._resolve(info.clone(), context);
             context.fully_specified.set(true);
@@ -99,29 +139,51 @@ impl Resolver {
                 })
             })
             .then(|info| {
-                let path = info.to_resolved_path().to_path_buf();
-                tracing::debug!(
+                let path = info.to_resolved_path();
+                debug!(
                     "Attempting to load '{}' as a file",
                     color::blue(&path.display())
                 );
-                if matches!(self.options.enforce_extension, EnforceExtension::Enabled) {
-                    self.resolve_file_with_ext(path, info)
-                } else if self.load_entry(&path).is_file() {
-                    let path = path;
-                    State::Success(ResolveResult::Resource(
-                        info.with_path(path).with_target(""),
-                    ))
+                context.record(AttemptPhase::File, || format!("'{}'", path.display()));
+                // nothing could be resolved if there is no extension.
+                if matches!(self.options.enforce_extension, EnforceExtension::Enabled)
+                    && !self.extensions(context).is_empty()
+                {
+                    self.resolve_file_with_ext(path.to_path_buf(), info, context)
                 } else {
-                    self.resolve_file_with_ext(path, info)
+                    match self.load_entry(&path).try_stat() {
+                        Ok(stat) if stat.is_file() => State::Success(ResolveResult::File(
+                            info.with_normalized_path(path).with_target(""),
+                        )),
+                        Ok(_) => self.resolve_file_with_ext(path.to_path_buf(), info, context),
+                        Err(error) => State::Error(error),
+                    }
                 }
             })
     }
 
+    /// Resolves `info` as a file and a directory in the order of
+    /// `path_preference`.
+    pub(crate) fn resolve_as_file_or_dir(&self, info: Info, context: &mut Context) -> State {
+        match self.options.path_preference {
+            PathPreference::File => self
+                .resolve_as_file(info, context)
+                .then(|info| self.resolve_as_dir(info, context)),
+            PathPreference::Directory => match self.resolve_as_dir(info, context) {
+                State::Failed(info) => State::Resolving(info),
+                state => state,
+            }
+            .then(|info| self.resolve_as_file(info, context)),
+        }
+    }
+
     pub(crate) fn resolve_as_dir(&self, info: Info, context: &mut Context) -> State {
         let dir = info.to_resolved_path();
         let entry = self.load_entry(&dir);
-        if !entry.is_dir() {
-            return State::Failed(info);
+        match entry.try_stat() {
+            Ok(stat) if stat.is_dir() => {}
+            Ok(_) => return State::Failed(info),
+            Err(error) => return State::Error(error),
         }
         let pkg_info = match entry.pkg_info(self) {
             Ok(pkg_info) => pkg_info,
@@ -136,25 +198,158 @@ impl Resolver {
     }
 
     pub(crate) fn resolve_as_modules(&self, info: Info, context: &mut Context) -> State {
+        let state = self.resolve_as_builtin(info);
+        let State::Resolving(info) = state else {
+            return state;
+        };
+        if info.request().kind() == PathKind::Normal {
+            let esm = matches!(
+                self.options.mode,
+                ResolutionMode::Esm | ResolutionMode::Deno
+            );
+            if let Some(reason) = invalid_module_request(info.request().target(), esm) {
+                return State::Error(Error::InvalidRequest(reason));
+            }
+            let graph = self.options.dependency_graph.as_ref();
+            let path = info.normalized_path().as_ref();
+            if let Some(dependencies) = graph.and_then(|graph| graph.dependencies_of(path)) {
+                return self.resolve_as_dependency(info, dependencies, context);
+            }
+        }
+        let state = self.resolve_as_modules_in_dir(info.clone(), context);
+        if state.is_finished() {
+            return state;
+        }
+        // the ancestors are walked by the loop of the caller.
+        let depth = context.depth.value();
+        if context.modules_walk_depth == Some(depth) {
+            return State::Failed(info);
+        }
+
+        // walk the ancestors in a loop rather than the recursion, so the
+        // depth of stack does not grow with the depth of directory.
+        let prev_walk_depth = context.modules_walk_depth;
+        let mut state = State::Failed(info.clone());
+        for parent_dir in self.modules_walk(info.normalized_path().as_ref()).skip(1) {
+            context.modules_walk_depth = Some(depth + 1);
+            state = self._resolve(info.clone().with_path(parent_dir), context);
+            if state.is_finished() {
+                break;
+            }
+        }
+        context.modules_walk_depth = prev_walk_depth;
+        if state.is_finished() {
+            state
+        } else {
+            State::Failed(info)
+        }
+    }
+
+    /// The directories where the `modules` are looked up for the bare
+    /// request from `path`, which are `path` and its ancestors until the
+    /// boundary, or `path` only if all of the `modules` are absolute.
+    pub(crate) fn modules_walk<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = &'a Path> {
+        let need_find_up = self
+            .options
+            .modules
+            .iter()
+            .any(|module| !Path::new(module).is_absolute());
+        let mut next = Some(path);
+        std::iter::from_fn(move || {
+            let dir = next?;
+            next = dir
+                .parent()
+                .filter(|_| need_find_up && !self.is_boundary(dir));
+            Some(dir)
+        })
+    }
+
+    /// Resolves the bare request by the directory of the dependency in
+    /// `dependencies` of the issuer, see `Options::dependency_graph`.
+    fn resolve_as_dependency(
+        &self,
+        info: Info,
+        dependencies: &BTreeMap<String, PathBuf>,
+        context: &mut Context,
+    ) -> State {
+        let target = info.request().target();
+        let name = get_module_name_from_request(target);
+        let Some(dir) = dependencies.get(name) else {
+            context.record(AttemptPhase::Modules, || {
+                format!("'{name}' is not a dependency in the graph")
+            });
+            return State::Failed(info);
+        };
+        context.record(AttemptPhase::Modules, || {
+            format!("'{}' (dependency graph)", dir.display())
+        });
+        // the request is joined to the parent of the package directory,
+        // the name is replaced if the directory is named differently, such
+        // as a linked workspace.
+        let module = if dir.ends_with(name) {
+            let depth = Path::new(name).components().count();
+            dir.ancestors()
+                .nth(depth)
+                .map(|modules_dir| (modules_dir, info.clone()))
+        } else {
+            dir.parent()
+                .zip(dir.file_name().and_then(|dir_name| dir_name.to_str()))
+                .map(|(modules_dir, dir_name)| {
+                    let target = format!("{dir_name}{}", &target[name.len()..]);
+                    (modules_dir, info.clone().with_target(&target))
+                })
+        };
+        let Some((modules_dir, module_info)) = module else {
+            return State::Failed(info);
+        };
+        match self.resolve_node_modules(module_info, modules_dir, context) {
+            State::Resolving(_) | State::Failed(_) => State::Failed(info),
+            state => state,
+        }
+    }
+
+    /// Try the `modules` directories of `info.path` without walking up.
+    fn resolve_as_modules_in_dir(&self, info: Info, context: &mut Context) -> State {
         let original_dir = info.normalized_path();
-        for module in &self.options.modules {
+        let dir_entry = self.load_entry(original_dir.as_ref());
+        for (index, module) in self.options.modules.iter().enumerate() {
             let node_modules_path = Path::new(module);
-            let (node_modules_path, need_find_up) = if node_modules_path.is_absolute() {
-                (Cow::Borrowed(node_modules_path), false)
+            let (node_modules_path, exists) = if node_modules_path.is_absolute() {
+                let exists = match self.load_entry(node_modules_path).try_stat() {
+                    Ok(stat) => stat.is_dir(),
+                    Err(error) => return State::Error(error),
+                };
+                (Cow::Borrowed(node_modules_path), exists)
             } else {
-                (Cow::Owned(original_dir.as_ref().join(module)), true)
+                let exists = dir_entry.has_modules_dir(self, index);
+                (Cow::Owned(original_dir.as_ref().join(module)), exists)
             };
-            let state = self
-                ._resolve_as_modules(info.clone(), original_dir, &node_modules_path, context)
-                .then(|info| {
-                    if !need_find_up {
-                        State::Resolving(info)
-                    } else if let Some(parent_dir) = original_dir.as_ref().parent() {
-                        self._resolve(info.with_path(parent_dir), context)
-                    } else {
-                        State::Resolving(info)
-                    }
-                });
+            let state = self._resolve_as_modules(
+                info.clone(),
+                original_dir,
+                &node_modules_path,
+                exists,
+                context,
+            );
+            if state.is_finished() {
+                return state;
+            }
+        }
+        State::Failed(info)
+    }
+
+    pub(crate) fn resolve_as_global_paths(&self, info: Info, context: &mut Context) -> State {
+        if !matches!(info.request().kind(), PathKind::Normal) {
+            return State::Failed(info);
+        }
+        for global_path in &self.options.global_paths {
+            debug!(
+                "Attempting to load '{}' from global path '{}'",
+                color::blue(&info.request().target()),
+                color::blue(&global_path.display())
+            );
+            let global_info = info.clone().with_path(global_path);
+            let state = self.resolve_node_modules(global_info, global_path, context);
             if state.is_finished() {
                 return state;
             }
@@ -167,14 +362,25 @@ impl Resolver {
         info: Info,
         original_dir: &NormalizedPath,
         node_modules_path: &Path,
+        exists: bool,
         context: &mut Context,
     ) -> State {
-        let entry = self.load_entry(node_modules_path);
+        context.record(AttemptPhase::Modules, || {
+            let suffix = if exists { "" } else { " (not exist)" };
+            format!("'{}'{suffix}", node_modules_path.display())
+        });
+        // the missing directory shares the description file of its parent,
+        // so there is no need to load the entry of it.
+        let entry = if exists {
+            self.load_entry(node_modules_path)
+        } else {
+            self.load_entry(original_dir.as_ref())
+        };
         let pkg_info = match entry.pkg_info(self) {
             Ok(pkg_info) => pkg_info.as_ref(),
             Err(err) => return State::Error(err),
         };
-        let state = if entry.is_dir() {
+        let state = if exists {
             // is there had `node_modules` folder?
             self.resolve_node_modules(info, node_modules_path, context)
                 .then(|info| {
@@ -184,8 +390,7 @@ impl Resolver {
                         is_resolve_self(pkg_info, request_module_name)
                     });
                     if is_resolve_self {
-                        let pkg_info = pkg_info.unwrap();
-                        ExportsFieldPlugin::new(pkg_info).apply(self, info, context)
+                        self.resolve_as_exports(pkg_info.unwrap(), info, context)
                     } else {
                         State::Resolving(info)
                     }
@@ -194,7 +399,7 @@ impl Resolver {
             // is `info.path` on the same level as package.json
             let request_module_name = get_module_name_from_request(info.request().target());
             if is_resolve_self(pkg_info.unwrap(), request_module_name) {
-                ExportsFieldPlugin::new(pkg_info.unwrap()).apply(self, info, context)
+                self.resolve_as_exports(pkg_info.unwrap(), info, context)
             } else {
                 State::Resolving(info)
             }
@@ -205,6 +410,31 @@ impl Resolver {
         state
     }
 
+    /// Applies the `exports` of `pkg_info` by `options.exports_priority`.
+    fn resolve_as_exports(
+        &self,
+        pkg_info: &DescriptionData,
+        info: Info,
+        context: &mut Context,
+    ) -> State {
+        match self.options.exports_priority {
+            ExportsPriority::Encapsulate => {
+                ExportsFieldPlugin::new(pkg_info).apply(self, info, context)
+            }
+            ExportsPriority::Fallback => {
+                match ExportsFieldPlugin::new(pkg_info).apply(self, info.clone(), context) {
+                    // not exported or the target does not exist.
+                    State::Failed(_)
+                    | State::Error(Error::UnexpectedValue(_) | Error::PackagePathNotExported(_)) => {
+                        State::Resolving(info)
+                    }
+                    state => state,
+                }
+            }
+            ExportsPriority::Ignore => State::Resolving(info),
+        }
+    }
+
     fn resolve_node_modules(
         &self,
         info: Info,
@@ -216,7 +446,19 @@ impl Resolver {
         let module_path = node_modules_path.join(request_module_name);
         let entry = self.load_entry(&module_path);
         let module_info = Info::new(node_modules_path, info.request().clone());
-        if !entry.is_dir() {
+        if context.resolve_to_context.get() {
+            // the context is the directory of module, so the files and
+            // the fields of description file are skipped.
+            return match self.resolve_as_context(module_info, context) {
+                State::Failed(_) => State::Resolving(info),
+                state => state,
+            };
+        }
+        let stat = match entry.try_stat() {
+            Ok(stat) => stat,
+            Err(error) => return State::Error(error),
+        };
+        if !stat.is_dir() {
             let state = self.resolve_as_file(module_info, context);
             if state.is_finished() {
                 state
@@ -231,10 +473,11 @@ impl Resolver {
             let state = if let Some(pkg_info) = pkg_info {
                 let out_node_modules = pkg_info.dir().eq(original_dir);
                 if !out_node_modules || is_resolve_self(pkg_info, request_module_name) {
-                    ExportsFieldPlugin::new(pkg_info).apply(self, module_info, context)
+                    self.resolve_as_exports(pkg_info, module_info, context)
                 } else {
                     State::Resolving(module_info)
                 }
+                .then(|info| TypesVersionsPlugin::new(pkg_info).apply(self, info, context))
                 .then(|info| ImportsFieldPlugin::new(pkg_info).apply(self, info, context))
                 .then(|info| MainFieldPlugin::new(pkg_info).apply(self, info, context))
                 .then(|info| BrowserFieldPlugin::new(pkg_info, true).apply(self, info, context))
@@ -243,8 +486,7 @@ impl Resolver {
             }
             .then(|info| self.resolve_as_context(info, context))
             .then(|info| self.resolve_as_fully_specified(info, context))
-            .then(|info| self.resolve_as_file(info, context))
-            .then(|info| self.resolve_as_dir(info, context));
+            .then(|info| self.resolve_as_file_or_dir(info, context));
 
             match state {
                 State::Failed(info) => State::Resolving(info),
@@ -262,22 +504,30 @@ fn is_resolve_self(pkg_info: &DescriptionData, request_module_name: &str) -> boo
         .map_or(false, |ans| ans)
 }
 
-/// split the index from `[module-name]/[path]`
+/// split the byte index from `[module-name]/[path]`
 pub(crate) fn split_slash_from_request(target: &str) -> Option<usize> {
-    let has_namespace_scope = target.starts_with('@');
-    let chars = target.chars().enumerate();
-    let slash_index_list: Vec<usize> = chars
-        .filter_map(|(index, char)| if '/' == char { Some(index) } else { None })
-        .collect();
-    if has_namespace_scope {
-        slash_index_list.get(1)
-    } else {
-        slash_index_list.first()
+    let skip = usize::from(target.starts_with('@'));
+    target.match_indices('/').nth(skip).map(|(index, _)| index)
+}
+
+/// Returns the reason if the target of a module request is rejected by
+/// the package resolution of Node.js. The encoded separators are only
+/// rejected in `esm`, because `require` looks them up as the file names.
+fn invalid_module_request(target: &str, esm: bool) -> Option<InvalidRequest> {
+    let lowercase = target.to_ascii_lowercase();
+    if esm && (lowercase.contains("%2f") || lowercase.contains("%5c")) {
+        return Some(InvalidRequest::EncodedSeparator);
     }
-    .copied()
+    let name = get_module_name_from_request(target);
+    let has_name = |scoped: &str| {
+        scoped
+            .split_once('/')
+            .map_or(false, |(_, name)| !name.is_empty())
+    };
+    (name.starts_with('@') && !has_name(name)).then_some(InvalidRequest::ScopeWithoutName)
 }
 
-fn get_module_name_from_request(target: &str) -> &str {
+pub(crate) fn get_module_name_from_request(target: &str) -> &str {
     split_slash_from_request(target).map_or(target, |index| &target[0..index])
 }
 
@@ -287,7 +537,11 @@ pub(crate) fn get_path_from_request(target: &str) -> Option<Cow<str>> {
 
 #[cfg(test)]
 mod test {
-    use super::{get_module_name_from_request, get_path_from_request, split_slash_from_request};
+    use super::{
+        get_module_name_from_request, get_path_from_request, invalid_module_request,
+        split_slash_from_request,
+    };
+    use crate::InvalidRequest;
 
     #[test]
     fn test_split_slash_from_request() {
@@ -296,6 +550,9 @@ mod test {
         assert_eq!(split_slash_from_request("@a"), None);
         assert_eq!(split_slash_from_request("@a/b"), None);
         assert_eq!(split_slash_from_request("@a/b/c"), Some(4));
+        assert_eq!(split_slash_from_request("@a/b/c/d"), Some(4));
+        assert_eq!(split_slash_from_request("中文/a"), Some(6));
+        assert_eq!(split_slash_from_request("@中/文/a"), Some(8));
     }
 
     #[test]
@@ -305,6 +562,9 @@ mod test {
         assert_eq!(get_module_name_from_request("@a"), "@a");
         assert_eq!(get_module_name_from_request("@a/b"), "@a/b");
         assert_eq!(get_module_name_from_request("@a/b/c"), "@a/b");
+        assert_eq!(get_module_name_from_request("@a/b/c/d"), "@a/b");
+        assert_eq!(get_module_name_from_request("中文/a"), "中文");
+        assert_eq!(get_module_name_from_request("@中/文/a"), "@中/文");
     }
 
     #[test]
@@ -314,5 +574,32 @@ mod test {
         assert_eq!(get_path_from_request("@a"), None);
         assert_eq!(get_path_from_request("@a/b"), None);
         assert_eq!(get_path_from_request("@a/b/c"), Some("/c".into()));
+        assert_eq!(get_path_from_request("@a/b/c/d"), Some("/c/d".into()));
+        assert_eq!(get_path_from_request("@a/b/"), Some("/".into()));
+        assert_eq!(get_path_from_request("中文/a"), Some("/a".into()));
+    }
+
+    #[test]
+    fn test_invalid_module_request() {
+        assert_eq!(invalid_module_request("a", true), None);
+        assert_eq!(invalid_module_request("@a/b", true), None);
+        assert_eq!(invalid_module_request("@a/b/c%20d", true), None);
+        assert_eq!(
+            invalid_module_request("@a", true),
+            Some(InvalidRequest::ScopeWithoutName)
+        );
+        assert_eq!(
+            invalid_module_request("@a/", true),
+            Some(InvalidRequest::ScopeWithoutName)
+        );
+        assert_eq!(
+            invalid_module_request("@a%2Fb", true),
+            Some(InvalidRequest::EncodedSeparator)
+        );
+        assert_eq!(
+            invalid_module_request("a/b%5cc", true),
+            Some(InvalidRequest::EncodedSeparator)
+        );
+        assert_eq!(invalid_module_request("a/b%5cc", false), None);
     }
 }
@@ -1,5 +1,8 @@
 use crate::{description::DescriptionData, info::Info, Resolver};
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 #[derive(Debug, Clone)]
 pub struct Resource {
@@ -7,11 +10,30 @@ pub struct Resource {
     pub query: Option<String>,
     pub fragment: Option<String>,
     pub description: Option<Arc<DescriptionData>>,
+    /// Whether the path ends with one of `binary_extensions`,
+    /// such as the native addon.
+    pub binary: bool,
+    /// Whether the path is in a package of `modules`, such as
+    /// `node_modules`, after the symlinks are resolved.
+    pub is_external: bool,
+    /// The directory of the package in `modules` containing the path, such
+    /// as `node_modules/@a/b` of `node_modules/@a/b/lib/c.js`.
+    pub package_root: Option<PathBuf>,
+    /// The paths traversed when the symlinks are resolved, from the
+    /// requested path to `path`, such as
+    /// `["node_modules/a/index.js", "packages/a/index.js"]`, it is empty
+    /// if there is no symlink or `symlinks` is disabled.
+    pub symlinks: Vec<PathBuf>,
 }
 
 impl Resource {
     pub(crate) fn new(info: Info, resolver: &Resolver) -> Self {
-        let path = info.normalized_path().as_ref().to_path_buf();
+        let path = resolver
+            .cache
+            .fs
+            .unicode_normalization()
+            .normalize_path(info.normalized_path().as_ref())
+            .into_owned();
         let query = info.request().query();
         let fragment = info.request().fragment();
         let description = resolver
@@ -19,11 +41,22 @@ impl Resource {
             .pkg_info(resolver)
             .unwrap()
             .clone();
+        let binary = resolver
+            .options
+            .binary_extensions
+            .iter()
+            .any(|ext| !ext.is_empty() && path.to_string_lossy().ends_with(ext.as_str()));
+        let package_root = package_root_of(&path, &resolver.options.modules)
+            .filter(|root| resolver.load_entry(root).is_dir());
         Resource {
+            is_external: package_root.is_some(),
+            package_root,
             path,
             query: (!query.is_empty()).then(|| query.into()),
             fragment: (!fragment.is_empty()).then(|| fragment.into()),
             description,
+            binary,
+            symlinks: vec![],
         }
     }
 
@@ -38,3 +71,50 @@ impl Resource {
         PathBuf::from(buf)
     }
 }
+
+/// Returns the package directory after the last component of `path` which
+/// is one of `modules`, the scoped package takes two components.
+pub(crate) fn package_root_of(path: &Path, modules: &[String]) -> Option<PathBuf> {
+    let components: Vec<_> = path.components().collect();
+    let index = components.iter().rposition(|component| {
+        modules
+            .iter()
+            .any(|name| component.as_os_str() == name.as_str())
+    })?;
+    let first = components.get(index + 1)?.as_os_str().to_str()?;
+    if first.starts_with('.') {
+        return None;
+    }
+    let len = if first.starts_with('@') { 2 } else { 1 };
+    let end = index + 1 + len;
+    (components.len() >= end).then(|| components[..end].iter().collect())
+}
+
+#[test]
+fn package_root_of_test() {
+    let modules = [String::from("node_modules"), String::from("web_modules")];
+    let root = |path: &str| package_root_of(Path::new(path), &modules);
+    assert_eq!(
+        root("/a/node_modules/b/c.js"),
+        Some(PathBuf::from("/a/node_modules/b"))
+    );
+    assert_eq!(
+        root("/a/node_modules/b"),
+        Some(PathBuf::from("/a/node_modules/b"))
+    );
+    assert_eq!(
+        root("/a/node_modules/@s/b/c.js"),
+        Some(PathBuf::from("/a/node_modules/@s/b"))
+    );
+    assert_eq!(
+        root("/a/node_modules/b/node_modules/c/d.js"),
+        Some(PathBuf::from("/a/node_modules/b/node_modules/c"))
+    );
+    assert_eq!(
+        root("/a/web_modules/b/c.js"),
+        Some(PathBuf::from("/a/web_modules/b"))
+    );
+    assert_eq!(root("/a/node_modules/@s"), None);
+    assert_eq!(root("/a/node_modules/.bin/c"), None);
+    assert_eq!(root("/a/src/c.js"), None);
+}
@@ -0,0 +1,48 @@
+use crate::{Cache, Options, Resolver};
+use std::{cmp::Reverse, path::Path, sync::Arc};
+
+impl Resolver {
+    /// Creates the resolvers of `roots` in `options` sharing `cache`, the
+    /// inner roots are placed first.
+    pub(crate) fn root_resolvers(options: &Options, cache: &Arc<Cache>) -> Vec<(Box<Path>, Self)> {
+        let mut roots: Vec<_> = options
+            .roots
+            .iter()
+            .map(|profile| {
+                let resolver = Self::new(Options {
+                    alias: profile
+                        .alias
+                        .clone()
+                        .unwrap_or_else(|| options.alias.clone()),
+                    tsconfig: profile
+                        .tsconfig
+                        .clone()
+                        .or_else(|| options.tsconfig.clone()),
+                    extensions: profile
+                        .extensions
+                        .clone()
+                        .unwrap_or_else(|| options.extensions.clone()),
+                    // the extensions of the profile are not replaced.
+                    target_extensions: match profile.extensions {
+                        Some(_) => vec![],
+                        None => options.target_extensions.clone(),
+                    },
+                    roots: vec![],
+                    external_cache: Some(cache.clone()),
+                    ..options.clone()
+                });
+                (profile.root.clone().into_boxed_path(), resolver)
+            })
+            .collect();
+        roots.sort_by_key(|(root, _)| Reverse(root.components().count()));
+        roots
+    }
+
+    /// Returns the resolver of the innermost root containing `path`.
+    pub(crate) fn root_resolver(&self, path: &Path) -> Option<&Self> {
+        self.roots
+            .iter()
+            .find(|(root, _)| path.starts_with(root))
+            .map(|(_, resolver)| resolver)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{FsEventKind, RResult, ResolveResult, Resolver, Resource};
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+#[derive(Debug, Default)]
+pub(crate) struct Sessions {
+    /// The events received while the cache is pinned, they are applied
+    /// after the sessions on the cache end.
+    pending: Mutex<Vec<(PathBuf, FsEventKind)>>,
+    /// Whether `pending` is not empty, so the resolution checks it without
+    /// locking.
+    deferred: AtomicBool,
+}
+
+/// A handle which pins the cache of resolver, such as a compilation, so
+/// the results in a session are not mixed by the old and new files:
+///
+/// - The events passed to [`Resolver::on_fs_event`] of the resolvers
+///   sharing the cache are deferred until all the sessions on the cache
+///   end. The events of the ended session's resolver are applied at once,
+///   and the ones of the other resolvers before their next resolution.
+/// - The cached files are reused without `CacheValidation`, so the files
+///   changed without an event are not reloaded until the sessions end.
+///
+/// The files which are not cached yet, such as a new package, are still
+/// read from the disk.
+#[derive(Debug)]
+pub struct Session<'a> {
+    resolver: &'a Resolver,
+    build_scope: Option<Box<str>>,
+}
+
+impl<'a> Session<'a> {
+    pub fn resolver(&self) -> &'a Resolver {
+        self.resolver
+    }
+
+    /// The build scope which the results are memoized in, see
+    /// [`Resolver::begin_scoped_session`].
+    pub fn build_scope(&self) -> Option<&str> {
+        self.build_scope.as_deref()
+    }
+
+    pub fn resolve(&self, path: &Path, request: &str) -> RResult<ResolveResult<Resource>> {
+        self.resolver
+            .resolve_memoized(path, request, None, self.build_scope())
+    }
+}
+
+impl Drop for Session<'_> {
+    fn drop(&mut self) {
+        if self.resolver.cache.fs.unpin() {
+            self.resolver.apply_deferred_fs_events();
+        }
+    }
+}
+
+impl Resolver {
+    /// Begins a session, see [`Session`].
+    pub fn begin_session(&self) -> Session<'_> {
+        self.pin();
+        Session {
+            resolver: self,
+            build_scope: None,
+        }
+    }
+
+    /// Begins a session whose results are memoized in `build_scope`, such
+    /// as the id of a compilation, rather than the resolver. The results
+    /// of a scope are shared by its sessions and dropped together by
+    /// [`Resolver::drop_build_scope`], while the entries and the
+    /// description files are kept for the next build. So a watch mode
+    /// could resolve freshly in each build with the warm file system cache.
+    pub fn begin_scoped_session(&self, build_scope: &str) -> Session<'_> {
+        self.pin();
+        Session {
+            resolver: self,
+            build_scope: Some(build_scope.into()),
+        }
+    }
+
+    /// Drops the results memoized in `build_scope`, and returns whether the
+    /// scope exists.
+    pub fn drop_build_scope(&self, build_scope: &str) -> bool {
+        let mut dropped = self.scoped_results.remove(build_scope).is_some();
+        for (_, resolver) in &self.roots {
+            dropped |= resolver.drop_build_scope(build_scope);
+        }
+        dropped
+    }
+
+    fn pin(&self) {
+        // holds the lock to prevent an event applied in the middle.
+        let mut pending = self.sessions.pending.lock().unwrap();
+        if !self.cache.fs.is_pinned() {
+            self.apply_pending(&mut pending);
+        }
+        self.cache.fs.pin();
+    }
+
+    /// Defers the event if there is an active session on the cache,
+    /// otherwise applies it.
+    pub(crate) fn dispatch_fs_event(&self, path: &Path, kind: FsEventKind) {
+        let mut pending = self.sessions.pending.lock().unwrap();
+        if self.cache.fs.is_pinned() {
+            pending.push((path.to_path_buf(), kind));
+            self.sessions.deferred.store(true, Ordering::Release);
+        } else {
+            // holds the lock to prevent a session beginning in the middle.
+            self.apply_pending(&mut pending);
+            self.invalidate(path, kind);
+        }
+    }
+
+    /// Applies the deferred events if the sessions on the cache have ended.
+    pub(crate) fn apply_deferred_fs_events(&self) {
+        if !self.sessions.deferred.load(Ordering::Acquire) || self.cache.fs.is_pinned() {
+            return;
+        }
+        let mut pending = self.sessions.pending.lock().unwrap();
+        if !self.cache.fs.is_pinned() {
+            self.apply_pending(&mut pending);
+        }
+    }
+
+    fn apply_pending(&self, pending: &mut Vec<(PathBuf, FsEventKind)>) {
+        for (path, kind) in std::mem::take(pending) {
+            self.invalidate(&path, kind);
+        }
+        self.sessions.deferred.store(false, Ordering::Release);
+    }
+}
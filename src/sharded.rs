@@ -0,0 +1,95 @@
+//! A concurrent map sharded by the hash of the keys, each shard is a
+//! `FxHashMap` guarded by a `RwLock`. It is an alternative to `DashMap`
+//! for the cached entries, the readers of a shard never wait for each
+//! other and the shards are more than `DashMap` at high parallelism.
+
+use rustc_hash::{FxHashMap, FxHasher};
+use std::{
+    borrow::Borrow,
+    hash::{Hash, Hasher},
+    sync::RwLock,
+};
+
+#[derive(Debug)]
+pub(crate) struct ShardedMap<K, V> {
+    shards: Box<[RwLock<FxHashMap<K, V>>]>,
+}
+
+impl<K: Hash + Eq, V: Clone> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        let parallelism = std::thread::available_parallelism().map_or(1, |n| n.get());
+        let shards = (parallelism * 4).next_power_of_two();
+        Self {
+            shards: (0..shards).map(|_| Default::default()).collect(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> ShardedMap<K, V> {
+    fn shard<Q: Hash + ?Sized>(&self, key: &Q) -> &RwLock<FxHashMap<K, V>> {
+        let mut hasher = FxHasher::default();
+        key.hash(&mut hasher);
+        // the high bits, the low bits of `FxHasher` are weak.
+        let index = (hasher.finish() >> 32) as usize & (self.shards.len() - 1);
+        &self.shards[index]
+    }
+
+    pub(crate) fn get<Q: Hash + Eq + ?Sized>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        self.shard(key).read().unwrap().get(key).cloned()
+    }
+
+    /// Returns the value of `key`, `value` is inserted if it is absent.
+    pub(crate) fn get_or_insert(&self, key: K, value: V) -> V {
+        self.shard(&key)
+            .write()
+            .unwrap()
+            .entry(key)
+            .or_insert(value)
+            .clone()
+    }
+
+    pub(crate) fn retain<F: FnMut(&K, &mut V) -> bool>(&self, mut f: F) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().retain(&mut f);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().unwrap().len())
+            .sum()
+    }
+
+    pub(crate) fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.write().unwrap().clear();
+        }
+    }
+}
+
+#[test]
+fn sharded_map_test() {
+    let map: ShardedMap<String, usize> = Default::default();
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let map = &map;
+            scope.spawn(move || {
+                for i in 0..100 {
+                    assert_eq!(map.get_or_insert(i.to_string(), i), i);
+                    assert_eq!(map.get_or_insert(i.to_string(), thread), i);
+                }
+            });
+        }
+    });
+    assert_eq!(map.len(), 100);
+    assert_eq!(map.get("42"), Some(42));
+    map.retain(|_, value| *value % 2 == 0);
+    assert_eq!(map.len(), 50);
+    assert_eq!(map.get("41"), None);
+    map.clear();
+    assert_eq!(map.len(), 0);
+}
@@ -0,0 +1,55 @@
+use crate::{
+    info::Info, kind::PathKind, parse::Request, resolve::get_module_name_from_request,
+    resource::package_root_of, RResult, ResolveResult, Resolver, Resource,
+};
+
+impl Resolver {
+    /// Returns the result of the source entry if the bare `request` is
+    /// resolved to `info` in a workspace package, which has an entry in
+    /// `source_entries` or `source_field`. The subpath of the package is
+    /// not redirected.
+    pub(crate) fn redirect_to_source(
+        &self,
+        request: &str,
+        info: &Info,
+    ) -> RResult<Option<ResolveResult<Resource>>> {
+        if self.options.source_field.is_none() && self.options.source_entries.is_empty() {
+            return Ok(None);
+        }
+        let request = Request::from_request(request);
+        let name = request.target();
+        if request.kind() != PathKind::Normal || get_module_name_from_request(name) != name {
+            return Ok(None);
+        }
+        let path = info.normalized_path().as_ref();
+        if package_root_of(path, &self.options.modules).is_some() {
+            return Ok(None);
+        }
+        let Some(pkg_info) = self.load_entry(path).pkg_info(self)?.clone() else {
+            return Ok(None);
+        };
+        if pkg_info.data().name() != Some(name) {
+            return Ok(None);
+        }
+        let source = self
+            .options
+            .source_entries
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, entry)| entry.as_str())
+            .or_else(|| {
+                let field = self.options.source_field.as_ref()?;
+                pkg_info.data().raw().get(field)?.as_str()
+            });
+        let Some(source) = source else {
+            return Ok(None);
+        };
+        let source = format!(
+            "./{}{}{}",
+            source.trim_start_matches("./"),
+            request.query(),
+            request.fragment()
+        );
+        self.resolve(pkg_info.dir().as_ref(), &source).map(Some)
+    }
+}
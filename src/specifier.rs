@@ -0,0 +1,196 @@
+use crate::{
+    description::ExportsForm, info::NormalizedPath, resource::package_root_of, ResolveResult,
+    Resolver,
+};
+use serde_json::Value;
+use std::{borrow::Cow, path::Path};
+
+impl Resolver {
+    /// Returns the shortest specifier which is resolved to `target` from
+    /// `from_dir`, such as the import added by a codemod. The file in a
+    /// package is imported by the package name and the subpath exported
+    /// by `"exports"` if it is public, otherwise by the relative path, and
+    /// the extension and `index` are omitted if the options allow. Each
+    /// candidate is checked by resolving it, returns `None` if none of
+    /// them is resolved to `target`.
+    pub fn specifier_for(&self, from_dir: &Path, target: &Path) -> Option<String> {
+        let target = NormalizedPath::new(target);
+        let target = target.as_ref();
+        let from_dir = NormalizedPath::new(from_dir);
+        let from_dir = from_dir.as_ref();
+        // the relative ones are tried if the package is not reachable
+        // from `from_dir` or the file is not exported.
+        [
+            self.package_specifiers(target),
+            self.relative_specifiers(from_dir, target),
+        ]
+        .into_iter()
+        .find_map(|mut candidates| {
+            candidates.sort_by_key(|candidate| (candidate.len(), candidate.clone()));
+            candidates.dedup();
+            candidates
+                .into_iter()
+                .find(|candidate| self.is_resolved_to(from_dir, candidate, target))
+        })
+    }
+
+    /// The candidates are resolved uncached, so they are neither memoized
+    /// nor tracked as the dependencies of the resolutions.
+    fn is_resolved_to(&self, from_dir: &Path, specifier: &str, target: &Path) -> bool {
+        let resolver = self.root_resolver(from_dir).unwrap_or(self);
+        match resolver.resolve_uncached(from_dir, specifier, None) {
+            Ok(ResolveResult::File(resource)) => {
+                resource.path == target
+                    || dunce::canonicalize(&resource.path).ok() == dunce::canonicalize(target).ok()
+            }
+            _ => false,
+        }
+    }
+
+    /// The paths of `path` without the extensions or `index` and itself,
+    /// the ones which are not allowed by the options, such as the path
+    /// without the extension if `enforce_extension` is enabled without
+    /// `extensions`, are dropped by resolving them.
+    fn shortened(&self, path: &str) -> Vec<String> {
+        let mut shortened = vec![path.to_string()];
+        for ext in self.options.extensions.iter().filter(|ext| !ext.is_empty()) {
+            let Some(stem) = path.strip_suffix(ext.as_str()) else {
+                continue;
+            };
+            shortened.push(stem.to_string());
+            let dir = if stem == "index" {
+                Some("")
+            } else {
+                stem.strip_suffix("/index")
+            };
+            if let Some(dir) = dir.filter(|_| self.options.main_files.iter().any(|f| f == "index"))
+            {
+                shortened.push(dir.to_string());
+            }
+        }
+        shortened
+    }
+
+    /// The specifiers of `target` in a package by its name, they are the
+    /// subpaths of `"exports"` if it exists.
+    fn package_specifiers(&self, target: &Path) -> Vec<String> {
+        let Some(root) = package_root_of(target, &self.options.modules) else {
+            return vec![];
+        };
+        let Some(subpath) = relative_to(&root, target) else {
+            return vec![];
+        };
+        let components: Vec<_> = root.components().collect();
+        let scoped = components.iter().rev().nth(1).map_or(false, |scope| {
+            scope.as_os_str().to_string_lossy().starts_with('@')
+        });
+        let len = if scoped { 2 } else { 1 };
+        let name = components[components.len() - len..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let pkg_info = self
+            .load_entry(&root)
+            .pkg_info(self)
+            .ok()
+            .cloned()
+            .flatten();
+        let exports = pkg_info
+            .as_ref()
+            .filter(|pkg_info| pkg_info.dir().as_ref() == root)
+            .and_then(|pkg_info| pkg_info.data().exports().cloned());
+        let Some(exports) = exports.filter(|exports| !exports.is_null()) else {
+            let mut candidates = vec![name.clone()];
+            candidates.extend(
+                self.shortened(&subpath)
+                    .into_iter()
+                    .map(|subpath| match subpath.is_empty() {
+                        true => name.clone(),
+                        false => format!("{name}/{subpath}"),
+                    }),
+            );
+            return candidates;
+        };
+        match ExportsForm::of(&exports) {
+            ExportsForm::Target | ExportsForm::Conditions => vec![name],
+            ExportsForm::Subpaths => {
+                let subpath = format!("./{subpath}");
+                let mut candidates = vec![];
+                for (key, value) in exports.as_object().into_iter().flatten() {
+                    let key = match key.split_once('*') {
+                        Some((before, after)) => {
+                            let mut targets = vec![];
+                            collect_targets(value, &mut targets);
+                            let Some(star) = targets.into_iter().find_map(|target| {
+                                let (prefix, suffix) = target.split_once('*')?;
+                                subpath.strip_prefix(prefix)?.strip_suffix(suffix)
+                            }) else {
+                                continue;
+                            };
+                            format!("{before}{star}{after}")
+                        }
+                        None => key.clone(),
+                    };
+                    match key.strip_prefix("./") {
+                        Some(key) => candidates.push(format!("{name}/{key}")),
+                        None if key == "." => candidates.push(name.clone()),
+                        None => {}
+                    }
+                }
+                candidates
+            }
+            ExportsForm::Null | ExportsForm::Mixed | ExportsForm::Invalid => vec![],
+        }
+    }
+
+    fn relative_specifiers(&self, from_dir: &Path, target: &Path) -> Vec<String> {
+        let Some(relative) = relative_to(from_dir, target) else {
+            return vec![];
+        };
+        self.shortened(&relative)
+            .into_iter()
+            .map(|path| {
+                if path.is_empty() {
+                    String::from(".")
+                } else if path == ".." || path.starts_with("../") {
+                    path
+                } else {
+                    format!("./{path}")
+                }
+            })
+            .collect()
+    }
+}
+
+/// The string targets in the `"exports"` value, including the ones in
+/// the conditions and the fallbacks.
+fn collect_targets<'a>(value: &'a Value, targets: &mut Vec<&'a str>) {
+    match value {
+        Value::String(target) => targets.push(target),
+        Value::Array(list) => list
+            .iter()
+            .for_each(|value| collect_targets(value, targets)),
+        Value::Object(map) => map
+            .values()
+            .for_each(|value| collect_targets(value, targets)),
+        _ => {}
+    }
+}
+
+/// The relative path from `dir` to `path` separated by `/`, `None` if
+/// they are on different roots.
+fn relative_to(dir: &Path, path: &Path) -> Option<String> {
+    let dir: Vec<_> = dir.components().collect();
+    let path: Vec<_> = path.components().collect();
+    let common = dir.iter().zip(&path).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+    let parents = std::iter::repeat(Cow::Borrowed("..")).take(dir.len() - common);
+    let names = path[common..]
+        .iter()
+        .map(|component| component.as_os_str().to_string_lossy());
+    Some(parents.chain(names).collect::<Vec<_>>().join("/"))
+}
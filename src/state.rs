@@ -18,9 +18,16 @@ impl State {
         }
     }
 
+    /// Maps the resolved file or directory, and the kind of result is kept.
     pub fn map_success<F: FnOnce(Info) -> State>(self, op: F) -> Self {
         match self {
-            State::Success(ResolveResult::Resource(info)) => op(info),
+            State::Success(ResolveResult::File(info)) => op(info),
+            State::Success(ResolveResult::Directory(info)) => match op(info) {
+                State::Success(ResolveResult::File(info)) => {
+                    State::Success(ResolveResult::Directory(info))
+                }
+                state => state,
+            },
             _ => self,
         }
     }
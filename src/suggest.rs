@@ -0,0 +1,175 @@
+use crate::{description::ExportsForm, kind::PathKind, Resolver};
+use serde_json::Value;
+use std::path::Path;
+
+/// The max count of suggestions attached to an error.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Levenshtein distance between `a` and `b`, counted by chars.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+/// Returns the part of `name` before the last `.`.
+fn stem(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(0) | None => name,
+        Some(index) => &name[0..index],
+    }
+}
+
+/// Returns the subpath exported by `exports` which is the nearest to the
+/// unexported `subpath`, such as `./utils` of `./lib/utils`. A pattern is
+/// filled by the rest of `subpath` after its prefix, or by the file name,
+/// and the subpaths mapped to `null` are skipped. The other subpaths
+/// sharing the file name with `subpath` are preferred regardless of the
+/// distance.
+pub(crate) fn nearest_subpath(exports: &Value, subpath: &str) -> Option<String> {
+    let name = subpath.rsplit('/').next().unwrap_or(subpath);
+    let candidates: Vec<(String, bool)> = match ExportsForm::of(exports) {
+        // the only subpath exported by the sugar.
+        ExportsForm::Target | ExportsForm::Conditions => {
+            return (subpath != ".").then(|| String::from("."));
+        }
+        ExportsForm::Subpaths => exports
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(_, value)| !value.is_null())
+            .filter_map(|(key, _)| match key.split_once('*') {
+                Some((before, after)) => {
+                    let rest = subpath.strip_prefix(before).unwrap_or(name);
+                    let star = rest.strip_suffix(after).unwrap_or(rest);
+                    (!star.is_empty()).then(|| (format!("{before}{star}{after}"), true))
+                }
+                None => Some((key.clone(), false)),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let requested = subpath.to_lowercase();
+    let requested_stem = stem(name).to_lowercase();
+    let max_distance = (requested.chars().count() / 2).max(1);
+    candidates
+        .into_iter()
+        .filter(|(candidate, _)| candidate != subpath)
+        .filter_map(|(candidate, filled)| {
+            let distance = edit_distance(&requested, &candidate.to_lowercase());
+            let candidate_name = candidate.rsplit('/').next().unwrap_or(&candidate);
+            let same_name = !filled
+                && !requested_stem.is_empty()
+                && requested_stem != "."
+                && stem(candidate_name).to_lowercase() == requested_stem;
+            (distance <= max_distance || same_name).then_some((!same_name, distance, candidate))
+        })
+        .min()
+        .map(|(_, _, candidate)| candidate)
+}
+
+impl Resolver {
+    /// Find the files which are similar to the failed `request`,
+    /// only works for relative and absolute requests.
+    pub(crate) fn suggest_for_failed(&self, path: &Path, request: &str) -> Vec<String> {
+        let parsed = Self::parse(request);
+        if !matches!(
+            parsed.kind(),
+            PathKind::Relative | PathKind::AbsolutePosix | PathKind::AbsoluteWin
+        ) || parsed.is_directory()
+        {
+            return vec![];
+        }
+        let target = parsed.target();
+        let (prefix, name) = match target.rfind(['/', '\\']) {
+            Some(index) => (&target[0..=index], &target[index + 1..]),
+            None => ("", target),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            return vec![];
+        }
+        let dir = path.join(if prefix.is_empty() { "." } else { prefix });
+        let dir = crate::info::NormalizedPath::new(dir);
+        let entry = self.load_entry(dir.as_ref());
+        if !entry.is_dir() {
+            return vec![];
+        }
+        let names = match self.cache.fs.read_dir(dir.as_ref(), entry.cached_stat()) {
+            Ok(names) => names,
+            Err(_) => return vec![],
+        };
+
+        let requested_stem = stem(name).to_lowercase();
+        let max_distance = (requested_stem.chars().count() / 4).max(1);
+        let mut candidates: Vec<(usize, &String)> = names
+            .iter()
+            .filter(|candidate| candidate.as_str() != name)
+            .filter_map(|candidate| {
+                let candidate_stem = stem(candidate).to_lowercase();
+                let distance = if candidate.to_lowercase() == name.to_lowercase() {
+                    0
+                } else {
+                    edit_distance(&requested_stem, &candidate_stem)
+                };
+                (distance <= max_distance).then_some((distance, candidate))
+            })
+            .collect();
+        // sort by distance and then by name to keep the output stable.
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, candidate)| format!("{prefix}{candidate}"))
+            .collect()
+    }
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("", ""), 0);
+    assert_eq!(edit_distance("abc", "abc"), 0);
+    assert_eq!(edit_distance("foo", "Foo"), 1);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("中文", "中"), 1);
+}
+
+#[test]
+fn test_nearest_subpath() {
+    let exports = serde_json::json!({
+        ".": "./index.js",
+        "./utils": "./lib/utils.js",
+        "./features/*.js": "./lib/features/*.js",
+        "./internal/*": null,
+    });
+    let nearest = |subpath: &str| nearest_subpath(&exports, subpath);
+    assert_eq!(nearest("./lib/utils").as_deref(), Some("./utils"));
+    assert_eq!(nearest("./util").as_deref(), Some("./utils"));
+    assert_eq!(nearest("./features/a").as_deref(), Some("./features/a.js"));
+    assert_eq!(
+        nearest("./lib/features/a.js").as_deref(),
+        Some("./features/a.js")
+    );
+    assert_eq!(nearest("./internal/a"), None);
+    assert_eq!(nearest("./src/utils.js").as_deref(), Some("./utils"));
+    assert_eq!(nearest("./unrelated/path"), None);
+    let exports = serde_json::json!({ "import": "./index.mjs" });
+    assert_eq!(nearest_subpath(&exports, "./a").as_deref(), Some("."));
+    assert_eq!(nearest_subpath(&serde_json::json!(false), "./a"), None);
+}
+
+#[test]
+fn test_stem() {
+    assert_eq!(stem("a.js"), "a");
+    assert_eq!(stem("a.d.ts"), "a.d");
+    assert_eq!(stem(".eslintrc"), ".eslintrc");
+    assert_eq!(stem("a"), "a");
+}
@@ -0,0 +1,216 @@
+use crate::{
+    description::{DescriptionData, ExportsForm},
+    map::{ExportsField, Field, ImportsField, MappedTarget},
+    RResult, Resolver,
+};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// A subpath exported by a package, see [`Resolver::list_exports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportedSubpath {
+    /// The subpath with the pattern expanded, such as `"./features/a"`.
+    pub subpath: String,
+    /// The file which the subpath is mapped to.
+    pub path: PathBuf,
+    /// The conditions matched by the target, same as
+    /// [`MappedTarget::conditions`].
+    pub conditions: Vec<String>,
+}
+
+impl Resolver {
+    /// Maps `subpath` of the package in `pkg_dir`, such as `"."` or
+    /// `"./feature"`, by the `exports_field` of the nearest description
+    /// file with `conditions` rather than `condition_names`. It returns
+    /// every target in order with the matched conditions, and an empty
+    /// list if the subpath is not exported. The targets are neither
+    /// checked nor resolved to files, it is useful to validate the
+    /// `"exports"` field with the same engine as `resolve`.
+    pub fn resolve_exports_target(
+        &self,
+        pkg_dir: &Path,
+        subpath: &str,
+        conditions: &[String],
+    ) -> RResult<Vec<MappedTarget>> {
+        let Some(pkg_info) = self.load_description_file(pkg_dir)? else {
+            return Ok(vec![]);
+        };
+        let Some(root) = self.exports_of(&pkg_info) else {
+            return Ok(vec![]);
+        };
+        ExportsField::field_targets(
+            root,
+            subpath,
+            conditions,
+            self.options.prefer_condition_order,
+        )
+    }
+
+    /// Lists the subpaths exported by the package named `package` in the
+    /// `modules` directories walked from `path`, or the package in `path`
+    /// if `package` is `None`. The patterns and the folder mappings are
+    /// expanded by the files in the package, and each subpath is mapped
+    /// with `conditions` by the same engine as `resolve`, so the subpaths
+    /// excluded by `null` or mapped to the missing files are dropped. The
+    /// list is sorted by the subpaths, and it is empty if the package has
+    /// no `"exports"` field.
+    pub fn list_exports(
+        &self,
+        path: &Path,
+        package: Option<&str>,
+        conditions: &[String],
+    ) -> RResult<Vec<ExportedSubpath>> {
+        let pkg_info = match package {
+            Some(name) => {
+                let mut found = None;
+                self.walk_packages(path, name, |_, pkg_info| {
+                    found = Some(pkg_info.clone());
+                    false
+                })?;
+                found
+            }
+            None => self.load_description_file(path)?,
+        };
+        let Some(pkg_info) = pkg_info else {
+            return Ok(vec![]);
+        };
+        let Some(root) = self.exports_of(&pkg_info) else {
+            return Ok(vec![]);
+        };
+        let pkg_dir = pkg_info.dir().as_ref();
+        let prefer_order = self.options.prefer_condition_order;
+
+        let mut subpaths = vec![];
+        match root {
+            Value::Object(map) if ExportsForm::of(root) == ExportsForm::Subpaths => {
+                for (key, value) in map {
+                    let is_folder = key.ends_with('/');
+                    if !is_folder && !key.contains('*') {
+                        subpaths.push(key.clone());
+                        continue;
+                    }
+                    // the targets with the `*` kept, such as `./lib/*.js`.
+                    let Ok(targets) = ExportsField::mapping(
+                        "*",
+                        !is_folder,
+                        is_folder,
+                        value,
+                        conditions,
+                        prefer_order,
+                    ) else {
+                        continue;
+                    };
+                    for target in targets {
+                        let Some((prefix, suffix)) = target.target.split_once('*') else {
+                            continue;
+                        };
+                        for file in self.list_files(pkg_dir, prefix) {
+                            let Some(star) = file
+                                .strip_prefix(prefix)
+                                .and_then(|file| file.strip_suffix(suffix))
+                                .filter(|star| !star.is_empty())
+                            else {
+                                continue;
+                            };
+                            subpaths.push(if is_folder {
+                                format!("{key}{star}")
+                            } else {
+                                key.replacen('*', star, 1)
+                            });
+                        }
+                    }
+                }
+            }
+            _ => subpaths.push(String::from(".")),
+        }
+        subpaths.sort();
+        subpaths.dedup();
+
+        let mut exported = vec![];
+        for subpath in subpaths {
+            let Ok(targets) = ExportsField::field_targets(root, &subpath, conditions, prefer_order)
+            else {
+                continue;
+            };
+            // the first existing one is resolved, same as `resolve`.
+            let found = targets.into_iter().find_map(|target| {
+                let path = pkg_dir.join(target.target.strip_prefix("./")?);
+                self.load_entry(&path)
+                    .is_file()
+                    .then_some((path, target.conditions))
+            });
+            if let Some((path, conditions)) = found {
+                exported.push(ExportedSubpath {
+                    subpath,
+                    path,
+                    conditions,
+                });
+            }
+        }
+        Ok(exported)
+    }
+
+    /// The first `exports_field` which is not `null` in `pkg_info`.
+    fn exports_of<'a>(&self, pkg_info: &'a DescriptionData) -> Option<&'a Value> {
+        self.options
+            .exports_field
+            .iter()
+            .filter_map(|field| pkg_info.data().get_filed(field))
+            .find(|root| ExportsForm::of(root) != ExportsForm::Null)
+    }
+
+    /// The files in `pkg_dir` starting with `prefix`, such as `./lib/a.js`
+    /// for `./lib/`, the `modules` directories are skipped.
+    pub(crate) fn list_files(&self, pkg_dir: &Path, prefix: &str) -> Vec<String> {
+        let Some(prefix) = prefix.strip_prefix("./") else {
+            return vec![];
+        };
+        let dir = prefix.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let mut files = vec![];
+        let mut stack = vec![pkg_dir.join(dir)];
+        while let Some(dir) = stack.pop() {
+            let Some(names) = self.list_dir(&dir) else {
+                continue;
+            };
+            for name in names.iter() {
+                if self.options.modules.contains(name) {
+                    continue;
+                }
+                let path = dir.join(name);
+                if self.load_entry(&path).is_dir() {
+                    stack.push(path);
+                } else if let Some(relative) = path
+                    .strip_prefix(pkg_dir)
+                    .ok()
+                    .and_then(|relative| relative.to_str())
+                {
+                    files.push(format!("./{}", relative.replace('\\', "/")));
+                }
+            }
+        }
+        files
+    }
+
+    /// Same as `resolve_exports_target`, but maps `specifier`, such as
+    /// `"#internal/a"`, by the `"imports"` field. The target is either a
+    /// relative path or a package request.
+    pub fn resolve_imports_target(
+        &self,
+        pkg_dir: &Path,
+        specifier: &str,
+        conditions: &[String],
+    ) -> RResult<Vec<MappedTarget>> {
+        let Some(pkg_info) = self.load_description_file(pkg_dir)? else {
+            return Ok(vec![]);
+        };
+        let Some(root) = pkg_info.data().imports() else {
+            return Ok(vec![]);
+        };
+        ImportsField::field_targets(
+            root,
+            specifier,
+            conditions,
+            self.options.prefer_condition_order,
+        )
+    }
+}
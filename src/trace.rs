@@ -0,0 +1,138 @@
+use crate::{info::Info, Attempt, Error, IgnoredReason, ResolveResult, Resolver};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// The version of the schema of `Trace`, it is increased when a field is
+/// removed or changed, but not when a field is added.
+const SCHEMA_VERSION: u32 = 1;
+
+/// The explanation of a resolution, it is serialized with a stable schema
+/// for the editors and the web UIs, such as:
+///
+/// ```json
+/// {
+///   "version": 1,
+///   "path": "/app/src",
+///   "request": "./a",
+///   "outcome": { "kind": "file", "path": "/app/src/a.js", "query": null, "fragment": null },
+///   "steps": [
+///     { "phase": "file", "detail": "'/app/src/a'" },
+///     { "phase": "extensions", "detail": "'/app/src/a[.js|.json|.node]'" }
+///   ],
+///   "suggestions": [],
+///   "duration_us": 42
+/// }
+/// ```
+#[derive(Debug, Clone, Serialize)]
+pub struct Trace {
+    /// The version of the schema.
+    pub version: u32,
+    pub path: PathBuf,
+    pub request: String,
+    pub outcome: TraceOutcome,
+    /// The candidates tried in order, same as
+    /// [`crate::ResolveFailed::attempts`].
+    pub steps: Vec<Attempt>,
+    /// Similar requests which could be resolved if it failed.
+    pub suggestions: Vec<String>,
+    /// The time of the resolution without the cached results, the steps
+    /// are recorded by another run which is not timed.
+    pub duration_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceOutcome {
+    File {
+        path: PathBuf,
+        query: Option<String>,
+        fragment: Option<String>,
+    },
+    Directory {
+        path: PathBuf,
+    },
+    Builtin {
+        name: String,
+    },
+    Ignored {
+        reason: IgnoredReason,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl Resolver {
+    /// Resolves `request` in `path` without the memoized results, and
+    /// explains it by the steps, see [`Trace`].
+    pub fn trace(&self, path: &Path, request: &str) -> Trace {
+        if let Some(resolver) = self.root_resolver(path) {
+            return resolver.trace(path, request);
+        }
+        let normalization = self.cache.fs.unicode_normalization();
+        let path = &*normalization.normalize_path(path);
+        let request = &*normalization.normalize_str(request);
+
+        let start = Instant::now();
+        let result = self.resolve_uncached(path, request, None);
+        let duration_us = start.elapsed().as_micros() as u64;
+
+        let (outcome, steps, suggestions) = match result {
+            Ok(result) => {
+                let outcome = match result {
+                    ResolveResult::File(resource) => TraceOutcome::File {
+                        path: resource.path,
+                        query: resource.query,
+                        fragment: resource.fragment,
+                    },
+                    ResolveResult::Directory(resource) => TraceOutcome::Directory {
+                        path: resource.path,
+                    },
+                    ResolveResult::Builtin(name) => TraceOutcome::Builtin { name },
+                    ResolveResult::Ignored { reason, .. } => TraceOutcome::Ignored { reason },
+                };
+                let steps = self
+                    .to_mode_request(request)
+                    .and_then(|request| {
+                        let info = Info::new(path, Self::parse(&request));
+                        self.record_attempts(path, info, None)
+                    })
+                    .unwrap_or_default();
+                (outcome, steps, vec![])
+            }
+            Err(Error::ResolveFailed(failed)) => {
+                // the steps are not repeated in the message.
+                let message = format!(
+                    "Can't resolve '{}' in '{}'",
+                    failed.request,
+                    failed.path.display()
+                );
+                let failed = *failed;
+                (
+                    TraceOutcome::Error { message },
+                    failed.attempts,
+                    failed.suggestions,
+                )
+            }
+            Err(error) => (
+                TraceOutcome::Error {
+                    message: format!("{error:?}"),
+                },
+                vec![],
+                vec![],
+            ),
+        };
+        Trace {
+            version: SCHEMA_VERSION,
+            path: path.to_path_buf(),
+            request: request.to_string(),
+            outcome,
+            steps,
+            suggestions,
+            duration_us,
+        }
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::context::Context;
 use crate::{Error, Info, RResult, ResolveResult, Resolver, State};
-use rustc_hash::FxHashMap;
+use indexmap::IndexMap;
 use std::{path::Path, sync::Arc};
 
 #[derive(Debug, Clone, Default)]
@@ -14,7 +14,9 @@ pub struct TsConfig {
 #[derive(Debug, Clone)]
 pub struct CompilerOptions {
     pub base_url: Option<String>,
-    pub paths: Option<FxHashMap<String, Vec<String>>>,
+    /// Keep the order of declaration, which breaks the tie of patterns.
+    pub paths: Option<IndexMap<String, Vec<String>>>,
+    pub root_dirs: Option<Vec<String>>,
 }
 
 impl TsConfig {
@@ -35,28 +37,58 @@ impl Resolver {
         context: &mut Context,
     ) -> RResult<TsConfig> {
         let json = self.parse_file_to_value(location, context)?;
-        let compiler_options = json.get("compilerOptions").map(|options| {
-            // TODO: should optimized
-            let base_url = options
-                .get("baseUrl")
-                .map(|v| v.as_str().unwrap().to_string());
-            let paths = options.get("paths").map(|v| {
-                let mut map = FxHashMap::default();
-                // TODO: should optimized
-                for (key, obj) in v.as_object().unwrap() {
-                    map.insert(
-                        key.to_string(),
-                        obj.as_array()
-                            .unwrap()
+        let invalid = |field: &str| {
+            Error::UnexpectedValue(format!(
+                "Invalid \"{field}\" defined in {}",
+                location.display()
+            ))
+        };
+        let compiler_options = json
+            .get("compilerOptions")
+            .map(|options| -> RResult<CompilerOptions> {
+                let base_url = options
+                    .get("baseUrl")
+                    .map(|v| {
+                        v.as_str()
+                            .map(String::from)
+                            .ok_or_else(|| invalid("baseUrl"))
+                    })
+                    .transpose()?;
+                let paths = options
+                    .get("paths")
+                    .map(|v| {
+                        v.as_object()
+                            .ok_or_else(|| invalid("paths"))?
                             .iter()
-                            .map(|v| v.as_str().unwrap().to_string())
-                            .collect(),
-                    );
-                }
-                map
-            });
-            CompilerOptions { base_url, paths }
-        });
+                            .map(|(key, targets)| {
+                                let targets = targets
+                                    .as_array()
+                                    .and_then(|targets| {
+                                        targets
+                                            .iter()
+                                            .map(|v| v.as_str().map(String::from))
+                                            .collect::<Option<Vec<_>>>()
+                                    })
+                                    .ok_or_else(|| invalid("paths"))?;
+                                Ok((key.to_string(), targets))
+                            })
+                            .collect::<RResult<IndexMap<_, _>>>()
+                    })
+                    .transpose()?;
+                let root_dirs = options.get("rootDirs").and_then(|v| {
+                    v.as_array().map(|dirs| {
+                        dirs.iter()
+                            .filter_map(|dir| dir.as_str().map(String::from))
+                            .collect()
+                    })
+                });
+                Ok(CompilerOptions {
+                    base_url,
+                    paths,
+                    root_dirs,
+                })
+            })
+            .transpose()?;
         let extends: Option<String> = json.get("extends").map(|v| v.to_string());
         Ok(TsConfig {
             extends,
@@ -70,7 +102,7 @@ impl Resolver {
         context: &mut Context,
     ) -> RResult<serde_json::Value> {
         let entry = self.load_entry(location);
-        if !entry.is_file() {
+        if !entry.try_stat()?.is_file() {
             // Its role is to ensure that `stat` exists
             return Err(Error::CantFindTsConfig(entry.path().into()));
         }
@@ -94,10 +126,16 @@ impl Resolver {
             // Is it better to use cache?
             if let State::Success(result) = state {
                 let extends_tsconfig_json = match result {
-                    ResolveResult::Resource(info) => {
+                    ResolveResult::File(info) | ResolveResult::Directory(info) => {
                         self.parse_file_to_value(&info.to_resolved_path(), context)
                     }
-                    ResolveResult::Ignored => {
+                    ResolveResult::Builtin(name) => {
+                        return Err(Error::UnexpectedValue(format!(
+                            "{s} is resolved to the builtin module {name} in {}",
+                            location.display()
+                        )))
+                    }
+                    ResolveResult::Ignored { .. } => {
                         return Err(Error::UnexpectedValue(format!(
                             "{s} had been ignored in {}",
                             location.display()
@@ -1,13 +1,19 @@
 // Copy from https://github.com/dividab/tsconfig-paths
 
 use crate::{context::Context, Info, RResult, Resolver, State};
-use rustc_hash::FxHashMap;
-use std::path::{Path, PathBuf};
-
+use indexmap::IndexMap;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// The compiler options in `tsconfig.json` which affect the resolution,
+/// the `extends` had been merged.
 #[derive(Default, Debug)]
 pub struct TsConfigInfo {
-    pub paths: Option<FxHashMap<String, Vec<String>>>,
+    pub paths: Option<IndexMap<String, Vec<String>>>,
     pub base_url: Option<String>,
+    pub root_dirs: Option<Vec<String>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -20,7 +26,7 @@ struct MappingEntry {
 impl Resolver {
     fn get_absolute_mapping_entries(
         absolute_base_url: &Path,
-        paths: &FxHashMap<String, Vec<String>>,
+        paths: &IndexMap<String, Vec<String>>,
     ) -> Vec<MappingEntry> {
         paths
             .iter()
@@ -35,52 +41,71 @@ impl Resolver {
             .collect()
     }
 
-    fn parse_tsconfig(&self, location: &Path, context: &mut Context) -> RResult<TsConfigInfo> {
+    /// Returns the parsed `tsconfig.json` at `location`, it is cached
+    /// until [`Resolver::clear_entries`] is called.
+    pub fn resolve_tsconfig(&self, location: &Path) -> RResult<Arc<TsConfigInfo>> {
+        let mut context = Context::new(false, false);
+        self.parse_tsconfig(location, &mut context)
+    }
+
+    fn parse_tsconfig(&self, location: &Path, context: &mut Context) -> RResult<Arc<TsConfigInfo>> {
+        if let Some(cached) = self.tsconfigs.get(location) {
+            return Ok(cached.clone());
+        }
         let tsconfig = self.parse_ts_file(location, context)?;
-        let base_url = tsconfig
-            .compiler_options
-            .as_ref()
-            .and_then(|options| options.base_url.clone());
-        let paths = tsconfig.compiler_options.and_then(|options| options.paths);
-        Ok(TsConfigInfo { paths, base_url })
+        let (base_url, paths, root_dirs) = match tsconfig.compiler_options {
+            Some(options) => (options.base_url, options.paths, options.root_dirs),
+            None => (None, None, None),
+        };
+        let tsconfig = Arc::new(TsConfigInfo {
+            paths,
+            base_url,
+            root_dirs,
+        });
+        self.tsconfigs.insert(location.into(), tsconfig.clone());
+        Ok(tsconfig)
     }
 
-    fn match_star<'a>(pattern: &'a str, search: &'a str) -> Option<&'a str> {
-        if search.len() < pattern.len() {
-            return None;
+    /// Returns the part matched by `*` in `pattern`, such as `"b"` for
+    /// `"a/*.js"` and `"a/b.js"`. Returns `None` if the pattern has no star.
+    pub(crate) fn match_star<'a>(pattern: &str, search: &'a str) -> Option<&'a str> {
+        let (prefix, suffix) = pattern.split_once('*')?;
+        if search.len() >= prefix.len() + suffix.len()
+            && search.starts_with(prefix)
+            && search.ends_with(suffix)
+        {
+            Some(&search[prefix.len()..search.len() - suffix.len()])
+        } else {
+            None
         }
-        if pattern == "*" {
-            return Some(search);
+    }
+
+    /// Same as `matchPatternOrExact` in tsc, the exact pattern wins, then the
+    /// pattern with the longest prefix before `*`, the former declared one
+    /// wins if the prefixes are same long.
+    fn find_best_mapping<'a, 'b>(
+        mappings: &'a [MappingEntry],
+        target: &'b str,
+    ) -> Option<(&'a MappingEntry, &'b str)> {
+        if let Some(entry) = mappings.iter().find(|entry| entry.pattern == target) {
+            return Some((entry, ""));
         }
-        let p = pattern.as_bytes();
-        let s = search.as_bytes();
-        p.iter()
-            .enumerate()
-            .find(|&(_, &c)| c == b'*')
-            .and_then(|(star_index, _)| {
-                let part1 = &p[..star_index];
-                if &s[0..star_index] != part1 {
-                    return None;
-                }
-                let part2 = &p[star_index + 1..];
-                if &s[s.len() - part2.len()..] != part2 {
-                    return None;
-                }
-                let len = s.len() - part2.len() - part1.len();
-                match std::str::from_utf8(&s[star_index..star_index + len]) {
-                    Ok(result) => Some(result),
-                    Err(error) => {
-                        panic!(
-                            "There has unexpected error when matching {pattern} and {search}, error: {error}"
-                        )
-                    }
-                }
-            })
+        let mut best: Option<(usize, &MappingEntry, &str)> = None;
+        for entry in mappings {
+            let Some(star_match) = Self::match_star(&entry.pattern, target) else {
+                continue;
+            };
+            let prefix_len = entry.pattern.find('*').unwrap_or_default();
+            if best.map_or(true, |(best_len, ..)| prefix_len > best_len) {
+                best = Some((prefix_len, entry, star_match));
+            }
+        }
+        best.map(|(_, entry, star_match)| (entry, star_match))
     }
 
     fn create_match_list(
         absolute_base_url: &Path,
-        paths: &Option<FxHashMap<String, Vec<String>>>,
+        paths: &Option<IndexMap<String, Vec<String>>>,
     ) -> Vec<MappingEntry> {
         paths
             .as_ref()
@@ -111,28 +136,14 @@ impl Resolver {
             location_dir.into()
         };
 
-        // resolve absolute path that relative from base_url
-        if tsconfig.base_url.is_some() && !is_relative_request {
-            let target = absolute_base_url.join(info.request().target());
-            let info = info.clone().with_path(target).with_target("");
-            let result = self._resolve(info, context);
-            if result.is_finished() {
-                return result;
-            }
-        }
-
         let absolute_path_mappings =
             Resolver::create_match_list(&absolute_base_url, &tsconfig.paths);
+        let target = info.request().target();
 
-        for entry in absolute_path_mappings {
-            let star_match = if entry.pattern == info.request().target() {
-                ""
-            } else if let Some(s) = Self::match_star(&entry.pattern, info.request().target()) {
-                s
-            } else {
-                continue;
-            };
-
+        // only the best matched pattern is used, and its substitutions are
+        // tried in order.
+        if let Some((entry, star_match)) = Self::find_best_mapping(&absolute_path_mappings, target)
+        {
             for physical_path_pattern in &entry.paths {
                 let physical_path = &physical_path_pattern
                     .display()
@@ -145,6 +156,18 @@ impl Resolver {
                 }
             }
         }
+
+        // resolve absolute path that relative from base_url
+        if tsconfig.base_url.is_some() {
+            let target = absolute_base_url.join(target);
+            let info = info.clone().with_path(target).with_target("");
+            let result = self._resolve(info, context);
+            if result.is_finished() {
+                return result;
+            }
+        }
+
+        // fall through to `modules`.
         self._resolve(info, context)
     }
 }
@@ -153,7 +176,7 @@ impl Resolver {
 fn test_get_absolute_mapping_entries() {
     let result = Resolver::get_absolute_mapping_entries(
         Path::new("/absolute/base/url"),
-        &FxHashMap::from_iter(vec![
+        &IndexMap::from_iter(vec![
             (
                 "*".to_string(),
                 (vec!["/foo1", "./foo2"])
@@ -187,7 +210,7 @@ fn test_get_absolute_mapping_entries() {
 
     let result = Resolver::get_absolute_mapping_entries(
         Path::new("/absolute/base/url"),
-        &FxHashMap::from_iter([]),
+        &IndexMap::from_iter([]),
     );
     assert!(result.is_empty());
 }
@@ -195,5 +218,36 @@ fn test_get_absolute_mapping_entries() {
 #[test]
 fn test_match_star() {
     // should not panic
-    assert_eq!(Resolver::match_star("abc/*", "./中文"), None)
+    assert_eq!(Resolver::match_star("abc/*", "./中文"), None);
+    assert_eq!(Resolver::match_star("*", "a/b"), Some("a/b"));
+    assert_eq!(Resolver::match_star("a*", "a"), Some(""));
+    assert_eq!(Resolver::match_star("a/*.js", "a/b.js"), Some("b"));
+    assert_eq!(Resolver::match_star("ab*ba", "aba"), None);
+    assert_eq!(Resolver::match_star("abc", "abc"), None);
+}
+
+#[test]
+fn test_find_best_mapping() {
+    let mappings = Resolver::get_absolute_mapping_entries(
+        Path::new("/base"),
+        &IndexMap::from_iter(
+            [
+                ("*", "./any/*"),
+                ("components/*", "./components/*"),
+                ("components/button", "./button"),
+                ("comp*", "./comp/*"),
+                ("components/*-icon", "./icons/*"),
+            ]
+            .map(|(key, value)| (key.to_string(), vec![value.to_string()])),
+        ),
+    );
+    let best = |target| {
+        Resolver::find_best_mapping(&mappings, target)
+            .map(|(entry, star_match)| (entry.pattern.as_str(), star_match))
+    };
+    assert_eq!(best("components/button"), Some(("components/button", "")));
+    assert_eq!(best("components/card"), Some(("components/*", "card")));
+    assert_eq!(best("components/a-icon"), Some(("components/*", "a-icon")));
+    assert_eq!(best("compose"), Some(("comp*", "ose")));
+    assert_eq!(best("react"), Some(("*", "react")));
 }
@@ -0,0 +1,343 @@
+//! A subset of the ranges of node-semver, which is used by the
+//! `typesVersions` field and `Resolver::resolve_with_version_check`.
+
+use crate::{Error, RResult, ResolveResult, Resolver, Resource, VersionMismatch};
+use std::{cmp::Ordering, path::Path};
+
+pub(crate) type Version = (u64, u64, u64);
+
+type PartialVersion = (Option<u64>, Option<u64>, Option<u64>);
+
+/// The dot-separated identifiers of the prerelease, such as `["beta", "2"]`
+/// of `1.0.0-beta.2`, empty for a release.
+type Prerelease<'a> = Vec<&'a str>;
+
+/// The primitive comparator such as `>=1.2.0-beta`.
+type Comparator<'a> = (&'static str, Version, Prerelease<'a>);
+
+/// Parse a partial version such as `4`, `4.2`, `4.x`, `4.2.1` and
+/// `4.2.1-beta`, the missing or wildcard parts are `None` and the build
+/// metadata is ignored. `None` if it is not a version, such as `latest`.
+fn parse_partial(version: &str) -> Option<(PartialVersion, Prerelease<'_>)> {
+    let version = version.trim().trim_start_matches('v');
+    let version = version
+        .split_once('+')
+        .map_or(version, |(version, _)| version);
+    let (version, prerelease) = match version.split_once('-') {
+        Some((version, prerelease)) => (version, prerelease.split('.').collect()),
+        None => (version, vec![]),
+    };
+    if prerelease.iter().any(|id: &&str| id.is_empty()) {
+        return None;
+    }
+    let mut parts = [None; 3];
+    let mut wildcard = false;
+    for (index, part) in version.split('.').enumerate() {
+        let slot = parts.get_mut(index)?;
+        match part {
+            "x" | "X" | "*" => wildcard = true,
+            _ if !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) => {
+                // the parts after a wildcard are wildcards too, such as `1.x.3`.
+                *slot = (!wildcard).then(|| part.parse().ok()).flatten();
+            }
+            _ => return None,
+        }
+    }
+    let [major, minor, patch] = parts;
+    // the prerelease is only meaningful for a full version.
+    let prerelease = if patch.is_some() { prerelease } else { vec![] };
+    Some(((major, minor, patch), prerelease))
+}
+
+/// Parse a partial version, the missing parts are `0`.
+pub(crate) fn parse_version(version: &str) -> Version {
+    let (major, minor, patch) = parse_partial(version).unwrap_or_default().0;
+    (
+        major.unwrap_or_default(),
+        minor.unwrap_or_default(),
+        patch.unwrap_or_default(),
+    )
+}
+
+/// Parse a full version such as `1.2.3` and `1.2.3-beta.1`.
+fn parse_full(version: &str) -> Option<(Version, Prerelease<'_>)> {
+    match parse_partial(version)? {
+        ((Some(major), Some(minor), Some(patch)), prerelease) => {
+            Some(((major, minor, patch), prerelease))
+        }
+        _ => None,
+    }
+}
+
+/// The smallest version greater than all the versions matched by the
+/// partial version, such as `3.0.0` of `2` and `2.2.0` of `2.1`.
+fn next(partial: PartialVersion) -> Option<Version> {
+    match partial {
+        (Some(major), Some(minor), Some(patch)) => Some((major, minor, patch + 1)),
+        (Some(major), Some(minor), None) => Some((major, minor + 1, 0)),
+        (Some(major), None, _) => Some((major + 1, 0, 0)),
+        (None, ..) => None,
+    }
+}
+
+/// The comparator matches nothing, it works as `<0.0.0-0` of node-semver.
+fn nothing<'a>() -> Vec<Comparator<'a>> {
+    vec![("<", (0, 0, 0), vec![])]
+}
+
+/// Convert the comparator such as `^1.2`, `~1.2.3`, `1.x` and `>=4.2`,
+/// which is split into the operator and the version, to the primitive
+/// comparators, empty represents any version.
+fn comparators<'a>(op: &'static str, version: &'a str) -> Option<Vec<Comparator<'a>>> {
+    let (partial, prerelease) = parse_partial(version)?;
+    let (major, minor, patch) = partial;
+    let lower = (
+        major.unwrap_or_default(),
+        minor.unwrap_or_default(),
+        patch.unwrap_or_default(),
+    );
+    let comparators = match op {
+        "^" => {
+            let upper = match partial {
+                (Some(0), Some(0), Some(patch)) => (0, 0, patch + 1),
+                (Some(0), Some(minor), _) => (0, minor + 1, 0),
+                (Some(major), ..) => (major + 1, 0, 0),
+                (None, ..) => return Some(vec![]),
+            };
+            vec![(">=", lower, prerelease), ("<", upper, vec![])]
+        }
+        "~" => {
+            let upper = match partial {
+                (Some(major), Some(minor), _) => (major, minor + 1, 0),
+                (Some(major), None, _) => (major + 1, 0, 0),
+                (None, ..) => return Some(vec![]),
+            };
+            vec![(">=", lower, prerelease), ("<", upper, vec![])]
+        }
+        _ if patch.is_some() => vec![(op, lower, prerelease)],
+        // the partial versions are compared as the ranges, such as `>2`
+        // is `>=3.0.0` and `<=2` is `<3.0.0`.
+        ">=" => vec![(">=", lower, vec![])],
+        "<" if major.is_some() => vec![("<", lower, vec![])],
+        ">" => next(partial).map_or_else(nothing, |upper| vec![(">=", upper, vec![])]),
+        "<=" => next(partial).map_or_else(Vec::new, |upper| vec![("<", upper, vec![])]),
+        "<" => nothing(),
+        _ => match next(partial) {
+            Some(upper) => vec![(">=", lower, vec![]), ("<", upper, vec![])],
+            None => vec![],
+        },
+    };
+    Some(comparators)
+}
+
+/// Split the operator such as `>=` and `^` from the comparator, it is `=`
+/// if missing.
+fn split_op(comparator: &str) -> (&'static str, &str) {
+    [">=", "<=", ">", "<", "=", "^", "~"]
+        .into_iter()
+        .find_map(|op| comparator.strip_prefix(op).map(|rest| (op, rest)))
+        .unwrap_or(("=", comparator))
+}
+
+/// Parse the `range` which is consisted of comparators such as
+/// `>=4.2 <5`, `^1.2`, `~1.2.3`, `1.x`, `1 - 2` and `||` into the sets of
+/// the primitive comparators. `None` if it is invalid, such as `latest`
+/// and `workspace:*`.
+fn parse_range(range: &str) -> Option<Vec<Vec<Comparator<'_>>>> {
+    range
+        .split("||")
+        .map(|set| {
+            if let Some((lower, upper)) = set.split_once(" - ") {
+                let mut comparators_of_set = comparators(">=", lower)?;
+                comparators_of_set.extend(comparators("<=", upper)?);
+                return Some(comparators_of_set);
+            }
+            let mut comparators_of_set = vec![];
+            let mut tokens = set.split_whitespace();
+            while let Some(token) = tokens.next() {
+                let (op, version) = split_op(token);
+                // the operator may be separated by spaces, such as `>= 1.2`.
+                let version = match version.is_empty() {
+                    true => tokens.next()?,
+                    false => version,
+                };
+                comparators_of_set.extend(comparators(op, version)?);
+            }
+            Some(comparators_of_set)
+        })
+        .collect()
+}
+
+/// Compare the prerelease identifiers as node-semver, the numeric ones
+/// are lower than the others and a release is greater than its
+/// prereleases.
+fn compare_prerelease(a: &[&str], b: &[&str]) -> Ordering {
+    match (a.is_empty(), b.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+    }
+}
+
+/// Whether `version` satisfies all the comparators of `set`, `None`
+/// represents the latest version, which is greater than any version. A
+/// prerelease only satisfies the set that has a comparator with the
+/// prerelease of the same version, such as `>=1.0.0-beta`, so `^1.0.0`
+/// doesn't match `1.0.0-beta`.
+fn satisfies_set(set: &[Comparator], version: Option<(Version, &[&str])>) -> bool {
+    let Some((version, prerelease)) = version else {
+        return set.iter().all(|(op, ..)| op.starts_with('>'));
+    };
+    set.iter().all(|(op, expected, expected_prerelease)| {
+        let ordering = version
+            .cmp(expected)
+            .then_with(|| compare_prerelease(prerelease, expected_prerelease));
+        match *op {
+            ">=" => ordering.is_ge(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            "<" => ordering.is_lt(),
+            _ => ordering.is_eq(),
+        }
+    }) && (prerelease.is_empty()
+        || set.iter().any(|(_, expected, expected_prerelease)| {
+            !expected_prerelease.is_empty() && *expected == version
+        }))
+}
+
+/// Whether `version` satisfies the `range`, see [`parse_range`], `None`
+/// represents the latest version. An invalid range is never satisfied.
+pub(crate) fn satisfies(range: &str, version: Option<Version>) -> bool {
+    parse_range(range).map_or(false, |sets| {
+        sets.iter()
+            .any(|set| satisfies_set(set, version.map(|version| (version, &[][..]))))
+    })
+}
+
+impl Resolver {
+    /// Resolve `request` as `resolve`, then check the `version` in the
+    /// description file of the resolved package satisfies `range`, such
+    /// as `^1.2.0`. The description file is the one in `package_root` of
+    /// the result if it is in `modules`, or the nearest one otherwise.
+    ///
+    /// It returns `Error::VersionMismatch` if not satisfied or the version
+    /// is missing, and `Error::UnexpectedValue` if `range` is invalid,
+    /// such as `latest`. The ignored result is returned as is.
+    pub fn resolve_with_version_check(
+        &self,
+        path: &Path,
+        request: &str,
+        range: &str,
+    ) -> RResult<ResolveResult<Resource>> {
+        let Some(sets) = parse_range(range) else {
+            return Err(Error::UnexpectedValue(format!(
+                "Invalid version range '{range}' of '{request}'"
+            )));
+        };
+        let result = self.resolve(path, request)?;
+        if let ResolveResult::File(resource) = &result {
+            let description = match &resource.package_root {
+                Some(package_root) => self
+                    .load_description_file(package_root)?
+                    .filter(|description| description.dir().as_ref() == package_root),
+                None => resource.description.clone(),
+            };
+            let version = description
+                .as_ref()
+                .and_then(|description| description.data().version());
+            let full = version.and_then(parse_full);
+            let satisfied = full.as_ref().map_or(false, |(version, prerelease)| {
+                sets.iter()
+                    .any(|set| satisfies_set(set, Some((*version, prerelease))))
+            });
+            if !satisfied {
+                return Err(Error::VersionMismatch(Box::new(VersionMismatch {
+                    request: request.to_string(),
+                    range: range.to_string(),
+                    version: version.map(String::from),
+                    path: description.map(|description| description.dir().to_path_buf()),
+                })));
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[test]
+fn test_satisfies() {
+    assert!(satisfies("*", None));
+    assert!(satisfies("*", Some((3, 0, 0))));
+    assert!(satisfies(">=4.2", None));
+    assert!(!satisfies("<4.0", None));
+    assert!(satisfies(">=4.2", Some((4, 2, 0))));
+    assert!(!satisfies(">=4.2", Some((4, 1, 9))));
+    assert!(satisfies(">=3.1 <4", Some((3, 9, 0))));
+    assert!(!satisfies(">=3.1 <4", Some((4, 0, 0))));
+    assert!(satisfies("<3 || >=4.5", Some((4, 5, 0))));
+    assert!(satisfies("4.2", Some(parse_version("4.2"))));
+    assert!(satisfies("4.2", Some((4, 2, 9))));
+    assert!(!satisfies("4.2.1", Some((4, 2, 9))));
+    assert!(satisfies("^1.2", Some((1, 9, 0))));
+    assert!(!satisfies("^1.2", Some((2, 0, 0))));
+    assert!(!satisfies("^1.2.3", Some((1, 2, 2))));
+    assert!(satisfies("^0.2.3", Some((0, 2, 9))));
+    assert!(!satisfies("^0.2.3", Some((0, 3, 0))));
+    assert!(!satisfies("^0.0.3", Some((0, 0, 4))));
+    assert!(satisfies("~1.2.3", Some((1, 2, 9))));
+    assert!(!satisfies("~1.2.3", Some((1, 3, 0))));
+    assert!(satisfies("1.x", Some((1, 5, 0))));
+    assert!(!satisfies("1.x", Some((2, 0, 0))));
+    assert!(satisfies("1.2.3 - 2.3.4", Some((2, 3, 4))));
+    assert!(!satisfies("1.2.3 - 2.3.4", Some((2, 3, 5))));
+    assert!(satisfies(
+        "^16.0.0 || ^17.0.0",
+        Some(parse_version("17.0.2"))
+    ));
+    assert!(satisfies(">=1.0.0-beta", Some(parse_version("v1.0.0"))));
+    // the partial versions of the comparators and the hyphen ranges.
+    assert!(!satisfies(">2", Some((2, 5, 0))));
+    assert!(satisfies(">2", Some((3, 0, 0))));
+    assert!(satisfies("<=2", Some((2, 9, 9))));
+    assert!(!satisfies("<=2", Some((3, 0, 0))));
+    assert!(satisfies(">2.1 <=2.2", Some((2, 2, 5))));
+    assert!(!satisfies(">2.1", Some((2, 1, 9))));
+    assert!(satisfies("1 - 2", Some((2, 9, 0))));
+    assert!(!satisfies("1 - 2.3", Some((2, 4, 0))));
+    assert!(satisfies(">= 4.2", Some((4, 2, 0))));
+    assert!(satisfies("1.x.3", Some((1, 5, 0))));
+    // the invalid ranges.
+    assert!(!satisfies("latest", Some((1, 0, 0))));
+    assert!(!satisfies("workspace:*", Some((1, 0, 0))));
+    assert!(!satisfies("^1.a", Some((1, 0, 0))));
+}
+
+#[test]
+fn test_satisfies_prerelease() {
+    let satisfies = |range: &str, version: &str| {
+        let (version, prerelease) = parse_full(version).unwrap();
+        parse_range(range)
+            .unwrap()
+            .iter()
+            .any(|set| satisfies_set(set, Some((version, &prerelease))))
+    };
+    assert!(!satisfies("^1.0.0", "1.0.0-beta"));
+    assert!(!satisfies(">=0.9.0", "1.0.0-beta"));
+    assert!(satisfies("^1.0.0-alpha", "1.0.0-beta"));
+    assert!(satisfies(">=1.0.0-beta.2", "1.0.0-beta.10"));
+    assert!(!satisfies(">=1.0.0-beta.2", "1.0.0-beta.1"));
+    assert!(!satisfies(">=1.0.0-beta", "1.0.0-1"));
+    assert!(!satisfies("^1.0.0-beta", "1.1.0-beta"));
+    assert!(satisfies("^1.0.0-beta", "1.1.0"));
+    assert!(satisfies("1.0.0-beta", "1.0.0-beta+build"));
+}
@@ -0,0 +1,77 @@
+use crate::{
+    description::{DescriptionData, PkgJSON},
+    info::Info,
+    parse::Request,
+    Error, RResult, ResolveResult, Resolver, Resource,
+};
+use rayon::prelude::*;
+use std::{path::PathBuf, sync::Arc};
+
+/// The state captured by a prior build, such as the persistent cache of
+/// a bundler, which is used by `Resolver::warm_from`.
+#[derive(Debug, Default, Clone)]
+pub struct WarmEntries {
+    /// The directories where the description file located, with the
+    /// content of it.
+    pub descriptions: Vec<(PathBuf, String)>,
+    /// The `(path, request)` passed to `Resolver::resolve` and the path
+    /// it was resolved to.
+    pub resolutions: Vec<(PathBuf, String, PathBuf)>,
+    /// The fingerprint of the resolver which captured the entries, see
+    /// `Resolver::fingerprint`. They are rejected by `Resolver::warm_from`
+    /// if the fingerprint is changed, `None` is not checked.
+    pub fingerprint: Option<u64>,
+}
+
+impl Resolver {
+    /// Populates the cache before the first request to skip the cold-start
+    /// I/O. It is unsafe because the entries are trusted without checking
+    /// the file system, so they must be captured by the resolver with the
+    /// same options and the files must not be changed since then.
+    /// The entries which had been cached are kept.
+    pub fn warm_from(&self, entries: WarmEntries) -> RResult<()> {
+        if let Some(fingerprint) = entries.fingerprint {
+            if fingerprint != self.fingerprint {
+                return Err(Error::UnexpectedValue(format!(
+                    "The entries are captured by the options of fingerprint {fingerprint:016x}, but the resolver is {:016x}",
+                    self.fingerprint
+                )));
+            }
+        }
+        for (dir, content) in entries.descriptions {
+            let path = dir.join(&self.options.description_file);
+            let json = PkgJSON::parse(&content, &path, self.options.lenient_json)?;
+            let entry = self.load_entry(&dir);
+            entry.init_pkg_info(Arc::new(DescriptionData::new(json, entry.path())));
+        }
+        // the keys are normalized same as the ones of `resolve`.
+        let normalization = self.cache.fs.unicode_normalization();
+        for (path, request, resolved) in entries.resolutions {
+            let path = normalization.normalize_path(&path).into_owned();
+            let request = normalization.normalize_str(&request).into_owned();
+            let resolved = normalization.normalize_path(&resolved).into_owned();
+            let parsed = Self::parse(&request);
+            let info = Info::new(
+                resolved,
+                Request::default()
+                    .with_query(parsed.query())
+                    .with_fragment(parsed.fragment()),
+            );
+            self.results
+                .entry((path, request, None))
+                .or_insert_with(|| ResolveResult::File(Resource::new(info, self)));
+        }
+        Ok(())
+    }
+
+    /// Resolves the `(path, request)` of `jobs` on the global rayon pool
+    /// purely to populate the cache, such as in the idle time of a dev
+    /// server before the first build, it runs on the current pool if it is
+    /// called in `ThreadPool::install`. The results and errors are
+    /// discarded.
+    pub fn prefetch(&self, jobs: &[(PathBuf, String)]) {
+        jobs.par_iter().for_each(|(path, request)| {
+            let _ = self.resolve(path, request);
+        });
+    }
+}
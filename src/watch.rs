@@ -0,0 +1,56 @@
+use crate::{info::NormalizedPath, Resolver};
+use std::path::Path;
+
+/// The kind of the change reported by a file watcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsEventKind {
+    Create,
+    Modify,
+    Remove,
+}
+
+impl Resolver {
+    /// Invalidates the cache affected by the change of `path`, which is
+    /// reported by a file watcher such as notify or watchman, so that the
+    /// resolver could be reused in watch mode.
+    ///
+    /// The entries and cached files are shared by the resolvers with the
+    /// same cache, but the memoized results are not, so it should be called
+    /// on each resolver. The memoized results are cleared unless a file
+    /// other than JSON is modified, because they don't record the files
+    /// they depend on.
+    ///
+    /// The event is deferred until all the sessions on the cache end, see
+    /// [`crate::Session`].
+    pub fn on_fs_event(&self, path: &Path, kind: FsEventKind) {
+        self.dispatch_fs_event(path, kind);
+    }
+
+    pub(crate) fn invalidate(&self, path: &Path, kind: FsEventKind) {
+        let path = NormalizedPath::new(path);
+        self.cache.fs.invalidate(&path);
+        let is_config = path.ends_with(&self.options.description_file)
+            || path.extension().map_or(false, |ext| ext == "json");
+        // the description file is shared by the whole directory, and the
+        // creation or removal changes the parent directory.
+        let dir = if kind == FsEventKind::Modify && !is_config {
+            &path
+        } else {
+            path.parent().unwrap_or(&path)
+        };
+        self.cache.remove_entries(dir);
+        if is_config {
+            self.tsconfigs.clear();
+            self.import_maps.clear();
+        }
+        if kind != FsEventKind::Modify || is_config {
+            self.results.clear();
+            for scoped_results in self.scoped_results.iter() {
+                scoped_results.clear();
+            }
+        }
+        for (_, resolver) in &self.roots {
+            resolver.invalidate(&path, kind);
+        }
+    }
+}
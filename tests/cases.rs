@@ -1,40 +1,154 @@
+#[cfg(feature = "package-lock")]
+use nodejs_resolver::DependencyGraph;
 use nodejs_resolver::{
-    test_helper::{p, vec_to_set},
-    AliasMap, Cache, EnforceExtension, Error, Options, ResolveResult, Resolver,
+    test_helper::{p, vec_to_list},
+    AliasMap, Attempt, AttemptPhase, BeforeResolved, Budget, BuildEnv, Cache, CacheValidation,
+    CompatMode, Completion, CompletionKind, Diagnostic, DiagnosticKind, DualResolver,
+    EnforceExtension, Error, ExportedSubpath, ExportsFieldMisuse, ExportsPriority,
+    FileSystemCaseSensitivity, FsEventKind, IgnoredReason, InstallLayout, InvalidRequest,
+    ManifestErrorPolicy, ManifestWarning, MappedTarget, ModuleType, Options, PackageCopy,
+    PathPreference, PipelineStage, PrefixMap, ProjectLayout, Regex, ResolutionGraph,
+    ResolutionMode, ResolveResult, Resolver, ResolverFactory, RootProfile, Session, Target,
+    TraceOutcome, UnicodeNormalization, Verdict, WarmEntries,
 };
+#[cfg(any(feature = "package-lock", feature = "pnpm-lock", feature = "yarn-lock"))]
+use nodejs_resolver::{LockedVersions, LockfileCheck, LockfileMismatch, LockfileWarning};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 fn should_equal(resolver: &Resolver, path: &Path, request: &str, expected: PathBuf) {
     match resolver.resolve(path, request) {
-        Ok(ResolveResult::Resource(resource)) => {
+        Ok(ResolveResult::File(resource)) => {
             assert_eq!(resource.join(), expected);
         }
-        Ok(ResolveResult::Ignored) => panic!("should not ignored"),
+        Ok(result) => panic!("should be a file, but got {result:?}"),
         Err(error) => panic!("{error:?}"),
     }
 }
 
+fn should_directory(resolver: &Resolver, path: &Path, request: &str, expected: PathBuf) {
+    match resolver.resolve(path, request) {
+        Ok(ResolveResult::Directory(resource)) => {
+            assert_eq!(resource.join(), expected);
+        }
+        Ok(result) => panic!("should be a directory, but got {result:?}"),
+        Err(error) => panic!("{error:?}"),
+    }
+}
+
+fn should_builtin(resolver: &Resolver, path: &Path, request: &str, expected: &str) {
+    match resolver.resolve(path, request) {
+        Ok(ResolveResult::Builtin(name)) => assert_eq!(name, expected),
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
 fn should_ignored(resolver: &Resolver, path: &Path, request: &str) {
     match resolver.resolve(path, request) {
-        Ok(ResolveResult::Ignored) => {}
+        Ok(ResolveResult::Ignored {
+            request: ignored, ..
+        }) => assert_eq!(ignored, request),
         _ => unreachable!(),
     }
 }
 
 fn should_failed(resolver: &Resolver, path: &Path, request: &str) {
     let result = resolver.resolve(path, request);
-    if !matches!(result, Err(Error::ResolveFailedTag)) {
+    if !matches!(result, Err(Error::ResolveFailed(_))) {
         println!("{result:?}");
         panic!("should failed");
     }
 }
 
-fn should_overflow(resolver: &Resolver, path: &Path, request: &str) {
-    let result = resolver.resolve(path, request);
-    if !matches!(result, Err(Error::Overflow)) {
-        println!("{result:?}");
-        unreachable!();
+fn should_suggested(resolver: &Resolver, path: &Path, request: &str, expected: Vec<&str>) {
+    match resolver.resolve(path, request) {
+        Err(Error::ResolveFailed(failed)) => {
+            assert_eq!(failed.request, request);
+            assert_eq!(failed.suggestions, expected);
+        }
+        result => {
+            println!("{result:?}");
+            panic!("should failed");
+        }
+    }
+}
+
+fn should_overflow(resolver: &Resolver, path: &Path, request: &str, expected: Budget) {
+    match resolver.resolve(path, request) {
+        Err(Error::Overflow(budget)) => assert_eq!(budget, expected),
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
+fn should_unsupported_dir_import(
+    resolver: &Resolver,
+    path: &Path,
+    request: &str,
+    suggestion: Option<&str>,
+) {
+    match resolver.resolve(path, request) {
+        Err(Error::UnsupportedDirImport(error)) => {
+            assert_eq!(error.request, request);
+            assert_eq!(error.suggestion.as_deref(), suggestion);
+        }
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
+fn should_not_exported(resolver: &Resolver, path: &Path, request: &str, expected_err_msg: String) {
+    match resolver.resolve(path, request) {
+        Err(Error::PackagePathNotExported(error)) => {
+            assert!(error.to_string().contains(&expected_err_msg))
+        }
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
+fn should_invalid_specifier(resolver: &Resolver, path: &Path, request: &str, segment: &str) {
+    match resolver.resolve(path, request) {
+        Err(Error::InvalidSpecifier(error)) => assert_eq!(error.segment, segment),
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
+fn should_resolve_cycle(resolver: &Resolver, path: &Path, request: &str, expected: Vec<&str>) {
+    match resolver.resolve(path, request) {
+        Err(Error::ResolveCycle(cycle)) => assert_eq!(
+            cycle
+                .iter()
+                .map(|(_, request)| request.as_str())
+                .collect::<Vec<_>>(),
+            expected
+        ),
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    }
+}
+
+fn should_alias_cycle(resolver: &Resolver, path: &Path, request: &str, expected: Vec<&str>) {
+    match resolver.resolve(path, request) {
+        Err(Error::AliasCycle(chain)) => assert_eq!(chain, expected),
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
     }
 }
 
@@ -505,7 +619,12 @@ fn alias_test() {
         "fs",
         p(vec!["alias", "node_modules", "browser", "index.js"]),
     );
-    should_overflow(&resolver, &alias_cases_path, "./e");
+    should_alias_cycle(
+        &resolver,
+        &alias_cases_path,
+        "./e",
+        vec!["./e", "./d", "./e"],
+    );
     should_equal(
         &resolver,
         &alias_cases_path,
@@ -773,192 +892,3326 @@ fn alias_test() {
 }
 
 #[test]
-fn fallback_test() {
-    let alias_cases_path = p(vec!["alias"]);
+fn prefix_test() {
+    let case_path = p(vec!["prefix", "src"]);
     let resolver = Resolver::new(Options {
-        fallback: vec![
-            (
-                String::from("aliasA"),
-                vec![AliasMap::Target(String::from("./a"))],
-            ),
-            // -- exists
-            (
-                String::from("./e"),
-                vec![AliasMap::Target(String::from("./d"))],
-            ),
-            (
-                String::from("./d"),
-                vec![AliasMap::Target(String::from("./e"))],
-            ),
-            // --
-            // in-exists
-            (
-                String::from("./ee"),
-                vec![AliasMap::Target(String::from("./dd"))],
-            ),
-            (
-                String::from("./dd"),
-                vec![AliasMap::Target(String::from("./ee"))],
-            ),
-            (
-                String::from("./ff"),
-                vec![AliasMap::Target(String::from("./ccc"))],
-            ),
+        prefix: vec![
+            ("~".to_string(), PrefixMap::Module),
+            ("@/".to_string(), PrefixMap::Dir(p(vec!["prefix", "src"]))),
         ],
+        alias: vec![("m1".to_string(), vec![AliasMap::Target("m2".to_string())])],
         ..Default::default()
     });
-    // maybe better is `should_overflow(&resolver, &alias_cases_path, "./ee");`
-    should_failed(&resolver, &alias_cases_path, "./ee");
-    should_failed(&resolver, &alias_cases_path, "./ff");
     should_equal(
         &resolver,
-        &alias_cases_path,
-        "./d",
-        p(vec!["alias", "d", "index.js"]),
+        &case_path,
+        "~m1/b",
+        p(vec!["node_modules", "m2", "b.js"]),
     );
     should_equal(
         &resolver,
-        &alias_cases_path,
-        "./e",
-        p(vec!["alias", "e", "index"]),
+        &case_path.join("utils"),
+        "@/utils",
+        p(vec!["prefix", "src", "utils", "index.js"]),
     );
     should_equal(
         &resolver,
-        &alias_cases_path,
-        "aliasA",
-        p(vec!["alias", "a", "index"]),
+        &case_path,
+        "@/app?query#fragment",
+        p(vec!["prefix", "src", "app.js?query#fragment"]),
     );
+    should_failed(&resolver, &case_path, "~");
+    should_failed(&resolver, &case_path, "@/not-exist");
 }
 
 #[test]
-fn symlink_test() {
-    let symlink_cases_path = p(vec!["symlink"]);
-    let resolver = Resolver::new(Options::default());
-
+fn prefix_protocol_test() {
+    let case_path = p(vec!["prefix", "src"]);
+    let resolver = Resolver::new(Options {
+        prefix: vec![
+            ("npm:".to_string(), PrefixMap::Package),
+            ("jsr:".to_string(), PrefixMap::Jsr),
+        ],
+        ..Default::default()
+    });
+    let module = |path: Vec<&str>| {
+        let mut full = vec!["prefix", "node_modules"];
+        full.extend(path);
+        p(full)
+    };
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./this/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "npm:pkg",
+        module(vec!["pkg", "index.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "npm:pkg@1.0.0",
+        module(vec!["pkg", "index.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./node.relative.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "npm:pkg@^1/lib/sub",
+        module(vec!["pkg", "lib", "sub.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./node.relative.sym.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "npm:pkg/lib/sub?q",
+        module(vec!["pkg", "lib", "sub.js?q"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./this/this/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "npm:@scope/pkg@~2",
+        module(vec!["@scope", "pkg", "index.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./outer/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "jsr:@std/path",
+        module(vec!["@jsr", "std__path", "mod.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./outer/linked/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
-    );
+        &case_path,
+        "jsr:@std/path@^1/posix",
+        module(vec!["@jsr", "std__path", "posix.js"]),
+    );
+    // `jsr:` only supports the scoped packages.
+    should_failed(&resolver, &case_path, "jsr:path");
+    should_failed(&resolver, &case_path, "npm:");
+    should_failed(&resolver, &case_path, "npm:@scope");
+    should_failed(&resolver, &case_path, "npm:missing@1");
+}
+
+#[test]
+fn alias_cycle_test() {
+    let alias_cases_path = p(vec!["alias"]);
+    let resolver = Resolver::new(Options {
+        alias: vec![
+            (
+                String::from("first"),
+                vec![AliasMap::Target(String::from("second"))],
+            ),
+            (
+                String::from("second"),
+                vec![AliasMap::Target(String::from("./a"))],
+            ),
+            (String::from("x"), vec![AliasMap::Target(String::from("y"))]),
+            (String::from("y"), vec![AliasMap::Target(String::from("z"))]),
+            (String::from("z"), vec![AliasMap::Target(String::from("x"))]),
+        ],
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./outer/linked/this/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &alias_cases_path,
+        "first/index",
+        p(vec!["alias", "a", "index"]),
     );
-    should_equal(
+    should_alias_cycle(&resolver, &alias_cases_path, "x", vec!["x", "y", "z", "x"]);
+    should_alias_cycle(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./outer/linked/this/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &alias_cases_path,
+        "y/index",
+        vec!["y", "z", "x", "y"],
     );
+}
+
+#[test]
+fn scoped_alias_test() {
+    let case_path = p(vec!["scoped-alias"]);
+    let resolver = Resolver::new(Options {
+        scoped_alias: vec![(
+            case_path.join("packages").join("legacy"),
+            vec![(
+                String::from("react"),
+                vec![AliasMap::Target(String::from("react16"))],
+            )],
+        )],
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./that/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("packages").join("legacy").join("src"),
+        "react",
+        p(vec!["scoped-alias", "node_modules", "react16", "index.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./that/outer/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("packages").join("modern"),
+        "react",
+        p(vec!["scoped-alias", "node_modules", "react", "index.js"]),
     );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./that/outer/linked/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "react",
+        p(vec!["scoped-alias", "node_modules", "react", "index.js"]),
     );
+}
+
+#[test]
+fn module_type_defaults_test() {
+    let case_path = p(vec!["module-type"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![String::from(".js"), String::from(".mjs")],
+        module_type_defaults: true,
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked"),
-        "./that/outer/linked/that/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("esm"),
+        "pkg",
+        p(vec!["module-type", "node_modules", "pkg", "index.mjs"]),
     );
-
     should_equal(
         &resolver,
-        &symlink_cases_path,
-        "./lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("esm"),
+        "legacy",
+        p(vec![
+            "module-type",
+            "node_modules",
+            "legacy",
+            "lib",
+            "index.mjs",
+        ]),
     );
-
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/this"),
-        "./lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("esm"),
+        "./a.js",
+        p(vec!["module-type", "esm", "a.js"]),
     );
+    should_failed(&resolver, &case_path.join("esm"), "./a");
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/this"),
-        "./outer/linked/lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("cjs"),
+        "pkg",
+        p(vec!["module-type", "node_modules", "pkg", "index.cjs"]),
     );
-
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/this/lib"),
-        "./index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("cjs"),
+        "legacy",
+        p(vec![
+            "module-type",
+            "node_modules",
+            "legacy",
+            "lib",
+            "index.js",
+        ]),
     );
-
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/this/outer/linked"),
-        "./index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("cjs"),
+        "./a",
+        p(vec!["module-type", "cjs", "a.js"]),
     );
+
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_list(vec!["import"]),
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/this/outer/linked"),
-        "./lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path.join("cjs"),
+        "pkg",
+        p(vec!["module-type", "node_modules", "pkg", "index.mjs"]),
     );
+}
 
+#[test]
+fn esm_mode_test() {
+    let case_path = p(vec!["esm-mode"]);
+    let resolver = Resolver::new(Options {
+        mode: ResolutionMode::Esm,
+        extensions: vec![String::from(".ts")],
+        main_fields: vec![String::from("module"), String::from("main")],
+        ..Default::default()
+    });
+    should_equal(&resolver, &case_path, "./b.js", p(vec!["esm-mode", "b.js"]));
+    should_failed(&resolver, &case_path, "./b");
+    should_unsupported_dir_import(&resolver, &case_path, "./dir", Some("./dir/index.js"));
+    should_unsupported_dir_import(&resolver, &case_path, "./dir/", Some("./dir/index.js"));
+    should_unsupported_dir_import(&resolver, &case_path, "./node_modules/pkg-main", None);
+    should_unsupported_dir_import(
+        &resolver,
+        &case_path,
+        "pkg-main/lib",
+        Some("pkg-main/lib/index.js"),
+    );
     should_equal(
         &resolver,
-        &symlink_cases_path.join("linked/that"),
-        "./lib/index.js",
-        p(vec!["symlink", "lib", "index.js"]),
+        &case_path,
+        "./dir/index.js",
+        p(vec!["esm-mode", "dir", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a%20b.js",
+        p(vec!["esm-mode", "a b.js"]),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "./dir%2Findex.js",
+        String::from("must not include encoded '/' or '\\' characters"),
+    );
+    let url = format!("file://{}", p(vec!["esm-mode", "b.js"]).display());
+    should_equal(&resolver, &case_path, &url, p(vec!["esm-mode", "b.js"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg",
+        p(vec!["esm-mode", "node_modules", "pkg", "index.mjs"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg-main",
+        p(vec![
+            "esm-mode",
+            "node_modules",
+            "pkg-main",
+            "lib",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg-exports/sub",
+        p(vec!["esm-mode", "node_modules", "pkg-exports", "sub.js"]),
+    );
+    should_not_exported(
+        &resolver,
+        &case_path,
+        "pkg-exports/private.js",
+        String::from("is not exported"),
+    );
+}
+
+#[test]
+fn deno_mode_test() {
+    let case_path = p(vec!["deno-mode"]);
+    let src = p(vec!["deno-mode", "src"]);
+    let node = Resolver::new(Default::default());
+    let resolver = Resolver::new(Options {
+        mode: ResolutionMode::Deno,
+        import_map: Some(p(vec!["deno-mode", "importmap.json"])),
+        extensions: vec![String::from(".ts")],
+        external_cache: Some(node.cache()),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &src,
+        "./a.ts",
+        p(vec!["deno-mode", "src", "a.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "./a.ts?v=1#x",
+        p(vec!["deno-mode", "src", "a.ts?v=1#x"]),
+    );
+    let url = format!("file://{}", src.join("a%2Ets").display());
+    should_equal(
+        &resolver,
+        &case_path,
+        &url,
+        p(vec!["deno-mode", "src", "a.ts"]),
+    );
+    // the extension must be explicit.
+    should_failed(&resolver, &src, "./a");
+    should_unsupported_dir_import(&resolver, &src, "./utils", None);
+    // only the import map resolves the bare specifiers.
+    should_equal(
+        &resolver,
+        &src,
+        "pkg",
+        p(vec!["deno-mode", "vendor", "pkg.ts"]),
+    );
+    should_failed(&resolver, &case_path, "pkg/index.js");
+    should_equal(
+        &node,
+        &case_path,
+        "pkg",
+        p(vec!["deno-mode", "node_modules", "pkg", "index.js"]),
+    );
+}
+
+#[test]
+fn cjs_mode_test() {
+    let case_path = p(vec!["cjs-mode"]);
+    let resolver = Resolver::new(Options {
+        mode: ResolutionMode::Cjs,
+        extensions: vec![String::from(".ts")],
+        main_fields: vec![String::from("module"), String::from("main")],
+        condition_names: vec_to_list(vec!["import"]),
+        ..Default::default()
+    });
+    // LOAD_AS_FILE
+    should_equal(&resolver, &case_path, "./a", p(vec!["cjs-mode", "a.js"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a.json",
+        p(vec!["cjs-mode", "a.json"]),
+    );
+    should_equal(&resolver, &case_path, "./b", p(vec!["cjs-mode", "b.json"]));
+    should_equal(&resolver, &case_path, "./c", p(vec!["cjs-mode", "c.node"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./file-and-dir",
+        p(vec!["cjs-mode", "file-and-dir.js"]),
+    );
+    // LOAD_AS_DIRECTORY
+    should_equal(
+        &resolver,
+        &case_path,
+        "./dir",
+        p(vec!["cjs-mode", "dir", "lib", "main.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "./dir-bad-main",
+        p(vec!["cjs-mode", "dir-bad-main", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "./file-and-dir/",
+        p(vec!["cjs-mode", "file-and-dir", "index.js"]),
+    );
+    // LOAD_NODE_MODULES
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg",
+        p(vec!["cjs-mode", "node_modules", "pkg", "index.cjs"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg-module",
+        p(vec!["cjs-mode", "node_modules", "pkg-module", "cjs.js"]),
+    );
+    should_failed(&resolver, &case_path, "./not-exist");
+}
+
+#[test]
+fn binary_extensions_test() {
+    let case_path = p(vec!["native-addon"]);
+    let resolver = Resolver::new(Options {
+        extensions: vec![String::from(".js")],
+        binary_extensions: vec![String::from(".node"), String::from(".wasm")],
+        condition_names: vec_to_list(vec!["node"]),
+        ..Default::default()
+    });
+    let is_binary = |request: &str| match resolver.resolve(&case_path, request) {
+        Ok(ResolveResult::File(resource)) => resource.binary,
+        result => panic!("{result:?}"),
+    };
+    should_equal(
+        &resolver,
+        &case_path,
+        "./addon",
+        p(vec!["native-addon", "addon.node"]),
+    );
+    assert!(is_binary("./addon"));
+    assert!(is_binary("./addon.node"));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./m",
+        p(vec!["native-addon", "m.wasm"]),
+    );
+    assert!(is_binary("./m"));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib",
+        p(vec!["native-addon", "lib.js"]),
+    );
+    assert!(!is_binary("./lib"));
+    should_equal(
+        &resolver,
+        &case_path,
+        "addon",
+        p(vec![
+            "native-addon",
+            "node_modules",
+            "addon",
+            "build",
+            "addon.node",
+        ]),
+    );
+    assert!(is_binary("addon"));
+    assert!(!is_binary("addon/js"));
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![String::from(".js")],
+        binary_extensions: vec![],
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "./addon");
+}
+
+#[test]
+fn resolve_failed_attempts_test() {
+    let case_path = p(vec!["exports-field"]);
+    let resolver = Resolver::new(Options {
+        alias: vec![(
+            String::from("x"),
+            vec![AliasMap::Target(String::from("./not-exist"))],
+        )],
+        ..Default::default()
+    });
+    let failed = match resolver.resolve(&case_path, "x") {
+        Err(Error::ResolveFailed(failed)) => failed,
+        result => panic!("{result:?}"),
+    };
+    let has = |phase: AttemptPhase, detail: String| {
+        assert!(
+            failed.attempts.contains(&Attempt { phase, detail }),
+            "{:?}",
+            failed.attempts
+        )
+    };
+    has(AttemptPhase::Alias, String::from("'x' -> './not-exist'"));
+    has(
+        AttemptPhase::File,
+        format!("'{}'", case_path.join("not-exist").display()),
+    );
+    has(
+        AttemptPhase::Extensions,
+        format!(
+            "'{}[.js|.json|.node]'",
+            case_path.join("not-exist").display()
+        ),
+    );
+    has(
+        AttemptPhase::Modules,
+        format!("'{}'", case_path.join("node_modules").display()),
+    );
+    let message = failed.to_string();
+    assert!(message.starts_with(&format!(
+        "Can't resolve 'x' in '{}'\n  aliases:\n    'x' -> './not-exist'\n  files:",
+        case_path.display()
+    )));
+
+    let failed = match resolver.resolve(&case_path, "exports-field/dist/not-exist") {
+        Err(Error::ResolveFailed(failed)) => failed,
+        result => panic!("{result:?}"),
+    };
+    assert!(failed
+        .attempts
+        .iter()
+        .any(|attempt| attempt.phase == AttemptPhase::Exports));
+}
+
+#[test]
+fn load_description_file_test() {
+    let case_path = p(vec!["imports-field"]);
+    let resolver = Resolver::new(Default::default());
+    let description = resolver.load_description_file(&case_path).unwrap().unwrap();
+    assert_eq!(description.dir().as_ref(), case_path);
+    assert_eq!(description.data().name(), Some("imports-field"));
+    assert_eq!(description.data().version(), Some("1.0.0"));
+    assert_eq!(
+        description.data().exports(),
+        Some(&serde_json::json!("./a.js"))
+    );
+    assert_eq!(
+        description.data().imports().unwrap()["#b"],
+        serde_json::json!("../b.js")
+    );
+    assert_eq!(description.data().side_effects(), None);
+
+    // nearest description file is shared with the resolution.
+    let nested = resolver
+        .load_description_file(&case_path.join("dir"))
+        .unwrap()
+        .unwrap();
+    assert!(Arc::ptr_eq(&description, &nested));
+    let resource = match resolver.resolve(&case_path, "./b.js") {
+        Ok(ResolveResult::File(resource)) => resource,
+        result => panic!("{result:?}"),
+    };
+    assert!(Arc::ptr_eq(&description, &resource.description.unwrap()));
+
+    assert!(resolver
+        .load_description_file(&p(vec![]))
+        .unwrap()
+        .is_none());
+    let description = resolver
+        .load_description_file(&p(vec!["incorrect-package", "sideeffects-map"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        description.data().side_effects(),
+        Some(&serde_json::json!({}))
+    );
+    assert!(matches!(
+        resolver.load_description_file(&p(vec!["incorrect-package", "pack1"])),
+        Err(Error::UnexpectedJson(_))
+    ));
+}
+
+#[test]
+fn lenient_json_test() {
+    let case_path = p(vec!["lenient-json"]);
+    let resolver = Resolver::new(Default::default());
+    match resolver.resolve(&case_path, "./") {
+        Err(Error::UnexpectedJson((path, error))) => {
+            assert_eq!(*path, case_path.join("package.json"));
+            assert_eq!(error.line(), 2);
+            assert_eq!(error.column(), 3);
+        }
+        result => panic!("{result:?}"),
+    }
+
+    let resolver = Resolver::new(Options {
+        lenient_json: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./",
+        p(vec!["lenient-json", "main.js"]),
+    );
+}
+
+#[test]
+fn lenient_json_shared_cache_test() {
+    let case_path = p(vec!["lenient-json"]);
+    let cache = Arc::new(Cache::default());
+    let lenient = Resolver::new(Options {
+        lenient_json: true,
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    let strict = Resolver::new(Options {
+        external_cache: Some(cache),
+        ..Default::default()
+    });
+    should_equal(
+        &lenient,
+        &case_path,
+        "./",
+        p(vec!["lenient-json", "main.js"]),
+    );
+    // the description file parsed by the lenient resolver is not shared.
+    assert!(matches!(
+        strict.resolve(&case_path, "./"),
+        Err(Error::UnexpectedJson(_))
+    ));
+    assert!(matches!(
+        strict.load_description_file(&case_path),
+        Err(Error::UnexpectedJson(_))
+    ));
+}
+
+#[test]
+fn debug_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Options {
+        debug: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/index",
+        p(vec!["simple", "lib", "index.js"]),
+    );
+    // creating another resolver should not re-initialize the global subscriber.
+    let resolver = Resolver::new(Options {
+        debug: true,
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "./not-exist");
+}
+
+#[test]
+fn resolve_cycle_test() {
+    let case_path = p(vec!["resolve-cycle"]);
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "a",
+        p(vec!["resolve-cycle", "node_modules", "a", "main.js"]),
+    );
+    // the main field of `a` is aliased back to `a`.
+    let resolver = Resolver::new(Options {
+        alias: vec![(
+            String::from("./main.js"),
+            vec![AliasMap::Target(String::from("a"))],
+        )],
+        ..Default::default()
+    });
+    should_resolve_cycle(&resolver, &case_path, "a", vec!["a", "./main.js", "a", "a"]);
+}
+
+#[test]
+fn budget_test() {
+    let alias_cases_path = p(vec!["alias"]);
+    let alias = vec![
+        (
+            String::from("first"),
+            vec![AliasMap::Target(String::from("second"))],
+        ),
+        (
+            String::from("second"),
+            vec![AliasMap::Target(String::from("third"))],
+        ),
+        (
+            String::from("third"),
+            vec![AliasMap::Target(String::from("./a"))],
+        ),
+    ];
+    let resolver = Resolver::new(Options {
+        alias: alias.clone(),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "first/index",
+        p(vec!["alias", "a", "index"]),
+    );
+    let resolver = Resolver::new(Options {
+        alias: alias.clone(),
+        max_depth: 3,
+        ..Default::default()
+    });
+    should_overflow(
+        &resolver,
+        &alias_cases_path,
+        "first/index",
+        Budget::MaxDepth(3),
+    );
+    let resolver = Resolver::new(Options {
+        alias,
+        max_redirects: 2,
+        ..Default::default()
+    });
+    should_overflow(
+        &resolver,
+        &alias_cases_path,
+        "first/index",
+        Budget::MaxRedirects(2),
+    );
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "second/index",
+        p(vec!["alias", "a", "index"]),
+    );
+}
+
+#[test]
+fn long_alias_chain_test() {
+    let alias_cases_path = p(vec!["alias"]);
+    let alias = (0..1000)
+        .map(|index| {
+            (
+                format!("a{index}"),
+                vec![AliasMap::Target(format!("a{}", index + 1))],
+            )
+        })
+        .collect();
+    let resolver = Resolver::new(Options {
+        alias,
+        max_redirects: u16::MAX,
+        ..Default::default()
+    });
+    // the redirects are bounded by `max_depth` on the default stack of a
+    // spawned thread.
+    std::thread::Builder::new()
+        .stack_size(2 * 1024 * 1024)
+        .spawn(move || should_overflow(&resolver, &alias_cases_path, "a0", Budget::MaxDepth(127)))
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+#[test]
+fn loader_resolver_test() {
+    let case_path = p(vec!["loader"]);
+    let cache = Arc::new(Cache::default());
+    let resolver = Resolver::new(Options {
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    let loader_resolver = Resolver::new_loader_resolver(Options {
+        external_cache: Some(resolver.cache()),
+        ..Default::default()
+    });
+    assert!(Arc::ptr_eq(&loader_resolver.cache(), &cache));
+    should_equal(
+        &resolver,
+        &case_path,
+        "my-loader",
+        p(vec!["loader", "node_modules", "my-loader", "index.js"]),
+    );
+    should_equal(
+        &loader_resolver,
+        &case_path,
+        "my-loader",
+        p(vec!["loader", "node_modules", "my-loader", "loader.js"]),
+    );
+    should_equal(
+        &loader_resolver,
+        &case_path,
+        "plain-loader",
+        p(vec!["loader", "node_modules", "plain-loader", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "exports-loader",
+        p(vec![
+            "loader",
+            "node_modules",
+            "exports-loader",
+            "dist",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &loader_resolver,
+        &case_path,
+        "exports-loader",
+        p(vec![
+            "loader",
+            "node_modules",
+            "exports-loader",
+            "dist",
+            "loader.js",
+        ]),
+    );
+}
+
+#[test]
+fn warm_from_test() {
+    let case_path = p(vec!["warm"]);
+    let pkg_path = p(vec!["warm", "node_modules", "pkg"]);
+    let resolver = Resolver::new(Default::default());
+    resolver
+        .warm_from(WarmEntries {
+            // differs from the file on the disk to make sure it is used.
+            descriptions: vec![(
+                pkg_path.clone(),
+                String::from(r#"{ "name": "pkg", "main": "./b.js" }"#),
+            )],
+            resolutions: vec![(
+                case_path.clone(),
+                String::from("virtual?query"),
+                pkg_path.join("a.js"),
+            )],
+            fingerprint: Some(resolver.fingerprint()),
+        })
+        .unwrap();
+    should_equal(&resolver, &case_path, "pkg", pkg_path.join("b.js"));
+    match resolver.resolve(&case_path, "virtual?query") {
+        Ok(ResolveResult::File(resource)) => {
+            assert_eq!(resource.path, pkg_path.join("a.js"));
+            assert_eq!(resource.query.as_deref(), Some("?query"));
+            assert_eq!(resource.description.unwrap().data().raw()["main"], "./b.js");
+        }
+        result => panic!("{result:?}"),
+    }
+    let resolver = Resolver::new(Default::default());
+    should_equal(&resolver, &case_path, "pkg", pkg_path.join("a.js"));
+    assert!(matches!(
+        resolver.warm_from(WarmEntries {
+            descriptions: vec![(pkg_path.clone(), String::from("{"))],
+            resolutions: vec![],
+            fingerprint: None,
+        }),
+        Err(Error::UnexpectedJson(_))
+    ));
+    // captured by the resolver of other options.
+    assert!(matches!(
+        resolver.warm_from(WarmEntries {
+            descriptions: vec![(pkg_path.clone(), String::from("{}"))],
+            fingerprint: Some(Options::default().fingerprint() + 1),
+            ..Default::default()
+        }),
+        Err(Error::UnexpectedValue(_))
+    ));
+    // the entries captured in NFD are hit by the requests in NFC.
+    let resolver = Resolver::new(Options {
+        external_cache: Some(Arc::new(
+            Cache::default().with_unicode_normalization(UnicodeNormalization::Nfc),
+        )),
+        ..Default::default()
+    });
+    resolver
+        .warm_from(WarmEntries {
+            resolutions: vec![(
+                case_path.join("cafe\u{301}"),
+                String::from("./cafe\u{301}"),
+                pkg_path.join("a.js"),
+            )],
+            ..Default::default()
+        })
+        .unwrap();
+    should_equal(
+        &resolver,
+        &case_path.join("caf\u{e9}"),
+        "./caf\u{e9}",
+        pkg_path.join("a.js"),
+    );
+}
+
+#[test]
+fn prefetch_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Default::default());
+    resolver.prefetch(&[]);
+    assert!(resolver.cache().is_empty());
+    let jobs: Vec<_> = ["./lib/index", "./lib/index.js", "./inexist", "inexist", "#"]
+        .into_iter()
+        .map(|request| (case_path.clone(), String::from(request)))
+        .collect();
+    resolver.prefetch(&jobs);
+    assert!(!resolver.cache().is_empty());
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/index",
+        p(vec!["simple", "lib", "index.js"]),
+    );
+    should_failed(&resolver, &case_path, "./inexist");
+}
+
+#[test]
+fn multi_root_test() {
+    let case_path = p(vec!["multi-root"]);
+    let alias = |dir: PathBuf| {
+        Some(vec![(
+            String::from("@"),
+            vec![AliasMap::Target(dir.display().to_string())],
+        )])
+    };
+    let resolver = Resolver::new(Options {
+        roots: vec![
+            RootProfile {
+                root: p(vec!["multi-root", "a"]),
+                alias: alias(p(vec!["multi-root", "a", "lib"])),
+                extensions: Some(vec![String::from(".ts")]),
+                ..Default::default()
+            },
+            RootProfile {
+                root: p(vec!["multi-root", "b"]),
+                alias: alias(p(vec!["multi-root", "b", "lib"])),
+                ..Default::default()
+            },
+            RootProfile {
+                root: p(vec!["multi-root", "a", "nested"]),
+                alias: alias(p(vec!["multi-root", "shared"])),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &p(vec!["multi-root", "a"]),
+        "@/x",
+        p(vec!["multi-root", "a", "lib", "x.ts"]),
+    );
+    should_equal(
+        &resolver,
+        &p(vec!["multi-root", "b"]),
+        "@/x",
+        p(vec!["multi-root", "b", "lib", "x.js"]),
+    );
+    // the innermost root wins, and the unset fields are inherited.
+    should_equal(
+        &resolver,
+        &p(vec!["multi-root", "a", "nested"]),
+        "@/x",
+        p(vec!["multi-root", "shared", "x.js"]),
+    );
+    should_failed(&resolver, &case_path, "@/x");
+    should_equal(
+        &resolver,
+        &case_path,
+        "./a/lib/x",
+        p(vec!["multi-root", "a", "lib", "x.js"]),
+    );
+    resolver.clear_entries();
+    should_equal(
+        &resolver,
+        &p(vec!["multi-root", "a"]),
+        "@/x",
+        p(vec!["multi-root", "a", "lib", "x.ts"]),
+    );
+}
+
+#[test]
+fn boundaries_test() {
+    let src = p(vec!["boundary", "ws", "src"]);
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &src,
+        "outer",
+        p(vec!["boundary", "node_modules", "outer", "index.js"]),
+    );
+    let pkg_info = resolver.load_description_file(&src).unwrap().unwrap();
+    assert_eq!(pkg_info.data().name(), Some("outer-root"));
+
+    // the cache is shared but the entries are not polluted.
+    let resolver = Resolver::new(Options {
+        boundaries: vec![p(vec!["boundary", "ws"])],
+        external_cache: Some(resolver.cache()),
+        ..Default::default()
+    });
+    should_failed(&resolver, &src, "outer");
+    should_equal(
+        &resolver,
+        &src,
+        "inner",
+        p(vec!["boundary", "ws", "node_modules", "inner", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "./a",
+        p(vec!["boundary", "ws", "src", "a.js"]),
+    );
+    assert!(resolver.load_description_file(&src).unwrap().is_none());
+    assert!(resolver
+        .find_package_copies(&src, "outer")
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        resolver.find_package_copies(&src, "inner").unwrap().len(),
+        0
+    );
+
+    // the boundary is normalized.
+    let resolver = Resolver::new(Options {
+        boundaries: vec![p(vec!["boundary", "ws", "src", "..", ""])],
+        ..Default::default()
+    });
+    should_failed(&resolver, &src, "outer");
+}
+
+#[test]
+fn affected_resolutions_test() {
+    let src = p(vec!["affected", "src"]);
+    let file = |path: Vec<&str>| {
+        let mut path = path;
+        path.insert(0, "affected");
+        p(path)
+    };
+    let resolver = Resolver::new(Default::default());
+    should_equal(&resolver, &src, "./a", file(vec!["src", "a.js"]));
+    assert!(resolver
+        .affected_resolutions(&[file(vec!["src", "a.js"])])
+        .is_empty());
+
+    let resolver = Resolver::new(Options {
+        track_dependencies: true,
+        ..Default::default()
+    });
+    should_equal(&resolver, &src, "./a", file(vec!["src", "a.js"]));
+    should_equal(&resolver, &src, "./b", file(vec!["src", "b.js"]));
+    should_equal(
+        &resolver,
+        &src,
+        "pkg",
+        file(vec!["node_modules", "pkg", "index.js"]),
+    );
+    let edge = |request: &str| (src.clone(), String::from(request));
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["src", "a.js"])]),
+        vec![edge("./a")]
+    );
+    // the missing path which had been looked up.
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["src", "b"])]),
+        vec![edge("./b")]
+    );
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["node_modules", "pkg", "package.json"])]),
+        vec![edge("pkg")]
+    );
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["node_modules"])]),
+        vec![edge("pkg")]
+    );
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["tsconfig.json"])]),
+        vec![edge("./a"), edge("./b"), edge("pkg")]
+    );
+    // the dependencies are kept after the events.
+    resolver.on_fs_event(&file(vec!["src", "a.js"]), FsEventKind::Remove);
+    assert_eq!(
+        resolver.affected_resolutions(&[file(vec!["src", "a.js"])]),
+        vec![edge("./a")]
+    );
+    resolver.clear_entries();
+    assert!(resolver
+        .affected_resolutions(&[file(vec!["tsconfig.json"])])
+        .is_empty());
+}
+
+#[test]
+fn optional_prefixes_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Options {
+        optional_prefixes: vec![String::from("fsevents"), String::from("./lib/")],
+        ..Default::default()
+    });
+    should_ignored(&resolver, &case_path, "fsevents");
+    should_ignored(&resolver, &case_path, "fsevents/lib");
+    should_ignored(&resolver, &case_path, "./lib/inexist");
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/index",
+        p(vec!["simple", "lib", "index.js"]),
+    );
+    should_failed(&resolver, &case_path, "inexist");
+    should_failed(&resolver, &case_path, "fsevents-x");
+    match resolver.resolve(&case_path, "fsevents") {
+        Ok(ResolveResult::Ignored { reason, .. }) => {
+            assert_eq!(reason, IgnoredReason::Optional(String::from("fsevents")))
+        }
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn find_package_copies_test() {
+    let case_path = p(vec!["package-copies"]);
+    let resolver = Resolver::new(Default::default());
+    let copy = |dir: PathBuf, version: &str| PackageCopy {
+        dir,
+        version: Some(String::from(version)),
+    };
+    assert_eq!(
+        resolver
+            .find_package_copies(
+                &p(vec!["package-copies", "packages", "app", "src"]),
+                "dup/index.js"
+            )
+            .unwrap(),
+        vec![
+            copy(
+                p(vec![
+                    "package-copies",
+                    "packages",
+                    "app",
+                    "node_modules",
+                    "dup"
+                ]),
+                "2.0.0"
+            ),
+            copy(p(vec!["package-copies", "node_modules", "dup"]), "1.0.0"),
+        ]
+    );
+    // the symlink is the same copy.
+    assert_eq!(
+        resolver
+            .find_package_copies(&p(vec!["package-copies", "packages", "lib", "src"]), "dup")
+            .unwrap(),
+        vec![copy(
+            p(vec!["package-copies", "node_modules", "dup"]),
+            "1.0.0"
+        )]
+    );
+    assert!(resolver
+        .find_package_copies(&case_path, "inexist")
+        .unwrap()
+        .is_empty());
+    assert!(resolver
+        .find_package_copies(&case_path, "./node_modules/dup")
+        .unwrap()
+        .is_empty());
+    // the ancestors are not walked if all of the modules are absolute,
+    // same as `resolve`.
+    let app_src = p(vec!["package-copies", "packages", "app", "src"]);
+    let resolver = Resolver::new(Options {
+        modules: vec![case_path.join("node_modules").display().to_string()],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &app_src,
+        "dup",
+        p(vec!["package-copies", "node_modules", "dup", "index.js"]),
+    );
+    assert_eq!(
+        resolver.find_package_copies(&app_src, "dup").unwrap(),
+        vec![copy(
+            p(vec!["package-copies", "node_modules", "dup"]),
+            "1.0.0"
+        )]
+    );
+}
+
+#[test]
+fn resolve_with_version_check_test() {
+    let case_path = p(vec!["package-copies", "packages", "app", "src"]);
+    let resolver = Resolver::new(Default::default());
+    let result = resolver.resolve_with_version_check(&case_path, "dup", "^2.0.0 || ^3.0.0");
+    match result {
+        Ok(ResolveResult::File(resource)) => assert_eq!(
+            resource.path,
+            p(vec![
+                "package-copies",
+                "packages",
+                "app",
+                "node_modules",
+                "dup",
+                "index.js"
+            ])
+        ),
+        result => panic!("{result:?}"),
+    }
+    match resolver.resolve_with_version_check(&case_path, "dup", "^1.0.0") {
+        Err(Error::VersionMismatch(mismatch)) => {
+            assert_eq!(mismatch.request, "dup");
+            assert_eq!(mismatch.range, "^1.0.0");
+            assert_eq!(mismatch.version.as_deref(), Some("2.0.0"));
+            assert_eq!(
+                mismatch.path,
+                Some(p(vec![
+                    "package-copies",
+                    "packages",
+                    "app",
+                    "node_modules",
+                    "dup"
+                ]))
+            );
+        }
+        result => panic!("{result:?}"),
+    }
+    assert!(matches!(
+        resolver.resolve_with_version_check(&case_path, "inexist", "*"),
+        Err(Error::ResolveFailed(_))
+    ));
+    // the version is missing.
+    match resolver.resolve_with_version_check(&p(vec!["simple"]), "./lib/index", "*") {
+        Err(Error::VersionMismatch(mismatch)) => assert_eq!(mismatch.version, None),
+        result => panic!("{result:?}"),
+    }
+    // the description file of the package root rather than the nearest one.
+    assert!(resolver
+        .resolve_with_version_check(&case_path, "nested", "^1.2.0")
+        .is_ok());
+    match resolver.resolve_with_version_check(&case_path, "beta", "^1.0.0") {
+        Err(Error::VersionMismatch(mismatch)) => {
+            assert_eq!(mismatch.version.as_deref(), Some("1.0.0-beta.2"))
+        }
+        result => panic!("{result:?}"),
+    }
+    assert!(resolver
+        .resolve_with_version_check(&case_path, "beta", "^1.0.0-beta")
+        .is_ok());
+    for range in ["latest", "workspace:*"] {
+        match resolver.resolve_with_version_check(&case_path, "dup", range) {
+            Err(Error::UnexpectedValue(message)) => {
+                assert_eq!(message, format!("Invalid version range '{range}' of 'dup'"))
+            }
+            result => panic!("{result:?}"),
+        }
+    }
+}
+
+#[test]
+fn resolve_bin_test() {
+    let case_path = p(vec!["bin", "src"]);
+    let resolver = Resolver::new(Default::default());
+    let resolve_bin = |package_name: &str, bin_name: &str| {
+        resolver
+            .resolve_bin(package_name, bin_name, &case_path)
+            .unwrap()
+    };
+    assert_eq!(
+        resolve_bin("cli-a", "cli-a"),
+        Some(p(vec!["bin", "node_modules", "cli-a", "bin", "cli.js"]))
+    );
+    assert_eq!(resolve_bin("cli-a", "cli"), None);
+    assert_eq!(
+        resolve_bin("@scope/cli-b", "b1"),
+        Some(p(vec!["bin", "node_modules", "@scope", "cli-b", "b1.js"]))
+    );
+    assert_eq!(
+        resolve_bin("@scope/cli-b", "b2"),
+        Some(p(vec![
+            "bin",
+            "node_modules",
+            "@scope",
+            "cli-b",
+            "bin",
+            "b2.js"
+        ]))
+    );
+    assert_eq!(resolve_bin("@scope/cli-b", "missing"), None);
+    assert_eq!(resolve_bin("@scope/cli-b", "cli-b"), None);
+    assert_eq!(resolve_bin("inexist", "inexist"), None);
+}
+
+#[test]
+fn invalid_request_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Default::default());
+    for (request, reason) in [
+        (" ", InvalidRequest::Whitespace),
+        ("\t\n", InvalidRequest::Whitespace),
+        ("#!/usr/bin/env node", InvalidRequest::Shebang),
+    ] {
+        match resolver.resolve(&case_path, request) {
+            Err(Error::InvalidRequest(actual)) => assert_eq!(actual, reason),
+            result => panic!("{result:?}"),
+        }
+    }
+    // resolved as the path itself.
+    should_equal(
+        &resolver,
+        &case_path,
+        "",
+        p(vec!["simple", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "?a",
+        p(vec!["simple", "lib", "index.js?a"]),
+    );
+}
+
+#[test]
+fn compat_mode_test() {
+    let case_path = p(vec!["compat-mode"]);
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Webpack,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "a",
+        p(vec!["compat-mode", "node_modules", "a", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "b?c",
+        p(vec!["compat-mode", "node_modules", "b", "index.js?c"]),
+    );
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Node,
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "a");
+    should_failed(&resolver, &case_path, "b?c");
+    should_failed(&resolver, &case_path, "b#c");
+    should_equal(
+        &resolver,
+        &case_path,
+        "b/index.js?c",
+        p(vec!["compat-mode", "node_modules", "b", "index.js?c"]),
+    );
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Node,
+        mode: ResolutionMode::Cjs,
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "b/index.js?c");
+    should_failed(&resolver, &case_path, "./node_modules/b/index.js#c");
+
+    // the package name of the non-ASCII chars.
+    for compat in [CompatMode::Webpack, CompatMode::Node] {
+        let resolver = Resolver::new(Options {
+            compat,
+            ..Default::default()
+        });
+        should_equal(
+            &resolver,
+            &case_path,
+            "ñ",
+            p(vec!["compat-mode", "node_modules", "ñ", "index.js"]),
+        );
+    }
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Node,
+        ..Default::default()
+    });
+    should_failed(&resolver, &case_path, "ñ?c");
+
+    // `alias` takes precedence over `"imports"` only in webpack.
+    let alias = vec![(
+        String::from("#a"),
+        vec![AliasMap::Target(String::from("./src/b.js"))],
+    )];
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Webpack,
+        alias: alias.clone(),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "#a",
+        p(vec!["compat-mode", "src", "b.js"]),
+    );
+    let resolver = Resolver::new(Options {
+        compat: CompatMode::Node,
+        alias,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "#a",
+        p(vec!["compat-mode", "src", "a.js"]),
+    );
+}
+
+#[test]
+fn condition_order_test() {
+    let case_path = p(vec!["condition-order"]);
+    let pkg = |file: &str| p(vec!["condition-order", "node_modules", "pkg", file]);
+    let resolver = |condition_names: Vec<&str>, prefer_condition_order: bool| {
+        Resolver::new(Options {
+            condition_names: vec_to_list(condition_names),
+            prefer_condition_order,
+            ..Default::default()
+        })
+    };
+    // the declared order wins by default.
+    let declared = resolver(vec!["worker", "import"], false);
+    should_equal(&declared, &case_path, "pkg", pkg("import.mjs"));
+    let ordered = resolver(vec!["worker", "import"], true);
+    should_equal(&ordered, &case_path, "pkg", pkg("worker.js"));
+    let ordered = resolver(vec!["unknown"], true);
+    should_equal(&ordered, &case_path, "pkg", pkg("index.js"));
+    // the nested conditions are ordered too.
+    let ordered = resolver(vec!["require", "worker", "node"], true);
+    should_equal(&ordered, &case_path, "pkg/nested", pkg("require.js"));
+    let ordered = resolver(vec!["worker", "require", "node"], true);
+    should_equal(&ordered, &case_path, "pkg/nested", pkg("worker.js"));
+
+    // `"module-sync"` is enabled for both `require` and `import`.
+    let mode = |mode: ResolutionMode, condition_names: Vec<&str>, prefer_condition_order: bool| {
+        Resolver::new(Options {
+            mode,
+            condition_names: vec_to_list(condition_names),
+            prefer_condition_order,
+            ..Default::default()
+        })
+    };
+    let cjs = mode(ResolutionMode::Cjs, vec![], false);
+    should_equal(&cjs, &case_path, "pkg", pkg("sync.js"));
+    let esm = mode(ResolutionMode::Esm, vec![], false);
+    should_equal(&esm, &case_path, "pkg", pkg("import.mjs"));
+    let esm = mode(ResolutionMode::Esm, vec!["module-sync"], true);
+    should_equal(&esm, &case_path, "pkg", pkg("sync.js"));
+    // `"require"` takes the priority of the replaced `"import"`.
+    let cjs = mode(ResolutionMode::Cjs, vec!["import", "worker"], true);
+    should_equal(&cjs, &case_path, "pkg", pkg("require.js"));
+}
+
+#[test]
+fn build_env_test() {
+    let case_path = p(vec!["build-env"]);
+    let pkg = |file: &str| p(vec!["build-env", "node_modules", "pkg", file]);
+    let resolver = |env: Option<BuildEnv>, condition_names: Vec<&str>| {
+        Resolver::new(Options {
+            env,
+            condition_names: vec_to_list(condition_names),
+            ..Default::default()
+        })
+    };
+    should_equal(&resolver(None, vec![]), &case_path, "pkg", pkg("index.js"));
+    should_equal(
+        &resolver(Some(BuildEnv::Development), vec![]),
+        &case_path,
+        "pkg",
+        pkg("dev.js"),
+    );
+    // the other condition is removed.
+    let production = resolver(Some(BuildEnv::Production), vec!["development"]);
+    should_equal(&production, &case_path, "pkg", pkg("prod.js"));
+    assert_eq!(
+        production.options().condition_names,
+        vec_to_list(vec!["production"])
+    );
+    let esm = Resolver::new(Options {
+        mode: ResolutionMode::Esm,
+        env: Some(BuildEnv::Production),
+        ..Default::default()
+    });
+    should_equal(&esm, &case_path, "pkg", pkg("prod.js"));
+}
+
+#[test]
+fn package_root_test() {
+    let resolver = Resolver::new(Default::default());
+    let resource = |path: PathBuf, request: &str| match resolver.resolve(&path, request) {
+        Ok(ResolveResult::File(resource)) => resource,
+        result => panic!("{result:?}"),
+    };
+    let pkg = resource(p(vec!["build-env"]), "pkg");
+    assert!(pkg.is_external);
+    assert_eq!(
+        pkg.package_root,
+        Some(p(vec!["build-env", "node_modules", "pkg"]))
+    );
+    let scoped = resource(p(vec!["prefix"]), "@scope/pkg");
+    assert_eq!(
+        scoped.package_root,
+        Some(p(vec!["prefix", "node_modules", "@scope", "pkg"]))
+    );
+    let local = resource(p(vec!["rewrite"]), "./src/modern");
+    assert!(!local.is_external);
+    assert_eq!(local.package_root, None);
+}
+
+#[test]
+fn exports_sugar_test() {
+    let case_path = p(vec!["exports-sugar"]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec![String::from("require")],
+        ..Default::default()
+    });
+    let module = |name: &str, file: &str| p(vec!["exports-sugar", "node_modules", name, file]);
+    for name in ["string", "conditions", "dot", "array"] {
+        should_equal(&resolver, &case_path, name, module(name, "index.js"));
+        should_not_exported(
+            &resolver,
+            &case_path,
+            &format!("{name}/other.js"),
+            format!("Package path {name}/other.js is not exported"),
+        );
+    }
+    // `null` is the same as the absent `"exports"`.
+    should_equal(&resolver, &case_path, "null", module("null", "main.js"));
+    should_equal(
+        &resolver,
+        &case_path,
+        "null/other.js",
+        module("null", "other.js"),
+    );
+}
+
+#[test]
+fn resolve_exports_target_test() {
+    let pkg_dir = p(vec!["condition-order", "node_modules", "pkg"]);
+    let resolver = Resolver::new(Default::default());
+    let targets = |subpath: &str, conditions: Vec<&str>| {
+        resolver
+            .resolve_exports_target(&pkg_dir, subpath, &vec_to_list(conditions))
+            .unwrap()
+    };
+    let mapped = |target: &str, conditions: Vec<&str>| MappedTarget {
+        target: String::from(target),
+        conditions: vec_to_list(conditions),
+    };
+    assert_eq!(
+        targets(".", vec!["worker", "require"]),
+        vec![mapped("./worker.js", vec!["worker"])]
+    );
+    assert_eq!(
+        targets(".", vec![]),
+        vec![mapped("./index.js", vec!["default"])]
+    );
+    assert_eq!(
+        targets("./nested", vec!["worker", "node"]),
+        vec![mapped("./worker.js", vec!["node", "worker"])]
+    );
+    assert_eq!(targets("./missing", vec!["node"]), vec![]);
+    assert!(resolver
+        .resolve_exports_target(&pkg_dir, "missing", &[])
+        .is_err());
+    assert_eq!(
+        resolver
+            .resolve_exports_target(&p(vec!["condition-order"]), ".", &[])
+            .unwrap(),
+        vec![]
+    );
+}
+
+#[test]
+fn resolve_imports_target_test() {
+    let pkg_dir = p(vec!["condition-order", "node_modules", "pkg"]);
+    let resolver = Resolver::new(Default::default());
+    let targets = |specifier: &str, conditions: Vec<&str>| {
+        resolver
+            .resolve_imports_target(&pkg_dir, specifier, &vec_to_list(conditions))
+            .unwrap()
+    };
+    let mapped = |target: &str, conditions: Vec<&str>| MappedTarget {
+        target: String::from(target),
+        conditions: vec_to_list(conditions),
+    };
+    assert_eq!(
+        targets("#internal/a/b", vec!["require", "node"]),
+        vec![mapped("./lib/a/b.js", vec!["node", "require"])]
+    );
+    assert_eq!(
+        targets("#internal/a", vec!["node"]),
+        vec![mapped("./browser/a.js", vec!["default"])]
+    );
+    assert_eq!(
+        targets("#dep", vec!["worker"]),
+        vec![mapped("dep", vec![]), mapped("./worker.js", vec!["worker"])]
+    );
+    assert_eq!(targets("#missing", vec![]), vec![]);
+    assert!(resolver
+        .resolve_imports_target(&pkg_dir, "internal", &[])
+        .is_err());
+}
+
+#[test]
+fn ordering_test() {
+    let case_path = p(vec!["alias"]);
+    let alias = |keys: [(&str, &str); 2]| {
+        keys.into_iter()
+            .map(|(from, to)| (String::from(from), vec![AliasMap::Target(String::from(to))]))
+            .collect::<Vec<_>>()
+    };
+    // the former declared key wins.
+    let resolver = Resolver::new(Options {
+        alias: alias([("x", "./b"), ("x/dir", "./c/dir")]),
+        extensions: vec![String::from("")],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "x/dir",
+        p(vec!["alias", "b", "dir", "index"]),
+    );
+    let resolver = Resolver::new(Options {
+        alias: alias([("x/dir", "./c/dir"), ("x", "./b")]),
+        extensions: vec![String::from("")],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "x/dir",
+        p(vec!["alias", "c", "dir", "index"]),
+    );
+
+    // same results across runs and threads.
+    let requests = [
+        (p(vec!["alias"]), "x/dir"),
+        (p(vec!["exports-field"]), "exports-field"),
+        (p(vec!["exports-field"]), "exports-field/dist/main.js"),
+        (p(vec!["browser-module"]), "./lib/replaced"),
+        (p(vec!["full", "a"]), "package2"),
+        (p(vec!["extensions"]), "./a"),
+    ];
+    let outcomes = |resolver: &Resolver| {
+        requests
+            .iter()
+            .map(|(path, request)| match resolver.resolve(path, request) {
+                Ok(ResolveResult::File(resource)) => format!("{:?}", resource.join()),
+                Ok(ResolveResult::Ignored { .. }) => String::from("ignored"),
+                Ok(result) => format!("{result:?}"),
+                Err(error) => format!("{error:?}"),
+            })
+            .collect::<Vec<_>>()
+    };
+    let options = Options {
+        alias: alias([("x/dir", "./c/dir"), ("x", "./b")]),
+        condition_names: vec_to_list(vec!["webpack", "browser", "import", "require"]),
+        browser_field: true,
+        ..Default::default()
+    };
+    let expected = outcomes(&Resolver::new(options.clone()));
+    let shared = Resolver::new(options.clone());
+    std::thread::scope(|scope| {
+        let handles = (0..8)
+            .map(|index| {
+                let (shared, options, outcomes) = (&shared, &options, &outcomes);
+                scope.spawn(move || {
+                    if index % 2 == 0 {
+                        outcomes(shared)
+                    } else {
+                        outcomes(&Resolver::new(options.clone()))
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+    });
+}
+
+#[test]
+fn main_files_test() {
+    let case_path = p(vec!["main-files"]);
+    let resolver = |main_files: Vec<&str>, extensions: Vec<&str>, enforce| {
+        Resolver::new(Options {
+            main_files: main_files.into_iter().map(String::from).collect(),
+            extensions: extensions.into_iter().map(String::from).collect(),
+            enforce_extension: enforce,
+            ..Default::default()
+        })
+    };
+    // the main files are tried in order, then the extensions for each one.
+    let r = resolver(vec!["index", "main"], vec![".js"], EnforceExtension::Auto);
+    should_equal(&r, &case_path, "./a", p(vec!["main-files", "a", "main.js"]));
+    let r = resolver(
+        vec!["index", "main"],
+        vec![".ts", ".js"],
+        EnforceExtension::Auto,
+    );
+    should_equal(
+        &r,
+        &case_path,
+        "./a",
+        p(vec!["main-files", "a", "index.ts"]),
+    );
+    let r = resolver(
+        vec!["main", "index"],
+        vec![".ts", ".js"],
+        EnforceExtension::Auto,
+    );
+    should_equal(&r, &case_path, "./a", p(vec!["main-files", "a", "main.js"]));
+    let r = resolver(vec!["main"], vec![".ts", ".js"], EnforceExtension::Auto);
+    should_equal(&r, &case_path, "./c", p(vec!["main-files", "c", "main.ts"]));
+    let r = resolver(vec!["main"], vec![".js", ".ts"], EnforceExtension::Auto);
+    should_equal(&r, &case_path, "./c", p(vec!["main-files", "c", "main.js"]));
+
+    // the main file with dot and `enforce_extension`.
+    let r = resolver(
+        vec!["index.server", "index"],
+        vec![".js"],
+        EnforceExtension::Disabled,
+    );
+    should_equal(
+        &r,
+        &case_path,
+        "./b",
+        p(vec!["main-files", "b", "index.server"]),
+    );
+    let r = resolver(
+        vec!["index.server", "index"],
+        vec![".js"],
+        EnforceExtension::Enabled,
+    );
+    should_equal(
+        &r,
+        &case_path,
+        "./b",
+        p(vec!["main-files", "b", "index.server.js"]),
+    );
+    let r = resolver(vec!["main"], vec![".js"], EnforceExtension::Enabled);
+    should_equal(&r, &case_path, "./a", p(vec!["main-files", "a", "main.js"]));
+    let r = resolver(vec!["main.js"], vec![".js"], EnforceExtension::Enabled);
+    should_failed(&r, &case_path, "./a");
+    should_equal(
+        &r,
+        &case_path,
+        "./a/main",
+        p(vec!["main-files", "a", "main.js"]),
+    );
+
+    // no main file.
+    let r = resolver(
+        vec!["index", "main"],
+        vec![".js", ".ts"],
+        EnforceExtension::Auto,
+    );
+    should_failed(&r, &case_path, "./d");
+    let r = resolver(vec![], vec![".js"], EnforceExtension::Auto);
+    should_failed(&r, &case_path, "./a");
+}
+
+#[test]
+fn exports_priority_test() {
+    let case_path = p(vec!["exports-priority"]);
+    let pkg = |name: &str, file: &str| p(vec!["exports-priority", "node_modules", name, file]);
+    let resolver = |exports_priority| {
+        Resolver::new(Options {
+            exports_priority,
+            ..Default::default()
+        })
+    };
+
+    let r = resolver(ExportsPriority::Encapsulate);
+    should_equal(&r, &case_path, "pkg", pkg("pkg", "exported.js"));
+    should_not_exported(
+        &r,
+        &case_path,
+        "pkg/lib/internal.js",
+        String::from("Package path pkg/lib/internal.js is not exported"),
+    );
+    should_failed(&r, &case_path, "pkg/missing");
+    should_failed(&r, &case_path, "broken");
+
+    let r = resolver(ExportsPriority::Fallback);
+    should_equal(&r, &case_path, "pkg", pkg("pkg", "exported.js"));
+    should_equal(
+        &r,
+        &case_path,
+        "pkg/lib/internal.js",
+        p(vec![
+            "exports-priority",
+            "node_modules",
+            "pkg",
+            "lib",
+            "internal.js",
+        ]),
+    );
+    should_failed(&r, &case_path, "pkg/missing");
+    should_equal(&r, &case_path, "broken", pkg("broken", "main.js"));
+
+    let r = resolver(ExportsPriority::Ignore);
+    should_equal(&r, &case_path, "pkg", pkg("pkg", "main.js"));
+    should_equal(&r, &case_path, "pkg/exported", pkg("pkg", "exported.js"));
+    should_equal(&r, &case_path, "broken", pkg("broken", "main.js"));
+}
+
+#[test]
+fn invalid_specifier_test() {
+    let case_path = p(vec!["invalid-specifier"]);
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg",
+        p(vec![
+            "invalid-specifier",
+            "node_modules",
+            "pkg",
+            "lib",
+            "a.js",
+        ]),
+    );
+    should_invalid_specifier(&resolver, &case_path, "pkg/dep", "node_modules");
+    should_invalid_specifier(&resolver, &case_path, "pkg/lib/../package.json", "..");
+    should_invalid_specifier(
+        &resolver,
+        &case_path,
+        "pkg/lib/%2e%2e/package.json",
+        "%2e%2e",
+    );
+    should_invalid_specifier(&resolver, &case_path, "pkg/encoded", "lib%2Fa.js");
+    should_equal(
+        &resolver,
+        &case_path,
+        "#lib/b.js",
+        p(vec!["invalid-specifier", "lib", "b.js"]),
+    );
+    should_invalid_specifier(&resolver, &case_path, "#dep", "node_modules");
+    should_invalid_specifier(&resolver, &case_path, "#lib/../package.json", "..");
+    match resolver.resolve(&case_path, "pkg/dep") {
+        Err(Error::InvalidSpecifier(error)) => {
+            assert_eq!(error.request, "pkg/dep");
+            assert_eq!(error.target, "./node_modules/dep/index.js");
+            assert_eq!(
+                error.path.as_ref(),
+                p(vec![
+                    "invalid-specifier",
+                    "node_modules",
+                    "pkg",
+                    "package.json"
+                ])
+            );
+        }
+        result => unreachable!("{result:?}"),
+    }
+}
+
+#[test]
+fn pnpm_store_test() {
+    let case_path = p(vec!["pnpm-store"]);
+    let store = |id: &str, name: &str| {
+        let mut path = p(vec![
+            "pnpm-store",
+            "node_modules",
+            ".pnpm",
+            id,
+            "node_modules",
+        ]);
+        path.extend(name.split('/'));
+        path.push("index.js");
+        path
+    };
+    let a = store("a@1.0.0", "a");
+    let c = store("@s+c@1.0.0_a@1.0.0", "@s/c");
+    let cases = [
+        (case_path.clone(), "a", a.clone()),
+        (case_path.clone(), "b", store("b@1.0.0", "b")),
+        (case_path.clone(), "@s/c", c.clone()),
+        (case_path, "w", p(vec!["pnpm-store", "w", "index.js"])),
+        // the dependencies of the packages in the store.
+        (
+            a.parent().unwrap().to_path_buf(),
+            "b",
+            store("b@2.0.0", "b"),
+        ),
+        (c.parent().unwrap().to_path_buf(), "a", a.clone()),
+        (a.parent().unwrap().to_path_buf(), "./index.js", a),
+    ];
+    for pnpm_store in [true, false] {
+        let resolver = Resolver::new(Options {
+            pnpm_store,
+            ..Default::default()
+        });
+        for (path, request, expected) in &cases {
+            should_equal(&resolver, path, request, expected.clone());
+        }
+    }
+}
+
+#[test]
+fn import_map_test() {
+    let case_path = p(vec!["import-map"]);
+    let src = p(vec!["import-map", "src"]);
+    let resolver = Resolver::new(Options {
+        import_map: Some(p(vec!["import-map", "importmap.json"])),
+        ..Default::default()
+    });
+    should_equal(&resolver, &src, "a", p(vec!["import-map", "a.js"]));
+    should_equal(
+        &resolver,
+        &src,
+        "a/x?q",
+        p(vec!["import-map", "lib", "a", "x.js?q"]),
+    );
+    // it is applied before the `node_modules` lookup.
+    should_equal(
+        &resolver,
+        &src,
+        "pkg",
+        p(vec!["import-map", "vendor", "pkg.js"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "./old.js",
+        p(vec!["import-map", "src", "new.js"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "./app",
+        p(vec!["import-map", "src", "app.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path.join("legacy"),
+        "a",
+        p(vec!["import-map", "legacy", "a.js"]),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &src,
+        "blocked",
+        String::from("'blocked' is blocked by 'blocked' in the import map"),
+    );
+    should_unexpected_value_error(
+        &resolver,
+        &src,
+        "remote",
+        String::from("'remote' is blocked by 'remote' in the import map"),
+    );
+    should_failed(&resolver, &src, "a/missing");
+}
+
+#[test]
+fn builtin_modules_test() {
+    let case_path = p(vec!["builtin"]);
+    let resolver = Resolver::new(Options {
+        builtin_modules: true,
+        alias: vec![(
+            String::from("path"),
+            vec![AliasMap::Target(String::from("path-browserify"))],
+        )],
+        ..Default::default()
+    });
+    should_builtin(&resolver, &case_path, "fs", "node:fs");
+    should_builtin(&resolver, &case_path, "node:fs", "node:fs");
+    should_builtin(&resolver, &case_path, "fs/promises", "node:fs/promises");
+    should_builtin(&resolver, &case_path, "node:test", "node:test");
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "node:missing",
+        String::from("No such built-in module: node:missing"),
+    );
+    // only with `node:` scheme.
+    should_failed(&resolver, &case_path, "test");
+    should_failed(&resolver, &case_path, "fs/missing");
+    should_equal(
+        &resolver,
+        &case_path,
+        "path",
+        p(vec![
+            "builtin",
+            "node_modules",
+            "path-browserify",
+            "index.js",
+        ]),
+    );
+
+    let resolver = Resolver::new(Default::default());
+    should_equal(
+        &resolver,
+        &case_path,
+        "fs",
+        p(vec!["builtin", "node_modules", "fs", "index.js"]),
+    );
+}
+
+#[test]
+fn rewrites_test() {
+    let case_path = p(vec!["rewrite"]);
+    let rule = |regex: &str, template: &str| (Regex::new(regex).unwrap(), String::from(template));
+    let resolver = Resolver::new(Options {
+        rewrites: vec![
+            rule("^lodash/(.*)$", "lodash-es/$1"),
+            rule("^@legacy/(?P<name>.*)$", "@/${name}"),
+            rule("^x/(.*)$", "./missing/$1"),
+            rule("^x/(.*)$", "./src/$1"),
+            rule("^same$", "same"),
+        ],
+        alias: vec![(
+            String::from("@"),
+            vec![AliasMap::Target(String::from("./src"))],
+        )],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "lodash/map",
+        p(vec!["rewrite", "node_modules", "lodash-es", "map.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "lodash/map?query#fragment",
+        p(vec![
+            "rewrite",
+            "node_modules",
+            "lodash-es",
+            "map.js?query#fragment",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "lodash",
+        p(vec!["rewrite", "node_modules", "lodash", "index.js"]),
+    );
+    // the rewritten request is aliased.
+    should_equal(
+        &resolver,
+        &case_path,
+        "@legacy/modern",
+        p(vec!["rewrite", "src", "modern.js"]),
+    );
+    // the latter rule is tried if the former one can't be resolved.
+    should_equal(
+        &resolver,
+        &case_path,
+        "x/modern",
+        p(vec!["rewrite", "src", "modern.js"]),
+    );
+    should_failed(&resolver, &case_path, "x/inexist");
+    should_failed(&resolver, &case_path, "same");
+}
+
+#[test]
+fn extension_hints_test() {
+    let case_path = p(vec!["extension-hints"]);
+    let file = |path: Vec<&str>| {
+        let mut path = path;
+        path.insert(0, "extension-hints");
+        p(path)
+    };
+    let resolver = Resolver::new(Default::default());
+    let hinted = |request: &str, extensions: Vec<&str>| match resolver.resolve_with_extensions(
+        &case_path,
+        request,
+        &vec_to_list(extensions),
+    ) {
+        Ok(ResolveResult::File(resource)) => Some(resource.path),
+        _ => None,
+    };
+    assert_eq!(
+        hinted("./style", vec![".css", ".scss"]),
+        Some(file(vec!["style.css"]))
+    );
+    assert_eq!(
+        hinted("./style", vec![".scss"]),
+        Some(file(vec!["style.scss"]))
+    );
+    assert_eq!(
+        hinted("./dir", vec![".css"]),
+        Some(file(vec!["dir", "index.css"]))
+    );
+    assert_eq!(hinted("./style", vec![]), None);
+    assert_eq!(hinted("./style.css", vec![]), Some(file(vec!["style.css"])));
+    // the results are memoized with the extensions.
+    should_equal(&resolver, &case_path, "./style", file(vec!["style.js"]));
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![],
+        binary_extensions: vec![],
+        enforce_extension: EnforceExtension::Enabled,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./style.css",
+        file(vec!["style.css"]),
+    );
+    should_failed(&resolver, &case_path, "./style");
+    should_failed(&resolver, &case_path, "./dir");
+}
+
+#[test]
+fn path_preference_test() {
+    let case_path = p(vec!["path-preference"]);
+    let file = |path: Vec<&str>| {
+        let mut path = path;
+        path.insert(0, "path-preference");
+        p(path)
+    };
+    let resolver = Resolver::new(Default::default());
+    should_equal(&resolver, &case_path, "./foo", file(vec!["foo.js"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg",
+        file(vec!["node_modules", "pkg.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        path_preference: PathPreference::Directory,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./foo",
+        file(vec!["foo", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "pkg",
+        file(vec!["node_modules", "pkg", "index.js"]),
+    );
+    // the file is tried if the directory can't be resolved.
+    should_equal(&resolver, &case_path, "./bar", file(vec!["bar.js"]));
+    should_equal(&resolver, &case_path, "./baz", file(vec!["baz.js"]));
+    should_failed(&resolver, &case_path, "./inexist");
+}
+
+#[test]
+fn pipeline_test() {
+    let case_path = p(vec!["pipeline"]);
+    let options = Options {
+        alias: vec![(
+            String::from("#a"),
+            vec![AliasMap::Target(String::from("./alias.js"))],
+        )],
+        browser_field: true,
+        ..Default::default()
+    };
+    let resolver = Resolver::new(options.clone());
+    assert_eq!(resolver.options().pipeline, PipelineStage::DEFAULT);
+    should_equal(&resolver, &case_path, "#a", p(vec!["pipeline", "alias.js"]));
+    let resolver = Resolver::new(Options {
+        pipeline: vec![PipelineStage::ImportsField, PipelineStage::Alias],
+        ..options.clone()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "#a",
+        p(vec!["pipeline", "imports.js"]),
+    );
+    let resolver = Resolver::new(Options {
+        pipeline: vec![PipelineStage::ImportsField],
+        alias: vec![],
+        ..options.clone()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "#a",
+        p(vec!["pipeline", "imports.js"]),
+    );
+    let resolver = Resolver::new(Options {
+        pipeline: vec![],
+        ..options.clone()
+    });
+    should_failed(&resolver, &case_path, "#a");
+
+    // the `"browser"` field of the issuer is disabled.
+    let browser_module = p(vec!["browser-module"]);
+    let resolver = Resolver::new(Options {
+        pipeline: PipelineStage::DEFAULT
+            .into_iter()
+            .filter(|stage| *stage != PipelineStage::BrowserField)
+            .collect(),
+        ..options
+    });
+    should_equal(
+        &resolver,
+        &browser_module,
+        "./lib/replaced",
+        p(vec!["browser-module", "lib", "replaced.js"]),
+    );
+}
+
+#[test]
+fn alias_from_json_test() {
+    let alias_cases_path = p(vec!["alias"]);
+    let alias = AliasMap::from_json(&serde_json::json!({
+        "fs": false,
+        "b$": "./a",
+        "multi": ["./not-exist", false],
+        "c": ["./not-exist", "./a"],
+    }))
+    .unwrap();
+    assert_eq!(
+        alias,
+        vec![
+            ("fs".to_string(), vec![AliasMap::Ignored]),
+            ("b$".to_string(), vec![AliasMap::Target("./a".to_string())]),
+            (
+                "multi".to_string(),
+                vec![
+                    AliasMap::Target("./not-exist".to_string()),
+                    AliasMap::Ignored
+                ]
+            ),
+            (
+                "c".to_string(),
+                vec![
+                    AliasMap::Target("./not-exist".to_string()),
+                    AliasMap::Target("./a".to_string())
+                ]
+            ),
+        ]
+    );
+    let resolver = Resolver::new(Options {
+        alias,
+        ..Default::default()
+    });
+    should_ignored(&resolver, &alias_cases_path, "fs");
+    should_ignored(&resolver, &alias_cases_path, "fs/promises");
+    should_ignored(&resolver, &alias_cases_path, "multi");
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "b",
+        p(vec!["alias", "a", "index"]),
+    );
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "c",
+        p(vec!["alias", "a", "index"]),
+    );
+
+    assert!(AliasMap::from_json(&serde_json::json!({ "fs": true })).is_err());
+    assert!(AliasMap::from_json(&serde_json::json!({ "fs": [1] })).is_err());
+    assert!(AliasMap::from_json(&serde_json::json!(["fs"])).is_err());
+}
+
+#[test]
+fn fallback_test() {
+    let alias_cases_path = p(vec!["alias"]);
+    let resolver = Resolver::new(Options {
+        fallback: vec![
+            (
+                String::from("aliasA"),
+                vec![AliasMap::Target(String::from("./a"))],
+            ),
+            // -- exists
+            (
+                String::from("./e"),
+                vec![AliasMap::Target(String::from("./d"))],
+            ),
+            (
+                String::from("./d"),
+                vec![AliasMap::Target(String::from("./e"))],
+            ),
+            // --
+            // in-exists
+            (
+                String::from("./ee"),
+                vec![AliasMap::Target(String::from("./dd"))],
+            ),
+            (
+                String::from("./dd"),
+                vec![AliasMap::Target(String::from("./ee"))],
+            ),
+            (
+                String::from("./ff"),
+                vec![AliasMap::Target(String::from("./ccc"))],
+            ),
+        ],
+        ..Default::default()
+    });
+    // maybe better is `should_overflow(&resolver, &alias_cases_path, "./ee");`
+    should_failed(&resolver, &alias_cases_path, "./ee");
+    should_failed(&resolver, &alias_cases_path, "./ff");
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "./d",
+        p(vec!["alias", "d", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "./e",
+        p(vec!["alias", "e", "index"]),
+    );
+    should_equal(
+        &resolver,
+        &alias_cases_path,
+        "aliasA",
+        p(vec!["alias", "a", "index"]),
+    );
+}
+
+#[cfg(feature = "concurrent-alt")]
+#[test]
+fn cache_backend_test() {
+    use nodejs_resolver::CacheBackend;
+    let case_path = p(vec!["full", "a"]);
+    let cache = Arc::new(Cache::default().with_backend(CacheBackend::ShardedRwLock));
+    let resolver = Resolver::new(Options {
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "package2",
+        p(vec!["full", "a", "node_modules", "package2", "a.js"]),
+    );
+    assert!(!cache.is_empty());
+    resolver.clear_entries();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn case_sensitivity_test() {
+    let case_path = p(vec!["case-sensitivity"]);
+    let resolver = |case_sensitivity| {
+        let cache = Cache::default().with_case_sensitivity(case_sensitivity);
+        assert_eq!(cache.fs.case_sensitivity(), case_sensitivity);
+        Resolver::new(Options {
+            external_cache: Some(Arc::new(cache)),
+            ..Default::default()
+        })
+    };
+
+    let sensitive = resolver(FileSystemCaseSensitivity::Sensitive);
+    should_equal(&sensitive, &case_path, "./Foo", case_path.join("Foo.js"));
+    should_equal(
+        &sensitive,
+        &case_path,
+        "./Dir",
+        case_path.join("Dir/index.js"),
+    );
+    should_failed(&sensitive, &case_path, "./foo");
+    should_failed(&sensitive, &case_path, "./dir/index.js");
+    should_failed(&sensitive, &case_path, "Pkg");
+
+    // the requested casing is kept, same as macOS.
+    let insensitive = resolver(FileSystemCaseSensitivity::Insensitive);
+    should_equal(&insensitive, &case_path, "./foo", case_path.join("foo.js"));
+    should_equal(
+        &insensitive,
+        &case_path,
+        "./dir",
+        case_path.join("dir/index.js"),
+    );
+    should_equal(
+        &insensitive,
+        &case_path,
+        "pkg",
+        case_path.join("node_modules/pkg/Main.js"),
+    );
+    should_failed(&insensitive, &case_path, "./bar");
+}
+
+#[test]
+fn resolver_factory_test() {
+    let case_path = p(vec!["extensions"]);
+    let factory = ResolverFactory::default();
+    let options = || Options {
+        extensions: vec![String::from(".ts"), String::from(".js")],
+        ..Default::default()
+    };
+    let resolver = factory.get(options());
+    assert!(Arc::ptr_eq(&resolver.cache(), &factory.cache()));
+    assert!(Arc::ptr_eq(&resolver, &factory.get(options())));
+    // the cache of the options is replaced.
+    let resolver = factory.get(Options {
+        external_cache: Some(Arc::new(Cache::default())),
+        ..options()
+    });
+    assert!(Arc::ptr_eq(&resolver.cache(), &factory.cache()));
+    assert_eq!(factory.len(), 1);
+    should_equal(&resolver, &case_path, "./a", case_path.join("a.ts"));
+
+    let js = factory.get(Options::default());
+    assert!(!Arc::ptr_eq(&resolver, &js));
+    assert!(Arc::ptr_eq(&js.cache(), &factory.cache()));
+    should_equal(&js, &case_path, "./a", case_path.join("a.js"));
+    assert_eq!(factory.len(), 2);
+
+    factory.clear();
+    assert!(factory.is_empty());
+    assert!(!factory.cache().is_empty());
+    assert!(!Arc::ptr_eq(&js, &factory.get(Options::default())));
+}
+
+#[test]
+fn fingerprint_test() {
+    let fingerprint = Options::default().fingerprint();
+    assert_eq!(fingerprint, Options::default().fingerprint());
+    assert_eq!(fingerprint, Resolver::new(Options::default()).fingerprint());
+    // they don't affect the results.
+    assert_eq!(
+        fingerprint,
+        Options {
+            debug: true,
+            external_cache: Some(Arc::new(Cache::default())),
+            ..Default::default()
+        }
+        .fingerprint()
+    );
+    let changed = [
+        Options {
+            extensions: vec![String::from(".ts")],
+            ..Default::default()
+        },
+        Options {
+            main_fields: vec![],
+            ..Default::default()
+        },
+        Options {
+            max_depth: 1,
+            ..Default::default()
+        },
+        Options {
+            rewrites: vec![(Regex::new("^a$").unwrap(), String::from("b"))],
+            ..Default::default()
+        },
+        Options {
+            before_resolved: Some(Arc::new(SourcePolicy)),
+            ..Default::default()
+        },
+    ];
+    for options in &changed {
+        assert_ne!(options.fingerprint(), fingerprint);
+    }
+
+    // the rewrites are hashed by the source of regex, rather than the state
+    // of it, which changes after matching on the threads.
+    let options = Options {
+        rewrites: vec![(Regex::new("^a(.*)$").unwrap(), String::from("b$1"))],
+        ..Default::default()
+    };
+    let fingerprint = options.fingerprint();
+    let factory = ResolverFactory::default();
+    let resolver = factory.get(options.clone());
+    std::thread::scope(|scope| {
+        for _ in 0..2 {
+            scope.spawn(|| assert!(options.rewrites[0].0.is_match("ab")));
+        }
+    });
+    assert_eq!(options.fingerprint(), fingerprint);
+    assert_eq!(resolver.fingerprint(), fingerprint);
+    // the resolver of the cloned options is reused.
+    assert!(Arc::ptr_eq(&resolver, &factory.get(options)));
+
+    // the hooks are the same in the fingerprint, but not the resolvers.
+    let factory = ResolverFactory::default();
+    let hooked = || Options {
+        before_resolved: Some(Arc::new(SourcePolicy)),
+        ..Default::default()
+    };
+    assert_eq!(hooked().fingerprint(), hooked().fingerprint());
+    assert!(!Arc::ptr_eq(&factory.get(hooked()), &factory.get(hooked())));
+    let options = hooked();
+    assert!(Arc::ptr_eq(
+        &factory.get(options.clone()),
+        &factory.get(options)
+    ));
+}
+
+#[test]
+fn expand_env_test() {
+    let case_path = p(vec!["simple"]);
+    std::env::set_var("NODEJS_RESOLVER_FIXTURES", p(vec![]));
+    let options = || Options {
+        alias: vec![(
+            String::from("@lib"),
+            vec![AliasMap::Target(String::from(
+                "${NODEJS_RESOLVER_FIXTURES}/simple/lib",
+            ))],
+        )],
+        tsconfig: Some(PathBuf::from("${NODEJS_RESOLVER_FIXTURES}/tsconfig.json")),
+        roots: vec![RootProfile {
+            root: PathBuf::from("${NODEJS_RESOLVER_FIXTURES}/simple"),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let expanded = options().expand_env().unwrap();
+    assert_eq!(expanded.tsconfig, Some(p(vec!["tsconfig.json"])));
+    assert_eq!(expanded.roots[0].root, case_path);
+    assert_ne!(expanded.fingerprint(), options().fingerprint());
+    let resolver = Resolver::new(Options {
+        tsconfig: None,
+        ..expanded
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "@lib/index",
+        case_path.join("lib/index.js"),
+    );
+
+    assert!(matches!(
+        Options {
+            alias: vec![(
+                String::from("@lib"),
+                vec![AliasMap::Target(String::from(
+                    "${NODEJS_RESOLVER_UNDEFINED}"
+                ))],
+            )],
+            ..Default::default()
+        }
+        .expand_env(),
+        Err(Error::UnexpectedValue(_))
+    ));
+}
+
+#[test]
+fn trace_test() {
+    let case_path = p(vec!["simple"]);
+    let resolver = Resolver::new(Options {
+        alias: vec![(String::from("fs"), vec![AliasMap::Ignored])],
+        ..Default::default()
+    });
+    let trace = resolver.trace(&case_path, "./lib/index?a");
+    match &trace.outcome {
+        TraceOutcome::File { path, query, .. } => {
+            assert_eq!(path, &case_path.join("lib/index.js"));
+            assert_eq!(query.as_deref(), Some("?a"));
+        }
+        outcome => panic!("{outcome:?}"),
+    }
+    assert_eq!(
+        trace
+            .steps
+            .iter()
+            .map(|step| step.phase)
+            .collect::<Vec<_>>(),
+        vec![AttemptPhase::File, AttemptPhase::Extensions]
+    );
+    let json = serde_json::to_value(&trace).unwrap();
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["request"], "./lib/index?a");
+    assert_eq!(json["outcome"]["kind"], "file");
+    assert_eq!(json["steps"][1]["phase"], "extensions");
+    assert!(json["duration_us"].is_u64());
+
+    let json = serde_json::to_value(resolver.trace(&case_path, "./lib/inde")).unwrap();
+    assert_eq!(json["outcome"]["kind"], "error");
+    assert_eq!(json["suggestions"][0], "./lib/index.js");
+    assert_eq!(json["steps"][0]["phase"], "file");
+
+    let json = serde_json::to_value(resolver.trace(&case_path, "fs")).unwrap();
+    assert_eq!(json["outcome"]["kind"], "ignored");
+    assert_eq!(json["outcome"]["reason"]["alias"], "fs");
+}
+
+#[test]
+fn completion_test() {
+    let issuer = p(vec!["completion", "src", "index.js"]);
+    let resolver = Resolver::new(Options::default());
+    let complete = |specifier: &str| {
+        resolver
+            .completion(&issuer, specifier)
+            .into_iter()
+            .map(|completion| completion.specifier)
+            .collect::<Vec<_>>()
+    };
+    // the same case first, then the shorter ones.
+    let completions = resolver.completion(&issuer, "./comp");
+    assert_eq!(
+        completions,
+        vec![
+            Completion {
+                specifier: String::from("./comp"),
+                kind: CompletionKind::File,
+            },
+            Completion {
+                specifier: String::from("./components"),
+                kind: CompletionKind::Directory,
+            },
+            Completion {
+                specifier: String::from("./Component"),
+                kind: CompletionKind::File,
+            },
+        ]
+    );
+    assert_eq!(
+        complete("./"),
+        vec![
+            "./comp",
+            "./index",
+            "./Component",
+            "./style.css",
+            "./components",
+            "./node_modules"
+        ]
+    );
+    assert_eq!(complete("./."), vec!["./.hidden"]);
+    assert_eq!(
+        complete("../node_modules/lodash/fp/"),
+        vec![
+            "../node_modules/lodash/fp/map",
+            "../node_modules/lodash/fp/filter"
+        ]
+    );
+    assert!(complete("./inexist/").is_empty());
+
+    // the nearer packages first.
+    assert_eq!(complete("lod"), vec!["lodash", "lodash-es"]);
+    assert_eq!(complete("l"), vec!["local-pkg", "lodash", "lodash-es"]);
+    assert_eq!(complete("@"), vec!["@scope"]);
+    assert_eq!(complete("@scope/"), vec!["@scope/pkg", "@scope/other"]);
+    assert_eq!(
+        resolver.completion(&issuer, "@scope/p")[0].kind,
+        CompletionKind::Package
+    );
+
+    // the subpaths of `exports`, or the files if it is absent.
+    assert_eq!(
+        complete("@scope/pkg/"),
+        vec!["@scope/pkg/utils", "@scope/pkg/feature/"]
+    );
+    assert_eq!(
+        resolver.completion(&issuer, "@scope/pkg/u")[0].kind,
+        CompletionKind::Subpath
+    );
+    assert_eq!(complete("lodash/fp/m"), vec!["lodash/fp/map"]);
+    assert!(complete("inexist/a").is_empty());
+}
+
+#[test]
+fn specifier_for_test() {
+    let from_dir = p(vec!["specifier-for", "src"]);
+    let resolver = Resolver::new(Options::default());
+    let specifier_for = |target: Vec<&str>| {
+        resolver.specifier_for(&from_dir, &p([vec!["specifier-for"], target].concat()))
+    };
+    assert_eq!(
+        specifier_for(vec!["src", "a.js"]),
+        Some(String::from("./a"))
+    );
+    assert_eq!(
+        specifier_for(vec!["src", "utils", "index.js"]),
+        Some(String::from("./utils"))
+    );
+    assert_eq!(
+        specifier_for(vec!["lib", "shared.js"]),
+        Some(String::from("../lib/shared"))
+    );
+    // by the subpaths of `"exports"`.
+    assert_eq!(
+        specifier_for(vec!["node_modules", "pkg", "dist", "index.js"]),
+        Some(String::from("pkg"))
+    );
+    assert_eq!(
+        specifier_for(vec!["node_modules", "pkg", "dist", "helper.js"]),
+        Some(String::from("pkg/helper"))
+    );
+    assert_eq!(
+        specifier_for(vec!["node_modules", "pkg", "dist", "features", "x.js"]),
+        Some(String::from("pkg/features/x"))
+    );
+    // the file which is not exported is imported by the relative path.
+    assert_eq!(
+        specifier_for(vec!["node_modules", "pkg", "dist", "private.js"]),
+        Some(String::from("../node_modules/pkg/dist/private"))
+    );
+    // without `"exports"`.
+    assert_eq!(
+        specifier_for(vec!["node_modules", "plain", "index.js"]),
+        Some(String::from("plain"))
+    );
+    assert_eq!(
+        specifier_for(vec!["node_modules", "plain", "lib", "a.js"]),
+        Some(String::from("plain/lib/a"))
+    );
+    assert_eq!(specifier_for(vec!["src", "missing.js"]), None);
+
+    let resolver = Resolver::new(Options {
+        extensions: vec![],
+        binary_extensions: vec![],
+        enforce_extension: EnforceExtension::Enabled,
+        ..Default::default()
+    });
+    assert_eq!(
+        resolver.specifier_for(&from_dir, &p(vec!["specifier-for", "src", "a.js"])),
+        Some(String::from("./a.js"))
+    );
+}
+
+#[test]
+fn specifier_for_untracked_test() {
+    let from_dir = p(vec!["specifier-for", "src"]);
+    let target = p(vec!["specifier-for", "src", "a.js"]);
+    let resolver = Resolver::new(Options {
+        track_dependencies: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        resolver.specifier_for(&from_dir, &target),
+        Some(String::from("./a"))
+    );
+    // the probed candidates are not the resolutions of the resolver.
+    assert!(resolver.affected_resolutions(&[target]).is_empty());
+}
+
+#[test]
+fn list_exports_test() {
+    let case_path = p(vec!["list-exports", "src"]);
+    let pkg_dir = p(vec!["list-exports", "node_modules", "pkg"]);
+    let resolver = Resolver::new(Options::default());
+    let list = |package: Option<&str>, conditions: Vec<&str>| {
+        let path = if package.is_some() {
+            &case_path
+        } else {
+            &pkg_dir
+        };
+        resolver
+            .list_exports(path, package, &vec_to_list(conditions))
+            .unwrap()
+    };
+    let exported = list(Some("pkg"), vec!["require"]);
+    assert_eq!(
+        exported
+            .iter()
+            .map(|exported| exported.subpath.as_str())
+            .collect::<Vec<_>>(),
+        vec![
+            ".",
+            "./features/a",
+            "./features/nested/b",
+            "./helper",
+            "./package.json",
+            "./styles/a.css",
+        ]
+    );
+    assert_eq!(
+        exported[0],
+        ExportedSubpath {
+            subpath: String::from("."),
+            path: pkg_dir.join("dist").join("index.js"),
+            conditions: vec![String::from("default")],
+        }
+    );
+    assert_eq!(
+        exported[2].path,
+        pkg_dir
+            .join("dist")
+            .join("features")
+            .join("nested")
+            .join("b.js")
+    );
+    // by the conditions.
+    let exported = list(None, vec!["import"]);
+    assert_eq!(exported[0].path, pkg_dir.join("dist").join("index.mjs"));
+    assert_eq!(exported[0].conditions, vec![String::from("import")]);
+    assert_eq!(exported.len(), 6);
+    // no `"exports"`.
+    assert_eq!(list(Some("plain"), vec![]), vec![]);
+    assert_eq!(list(Some("missing"), vec![]), vec![]);
+}
+
+#[test]
+fn check_package_test() {
+    let case_path = p(vec!["check-package"]);
+    let resolver = Resolver::new(Options::default());
+    assert_eq!(
+        resolver.check_package(&case_path.join("good")).unwrap(),
+        vec![]
+    );
+
+    let pkg_dir = case_path.join("bad");
+    let missing = |pointer: &str, target: &str| Diagnostic {
+        pointer: String::from(pointer),
+        kind: DiagnosticKind::MissingTarget(String::from(target)),
+    };
+    assert_eq!(
+        resolver.check_package(&pkg_dir).unwrap(),
+        vec![
+            Diagnostic {
+                pointer: String::from("/main"),
+                kind: DiagnosticKind::MissingMain(String::from("./missing.js")),
+            },
+            Diagnostic {
+                pointer: String::from("/exports/./import"),
+                kind: DiagnosticKind::FormatMismatch {
+                    condition: String::from("import"),
+                    path: pkg_dir.join("index.cjs"),
+                    module_type: ModuleType::CommonJs,
+                },
+            },
+            Diagnostic {
+                pointer: String::from("/exports/./require"),
+                kind: DiagnosticKind::FormatMismatch {
+                    condition: String::from("require"),
+                    path: pkg_dir.join("index.mjs"),
+                    module_type: ModuleType::Module,
+                },
+            },
+            missing("/exports/.~1a", "./a.js"),
+            missing("/exports/.~1none~1*", "./none/*.js"),
+            missing("/exports/.~1gone~1", "./gone/"),
+        ]
+    );
+
+    assert_eq!(
+        resolver.check_package(&case_path.join("invalid")).unwrap(),
+        vec![Diagnostic {
+            pointer: String::from("/exports/./import"),
+            kind: DiagnosticKind::InvalidExports(ExportsFieldMisuse::ConditionAfterDefault),
+        }]
+    );
+    assert!(matches!(
+        resolver.check_package(&case_path.join("empty")),
+        Err(Error::UnexpectedValue(_))
+    ));
+}
+
+#[test]
+fn target_extensions_test() {
+    let case_path = p(vec!["target-extensions"]);
+    let resolver = |target: Option<Target>| {
+        Resolver::new(Options {
+            target,
+            target_extensions: vec![(Target::Web, vec_to_list(vec![".web.js", ".js"]))],
+            ..Default::default()
+        })
+    };
+    let file = |name: &str| p(vec!["target-extensions", name]);
+    let r = resolver(None);
+    should_equal(&r, &case_path, "./a", file("a.js"));
+    should_equal(&r, &case_path, "./addon", file("addon.node"));
+    let r = resolver(Some(Target::Web));
+    should_equal(&r, &case_path, "./a", file("a.web.js"));
+    should_failed(&r, &case_path, "./addon");
+    let r = resolver(Some(Target::Node));
+    should_equal(&r, &case_path, "./a", file("a.js"));
+    should_equal(&r, &case_path, "./addon", file("addon.node"));
+    // `extensions` without `.node`.
+    let r = resolver(Some(Target::WebWorker));
+    assert_eq!(r.options().extensions, vec_to_list(vec![".js", ".json"]));
+    should_equal(&r, &case_path, "./a", file("a.js"));
+    should_failed(&r, &case_path, "./addon");
+}
+
+#[test]
+fn detect_layout_test() {
+    let case_path = p(vec!["detect-layout"]);
+    let detect = |name: &str| ProjectLayout::detect(&case_path.join(name)).unwrap();
+    assert_eq!(
+        ProjectLayout::detect(&case_path.join("pnpm").join("src")).unwrap(),
+        ProjectLayout {
+            root: case_path.join("pnpm"),
+            layout: InstallLayout::PnpmStore,
+            package_manager: None,
+            workspaces: true,
+        }
+    );
+    // without the virtual store.
+    assert_eq!(detect("hoisted-pnpm").layout, InstallLayout::Hoisted);
+    assert_eq!(detect("npm").layout, InstallLayout::Hoisted);
+    assert_eq!(detect("pnp").layout, InstallLayout::Pnp);
+    // by the hints without lockfile.
+    let hint = detect("hint");
+    assert_eq!(hint.layout, InstallLayout::PnpmStore);
+    assert_eq!(hint.package_manager.as_deref(), Some("pnpm"));
+    let engines = detect("engines");
+    assert_eq!(engines.layout, InstallLayout::Hoisted);
+    assert_eq!(engines.package_manager.as_deref(), Some("yarn"));
+    let workspace = ProjectLayout::detect(&case_path.join("workspace").join("packages").join("a"));
+    assert_eq!(
+        workspace.as_ref().map(|project| project.root.clone()),
+        Some(case_path.join("workspace"))
+    );
+    assert!(workspace.unwrap().workspaces);
+    // the lockfile of the ancestor is preferred to the hint of the package.
+    let nested = detect("workspace/packages/b");
+    assert_eq!(nested.root, case_path.join("workspace"));
+    assert_eq!(nested.layout, InstallLayout::Hoisted);
+    assert_eq!(nested.package_manager, None);
+
+    let options = Options::default()
+        .detect_layout(&case_path.join("pnpm"))
+        .unwrap();
+    assert!(options.pnpm_store);
+    let options = Options {
+        pnpm_store: true,
+        ..Default::default()
+    }
+    .detect_layout(&case_path.join("npm"))
+    .unwrap();
+    assert!(!options.pnpm_store);
+    assert!(matches!(
+        Options::default().detect_layout(&case_path.join("pnp")),
+        Err(Error::UnexpectedValue(_))
+    ));
+}
+
+#[test]
+#[cfg(feature = "package-lock")]
+fn dependency_graph_test() {
+    let root = p(vec!["dependency-graph"]);
+    let file = |path: Vec<&str>| p([vec!["dependency-graph"], path].concat());
+    let src = file(vec!["src"]);
+    let workspace = file(vec!["packages", "w-dir"]);
+    // the hoisted packages are found in the physical layout.
+    let resolver = Resolver::new(Options::default());
+    should_equal(
+        &resolver,
+        &src,
+        "c",
+        file(vec!["node_modules", "c", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &workspace,
+        "b",
+        file(vec!["node_modules", "b", "index.js"]),
+    );
+
+    let content = std::fs::read_to_string(root.join("package-lock.json")).unwrap();
+    let graph = DependencyGraph::from_package_lock(&root, &content).unwrap();
+    let resolver = Resolver::new(Options {
+        dependency_graph: Some(std::sync::Arc::new(graph)),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &src,
+        "a",
+        file(vec!["node_modules", "a", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "b",
+        file(vec!["node_modules", "b", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &file(vec!["node_modules", "a"]),
+        "b",
+        file(vec!["node_modules", "a", "node_modules", "b", "index.js"]),
+    );
+    // the linked workspace is resolved by its directory and `"exports"`.
+    should_equal(
+        &resolver,
+        &src,
+        "w",
+        file(vec!["packages", "w-dir", "main.js"]),
+    );
+    should_equal(
+        &resolver,
+        &src,
+        "w/feature",
+        file(vec!["packages", "w-dir", "feature.js"]),
+    );
+    // not declared in the lockfile.
+    should_failed(&resolver, &src, "c");
+    should_failed(&resolver, &workspace, "b");
+}
+
+#[cfg(any(feature = "package-lock", feature = "pnpm-lock", feature = "yarn-lock"))]
+#[derive(Debug, Default)]
+struct CollectMismatches(std::sync::Mutex<Vec<LockfileMismatch>>);
+
+#[cfg(any(feature = "package-lock", feature = "pnpm-lock", feature = "yarn-lock"))]
+impl LockfileWarning for CollectMismatches {
+    fn warn(&self, mismatch: &LockfileMismatch) {
+        self.0.lock().unwrap().push(mismatch.clone());
+    }
+}
+
+/// Checks the packages in the fixture `lockfile-check` by the versions
+/// parsed from `lockfile`.
+#[cfg(any(feature = "package-lock", feature = "pnpm-lock", feature = "yarn-lock"))]
+fn check_lockfile(lockfile: &str, parse: fn(&Path, &str) -> Result<LockedVersions, Error>) {
+    let root = p(vec!["lockfile-check"]);
+    let src = root.join("src");
+    let content = std::fs::read_to_string(root.join(lockfile)).unwrap();
+    let versions = parse(&root, &content).unwrap();
+    let warnings = Arc::new(CollectMismatches::default());
+    let resolver = Resolver::new(Options {
+        lockfile_check: Some(LockfileCheck {
+            versions: Arc::new(versions),
+            warning: warnings.clone(),
+        }),
+        ..Default::default()
+    });
+    for name in ["a", "b", "c"] {
+        should_equal(
+            &resolver,
+            &src,
+            name,
+            root.join("node_modules").join(name).join("index.js"),
+        );
+    }
+    // the cached result is not reported again, and the files out of
+    // the packages are not verified.
+    should_equal(
+        &resolver,
+        &src,
+        "b",
+        root.join("node_modules").join("b").join("index.js"),
+    );
+    should_equal(&resolver, &root, "./src/index.js", src.join("index.js"));
+    assert_eq!(
+        *warnings.0.lock().unwrap(),
+        vec![
+            LockfileMismatch {
+                path: root.join("node_modules").join("b"),
+                name: String::from("b"),
+                version: Some(String::from("2.1.0")),
+                pinned: vec![String::from("2.0.0")],
+            },
+            LockfileMismatch {
+                path: root.join("node_modules").join("c"),
+                name: String::from("c"),
+                version: Some(String::from("1.0.0")),
+                pinned: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "package-lock")]
+fn lockfile_check_package_lock_test() {
+    check_lockfile("package-lock.json", LockedVersions::from_package_lock);
+}
+
+#[test]
+#[cfg(feature = "pnpm-lock")]
+fn lockfile_check_pnpm_lock_test() {
+    check_lockfile("pnpm-lock.yaml", LockedVersions::from_pnpm_lock);
+}
+
+#[test]
+#[cfg(feature = "yarn-lock")]
+fn lockfile_check_yarn_lock_test() {
+    check_lockfile("yarn.lock", LockedVersions::from_yarn_lock);
+}
+
+#[test]
+fn not_exported_suggestion_test() {
+    let src = p(vec!["not-exported", "src"]);
+    let resolver = Resolver::new(Options::default());
+    let not_exported = |request: &str| match resolver.resolve(&src, request) {
+        Err(Error::PackagePathNotExported(error)) => error,
+        result => {
+            println!("{result:?}");
+            unreachable!();
+        }
+    };
+    let error = not_exported("pkg/lib/utils");
+    assert_eq!(error.subpath, "./lib/utils");
+    assert_eq!(error.suggestion.as_deref(), Some("./utils"));
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "Package path pkg/lib/utils is not exported in {}, did you mean './utils'?",
+            p(vec!["not-exported", "node_modules", "pkg", "package.json"]).display()
+        )
+    );
+    assert_eq!(
+        not_exported("pkg/util").suggestion.as_deref(),
+        Some("./utils")
+    );
+    // the subpaths mapped to `null` are not suggested.
+    assert_eq!(not_exported("pkg/internal/a").suggestion, None);
+    should_equal(
+        &resolver,
+        &src,
+        "pkg/utils",
+        p(vec![
+            "not-exported",
+            "node_modules",
+            "pkg",
+            "lib",
+            "utils.js",
+        ]),
+    );
+}
+
+#[test]
+fn unicode_normalization_test() {
+    let case_path = p(vec!["unicode-normalization"]);
+    let nfc = Arc::new(Cache::default().with_unicode_normalization(UnicodeNormalization::Nfc));
+    assert_eq!(nfc.fs.unicode_normalization(), UnicodeNormalization::Nfc);
+    let resolver = Resolver::new(Options {
+        external_cache: Some(nfc.clone()),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "./caf\u{e9}",
+        case_path.join("caf\u{e9}.js"),
+    );
+    let len = nfc.len();
+    // the request in NFD shares the entries of NFC.
+    should_equal(
+        &resolver,
+        &case_path,
+        "./cafe\u{301}",
+        case_path.join("caf\u{e9}.js"),
+    );
+    assert_eq!(nfc.len(), len);
+    should_equal(
+        &resolver,
+        &case_path,
+        "./n\u{303}",
+        case_path.join("\u{f1}/index.js"),
+    );
+    should_equal(
+        &resolver,
+        &case_path.join("n\u{303}"),
+        "./index",
+        case_path.join("\u{f1}/index.js"),
+    );
+
+    // the names are matched as they are.
+    #[cfg(not(target_os = "macos"))]
+    {
+        let resolver = Resolver::new(Options {
+            external_cache: Some(Arc::new(
+                Cache::default().with_unicode_normalization(UnicodeNormalization::None),
+            )),
+            ..Default::default()
+        });
+        should_equal(
+            &resolver,
+            &case_path,
+            "./caf\u{e9}",
+            case_path.join("caf\u{e9}.js"),
+        );
+        should_failed(&resolver, &case_path, "./cafe\u{301}");
+    }
+}
+
+#[test]
+fn symlink_chain_test() {
+    let case_path = p(vec!["symlink"]);
+    let linked = case_path.join("linked");
+    let resolver = Resolver::new(Options::default());
+    let symlinks =
+        |resolver: &Resolver, path: &Path, request: &str| match resolver.resolve(path, request) {
+            Ok(ResolveResult::File(resource)) => resource.symlinks,
+            result => panic!("{result:?}"),
+        };
+    let real = p(vec!["symlink", "lib", "index.js"]);
+    assert_eq!(
+        symlinks(&resolver, &linked, "./node.relative.sym.js"),
+        vec![
+            linked.join("node.relative.sym.js"),
+            linked.join("node.relative.js"),
+            real.clone(),
+        ]
+    );
+    assert_eq!(
+        symlinks(&resolver, &linked, "./that/index.js"),
+        vec![
+            linked.join("that/index.js"),
+            linked.join("this/index.js"),
+            linked.join("index.js"),
+            real.clone(),
+        ]
+    );
+    assert_eq!(
+        symlinks(&resolver, &linked, "./lib/index.js"),
+        vec![linked.join("lib/index.js"), real]
+    );
+    assert!(symlinks(&resolver, &case_path, "./lib/index.js").is_empty());
+    let resolver = Resolver::new(Options {
+        symlinks: false,
+        ..Default::default()
+    });
+    assert!(symlinks(&resolver, &linked, "./index.js").is_empty());
+}
+
+#[test]
+fn symlink_test() {
+    let symlink_cases_path = p(vec!["symlink"]);
+    let resolver = Resolver::new(Options::default());
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./this/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./node.relative.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./node.relative.sym.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./this/this/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./outer/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./outer/linked/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./outer/linked/this/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./outer/linked/this/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./that/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./that/outer/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./that/outer/linked/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked"),
+        "./that/outer/linked/that/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path,
+        "./lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/this"),
+        "./lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/this"),
+        "./outer/linked/lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/this/lib"),
+        "./index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/this/outer/linked"),
+        "./index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/this/outer/linked"),
+        "./lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &symlink_cases_path.join("linked/that"),
+        "./lib/index.js",
+        p(vec!["symlink", "lib", "index.js"]),
     );
     should_equal(
         &resolver,
@@ -1145,7 +4398,7 @@ fn pnpm_structure_test() {
             "index.js",
         ]),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &case_path.join("exports-field-a"),
         "exports-field-b",
@@ -1323,6 +4576,12 @@ fn resolve_test() {
         "./#/#.js",
         p(vec!["no#fragment", "#", "#.js"]),
     );
+    should_equal(
+        &resolver,
+        &p(vec!["node_modules", "complexm"]),
+        "m2/b.js",
+        p(vec!["node_modules", "m2", "b.js"]),
+    );
     let web_modules_path = fixture_path.join("node_modules/complexm/web_modules/m1");
     should_equal(
         &resolver,
@@ -1349,6 +4608,22 @@ fn resolve_test() {
         "m1/a",
         p(vec!["node_modules", "m1", "a.js"]),
     );
+    let resolver = Resolver::new(Options {
+        modules: vec![String::from("web_modules"), String::from("node_modules")],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &p(vec!["node_modules", "complexm"]),
+        "m1/a",
+        p(vec![
+            "node_modules",
+            "complexm",
+            "web_modules",
+            "m1",
+            "a.js",
+        ]),
+    );
 }
 
 #[test]
@@ -1484,131 +4759,574 @@ fn browser_filed_test() {
     );
     should_equal(
         &resolver,
-        &browser_module_case_path,
-        "./toString",
-        p(vec!["browser-module", "lib", "toString.js"]),
+        &browser_module_case_path,
+        "./toString",
+        p(vec!["browser-module", "lib", "toString.js"]),
+    );
+    should_failed(&resolver, &browser_module_case_path, "toString");
+    should_failed(&resolver, &browser_module_case_path, "./toString.js");
+    should_equal(
+        &resolver,
+        &browser_module_case_path,
+        "./lib/redirect",
+        p(vec!["browser-module", "lib", "sub.js"]),
+    );
+    should_equal(
+        &resolver,
+        &browser_module_case_path,
+        "./lib/redirect2",
+        p(vec!["browser-module", "lib", "sub", "dir", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &browser_module_case_path,
+        "./lib/redirect3",
+        p(vec![
+            "browser-module",
+            "lib",
+            "redirect3-target",
+            "dir",
+            "index.js",
+        ]),
+    );
+
+    // browser with alias
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        alias: vec![(
+            String::from("./lib/toString.js"),
+            vec![AliasMap::Target(String::from("module-d"))],
+        )],
+        ..Default::default()
+    });
+
+    should_equal(
+        &resolver,
+        &browser_module_case_path,
+        "./toString",
+        p(vec!["browser-module", "node_modules", "module-c.js"]),
+    );
+
+    let lib_path = browser_module_case_path.join("lib");
+    should_ignored(&resolver, &lib_path, "./ignore");
+    should_ignored(&resolver, &lib_path, "./ignore.js");
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./toString",
+        p(vec!["browser-module", "lib", "toString.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./toString.js",
+        p(vec!["browser-module", "lib", "toString.js"]),
+    );
+
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./replaced",
+        p(vec!["browser-module", "lib", "browser.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./replaced.js",
+        p(vec!["browser-module", "lib", "browser.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "module-a",
+        p(vec!["browser-module", "browser", "module-a.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "module-b",
+        p(vec!["browser-module", "node_modules", "module-c.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "module-d",
+        p(vec!["browser-module", "node_modules", "module-c.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./redirect",
+        p(vec!["browser-module", "lib", "sub.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./redirect2",
+        p(vec!["browser-module", "lib", "sub", "dir", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &lib_path,
+        "./redirect3",
+        p(vec![
+            "browser-module",
+            "lib",
+            "redirect3-target",
+            "dir",
+            "index.js",
+        ]),
+    );
+
+    let browser_after_main_path = p(vec!["browser-after-main"]);
+    should_ignored(&resolver, &browser_after_main_path, ".");
+    should_ignored(&resolver, &p(vec![]), "./browser-after-main");
+    should_ignored(&resolver, &browser_after_main_path, ".");
+
+    // TODO: alias_fields
+}
+
+#[test]
+fn suggestions_test() {
+    let case_path = p(vec!["suggestions"]);
+    let resolver = Resolver::new(Options::default());
+    should_suggested(
+        &resolver,
+        &case_path,
+        "./components/button",
+        vec!["./components/Button.tsx", "./components/button.css"],
+    );
+    should_suggested(&resolver, &case_path, "./utils", vec!["./utils.mjs"]);
+    should_suggested(&resolver, &case_path, "./util", vec!["./utils.mjs"]);
+    should_suggested(&resolver, &case_path, "./index", vec!["./index.ts"]);
+    should_suggested(
+        &resolver,
+        &case_path.join("components"),
+        "../Index",
+        vec!["../index.ts"],
+    );
+    should_suggested(&resolver, &case_path, "./zzz", vec![]);
+    should_suggested(&resolver, &case_path, "./not-exist/index", vec![]);
+    should_suggested(&resolver, &case_path, "utils", vec![]);
+}
+
+#[test]
+fn resolve_types_test() {
+    let case_path = p(vec!["types"]);
+    let resolver = Resolver::new(Options::default());
+    should_equal(&resolver, &case_path, "./foo", p(vec!["types", "foo.js"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "with-types",
+        p(vec!["types", "node_modules", "with-types", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "with-exports",
+        p(vec!["types", "node_modules", "with-exports", "index.js"]),
+    );
+
+    let resolver = Resolver::new(Options {
+        resolve_types: true,
+        ..Default::default()
+    });
+    assert_eq!(
+        resolver.options().main_fields,
+        vec!["types", "typings", "main"]
+    );
+    assert_eq!(
+        resolver.options().extensions,
+        vec![".d.ts", ".js", ".json", ".node"]
+    );
+    should_equal(&resolver, &case_path, "./foo", p(vec!["types", "foo.d.ts"]));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./foo.js",
+        p(vec!["types", "foo.js"]),
     );
-    should_failed(&resolver, &browser_module_case_path, "toString");
-    should_failed(&resolver, &browser_module_case_path, "./toString.js");
     should_equal(
         &resolver,
-        &browser_module_case_path,
-        "./lib/redirect",
-        p(vec!["browser-module", "lib", "sub.js"]),
+        &case_path,
+        "with-types",
+        p(vec!["types", "node_modules", "with-types", "index.d.ts"]),
     );
     should_equal(
         &resolver,
-        &browser_module_case_path,
-        "./lib/redirect2",
-        p(vec!["browser-module", "lib", "sub", "dir", "index.js"]),
+        &case_path,
+        "with-typings",
+        p(vec![
+            "types",
+            "node_modules",
+            "with-typings",
+            "lib",
+            "main.d.ts",
+        ]),
     );
     should_equal(
         &resolver,
-        &browser_module_case_path,
-        "./lib/redirect3",
+        &case_path,
+        "with-exports",
         p(vec![
-            "browser-module",
-            "lib",
-            "redirect3-target",
-            "dir",
-            "index.js",
+            "types",
+            "node_modules",
+            "with-exports",
+            "types",
+            "index.d.ts",
         ]),
     );
+    should_equal(
+        &resolver,
+        &case_path,
+        "without-types",
+        p(vec!["types", "node_modules", "without-types", "index.js"]),
+    );
 
-    // browser with alias
+    // `"types"` is matched before `"require"` though it is declared later.
     let resolver = Resolver::new(Options {
-        browser_field: true,
-        alias: vec![(
-            String::from("./lib/toString.js"),
-            vec![AliasMap::Target(String::from("module-d"))],
-        )],
+        condition_names: vec_to_list(vec!["require"]),
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "types-last",
+        p(vec!["types", "node_modules", "types-last", "index.js"]),
+    );
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_list(vec!["require"]),
+        resolve_types: true,
         ..Default::default()
     });
+    should_equal(
+        &resolver,
+        &case_path,
+        "types-last",
+        p(vec!["types", "node_modules", "types-last", "index.d.ts"]),
+    );
+}
 
+#[test]
+fn types_versions_test() {
+    let case_path = p(vec!["types"]);
+    let pkg = |paths: Vec<&str>| {
+        let mut path = p(vec!["types", "node_modules", "types-versions"]);
+        for item in paths {
+            path.push(item);
+        }
+        path
+    };
+    let resolver = Resolver::new(Options::default());
     should_equal(
         &resolver,
-        &browser_module_case_path,
-        "./toString",
-        p(vec!["browser-module", "node_modules", "module-c.js"]),
+        &case_path,
+        "types-versions/operators",
+        pkg(vec!["operators", "index.js"]),
     );
 
-    let lib_path = browser_module_case_path.join("lib");
-    should_ignored(&resolver, &lib_path, "./ignore");
-    should_ignored(&resolver, &lib_path, "./ignore.js");
+    let resolver = Resolver::new(Options {
+        resolve_types: true,
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &lib_path,
-        "./toString",
-        p(vec!["browser-module", "lib", "toString.js"]),
+        &case_path,
+        "types-versions",
+        pkg(vec!["ts4", "index.d.ts"]),
     );
     should_equal(
         &resolver,
-        &lib_path,
-        "./toString.js",
-        p(vec!["browser-module", "lib", "toString.js"]),
+        &case_path,
+        "types-versions/operators",
+        pkg(vec!["ts4", "operators", "index.d.ts"]),
     );
-
     should_equal(
         &resolver,
-        &lib_path,
-        "./replaced",
-        p(vec!["browser-module", "lib", "browser.js"]),
+        &case_path,
+        "types-versions/legacy",
+        pkg(vec!["old", "legacy.d.ts"]),
     );
+
+    let resolver = Resolver::new(Options {
+        resolve_types: true,
+        typescript_version: Some("3.9".to_string()),
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &lib_path,
-        "./replaced.js",
-        p(vec!["browser-module", "lib", "browser.js"]),
+        &case_path,
+        "types-versions",
+        pkg(vec!["ts3", "index.d.ts"]),
     );
     should_equal(
         &resolver,
-        &lib_path,
-        "module-a",
-        p(vec!["browser-module", "browser", "module-a.js"]),
+        &case_path,
+        "types-versions/operators",
+        pkg(vec!["ts3", "operators", "index.d.ts"]),
     );
+}
+
+#[test]
+fn resolution_graph_test() {
+    let graph = ResolutionGraph::new(Resolver::new(Options::default()));
+    let issuer = p(vec!["global-paths", "src", "index.js"]);
+    let other = p(vec!["a.js"]);
+    let m1 = p(vec!["node_modules", "m1", "a.js"]);
+    assert!(graph.resolve(&issuer, "m1/a").is_ok());
+    assert!(graph.resolve(&issuer, "m1/a").is_ok());
+    assert!(graph.resolve(&other, "./node_modules/m1/a").is_ok());
+    assert!(graph.resolve(&other, "./b").is_ok());
+    assert!(graph.resolve(&other, "./not-exist").is_err());
+
+    assert_eq!(graph.edges().len(), 3);
+    assert_eq!(graph.dependencies(&other).len(), 2);
+    let dependents = graph.dependents(&m1);
+    assert_eq!(dependents.len(), 2);
+    assert_eq!(dependents[0].issuer, issuer);
+    assert_eq!(dependents[0].request, "m1/a");
+    assert_eq!(dependents[1].issuer, other);
+    assert_eq!(dependents[1].request, "./node_modules/m1/a");
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph {\n"));
+    assert!(dot.contains(&format!(
+        "\"{}\" -> \"{}\" [label=\"m1/a\"];",
+        issuer.display(),
+        m1.display()
+    )));
+    graph.clear();
+    assert!(graph.edges().is_empty());
+}
+
+#[test]
+fn ignored_reason_test() {
+    let case_path = p(vec!["browser-module"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        alias: vec![("fs".to_string(), vec![AliasMap::Ignored])],
+        ..Default::default()
+    });
+    match resolver.resolve(&case_path, "./lib/ignore.js") {
+        Ok(ResolveResult::Ignored { reason, request }) => {
+            assert_eq!(request, "./lib/ignore.js");
+            assert_eq!(
+                reason,
+                IgnoredReason::BrowserField {
+                    dir: case_path.clone(),
+                    key: "./lib/ignore.js".to_string()
+                }
+            );
+        }
+        result => panic!("{result:?}"),
+    }
+    match resolver.resolve(&case_path, "fs/promises") {
+        Ok(ResolveResult::Ignored { reason, request }) => {
+            assert_eq!(request, "fs/promises");
+            assert_eq!(reason, IgnoredReason::Alias("fs".to_string()));
+        }
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn source_redirect_test() {
+    let case_path = p(vec!["source-root", "app"]);
+    let resolver = Resolver::new(Options {
+        source_field: Some(String::from("source")),
+        source_entries: vec![(String::from("b"), String::from("./src/main.ts"))],
+        ..Default::default()
+    });
     should_equal(
         &resolver,
-        &lib_path,
-        "module-b",
-        p(vec!["browser-module", "node_modules", "module-c.js"]),
+        &case_path,
+        "@ws/a",
+        p(vec!["source-root", "packages", "a", "src", "index.ts"]),
     );
     should_equal(
         &resolver,
-        &lib_path,
-        "module-d",
-        p(vec!["browser-module", "node_modules", "module-c.js"]),
+        &case_path,
+        "@ws/a?q",
+        p(vec!["source-root", "packages", "a", "src", "index.ts?q"]),
     );
     should_equal(
         &resolver,
-        &lib_path,
-        "./redirect",
-        p(vec!["browser-module", "lib", "sub.js"]),
+        &case_path,
+        "b",
+        p(vec!["source-root", "packages", "b", "src", "main.ts"]),
     );
+    // the subpath is not redirected.
     should_equal(
         &resolver,
-        &lib_path,
-        "./redirect2",
-        p(vec!["browser-module", "lib", "sub", "dir", "index.js"]),
+        &case_path,
+        "@ws/a/dist/util",
+        p(vec!["source-root", "packages", "a", "dist", "util.js"]),
     );
+    // the packages in `modules` are not redirected.
     should_equal(
         &resolver,
-        &lib_path,
-        "./redirect3",
+        &case_path,
+        "ext",
         p(vec![
-            "browser-module",
-            "lib",
-            "redirect3-target",
-            "dir",
+            "source-root",
+            "app",
+            "node_modules",
+            "ext",
+            "dist",
             "index.js",
         ]),
     );
+    should_equal(
+        &Resolver::new(Default::default()),
+        &case_path,
+        "@ws/a",
+        p(vec!["source-root", "packages", "a", "dist", "index.js"]),
+    );
+}
 
-    let browser_after_main_path = p(vec!["browser-after-main"]);
-    should_ignored(&resolver, &browser_after_main_path, ".");
-    should_ignored(&resolver, &p(vec![]), "./browser-after-main");
-    should_ignored(&resolver, &browser_after_main_path, ".");
+#[derive(Debug)]
+struct SourcePolicy;
 
-    // TODO: alias_fields
+impl BeforeResolved for SourcePolicy {
+    fn before_resolved(&self, _path: &Path, _request: &str, resolved: &Path) -> Verdict {
+        let resolved = resolved.display().to_string();
+        if resolved.ends_with("secret.js") {
+            Verdict::Reject
+        } else if resolved.contains("/ws/dist/") {
+            Verdict::Replace(PathBuf::from(resolved.replace("/ws/dist/", "/ws/src/")))
+        } else {
+            Verdict::Accept
+        }
+    }
+}
+
+#[test]
+fn before_resolved_test() {
+    let case_path = p(vec!["before-resolved"]);
+    let resolver = Resolver::new(Options {
+        before_resolved: Some(Arc::new(SourcePolicy)),
+        ..Default::default()
+    });
+    should_equal(&resolver, &case_path, "./a", case_path.join("a.js"));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./ws",
+        p(vec!["before-resolved", "ws", "src", "index.js"]),
+    );
+    match resolver.resolve(&case_path, "./ws?q#f") {
+        Ok(ResolveResult::File(resource)) => {
+            assert_eq!(
+                resource.path,
+                p(vec!["before-resolved", "ws", "src", "index.js"])
+            );
+            assert_eq!(resource.query.as_deref(), Some("?q"));
+            assert_eq!(resource.fragment.as_deref(), Some("#f"));
+        }
+        result => panic!("{result:?}"),
+    }
+    assert!(matches!(
+        resolver.resolve(&case_path, "./secret"),
+        Err(Error::Rejected(path)) if path == case_path.join("secret.js")
+    ));
+    should_equal(
+        &Resolver::new(Default::default()),
+        &case_path,
+        "./ws",
+        p(vec!["before-resolved", "ws", "dist", "index.js"]),
+    );
+    // the replaced path must be an absolute path to a file.
+    for replaced in [
+        PathBuf::from("a.js"),
+        case_path.join("inexist.js"),
+        case_path.join("ws"),
+    ] {
+        let resolver = Resolver::new(Options {
+            before_resolved: Some(Arc::new(ReplacePolicy(replaced))),
+            ..Default::default()
+        });
+        should_unexpected_value_error(
+            &resolver,
+            &case_path,
+            "./a",
+            String::from("which is not an absolute path to a file"),
+        );
+    }
+}
+
+#[derive(Debug)]
+struct ReplacePolicy(PathBuf);
+
+impl BeforeResolved for ReplacePolicy {
+    fn before_resolved(&self, _path: &Path, _request: &str, _resolved: &Path) -> Verdict {
+        Verdict::Replace(self.0.clone())
+    }
+}
+
+#[test]
+fn empty_module_test() {
+    let case_path = p(vec!["browser-module"]);
+    let empty_module = p(vec!["empty-module", "index.js"]);
+    let resolver = Resolver::new(Options {
+        browser_field: true,
+        alias: vec![("fs".to_string(), vec![AliasMap::Ignored])],
+        optional_prefixes: vec![String::from("missing")],
+        empty_module: Some(empty_module.clone()),
+        ..Default::default()
+    });
+    assert_eq!(resolver.empty_module_path(), Some(empty_module.as_path()));
+    should_equal(
+        &resolver,
+        &case_path,
+        "./lib/ignore.js",
+        empty_module.clone(),
+    );
+    should_equal(&resolver, &case_path, "fs/promises?q", empty_module);
+    // the optional requests which can't be resolved are still ignored.
+    should_ignored(&resolver, &case_path, "missing");
+    assert_eq!(Resolver::new(Default::default()).empty_module_path(), None);
+    // the relative path is resolved from the current directory.
+    let resolver = Resolver::new(Options {
+        empty_module: Some(PathBuf::from("./tests/fixtures/empty-module/index.js")),
+        ..Default::default()
+    });
+    assert_eq!(
+        resolver.empty_module_path(),
+        Some(p(vec!["empty-module", "index.js"]).as_path())
+    );
+}
+
+#[test]
+#[should_panic(expected = "is not a file")]
+fn missing_empty_module_test() {
+    let _ = Resolver::new(Options {
+        empty_module: Some(p(vec!["empty-module", "inexist.js"])),
+        ..Default::default()
+    });
+}
+
+#[test]
+fn dual_resolver_test() {
+    let case_path = p(vec!["browser-module"]);
+    let resolver = DualResolver::new(Options::default());
+    let pair = resolver.resolve(&case_path, "module-a");
+    match (pair.node, pair.browser) {
+        (Ok(ResolveResult::File(node)), Ok(ResolveResult::File(browser))) => {
+            assert_eq!(
+                node.join(),
+                p(vec!["browser-module", "node_modules", "module-a.js"])
+            );
+            assert_eq!(
+                browser.join(),
+                p(vec!["browser-module", "browser", "module-a.js"])
+            );
+        }
+        pair => panic!("{pair:?}"),
+    }
+    let pair = resolver.resolve(&case_path, "./lib/ignore.js");
+    assert!(matches!(pair.node, Ok(ResolveResult::File(_))));
+    assert!(matches!(pair.browser, Ok(ResolveResult::Ignored { .. })));
 }
 
 #[test]
@@ -1963,6 +5681,79 @@ fn missing_test() {
     );
 }
 
+#[derive(Debug, Default)]
+struct CollectWarnings(std::sync::Mutex<Vec<PathBuf>>);
+
+impl ManifestWarning for CollectWarnings {
+    fn warn(&self, path: &Path, error: &Error) {
+        assert!(matches!(error, Error::UnexpectedJson(_)));
+        self.0.lock().unwrap().push(path.to_path_buf());
+    }
+}
+
+#[test]
+fn manifest_error_policy_test() {
+    let case_path = p(vec!["incorrect-package", "pack1"]);
+    let resolver = Resolver::new(Default::default());
+    should_unexpected_json_error(&resolver, &case_path, "./a", case_path.join("package.json"));
+
+    let resolver = Resolver::new(Options {
+        on_manifest_error: ManifestErrorPolicy::Ignore,
+        ..Default::default()
+    });
+    should_equal(&resolver, &case_path, "./a", case_path.join("a.js"));
+    assert!(resolver
+        .load_description_file(&case_path)
+        .unwrap()
+        .map_or(true, |pkg_info| pkg_info.dir().as_ref() != case_path));
+
+    let warnings = Arc::new(CollectWarnings::default());
+    let resolver = Resolver::new(Options {
+        on_manifest_error: ManifestErrorPolicy::WarnAndIgnore(warnings.clone()),
+        ..Default::default()
+    });
+    should_equal(&resolver, &case_path, "./a", case_path.join("a.js"));
+    should_equal(&resolver, &case_path, "./a.js", case_path.join("a.js"));
+    // reported once.
+    assert_eq!(
+        *warnings.0.lock().unwrap(),
+        vec![case_path.join("package.json")]
+    );
+}
+
+#[test]
+fn manifest_error_policy_shared_cache_test() {
+    let case_path = p(vec!["incorrect-package", "pack1"]);
+    let cache = Arc::new(Cache::default());
+    let resolver = |on_manifest_error| {
+        Resolver::new(Options {
+            on_manifest_error,
+            external_cache: Some(cache.clone()),
+            ..Default::default()
+        })
+    };
+    let ignore = resolver(ManifestErrorPolicy::Ignore);
+    should_equal(&ignore, &case_path, "./a", case_path.join("a.js"));
+    // the parent description file cached for `Ignore` is not shared.
+    let error = resolver(ManifestErrorPolicy::Error);
+    should_unexpected_json_error(&error, &case_path, "./a", case_path.join("package.json"));
+
+    let warnings = [
+        Arc::new(CollectWarnings::default()),
+        Arc::new(CollectWarnings::default()),
+    ];
+    for warning in &warnings {
+        let resolver = resolver(ManifestErrorPolicy::WarnAndIgnore(warning.clone()));
+        should_equal(&resolver, &case_path, "./a", case_path.join("a.js"));
+    }
+    for warning in warnings {
+        assert_eq!(
+            *warning.0.lock().unwrap(),
+            vec![case_path.join("package.json")]
+        );
+    }
+}
+
 #[test]
 fn incorrect_package_test() {
     let incorrect_package_path = p(vec!["incorrect-package"]);
@@ -2027,6 +5818,85 @@ fn scoped_packages_test() {
             "index.js",
         ]),
     );
+    should_equal(
+        &resolver,
+        &scoped_path,
+        "@scope/exports",
+        p(vec![
+            "scoped",
+            "node_modules",
+            "@scope",
+            "exports",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &scoped_path,
+        "@scope/exports/deep/a/b",
+        p(vec![
+            "scoped",
+            "node_modules",
+            "@scope",
+            "exports",
+            "lib",
+            "deep",
+            "a",
+            "b.js",
+        ]),
+    );
+    should_not_exported(
+        &resolver,
+        &scoped_path,
+        "@scope/exports/lib/deep/a/b.js",
+        String::from("Package path @scope/exports/lib/deep/a/b.js is not exported"),
+    );
+    for (request, reason) in [
+        ("@scope", InvalidRequest::ScopeWithoutName),
+        ("@scope/", InvalidRequest::ScopeWithoutName),
+    ] {
+        match resolver.resolve(&scoped_path, request) {
+            Err(Error::InvalidRequest(actual)) => assert_eq!(actual, reason),
+            result => panic!("{request}: {result:?}"),
+        }
+    }
+    // the encoded separators are looked up as the file names by `require`,
+    // so the fallback is still applied.
+    should_failed(&resolver, &scoped_path, "exports%2Fdeep");
+    let resolver = Resolver::new(Options {
+        fallback: vec![(
+            String::from("exports%2Fdeep"),
+            vec![AliasMap::Target(String::from("@scope/pack1"))],
+        )],
+        browser_field: true,
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &scoped_path,
+        "exports%2Fdeep",
+        p(vec!["scoped", "node_modules", "@scope", "pack1", "main.js"]),
+    );
+    let resolver = Resolver::new(Options {
+        mode: ResolutionMode::Esm,
+        ..Default::default()
+    });
+    for (request, reason) in [
+        ("@scope%2Fexports", InvalidRequest::EncodedSeparator),
+        (
+            "@scope/exports/deep/a%2fb",
+            InvalidRequest::EncodedSeparator,
+        ),
+        (
+            "@scope/exports/deep/a%5Cb",
+            InvalidRequest::EncodedSeparator,
+        ),
+    ] {
+        match resolver.resolve(&scoped_path, request) {
+            Err(Error::InvalidRequest(actual)) => assert_eq!(actual, reason),
+            result => panic!("{request}: {result:?}"),
+        }
+    }
 }
 
 #[test]
@@ -2035,23 +5905,23 @@ fn exports_field_test() {
     let export_cases_path = p(vec!["exports-field"]);
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
-        condition_names: vec_to_set(vec!["webpack"]),
+        condition_names: vec_to_list(vec!["webpack"]),
         ..Default::default()
     });
     should_failed(&resolver, &export_cases_path, "@exports-field/coreaaaa");
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/x.js",
         "Package path exports-field/x.js is not exported".to_string(),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_invalid_specifier(
         &resolver,
         &export_cases_path,
         "exports-field/dist/a.js",
-        "Trying to access out of package scope. Requesting ./../../a.js".to_string(),
+        "..",
     );
     should_unexpected_value_error(
         &resolver,
@@ -2059,18 +5929,18 @@ fn exports_field_test() {
         "exports-field/dist/",
         "Resolving to directories is not possible with the exports field (request was exports-field/dist/ in".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/dist",
         "Package path exports-field/dist is not exported".to_string(),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_invalid_specifier(
         &resolver,
         &export_cases_path,
         "exports-field/dist/../../../a.js",
-        "Trying to access out of package scope. Requesting ./lib/lib2/../../../a.js".to_string(),
+        "..",
     );
     should_equal(
         &resolver,
@@ -2128,7 +5998,7 @@ fn exports_field_test() {
         p(vec!["exports-field", "a.js"]),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "@exports-field/core/a",
@@ -2153,7 +6023,7 @@ fn exports_field_test() {
         "./node_modules/exports-field/dist/main",
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/anything/else",
@@ -2167,14 +6037,14 @@ fn exports_field_test() {
         "Resolving to directories is not possible with the exports field (request was exports-field/ in".to_string(),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/dist",
         "Package path exports-field/dist is not exported".to_string(),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/lib",
@@ -2198,18 +6068,17 @@ fn exports_field_test() {
             "x.js",
         ]),
     );
-    should_unexpected_value_error(
+    should_invalid_specifier(
         &resolver,
         &p(vec!["exports-field-error"]),
         "exports-field",
-        "Trying to access out of package scope. Requesting ./a/../b/../../pack1/index.js"
-            .to_string(),
+        "..",
     );
 
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
         browser_field: true,
-        condition_names: vec_to_set(vec!["webpack"]),
+        condition_names: vec_to_list(vec!["webpack"]),
         ..Default::default()
     });
     should_equal(
@@ -2240,7 +6109,7 @@ fn exports_field_test() {
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
         browser_field: true,
-        condition_names: vec_to_set(vec!["node"]),
+        condition_names: vec_to_list(vec!["node"]),
         ..Default::default()
     });
     should_equal(
@@ -2274,7 +6143,7 @@ fn exports_field_test() {
             "main.js",
         ]),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path,
         "exports-field/main.js",
@@ -2283,7 +6152,7 @@ fn exports_field_test() {
 
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
-        condition_names: vec_to_set(vec!["require"]),
+        condition_names: vec_to_list(vec!["require"]),
         ..Default::default()
     });
     should_equal(
@@ -2317,7 +6186,7 @@ fn exports_field_test() {
     );
 
     let resolver = Resolver::new(Options {
-        condition_names: vec_to_set(vec!["import"]),
+        condition_names: vec_to_list(vec!["import"]),
         ..Default::default()
     });
     should_equal(
@@ -2377,7 +6246,7 @@ fn exports_field_test() {
 fn exports_filed_test_2() {
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
-        condition_names: vec_to_set(vec!["webpack"]),
+        condition_names: vec_to_list(vec!["webpack"]),
         ..Default::default()
     });
     let export_cases_path2 = p(vec!["exports-field2"]);
@@ -2444,14 +6313,14 @@ fn exports_filed_test_2() {
     );
     // TODO: error stack
     // TODO: should `exports-field?foo is not exported`.
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path2,
         "exports-field?foo",
         "Package path exports-field is not exported".to_string(),
     );
     // TODO: error stack
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path2,
         "exports-field#foo",
@@ -2596,68 +6465,68 @@ fn exports_filed_test_4() {
 fn exports_filed_test_5() {
     let export_cases_path5 = p(vec!["exports-field5"]);
     let resolver = Resolver::new(Options::default());
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/missing",
         "pkgexports/missing is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/invalid1",
         "pkgexports/invalid1 is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/invalid4",
         "pkgexports/invalid4 is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/sub/internal/test.js",
         "pkgexports/sub/internal/test.js is not exported".to_string(),
     );
     // FIXME:
-    // should_unexpected_value_error(
+    // should_not_exported(
     //     &resolver,
     //     &export_cases_path5,
     //     "pkgexports/sub/internal//test.js",
     //     "pkgexports/sub/internal//test.js is not exported".to_string(),
     // );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/null",
         "pkgexports/null is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/null",
         "pkgexports/null is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports////null",
         "pkgexports////null is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/null/subpath",
         "pkgexports/null/subpath is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/nofallback1",
         "nofallback1 is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/trailer",
@@ -2675,13 +6544,13 @@ fn exports_filed_test_5() {
         "pkgexports/belowdir/pkgexports/asdf.js",
         "Export should be relative path and start w".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/belowdir",
         "pkgexports/belowdir is not exported".to_string(),
     );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/invalid2",
@@ -2706,23 +6575,23 @@ fn exports_filed_test_5() {
         "Invalid \"builtin:x\" defined in".to_string(),
     );
     // FIXME:
-    // should_unexpected_value_error(
+    // should_not_exported(
     //     &resolver,
     //     &export_cases_path5,
     //     "pkgexports/nodemodules",
     //     "nodemodules is not exported".to_string(),
     // );
-    should_unexpected_value_error(
+    should_not_exported(
         &resolver,
         &export_cases_path5,
         "pkgexports/resolve-self-invalid",
         "Package path pkgexports/resolve-self-invalid is not".to_string(),
     );
-    should_unexpected_value_error(
+    should_invalid_specifier(
         &resolver,
         &export_cases_path5,
         "pkgexports/sub/./../asdf.js",
-        "Trying to access out of package scope. Requesting ././../asd".to_string(),
+        ".",
     );
     // FIXME:
     // should_unexpected_value_error(
@@ -2733,12 +6602,92 @@ fn exports_filed_test_5() {
     // );
 }
 
+#[test]
+fn strict_exports_test() {
+    fn should_invalid(
+        resolver: &Resolver,
+        path: &Path,
+        request: &str,
+        pointer: &str,
+        misuse: ExportsFieldMisuse,
+    ) {
+        match resolver.resolve(path, request) {
+            Err(Error::InvalidExportsField(error)) => {
+                assert_eq!(error.pointer, pointer);
+                assert_eq!(error.misuse, misuse);
+                assert_eq!(
+                    *error.path,
+                    *path.join("node_modules").join(request).join("package.json")
+                );
+            }
+            result => panic!("{result:?}"),
+        }
+    }
+
+    let case_path = p(vec!["exports-strict"]);
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_list(vec!["require"]),
+        ..Default::default()
+    });
+    should_unexpected_value_error(
+        &resolver,
+        &case_path,
+        "not-relative",
+        "target must start with".to_string(),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "after-default",
+        p(vec![
+            "exports-strict",
+            "node_modules",
+            "after-default",
+            "index.js",
+        ]),
+    );
+
+    let resolver = Resolver::new(Options {
+        condition_names: vec_to_list(vec!["require"]),
+        strict_exports: true,
+        ..Default::default()
+    });
+    should_invalid(
+        &resolver,
+        &case_path,
+        "not-relative",
+        "/exports/./0",
+        ExportsFieldMisuse::TargetNotRelative,
+    );
+    should_invalid(
+        &resolver,
+        &case_path,
+        "out-of-package",
+        "/exports/.~1lib~1*/require",
+        ExportsFieldMisuse::TargetOutOfPackage,
+    );
+    should_invalid(
+        &resolver,
+        &case_path,
+        "after-default",
+        "/exports/./import",
+        ExportsFieldMisuse::ConditionAfterDefault,
+    );
+    should_invalid(
+        &resolver,
+        &case_path,
+        "mixed-keys",
+        "/exports/.~1a",
+        ExportsFieldMisuse::MixedKeys,
+    );
+}
+
 #[test]
 fn imports_fields_test() {
     let import_cases_path = p(vec!["imports-field"]);
     let resolver = Resolver::new(Options {
         extensions: vec![String::from(".js")],
-        condition_names: vec_to_set(vec!["webpack"]),
+        condition_names: vec_to_list(vec!["webpack"]),
         ..Default::default()
     });
 
@@ -2766,12 +6715,7 @@ fn imports_fields_test() {
         "#imports-field",
         p(vec!["imports-field", "b.js"]),
     );
-    should_unexpected_value_error(
-        &resolver,
-        &import_cases_path,
-        "#b",
-        "Trying to access out of package scope. Requesting ../b.js".to_string(),
-    );
+    should_invalid_specifier(&resolver, &import_cases_path, "#b", "..");
     should_equal(
         &resolver,
         &import_cases_path,
@@ -3187,6 +7131,104 @@ fn tsconfig_paths_test() {
     );
 }
 
+#[test]
+fn tsconfig_paths_priority() {
+    let tsconfig_path = p(vec!["tsconfig-paths-priority"]);
+    let resolver = Resolver::new(Options {
+        tsconfig: Some(tsconfig_path.join("tsconfig.json")),
+        ..Default::default()
+    });
+    // substitutions are tried in order.
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "a",
+        p(vec!["tsconfig-paths-priority", "generated", "a.js"]),
+    );
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "b",
+        p(vec!["tsconfig-paths-priority", "b.js"]),
+    );
+    // longest prefix wins.
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "components/card",
+        p(vec![
+            "tsconfig-paths-priority",
+            "shared",
+            "components",
+            "card.js",
+        ]),
+    );
+    // exact pattern wins.
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "components/button",
+        p(vec!["tsconfig-paths-priority", "special", "button.js"]),
+    );
+    // unmatched requests fall through to `node_modules`.
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "react",
+        p(vec![
+            "tsconfig-paths-priority",
+            "node_modules",
+            "react",
+            "index.js",
+        ]),
+    );
+    should_equal(
+        &resolver,
+        &tsconfig_path,
+        "missing/index",
+        p(vec![
+            "tsconfig-paths-priority",
+            "node_modules",
+            "missing",
+            "index.js",
+        ]),
+    );
+    should_failed(&resolver, &tsconfig_path, "missing/not-exist");
+}
+
+#[test]
+fn resolve_tsconfig_test() {
+    let tsconfig_path = p(vec!["tsconfig-root-dirs", "tsconfig.json"]);
+    let resolver = Resolver::new(Default::default());
+    let tsconfig = resolver.resolve_tsconfig(&tsconfig_path).unwrap();
+    assert_eq!(tsconfig.base_url.as_deref(), Some("./src"));
+    assert_eq!(
+        tsconfig.paths.as_ref().unwrap().get("#/*"),
+        Some(&vec![String::from("./*")])
+    );
+    assert_eq!(
+        tsconfig.root_dirs,
+        Some(vec![String::from("./src"), String::from("./generated")])
+    );
+    let cached = resolver.resolve_tsconfig(&tsconfig_path).unwrap();
+    assert!(Arc::ptr_eq(&tsconfig, &cached));
+    resolver.clear_entries();
+    let reloaded = resolver.resolve_tsconfig(&tsconfig_path).unwrap();
+    assert!(!Arc::ptr_eq(&tsconfig, &reloaded));
+
+    assert!(matches!(
+        resolver.resolve_tsconfig(&p(vec!["tsconfig-paths", "not-exist.json"])),
+        Err(Error::CantFindTsConfig(_))
+    ));
+    // the malformed `baseUrl` and `paths`.
+    for name in ["tsconfig.json", "tsconfig.paths.json"] {
+        assert!(matches!(
+            resolver.resolve_tsconfig(&p(vec!["tsconfig-malformed", name])),
+            Err(Error::UnexpectedValue(_))
+        ));
+    }
+}
+
 #[test]
 fn tsconfig_paths_nested() {
     let tsconfig_path = p(vec!["tsconfig-paths-nested"]);
@@ -3352,7 +7394,7 @@ fn tsconfig_inexist() {
 fn load_description_data() {
     let case_path = p(vec!["exports-field"]);
     let resolver = Resolver::new(Options::default());
-    let resource = if let ResolveResult::Resource(resource) = resolver
+    let resource = if let ResolveResult::File(resource) = resolver
         .resolve(&case_path, "@scope/import-require")
         .unwrap()
     {
@@ -3383,7 +7425,7 @@ fn load_description_data() {
         serde_json::json!(["./index.js", "./a.js"])
     );
 
-    let resource = if let ResolveResult::Resource(resource) =
+    let resource = if let ResolveResult::File(resource) =
         resolver.resolve(&case_path, "exports-field").unwrap()
     {
         resource
@@ -3408,7 +7450,7 @@ fn load_description_data() {
         serde_json::Value::Bool(false)
     );
 
-    let resource = if let ResolveResult::Resource(resource) =
+    let resource = if let ResolveResult::File(resource) =
         resolver.resolve(&case_path, "string-side-effects").unwrap()
     {
         resource
@@ -3496,6 +7538,32 @@ fn shared_cache_test2() {
     );
 }
 
+#[test]
+fn shared_cache_partition_test() {
+    let case_path = p(vec!["description-file"]);
+    let cache = Arc::new(Cache::default());
+    assert!(cache.is_empty());
+    let resolver1 = Resolver::new(Options {
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    should_equal(&resolver1, &case_path, ".", case_path.join("a.js"));
+    let resolver2 = Resolver::new(Options {
+        external_cache: Some(cache.clone()),
+        description_file: "other.json".to_string(),
+        ..Default::default()
+    });
+    should_equal(&resolver2, &case_path, ".", case_path.join("b.js"));
+    should_equal(&resolver1, &case_path, ".", case_path.join("a.js"));
+    assert!(!cache.is_empty());
+    let len = cache.len();
+    resolver2.clear_entries();
+    assert!(cache.len() < len);
+    cache.clear();
+    assert!(cache.is_empty());
+    should_equal(&resolver1, &case_path, ".", case_path.join("a.js"));
+}
+
 #[test]
 fn empty_test() {
     let case_path = p(vec!["empty"]);
@@ -3509,7 +7577,7 @@ fn browser_it_self() {
     let case_path = p(vec!["browser-to-self"]);
     let resolver = Resolver::new(Options {
         browser_field: true,
-        condition_names: vec_to_set(vec!["browser"]),
+        condition_names: vec_to_list(vec!["browser"]),
         ..Default::default()
     });
     should_equal(
@@ -3547,7 +7615,7 @@ fn browser_it_self() {
             "package.json",
         ]),
     );
-    should_overflow(&resolver, &case_path, "c.js");
+    should_resolve_cycle(&resolver, &case_path, "c.js", vec!["c.js", "c.js", "c.js"]);
     let resolver = Resolver::new(Options {
         browser_field: true,
         main_fields: vec![
@@ -3621,28 +7689,44 @@ fn resolve_to_context_test() {
         resolve_to_context: true,
         ..Default::default()
     });
-    should_equal(&resolver, &p(vec![]), "./", p(vec![]));
-    should_equal(&resolver, &p(vec![]), "./dirOrFile", p(vec!["dirOrFile"]));
-    should_equal(
+    should_directory(&resolver, &p(vec![]), "./", p(vec![]));
+    should_directory(&resolver, &p(vec![]), "./dirOrFile", p(vec!["dirOrFile"]));
+    should_directory(
         &resolver,
         &p(vec![]),
         "./dirOrFile/../../fixtures/./dirOrFile/..",
         p(vec![]),
     );
-    should_equal(&resolver, &p(vec![]), "./m.js", p(vec!["m.js"]));
-    should_equal(&resolver, &p(vec![]), "./main-field", p(vec!["main-field"]));
-    should_equal(
+    should_directory(&resolver, &p(vec![]), "./m.js", p(vec!["m.js"]));
+    should_directory(&resolver, &p(vec![]), "./main-field", p(vec!["main-field"]));
+    should_directory(
         &resolver,
         &p(vec!["browser-module"]),
         "browser-string",
         p(vec!["browser-module", "node_modules", "browser-string"]),
     );
-    should_equal(
+    should_directory(
         &resolver,
         &p(vec![]),
         "./main-field-inexist",
         p(vec!["main-field-inexist"]),
     );
+    let case_path = p(vec!["resolve-to-context"]);
+    // the fields of description file and the files are not used.
+    should_directory(
+        &resolver,
+        &case_path,
+        "pkg",
+        p(vec!["resolve-to-context", "node_modules", "pkg"]),
+    );
+    should_directory(
+        &resolver,
+        &case_path,
+        "pkg/lib",
+        p(vec!["resolve-to-context", "node_modules", "pkg", "lib"]),
+    );
+    should_failed(&resolver, &case_path, "pkg/lib/index.js");
+    should_failed(&resolver, &case_path, "file");
 }
 
 #[test]
@@ -3679,6 +7763,328 @@ fn resolve_modules_test() {
         ..Default::default()
     });
     should_failed(&resolver, &p(vec![]), "recursive-module");
+
+    // the existence of `modules` directories is not shared between the
+    // resolvers with different `modules`.
+    let cache = Arc::new(Cache::default());
+    let resolver1 = Resolver::new(Options {
+        modules: vec!["alias".to_string()],
+        external_cache: Some(cache.clone()),
+        ..Default::default()
+    });
+    let resolver2 = Resolver::new(Options {
+        external_cache: Some(cache),
+        ..Default::default()
+    });
+    should_equal(&resolver1, &p(vec![]), "a", p(vec!["alias", "a", "index"]));
+    should_failed(&resolver2, &p(vec![]), "a");
+    should_equal(
+        &resolver2,
+        &p(vec![]),
+        "m1/a.js",
+        p(vec!["node_modules", "m1", "a.js"]),
+    );
+    should_failed(&resolver1, &p(vec![]), "m1/a.js");
+}
+
+#[test]
+fn deep_directory_modules_test() {
+    let root = std::env::temp_dir().join(format!("nodejs_resolver_deep_{}", std::process::id()));
+    let module_dir = root.join("node_modules").join("foo");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::write(module_dir.join("index.js"), "").unwrap();
+    // deeper than the limit of recursion.
+    let deep_dir = (0..200).fold(root.clone(), |dir, _| dir.join("d"));
+    std::fs::create_dir_all(&deep_dir).unwrap();
+    let resolver = Resolver::new(Default::default());
+    let result = resolver.resolve(&deep_dir, "foo");
+    std::fs::remove_dir_all(&root).unwrap();
+    match result {
+        Ok(ResolveResult::File(resource)) => {
+            assert_eq!(resource.path, module_dir.join("index.js"))
+        }
+        result => panic!("{result:?}"),
+    }
+}
+
+#[test]
+fn cache_validation_test() {
+    let root =
+        std::env::temp_dir().join(format!("nodejs_resolver_validation_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let pkg_path = root.join("package.json");
+    let load = |validation: CacheValidation| {
+        std::fs::write(&pkg_path, r#"{ "name": "a" }"#).unwrap();
+        let resolver = Resolver::new(Options {
+            external_cache: Some(Arc::new(Cache::with_validation(validation))),
+            ..Default::default()
+        });
+        let first = resolver.load_description_file(&root).unwrap().unwrap();
+        resolver.clear_entries();
+        let unchanged = resolver.load_description_file(&root).unwrap().unwrap();
+        std::fs::write(&pkg_path, r#"{ "name": "b" }"#).unwrap();
+        resolver.clear_entries();
+        let changed = resolver.load_description_file(&root).unwrap().unwrap();
+        (
+            Arc::ptr_eq(&first, &unchanged),
+            changed.data().name().map(String::from),
+        )
+    };
+    let never = load(CacheValidation::Never);
+    let content_hash = load(CacheValidation::ContentHash);
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(never, (true, Some(String::from("a"))));
+    assert_eq!(content_hash, (true, Some(String::from("b"))));
+}
+
+#[test]
+fn fs_event_test() {
+    let root = std::env::temp_dir().join(format!("nodejs_resolver_watch_{}", std::process::id()));
+    let module_dir = root.join("node_modules").join("foo");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::write(module_dir.join("index.js"), "").unwrap();
+    let resolver = Resolver::new(Default::default());
+    let resolve = |request: &str| match resolver.resolve(&root, request) {
+        Ok(ResolveResult::File(resource)) => Some(resource.path),
+        _ => None,
+    };
+    assert_eq!(resolve("foo"), Some(module_dir.join("index.js")));
+    assert_eq!(resolve("foo/main"), None);
+
+    let pkg_path = module_dir.join("package.json");
+    std::fs::write(&pkg_path, r#"{ "main": "./main.js" }"#).unwrap();
+    std::fs::write(module_dir.join("main.js"), "").unwrap();
+    // memoized until the events are received.
+    assert_eq!(resolve("foo"), Some(module_dir.join("index.js")));
+    resolver.on_fs_event(&module_dir.join("main.js"), FsEventKind::Create);
+    resolver.on_fs_event(&pkg_path, FsEventKind::Create);
+    assert_eq!(resolve("foo"), Some(module_dir.join("main.js")));
+    assert_eq!(resolve("foo/main"), Some(module_dir.join("main.js")));
+
+    std::fs::write(&pkg_path, r#"{ "main": "./index.js" }"#).unwrap();
+    resolver.on_fs_event(&pkg_path, FsEventKind::Modify);
+    assert_eq!(resolve("foo"), Some(module_dir.join("index.js")));
+
+    std::fs::remove_dir_all(&module_dir).unwrap();
+    resolver.on_fs_event(&module_dir, FsEventKind::Remove);
+    let result = resolve("foo");
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(result, None);
+}
+
+#[test]
+fn affected_failed_resolutions_test() {
+    let root =
+        std::env::temp_dir().join(format!("nodejs_resolver_affected_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    let resolver = Resolver::new(Options {
+        track_dependencies: true,
+        ..Default::default()
+    });
+    let failed = |request: &str| resolver.resolve(&root, request).is_err();
+    assert!(failed("./a"));
+    assert!(failed("foo"));
+
+    let file = root.join("a.js");
+    std::fs::write(&file, "").unwrap();
+    let module_dir = root.join("node_modules").join("foo");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::write(module_dir.join("index.js"), "").unwrap();
+    let affected = [
+        resolver.affected_resolutions(&[file.clone()]),
+        resolver.affected_resolutions(&[root.join("node_modules")]),
+    ];
+    resolver.on_fs_event(&file, FsEventKind::Create);
+    resolver.on_fs_event(&root.join("node_modules"), FsEventKind::Create);
+    let resolved = [failed("./a"), failed("foo")];
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(
+        affected,
+        [
+            vec![(root.clone(), String::from("./a"))],
+            vec![(root.clone(), String::from("foo"))]
+        ]
+    );
+    assert_eq!(resolved, [false, false]);
+}
+
+#[test]
+fn import_map_fs_event_test() {
+    let root =
+        std::env::temp_dir().join(format!("nodejs_resolver_import_map_{}", std::process::id()));
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join("a.js"), "").unwrap();
+    std::fs::write(root.join("b.js"), "").unwrap();
+    let import_map = root.join("importmap.json");
+    std::fs::write(&import_map, r#"{ "imports": { "x": "./a.js" } }"#).unwrap();
+    let resolver = Resolver::new(Options {
+        import_map: Some(import_map.clone()),
+        ..Default::default()
+    });
+    let resolve = || match resolver.resolve(&root, "x") {
+        Ok(ResolveResult::File(resource)) => Some(resource.path),
+        _ => None,
+    };
+    let before = resolve();
+    std::fs::write(&import_map, r#"{ "imports": { "x": "./b.js" } }"#).unwrap();
+    resolver.on_fs_event(&import_map, FsEventKind::Modify);
+    let after = resolve();
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(before, Some(root.join("a.js")));
+    assert_eq!(after, Some(root.join("b.js")));
+}
+
+#[test]
+#[cfg(windows)]
+fn long_description_file_test() {
+    // the relative path beyond `MAX_PATH` can't be prefixed by `\\?\`.
+    let name = "a".repeat(100);
+    let path = PathBuf::from(format!(r"{name}\{name}\{name}\package.json"));
+    let resolver = Resolver::new(Options::default());
+    assert!(matches!(
+        resolver.load_description_file(&path),
+        Err(Error::PathTooLong(_))
+    ));
+}
+
+#[test]
+fn stat_cache_test() {
+    let root = std::env::temp_dir().join(format!("nodejs_resolver_stat_{}", std::process::id()));
+    let file = root.join("a.js");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(&file, "").unwrap();
+    let resolver = Resolver::new(Default::default());
+    assert!(resolver.is_file(&file));
+    assert!(!resolver.is_dir(&file));
+    assert!(resolver.is_dir(&root));
+    assert!(resolver.read_meta(&file).file_type().unwrap().is_file());
+    assert!(resolver.read_meta(&root.join("b.js")).file_type().is_none());
+
+    // shared with the resolution.
+    assert!(resolver.resolve(&root, "./a").is_ok());
+    std::fs::remove_file(&file).unwrap();
+    assert!(resolver.is_file(&file));
+    resolver.on_fs_event(&file, FsEventKind::Remove);
+    let is_file = resolver.is_file(&file);
+    std::fs::remove_dir_all(&root).unwrap();
+    assert!(!is_file);
+}
+
+#[test]
+fn session_test() {
+    let root = std::env::temp_dir().join(format!("nodejs_resolver_session_{}", std::process::id()));
+    let module_dir = root.join("node_modules").join("foo");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::write(module_dir.join("index.js"), "").unwrap();
+    std::fs::write(module_dir.join("main.js"), "").unwrap();
+    let resolver = Resolver::new(Default::default());
+    let resolve = |session: &Session, request: &str| match session.resolve(&root, request) {
+        Ok(ResolveResult::File(resource)) => Some(resource.path),
+        _ => None,
+    };
+    let session = resolver.begin_session();
+    assert_eq!(resolve(&session, "foo"), Some(module_dir.join("index.js")));
+    let pkg_path = module_dir.join("package.json");
+    std::fs::write(&pkg_path, r#"{ "main": "./main.js" }"#).unwrap();
+    resolver.on_fs_event(&pkg_path, FsEventKind::Create);
+    let nested = resolver.begin_session();
+    assert_eq!(resolve(&nested, "foo"), Some(module_dir.join("index.js")));
+    drop(session);
+    assert_eq!(resolve(&nested, "foo"), Some(module_dir.join("index.js")));
+    drop(nested);
+    // the deferred event is applied after all the sessions end.
+    let session = resolver.begin_session();
+    let result = resolve(&session, "foo");
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(result, Some(module_dir.join("main.js")));
+}
+
+#[test]
+fn shared_cache_session_test() {
+    let root = std::env::temp_dir().join(format!(
+        "nodejs_resolver_shared_session_{}",
+        std::process::id()
+    ));
+    let module_dir = root.join("node_modules").join("foo");
+    std::fs::create_dir_all(&module_dir).unwrap();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::write(module_dir.join("index.js"), "").unwrap();
+    std::fs::write(module_dir.join("main.js"), "").unwrap();
+    let pkg_path = module_dir.join("package.json");
+    std::fs::write(&pkg_path, r#"{ "main": "./index.js" }"#).unwrap();
+    let cache = Arc::new(Cache::default());
+    let resolver = |modules: Vec<String>| {
+        Resolver::new(Options {
+            modules,
+            external_cache: Some(cache.clone()),
+            ..Default::default()
+        })
+    };
+    let a = resolver(vec![String::from("node_modules")]);
+    let b = resolver(vec![String::from("node_modules")]);
+    // the entries of another partition are loaded freshly.
+    let c = resolver(vec![
+        String::from("web_modules"),
+        String::from("node_modules"),
+    ]);
+    let resolve = |resolver: &Resolver, path: &Path| match resolver.resolve(path, "foo") {
+        Ok(ResolveResult::File(resource)) => Some(resource.path),
+        _ => None,
+    };
+    let session = b.begin_session();
+    assert!(session.resolve(&root, "foo").is_ok());
+    // exceeds the debounce interval of the modified time.
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    std::fs::write(&pkg_path, r#"{ "main": "./main.js" }"#).unwrap();
+    // the cached description file is pinned without the events.
+    let pinned = resolve(&c, &root);
+    a.on_fs_event(&pkg_path, FsEventKind::Modify);
+    b.on_fs_event(&pkg_path, FsEventKind::Modify);
+    // the event of another resolver is deferred too.
+    let deferred = resolve(&b, &root.join("src"));
+    drop(session);
+    let applied = [resolve(&a, &root), resolve(&b, &root)];
+    std::fs::remove_dir_all(&root).unwrap();
+    assert_eq!(pinned, Some(module_dir.join("index.js")));
+    assert_eq!(deferred, Some(module_dir.join("index.js")));
+    assert_eq!(
+        applied,
+        [
+            Some(module_dir.join("main.js")),
+            Some(module_dir.join("main.js"))
+        ]
+    );
+}
+
+#[test]
+fn global_paths_test() {
+    let case_path = p(vec!["global-paths", "src"]);
+    let resolver = Resolver::new(Options::default());
+    should_failed(&resolver, &case_path, "foo");
+    let resolver = Resolver::new(Options {
+        global_paths: vec![p(vec!["global-paths", "lib"])],
+        ..Default::default()
+    });
+    should_equal(
+        &resolver,
+        &case_path,
+        "foo",
+        p(vec!["global-paths", "lib", "foo", "index.js"]),
+    );
+    should_equal(
+        &resolver,
+        &case_path,
+        "bar",
+        p(vec!["global-paths", "lib", "bar.js"]),
+    );
+    should_failed(&resolver, &case_path, "./foo");
+    // ancestor `node_modules` have higher priority.
+    should_equal(
+        &resolver,
+        &case_path,
+        "m1/a",
+        p(vec!["node_modules", "m1", "a.js"]),
+    );
 }
 
 #[test]
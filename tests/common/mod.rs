@@ -0,0 +1,116 @@
+//! The proptest strategies of the package trees shared by the generated
+//! tests.
+
+#![allow(dead_code)]
+
+use proptest::{
+    option,
+    prelude::*,
+    sample::{select, subsequence},
+};
+use serde_json::{json, Value};
+use std::path::Path;
+
+pub fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+/// The files of a package tree, the paths are relative to its root.
+#[derive(Debug, Clone)]
+pub struct Tree {
+    pub files: Vec<(String, String)>,
+}
+
+impl Tree {
+    /// Writes the files under `root`.
+    pub fn write(&self, root: &Path) {
+        for (path, content) in &self.files {
+            write(&root.join(path), content);
+        }
+    }
+}
+
+fn exports_target() -> impl Strategy<Value = Value> {
+    select(&["./lib/index.js", "./lib/sub.js", "./missing.js", "./lib/"][..]).prop_map(Value::from)
+}
+
+fn exports() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        exports_target(),
+        (
+            exports_target(),
+            exports_target(),
+            exports_target(),
+            exports_target()
+        )
+            .prop_map(|(import, require, default, sub)| json!({
+                ".": { "import": import, "require": require, "default": default },
+                "./sub": sub,
+                "./*": "./lib/*.js",
+            })),
+        (exports_target(), exports_target())
+            .prop_map(|(node, fallback)| json!([{ "node": node }, fallback])),
+    ]
+}
+
+/// The files of the package `name` in `node_modules`.
+fn package(name: &'static str) -> impl Strategy<Value = Vec<(String, String)>> {
+    (
+        option::of(select(&["./lib/index.js", "lib", "./missing.js", "."][..])),
+        option::of(exports()),
+        option::of(select(&["./lib/sub.js", "q", "p/sub"][..])),
+        option::of(select(&["./lib/sub.js", "q"][..])),
+        subsequence(&["index.js", "sub.js"][..], 0..=2),
+    )
+        .prop_map(move |(main, exports, imports, browser, lib)| {
+            let mut pkg = json!({ "name": name });
+            if let Some(main) = main {
+                pkg["main"] = json!(main);
+            }
+            if let Some(exports) = exports {
+                pkg["exports"] = exports;
+            }
+            if let Some(imports) = imports {
+                pkg["imports"] = json!({ "#imp": imports });
+            }
+            if let Some(browser) = browser {
+                pkg["browser"] = json!({ "./lib/index.js": browser });
+            }
+            let dir = format!("node_modules/{name}");
+            let mut files = vec![(format!("{dir}/package.json"), pkg.to_string())];
+            files.extend(
+                lib.into_iter()
+                    .map(|file| (format!("{dir}/lib/{file}"), String::new())),
+            );
+            files
+        })
+}
+
+/// A source file `src/{name}` with one of the extensions, or nothing.
+fn source(name: &'static str) -> impl Strategy<Value = Option<(String, String)>> {
+    option::of(select(&[".js", ".json", ".ts"][..])).prop_map(move |ext| {
+        ext.map(|ext| {
+            let content = if ext == ".json" { "{}" } else { "" };
+            (format!("src/{name}{ext}"), String::from(content))
+        })
+    })
+}
+
+/// Generates the source files and the packages `p`, `q` and `@s/r` with
+/// the random `main`, `exports`, `imports` and `browser` fields.
+pub fn tree() -> impl Strategy<Value = Tree> {
+    (
+        (source("a"), source("b"), source("index")),
+        option::of(package("p")),
+        option::of(package("q")),
+        option::of(package("@s/r")),
+    )
+        .prop_map(|((a, b, index), p, q, r)| {
+            let sources = [a, b, index].into_iter().flatten();
+            let packages = [p, q, r].into_iter().flatten().flatten();
+            Tree {
+                files: sources.chain(packages).collect(),
+            }
+        })
+}
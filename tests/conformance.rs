@@ -0,0 +1,199 @@
+//! Differential tests against Node.js. The package trees are generated by
+//! the same strategies as the property tests with a deterministic runner, every request is resolved by
+//! `require.resolve` or `import.meta.resolve` of `node` and by this crate,
+//! and the results are compared.
+//!
+//! It spawns `node`, so it is ignored by default, run it by:
+//!
+//! ```bash
+//! cargo test --test conformance -- --ignored --nocapture
+//! ```
+
+mod common;
+
+use common::{tree, write, Tree};
+use nodejs_resolver::{CompatMode, Options, ResolutionMode, ResolveResult, Resolver};
+use proptest::{
+    collection::vec, prelude::*, sample::select, strategy::ValueTree, test_runner::TestRunner,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SEEDS: u64 = 32;
+
+/// The resolver uses `CompatMode::Node`, the known difference is that the
+/// directory target in `exports` is resolved to the main file of it, so
+/// the report is compared with a lower bound rather than requiring every
+/// request to be same.
+const MIN_MATCHED: f64 = 99.0;
+
+const REQUESTS: [&str; 17] = [
+    "./src/a",
+    "./src/a.js",
+    "./src/a.json",
+    "./src",
+    "./src/",
+    "./missing",
+    "p",
+    "p/sub",
+    "p/lib/sub",
+    "p/lib/sub.js",
+    "p/package.json",
+    "q",
+    "q/",
+    "@s/r",
+    "@s/r/sub",
+    "#imp",
+    "node_modules/p/lib/index.js",
+];
+
+const CJS_SCRIPT: &str = r#"
+const req = require("module").createRequire(process.cwd() + "/");
+const results = JSON.parse(process.argv[1]).map((request) => {
+    try {
+        return req.resolve(request);
+    } catch {
+        return null;
+    }
+});
+console.log(JSON.stringify(results));
+"#;
+
+const ESM_SCRIPT: &str = r#"
+import { fileURLToPath } from "node:url";
+const results = JSON.parse(process.argv[1]).map((request) => {
+    try {
+        const url = new URL(import.meta.resolve(request));
+        return fileURLToPath(url) + url.search + url.hash;
+    } catch {
+        return null;
+    }
+});
+console.log(JSON.stringify(results));
+"#;
+
+#[derive(Default)]
+struct Report {
+    total: usize,
+    mismatches: Vec<(u64, String, Option<String>, Option<String>)>,
+}
+
+impl Report {
+    /// The percentage of the requests resolved to the same result as node.
+    fn matched(&self) -> f64 {
+        (self.total - self.mismatches.len()) as f64 * 100.0 / self.total as f64
+    }
+
+    fn print(&self, mode: ResolutionMode) {
+        println!(
+            "{mode:?}: {}/{} matched ({:.1}%)",
+            self.total - self.mismatches.len(),
+            self.total,
+            self.matched()
+        );
+        for (seed, request, ours, node) in &self.mismatches {
+            println!("  seed {seed} '{request}': resolver {ours:?}, node {node:?}");
+        }
+    }
+}
+
+fn run_node(mode: ResolutionMode, root: &Path, requests: &[String]) -> Vec<Option<String>> {
+    let mut command = Command::new("node");
+    match mode {
+        ResolutionMode::Esm => command.args(["--input-type=module", "-e", ESM_SCRIPT]),
+        _ => command.args(["-e", CJS_SCRIPT]),
+    };
+    let output = command
+        .arg(serde_json::to_string(requests).unwrap())
+        .current_dir(root)
+        .output()
+        .expect("`node` is required by the conformance tests");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let results: Vec<Option<String>> = serde_json::from_slice(&output.stdout).unwrap();
+    // `import.meta.resolve` does not check the existence of the file, but
+    // `import` would fail on it.
+    results
+        .into_iter()
+        .map(|result| {
+            result.filter(|path| {
+                let file = path.split(['?', '#']).next().unwrap();
+                Path::new(file).is_file()
+            })
+        })
+        .collect()
+}
+
+/// The tree, the target of `#imp` in the root package.json and the
+/// suffixes of the requests in ESM.
+type Case = (Tree, &'static str, Vec<&'static str>);
+
+fn case() -> impl Strategy<Value = Case> {
+    (
+        tree(),
+        select(&["./src/a.js", "p", "./missing.js"][..]),
+        vec(select(&["", "", "?q", "#f"][..]), REQUESTS.len()),
+    )
+}
+
+fn check(mode: ResolutionMode, seed: u64, case: &Case, root: &Path, report: &mut Report) {
+    let (tree, imports, suffixes) = case;
+    tree.write(root);
+    write(
+        &root.join("package.json"),
+        &format!(r##"{{ "imports": {{ "#imp": "{imports}" }} }}"##),
+    );
+    let requests: Vec<String> = REQUESTS
+        .iter()
+        .zip(suffixes)
+        .map(|(request, suffix)| match mode {
+            // The query and fragment are the part of the file name in CJS.
+            ResolutionMode::Esm => format!("{request}{suffix}"),
+            _ => String::from(*request),
+        })
+        .collect();
+    let expected = run_node(mode, root, &requests);
+    let resolver = Resolver::new(Options {
+        mode,
+        compat: CompatMode::Node,
+        ..Default::default()
+    });
+    for (request, node) in requests.into_iter().zip(expected) {
+        let ours = match resolver.resolve(root, &request) {
+            Ok(ResolveResult::File(resource)) => Some(resource.join().display().to_string()),
+            _ => None,
+        };
+        report.total += 1;
+        if ours != node {
+            report.mismatches.push((seed, request, ours, node));
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn node_conformance_test() {
+    let base: PathBuf = std::env::temp_dir().join(format!(
+        "nodejs_resolver_conformance_{}",
+        std::process::id()
+    ));
+    let mut failed = false;
+    for mode in [ResolutionMode::Cjs, ResolutionMode::Esm] {
+        let mut report = Report::default();
+        // the same cases in each run.
+        let mut runner = TestRunner::deterministic();
+        for seed in 0..SEEDS {
+            let case = case().new_tree(&mut runner).unwrap().current();
+            let root = base.join(format!("{mode:?}_{seed}"));
+            std::fs::create_dir_all(&root).unwrap();
+            check(mode, seed, &case, &root, &mut report);
+        }
+        report.print(mode);
+        failed |= report.matched() < MIN_MATCHED;
+    }
+    std::fs::remove_dir_all(&base).unwrap();
+    assert!(!failed, "the compatibility is lower than {MIN_MATCHED}%");
+}
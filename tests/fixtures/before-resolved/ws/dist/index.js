@@ -0,0 +1 @@
+module.exports = 'ws/dist/index.js';
@@ -0,0 +1 @@
+module.exports = 'ws/src/index.js';
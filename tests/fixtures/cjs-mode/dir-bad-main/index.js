@@ -0,0 +1 @@
+module.exports = 'dir-bad-main/index.js';
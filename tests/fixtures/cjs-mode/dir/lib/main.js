@@ -0,0 +1 @@
+module.exports = 'dir/lib/main.js';
@@ -0,0 +1 @@
+// Plug'n'Play
@@ -0,0 +1 @@
+export default 'b.js';
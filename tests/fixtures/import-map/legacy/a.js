@@ -0,0 +1 @@
+export default 'legacy/a.js';
@@ -0,0 +1 @@
+export default 'lib/a/x.js';
@@ -0,0 +1 @@
+export default 'src/new.js';
@@ -0,0 +1 @@
+export default 'cjs/a.js';
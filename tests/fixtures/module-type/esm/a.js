@@ -0,0 +1 @@
+export default 'esm/a.js';
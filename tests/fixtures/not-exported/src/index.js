@@ -0,0 +1 @@
+require('pkg/lib/utils');
@@ -0,0 +1 @@
+module.exports = 'packages/legacy/src/index.js';
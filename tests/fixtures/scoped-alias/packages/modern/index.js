@@ -0,0 +1 @@
+module.exports = 'packages/modern/index.js';
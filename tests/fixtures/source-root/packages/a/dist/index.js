@@ -0,0 +1 @@
+export default 'packages/a/dist/index.js';
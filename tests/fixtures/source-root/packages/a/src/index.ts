@@ -0,0 +1 @@
+export default 'packages/a/src/index.ts';
@@ -0,0 +1 @@
+export default 'packages/b/dist/index.js';
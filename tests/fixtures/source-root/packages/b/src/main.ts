@@ -0,0 +1 @@
+export default 'packages/b/src/main.ts';
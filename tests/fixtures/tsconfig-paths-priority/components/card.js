@@ -0,0 +1 @@
+module.exports = 'components/card.js';
@@ -0,0 +1 @@
+module.exports = 'generated/a.js';
@@ -0,0 +1 @@
+module.exports = 'special/button.js';
@@ -0,0 +1,141 @@
+//! Property tests of the resolution, the package trees, the options and
+//! the requests are generated by proptest, so a failure is shrunk to a
+//! minimal case and persisted in `proptest-regressions`.
+
+mod common;
+
+use common::{tree, Tree};
+use nodejs_resolver::{AliasMap, Error, Options, ResolveResult, Resolver};
+use proptest::{
+    collection::vec,
+    option,
+    prelude::*,
+    sample::{select, subsequence},
+};
+use std::{
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+fn options() -> impl Strategy<Value = Options> {
+    let target = || option::of(select(&["p", "q", "./src/a", "x", "y"][..]));
+    (
+        [target(), target(), target()],
+        any::<bool>(),
+        subsequence(&["node", "import", "require"][..], 0..=3),
+        prop::bool::weighted(0.25),
+    )
+        .prop_map(
+            |(targets, browser_field, condition_names, fully_specified)| {
+                let alias = ["x", "y", "p"]
+                    .into_iter()
+                    .zip(targets)
+                    .filter_map(|(key, to)| {
+                        let to = AliasMap::Target(String::from(to?));
+                        Some((String::from(key), vec![to]))
+                    })
+                    .collect();
+                Options {
+                    alias,
+                    browser_field,
+                    condition_names: condition_names.into_iter().map(String::from).collect(),
+                    extensions: vec![String::from(".js"), String::from(".ts")],
+                    fully_specified,
+                    ..Default::default()
+                }
+            },
+        )
+}
+
+fn request() -> impl Strategy<Value = String> {
+    (
+        select(
+            &[
+                "./src/a",
+                "./src/a.js",
+                "./src",
+                "./src/",
+                "p",
+                "p/sub",
+                "p/lib/sub",
+                "q",
+                "@s/r",
+                "@s/r/sub",
+                "x",
+                "y",
+                "#imp",
+                "./missing",
+                "..",
+                ".",
+                "p/package.json",
+            ][..],
+        ),
+        select(&["", "", "?q"][..]),
+        select(&["", "", "#f"][..]),
+    )
+        .prop_map(|(request, query, fragment)| format!("{request}{query}{fragment}"))
+}
+
+/// The comparable outcome of a resolution.
+fn outcome(result: &Result<ResolveResult<nodejs_resolver::Resource>, Error>) -> String {
+    match result {
+        Ok(ResolveResult::File(resource)) => resource.join().display().to_string(),
+        Ok(ResolveResult::Directory(resource)) => format!("{}/", resource.join().display()),
+        Ok(ResolveResult::Builtin(name)) => name.to_string(),
+        Ok(ResolveResult::Ignored { .. }) => String::from("ignored"),
+        Err(error) => format!("{:?}", std::mem::discriminant(error)),
+    }
+}
+
+fn check(
+    tree: &Tree,
+    options: &Options,
+    requests: &[String],
+    root: &Path,
+) -> Result<(), TestCaseError> {
+    tree.write(root);
+    let resolver = Resolver::new(options.clone());
+    let issuer = root.join("src");
+    for request in requests {
+        let result = resolver.resolve(&issuer, request);
+        if let Ok(ResolveResult::File(resource)) = &result {
+            prop_assert!(
+                resource.path.is_file(),
+                "'{}' is resolved to '{}' which is not a file",
+                request,
+                resource.path.display()
+            );
+            prop_assert!(resource.path.starts_with(root));
+        }
+        // the memoized and fresh results are same.
+        let memoized = resolver.resolve(&issuer, request);
+        let fresh = Resolver::new(options.clone()).resolve(&issuer, request);
+        prop_assert_eq!(outcome(&result), outcome(&memoized), "'{}'", request);
+        prop_assert_eq!(outcome(&result), outcome(&fresh), "'{}'", request);
+    }
+    Ok(())
+}
+
+/// The number of the cases, each case is written in its own directory.
+static CASES: AtomicUsize = AtomicUsize::new(0);
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn resolve_property_test(
+        tree in tree(),
+        options in options(),
+        requests in vec(request(), 16),
+    ) {
+        let root = std::env::temp_dir().join(format!(
+            "nodejs_resolver_property_{}_{}",
+            std::process::id(),
+            CASES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&root).unwrap();
+        let result = check(&tree, &options, &requests, &root);
+        std::fs::remove_dir_all(&root).unwrap();
+        result?;
+    }
+}